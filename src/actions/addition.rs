@@ -1,40 +1,59 @@
+use chrono::Local;
 use rusqlite::Connection;
 
 use crate::{
     actions::display,
     args::{
+        itemref::{resolve_rowid, ItemRef},
         parser::{
             RecordCommand,
             TaskCommand,
         },
+        tag::extract_tags,
         timestr,
     },
     config::get_project,
-    context::Context,
+    context::{Context, Permission},
     db::{
-        crud::insert_item,
+        crud::{
+            get_item,
+            insert_item,
+        },
         item::{
             Item,
             RECORD,
             TASK,
         },
         link::add_link,
+        tag::add_tags,
         user::get_user_by_name,
     },
     github::{get_issue, is_gh_available, parse_issue_ref},
 };
 
 pub fn handle_taskcmd(conn: &Connection, ctx: &Context, cmd: &TaskCommand) -> Result<(), String> {
+    ctx.require(Permission::EditItems)?;
+
     // Handle --from-issue flag
     if let Some(ref issue_str) = cmd.from_issue {
         return handle_from_issue(conn, ctx, cmd, issue_str);
     }
 
-    let content = cmd.content.clone();
+    // Resolve the parent task, subtasks inherit its category unless given one
+    let parent = match cmd.parent {
+        Some(parent_ref) => Some(resolve_parent(conn, ctx, parent_ref)?),
+        None => None,
+    };
+
+    let (content, tags) = extract_tags(&cmd.content);
+    if content.is_empty() {
+        return Err("Task content cannot be only tags".to_string());
+    }
     let target_timestr = cmd.timestr.clone().unwrap_or_else(|| "today".to_string());
     let category: String = cmd
         .category
         .clone()
+        .or_else(|| parent.as_ref().map(|p| p.category.clone()))
         .unwrap_or_else(|| "default".to_string());
 
     // Validate project exists in config if specified
@@ -68,27 +87,42 @@ pub fn handle_taskcmd(conn: &Connection, ctx: &Context, cmd: &TaskCommand) -> Re
             new_task.namespace_id = Some(ctx.current_namespace_id);
             new_task.priority = cmd.priority;
             new_task.estimate_minutes = cmd.estimate;
-            insert_item(conn, &new_task).map_err(|e| e.to_string())?;
-
-            display::print_bold("Inserted Task:");
-            display::print_items(&[new_task], false, false);
-            Ok(())
+            new_task.parent_id = parent.and_then(|p| p.id);
+            new_task.id =
+                Some(insert_item(conn, &new_task, Some(ctx.current_user_id)).map_err(|e| e.to_string())?);
+            add_tags(conn, new_task.id.unwrap(), &tags)?;
+            new_task.tags = tags;
+
+            let header = if new_task.parent_id.is_some() {
+                "Inserted Subtask:"
+            } else {
+                "Inserted Task:"
+            };
+            display::output_items(conn, ctx, header, &[new_task], false, false)
         }
-        Err(_) => match timestr::parse_recurring_timestr(&target_timestr) {
-            Ok(cron_schedule) => {
+        Err(_) => match timestr::parse_recurring_schedule(&target_timestr) {
+            Ok(_) if parent.is_some() => Err("Subtasks cannot be recurring".to_string()),
+            Ok(schedule) if schedule.until.is_some_and(|until| until <= Local::now().timestamp()) => {
+                Err("The recurrence end date is in the past".to_string())
+            }
+            Ok(schedule) => {
                 let mut new_recurring_task =
-                    Item::create_recurring_task(category, content, cron_schedule, target_timestr);
+                    Item::create_recurring_task(category, content, schedule.cron, target_timestr);
+                new_recurring_task.recur_until = schedule.until;
+                new_recurring_task.recur_count = schedule.count;
                 // Set multi-tenant fields for recurring tasks too
                 new_recurring_task.owner_id = Some(ctx.current_user_id);
                 new_recurring_task.assignee_id = assignee_id;
                 new_recurring_task.namespace_id = Some(ctx.current_namespace_id);
                 new_recurring_task.priority = cmd.priority;
                 new_recurring_task.estimate_minutes = cmd.estimate;
-                insert_item(conn, &new_recurring_task).map_err(|e| e.to_string())?;
+                new_recurring_task.id = Some(
+                    insert_item(conn, &new_recurring_task, Some(ctx.current_user_id)).map_err(|e| e.to_string())?,
+                );
+                add_tags(conn, new_recurring_task.id.unwrap(), &tags)?;
+                new_recurring_task.tags = tags;
 
-                display::print_bold("Inserted Recurring Task:");
-                display::print_items(&[new_recurring_task], false, false);
-                Ok(())
+                display::output_items(conn, ctx, "Inserted Recurring Task:", &[new_recurring_task], false, false)
             }
             Err(_) => Err(format!(
                 "Could not parse '{}' as a valid time or recurring schedule",
@@ -98,25 +132,36 @@ pub fn handle_taskcmd(conn: &Connection, ctx: &Context, cmd: &TaskCommand) -> Re
     }
 }
 
-pub fn handle_recordcmd(conn: &Connection, cmd: &RecordCommand) -> Result<(), String> {
+fn resolve_parent(conn: &Connection, ctx: &Context, parent_ref: ItemRef) -> Result<Item, String> {
+    let parent_id = resolve_rowid(conn, ctx, parent_ref)?;
+    let parent = get_item(conn, parent_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+    ctx.check_namespace(conn, &parent)?;
+    if parent.action != TASK {
+        return Err("Only regular tasks can have subtasks".to_string());
+    }
+    Ok(parent)
+}
+
+pub fn handle_recordcmd(conn: &Connection, ctx: &Context, cmd: &RecordCommand) -> Result<(), String> {
+    ctx.require(Permission::EditItems)?;
     let content = cmd.content.clone();
     let category: String = cmd
         .category
         .clone()
         .unwrap_or_else(|| "default".to_string());
-    let new_record = match &cmd.timestr {
+    let mut new_record = match &cmd.timestr {
         Some(t) => {
             let create_time = timestr::to_unix_epoch(t)?;
             Item::with_create_time(RECORD.to_string(), category, content, create_time)
         }
         None => Item::new(RECORD.to_string(), category, content),
     };
+    new_record.owner_id = Some(ctx.current_user_id);
+    new_record.namespace_id = Some(ctx.current_namespace_id);
 
-    insert_item(conn, &new_record).map_err(|e| e.to_string())?;
+    new_record.id = Some(insert_item(conn, &new_record, Some(ctx.current_user_id)).map_err(|e| e.to_string())?);
 
-    display::print_bold("Inserted Record:");
-    display::print_items(&[new_record], true, false);
-    Ok(())
+    display::output_items(conn, ctx, "Inserted Record:", &[new_record], true, false)
 }
 
 /// Handle --from-issue flag: create task from GitHub issue
@@ -145,10 +190,11 @@ fn handle_from_issue(
     }
 
     // Use issue title as task content, or allow override via cmd.content if provided
-    let content = if cmd.content.is_empty() || cmd.content == issue_str {
+    let (content, tags) = extract_tags(&cmd.content);
+    let content = if content.is_empty() || content == issue_str {
         issue.title.clone()
     } else {
-        cmd.content.clone()
+        content
     };
 
     let target_timestr = cmd.timestr.clone().unwrap_or_else(|| "today".to_string());
@@ -189,7 +235,9 @@ fn handle_from_issue(
     new_task.estimate_minutes = cmd.estimate;
     new_task.github_issue = Some(issue_str.to_string());
 
-    let task_id = insert_item(conn, &new_task).map_err(|e| e.to_string())?;
+    let task_id = insert_item(conn, &new_task, Some(ctx.current_user_id)).map_err(|e| e.to_string())?;
+    add_tags(conn, task_id, &tags)?;
+    new_task.tags = tags;
 
     // Auto-link the issue
     add_link(
@@ -201,9 +249,11 @@ fn handle_from_issue(
         Some(ctx.current_user_id),
     )?;
 
-    display::print_bold("Created task from GitHub issue:");
-    display::print_items(&[new_task], false, false);
-    println!("  Linked: {}", issue.url);
+    new_task.id = Some(task_id);
+    display::output_items(conn, ctx, "Created task from GitHub issue:", &[new_task], false, false)?;
+    if ctx.output.is_table() {
+        println!("  Linked: {}", issue.url);
+    }
 
     Ok(())
 }
@@ -219,7 +269,11 @@ mod tests {
                 RECURRING_TASK,
             },
         },
-        tests::get_test_conn,
+        tests::{
+            get_test_conn,
+            insert_recurring_task,
+            insert_task,
+        },
     };
 
     fn default_task_cmd(content: &str) -> TaskCommand {
@@ -233,6 +287,7 @@ mod tests {
             estimate: None,
             assignee: None,
             from_issue: None,
+            parent: None,
         }
     }
 
@@ -252,6 +307,35 @@ mod tests {
         assert_eq!(items[0].namespace_id, Some(ctx.current_namespace_id));
     }
 
+    #[test]
+    fn test_task_with_tags() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        handle_taskcmd(&conn, &ctx, &default_task_cmd("Fix login +backend +Security")).unwrap();
+        let items = query_items(&conn, &ItemQuery::new().with_action(TASK)).unwrap();
+        assert_eq!(items[0].content, "Fix login");
+        assert_eq!(
+            crate::db::tag::get_tags_for_item(&conn, items[0].id.unwrap()).unwrap(),
+            vec!["backend", "security"]
+        );
+
+        let err = handle_taskcmd(&conn, &ctx, &default_task_cmd("+backend")).unwrap_err();
+        assert!(err.contains("only tags"));
+    }
+
+    #[test]
+    fn test_viewer_cannot_add() {
+        let (conn, _temp_file) = get_test_conn();
+        crate::db::user::create_user(&conn, "vic", None, None).unwrap();
+        crate::db::namespace::add_user_to_namespace(&conn, "default", "vic", "viewer").unwrap();
+        let viewer = Context::resolve_with_config(&conn, Some("vic"), None, None).unwrap();
+
+        let err = handle_taskcmd(&conn, &viewer, &default_task_cmd("nope")).unwrap_err();
+        assert!(err.contains("cannot modify items"));
+        let items = query_items(&conn, &ItemQuery::new().with_action(TASK)).unwrap();
+        assert!(items.is_empty());
+    }
+
     #[test]
     fn test_filled_task() {
         let tc = TaskCommand {
@@ -264,6 +348,7 @@ mod tests {
             estimate: None,
             assignee: None,
             from_issue: None,
+            parent: None,
         };
         let (conn, _temp_file) = get_test_conn();
         let ctx = Context::default_from_db(&conn).unwrap();
@@ -296,6 +381,7 @@ mod tests {
             estimate: Some(120), // 2 hours
             assignee: None,
             from_issue: None,
+            parent: None,
         };
         let (conn, _temp_file) = get_test_conn();
         let ctx = Context::default_from_db(&conn).unwrap();
@@ -314,7 +400,8 @@ mod tests {
             timestr: None,
         };
         let (conn, _temp_file) = get_test_conn();
-        handle_recordcmd(&conn, &rc).unwrap();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        handle_recordcmd(&conn, &ctx, &rc).unwrap();
         let items = query_items(
             &conn,
             &ItemQuery::new()
@@ -343,6 +430,7 @@ mod tests {
             estimate: None,
             assignee: None,
             from_issue: None,
+            parent: None,
         };
         handle_taskcmd(&conn, &ctx, &daily).unwrap();
 
@@ -356,6 +444,7 @@ mod tests {
             estimate: None,
             assignee: None,
             from_issue: None,
+            parent: None,
         };
         handle_taskcmd(&conn, &ctx, &weekly).unwrap();
 
@@ -369,6 +458,7 @@ mod tests {
             estimate: None,
             assignee: None,
             from_issue: None,
+            parent: None,
         };
         handle_taskcmd(&conn, &ctx, &monthly).unwrap();
 
@@ -410,6 +500,7 @@ mod tests {
             estimate: None,
             assignee: None,
             from_issue: None,
+            parent: None,
         };
         handle_taskcmd(&conn, &ctx, &regular_task).unwrap();
 
@@ -423,6 +514,7 @@ mod tests {
             estimate: None,
             assignee: None,
             from_issue: None,
+            parent: None,
         };
         handle_taskcmd(&conn, &ctx, &recurring_task).unwrap();
 
@@ -448,10 +540,37 @@ mod tests {
             estimate: None,
             assignee: None,
             from_issue: None,
+            parent: None,
         };
         let (conn, _temp_file) = get_test_conn();
         let ctx = Context::default_from_db(&conn).unwrap();
         let result = handle_taskcmd(&conn, &ctx, &tc);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_subtask() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let parent_id = insert_task(&conn, "release", "ship 1.0", "tomorrow");
+
+        let mut tc = default_task_cmd("write changelog");
+        tc.parent = Some(ItemRef::Id(parent_id));
+        handle_taskcmd(&conn, &ctx, &tc).unwrap();
+        let subtasks = query_items(&conn, &ItemQuery::new().with_parent_id(parent_id)).unwrap();
+        assert_eq!(subtasks.len(), 1);
+        assert_eq!(subtasks[0].content, "write changelog");
+        // Category is inherited from the parent
+        assert_eq!(subtasks[0].category, "release");
+
+        tc.timestr = Some("daily 9am".to_string());
+        let err = handle_taskcmd(&conn, &ctx, &tc).unwrap_err();
+        assert!(err.contains("cannot be recurring"));
+
+        let recurring_id = insert_recurring_task(&conn, "work", "standup", "daily 9am");
+        let mut tc = default_task_cmd("prepare notes");
+        tc.parent = Some(ItemRef::Id(recurring_id));
+        let err = handle_taskcmd(&conn, &ctx, &tc).unwrap_err();
+        assert!(err.contains("Only regular tasks"));
+    }
 }
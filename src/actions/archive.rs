@@ -0,0 +1,124 @@
+use rusqlite::Connection;
+
+use crate::{
+    actions::{bulk, display},
+    args::{parser::ArchiveCommand, timestr::to_unix_epoch},
+    context::{Context, Permission},
+    db::{
+        archive::{archive_items, archive_path},
+        cache,
+    },
+};
+
+/// Handles the archive command - moves closed tasks and old records of the current namespace,
+/// or of every namespace the user is a member of with --all-namespaces, to the archive file.
+pub fn handle_archivecmd(conn: &Connection, ctx: &Context, cmd: &ArchiveCommand) -> Result<(), String> {
+    let namespace_ids = ctx.namespace_filter(conn)?;
+    for &ns in &namespace_ids {
+        ctx.require_in(conn, ns, Permission::EditItems)?;
+    }
+
+    // A bad date fails before asking
+    let before = to_unix_epoch(&cmd.before)?;
+    let question = format!("Move tasks closed and records made before {} to the archive?", cmd.before);
+    if !cmd.yes {
+        if !ctx.output.is_table() {
+            return Err(format!("{} Add --yes to go ahead without a prompt", question));
+        }
+        if !bulk::prompt_yes_no(&question) {
+            return Err("Not archiving".to_string());
+        }
+    }
+
+    let counts = archive_items(conn, before, Some(&namespace_ids))?;
+    let path = archive_path(conn)?;
+    // Indexes of the last listing may point at archived items
+    if counts.tasks + counts.records > 0 {
+        cache::clear(conn).map_err(|e| format!("Failed to clear cache: {:?}", e))?;
+    }
+
+    if !ctx.output.is_table() {
+        let output = serde_json::json!({
+            "tasks": counts.tasks,
+            "records": counts.records,
+            "path": path.to_string_lossy(),
+        });
+        println!("{}", output);
+        return Ok(());
+    }
+    if counts.tasks + counts.records == 0 {
+        println!("Nothing to archive before {}", cmd.before);
+        return Ok(());
+    }
+    display::print_bold(&format!(
+        "Archived {} and {} to {}",
+        plural(counts.tasks, "task"),
+        plural(counts.records, "record"),
+        path.display()
+    ));
+    println!("Find them with ctm search --archived");
+    Ok(())
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actions::search::handle_searchcmd,
+        args::parser::SearchCommand,
+        db::crud::get_item,
+        tests::{get_test_conn, insert_task, update_status},
+    };
+
+    #[test]
+    fn test_handle_archivecmd() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let id = insert_task(&conn, "work", "rotate the keys", "today");
+        update_status(&conn, id, 1);
+
+        let search_cmd = SearchCommand {
+            query: vec!["keys".to_string()],
+            limit: 20,
+            archived: true,
+        };
+        assert!(handle_searchcmd(&conn, &ctx, &search_cmd).unwrap_err().contains("Nothing has been archived"));
+
+        let cmd = ArchiveCommand {
+            before: "in 1 hour".to_string(),
+            yes: true,
+        };
+        handle_archivecmd(&conn, &ctx, &cmd).unwrap();
+        assert!(get_item(&conn, id).is_err());
+        handle_searchcmd(&conn, &ctx, &search_cmd).unwrap();
+        std::fs::remove_file(archive_path(&conn).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_bad_date_fails_before_asking() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let cmd = ArchiveCommand {
+            before: "the day after never".to_string(),
+            yes: false,
+        };
+        let err = handle_archivecmd(&conn, &ctx, &cmd).unwrap_err();
+        assert_ne!(err, "Not archiving");
+        assert!(!archive_path(&conn).unwrap().exists());
+    }
+
+    #[test]
+    fn test_plural() {
+        assert_eq!(plural(1, "task"), "1 task");
+        assert_eq!(plural(0, "record"), "0 records");
+        assert_eq!(plural(2, "record"), "2 records");
+    }
+}
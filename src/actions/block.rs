@@ -0,0 +1,114 @@
+use rusqlite::Connection;
+
+use crate::{
+    actions::display,
+    args::{itemref::resolve_rowid, parser::BlockCommand},
+    context::Context,
+    db::{
+        crud::get_item,
+        dependency::{add_dependency, mark_blocked, remove_dependency},
+        item::{Item, TASK},
+    },
+};
+
+/// Handles the block command - makes a task depend on another one
+pub fn handle_blockcmd(conn: &Connection, ctx: &Context, cmd: &BlockCommand) -> Result<(), String> {
+    let (mut item, blocker) = resolve_pair(conn, ctx, cmd)?;
+
+    add_dependency(conn, item.id.unwrap(), blocker.id.unwrap(), Some(ctx.current_user_id))?;
+    mark_blocked(conn, std::slice::from_mut(&mut item))?;
+
+    let header = format!(
+        "Task now depends on #{}: {}",
+        blocker.id.unwrap(),
+        blocker.content.lines().next().unwrap_or(&blocker.content)
+    );
+    display::output_items(conn, ctx, &header, &[item], false, false)
+}
+
+/// Handles the unblock command - removes a dependency between two tasks
+pub fn handle_unblockcmd(conn: &Connection, ctx: &Context, cmd: &BlockCommand) -> Result<(), String> {
+    let (mut item, blocker) = resolve_pair(conn, ctx, cmd)?;
+
+    if !remove_dependency(conn, item.id.unwrap(), blocker.id.unwrap())? {
+        return Err(format!("Task does not depend on #{}", blocker.id.unwrap()));
+    }
+    mark_blocked(conn, std::slice::from_mut(&mut item))?;
+
+    let header = format!("Removed dependency on #{}:", blocker.id.unwrap());
+    display::output_items(conn, ctx, &header, &[item], false, false)
+}
+
+// Resolves the dependent task, which is modified, and the task it depends on, which is only read.
+fn resolve_pair(conn: &Connection, ctx: &Context, cmd: &BlockCommand) -> Result<(Item, Item), String> {
+    let item_id = resolve_rowid(conn, ctx, cmd.index)?;
+    let blocker_id = resolve_rowid(conn, ctx, cmd.on)?;
+
+    let item = get_item(conn, item_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+    ctx.authorize_item(conn, &item)?;
+    let blocker = get_item(conn, blocker_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+    ctx.check_namespace(conn, &blocker)?;
+
+    if item.action != TASK || blocker.action != TASK {
+        return Err("Dependencies can only be set between regular tasks".to_string());
+    }
+    Ok((item, blocker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        args::itemref::ItemRef,
+        db::{
+            cache,
+            crud::query_items,
+            dependency::get_dependencies,
+            item::ItemQuery,
+        },
+        tests::{get_test_conn, insert_recurring_task, insert_task},
+    };
+
+    fn block_cmd(index: usize, on: usize) -> BlockCommand {
+        BlockCommand {
+            index: ItemRef::Index(index),
+            on: ItemRef::Index(on),
+        }
+    }
+
+    #[test]
+    fn test_handle_block_and_unblock() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let design = insert_task(&conn, "work", "design", "today");
+        let build = insert_task(&conn, "work", "build", "tomorrow");
+        let items = query_items(&conn, &ItemQuery::new().with_action(TASK)).unwrap();
+        cache::store(&conn, &items).unwrap();
+
+        handle_blockcmd(&conn, &ctx, &block_cmd(2, 1)).unwrap();
+        assert_eq!(get_dependencies(&conn, build).unwrap(), vec![design]);
+
+        let err = handle_blockcmd(&conn, &ctx, &block_cmd(1, 2)).unwrap_err();
+        assert!(err.contains("cycle"));
+
+        handle_unblockcmd(&conn, &ctx, &block_cmd(2, 1)).unwrap();
+        assert!(get_dependencies(&conn, build).unwrap().is_empty());
+        let err = handle_unblockcmd(&conn, &ctx, &block_cmd(2, 1)).unwrap_err();
+        assert!(err.contains("does not depend"));
+    }
+
+    #[test]
+    fn test_handle_block_only_tasks() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let task = insert_task(&conn, "work", "deploy", "today");
+        let recurring = insert_recurring_task(&conn, "work", "standup", "daily 9am");
+
+        let cmd = BlockCommand {
+            index: ItemRef::Id(task),
+            on: ItemRef::Id(recurring),
+        };
+        let err = handle_blockcmd(&conn, &ctx, &cmd).unwrap_err();
+        assert!(err.contains("regular tasks"));
+    }
+}
@@ -0,0 +1,246 @@
+use chrono::Local;
+use rusqlite::{types::Value, Connection};
+use serde_json::json;
+
+use crate::{
+    actions::display::{self, item_to_json, BoardColumn, JSON_SCHEMA_VERSION},
+    args::parser::{parse_status, BoardCommand},
+    context::Context,
+    db::{
+        cache,
+        crud::{mark_subtask_progress, query_items},
+        dependency::mark_blocked,
+        item::{Condition, Item, ItemQuery, TASK},
+        tag::mark_tags,
+        user::get_user_by_name,
+    },
+};
+
+// Left to right, the way work moves across the board
+const BOARD_STATUSES: [&str; 4] = ["pending", "ongoing", "suspended", "done"];
+
+/// Handles the board command - tasks in a column per status, with the number of tasks in each.
+/// The done column only goes back `--days`, the cards are cached like a listing.
+pub fn handle_boardcmd(conn: &Connection, ctx: &Context, cmd: &BoardCommand) -> Result<(), String> {
+    let columns = board_columns(conn, ctx, cmd)?;
+
+    // Cards are numbered across the columns, left to right
+    let cards: Vec<Item> = columns.iter().flat_map(|column| column.cards.clone()).collect();
+    cache::clear(conn).map_err(|e| e.to_string())?;
+    cache::store(conn, &cards).map_err(|e| e.to_string())?;
+
+    if !ctx.output.is_table() {
+        let mut index = 0;
+        let mut values = Vec::new();
+        for column in &columns {
+            let items = column
+                .cards
+                .iter()
+                .map(|item| {
+                    index += 1;
+                    item_to_json(conn, item, Some(index))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            values.push(json!({
+                "status": column.title,
+                "count": column.count,
+                "items": items,
+            }));
+        }
+        let output = json!({
+            "schema_version": JSON_SCHEMA_VERSION,
+            "columns": values,
+        });
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        return Ok(());
+    }
+
+    if columns.iter().all(|column| column.count == 0) {
+        display::print_bold("No tasks found");
+        return Ok(());
+    }
+    display::print_board(&columns);
+    Ok(())
+}
+
+// The columns in board order, every one of them even when it has no tasks
+fn board_columns(conn: &Connection, ctx: &Context, cmd: &BoardCommand) -> Result<Vec<BoardColumn>, String> {
+    let assignee_id = match &cmd.user {
+        Some(username) => {
+            let user = get_user_by_name(conn, username)?.ok_or_else(|| format!("User '{}' not found", username))?;
+            Some(user.id)
+        }
+        None => None,
+    };
+    let done_since = Condition {
+        sql: "modify_time >= ?".to_string(),
+        params: vec![Value::from(Local::now().timestamp() - cmd.days as i64 * 86400)],
+    };
+
+    let namespace_ids = ctx.namespace_filter(conn)?;
+
+    let mut columns: Vec<BoardColumn> = Vec::new();
+    for name in BOARD_STATUSES {
+        let status = parse_status(name)?;
+        let mut query = ItemQuery::new()
+            .with_action(TASK)
+            .with_statuses(vec![status])
+            .with_order_by("target_time")
+            .with_namespace_ids(namespace_ids.clone());
+        if let Some(aid) = assignee_id {
+            query = query.with_assignee_id(aid);
+        }
+        if let Some(category) = &cmd.category {
+            query = query.with_category(category);
+        }
+        if let Some(project) = &cmd.project {
+            query = query.with_project(project);
+        }
+        if status == 1 {
+            query = query.with_condition(&done_since);
+        }
+
+        let mut items = query_items(conn, &query).map_err(|e| e.to_string())?;
+        let count = items.len();
+        items.truncate(cmd.limit);
+        mark_blocked(conn, &mut items)?;
+        mark_subtask_progress(conn, &mut items).map_err(|e| e.to_string())?;
+        mark_tags(conn, &mut items)?;
+        columns.push(BoardColumn {
+            title: name.to_string(),
+            count,
+            cards: items,
+        });
+    }
+    Ok(columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{
+            archive::{archive_items, archive_path},
+            crud::{get_item, trash_item, update_item},
+            namespace::create_namespace,
+            user::create_user,
+        },
+        tests::{get_test_conn, insert_task, update_status},
+    };
+
+    // The number of tasks in each column, in board order
+    fn counts(columns: &[BoardColumn]) -> Vec<(&str, usize)> {
+        columns.iter().map(|column| (column.title.as_str(), column.count)).collect()
+    }
+
+    fn move_to(conn: &Connection, id: i64, namespace_id: i64) {
+        let mut item = get_item(conn, id).unwrap();
+        item.namespace_id = Some(namespace_id);
+        update_item(conn, &item, None).unwrap();
+    }
+
+    fn board(user: Option<&str>, project: Option<&str>, limit: usize) -> BoardCommand {
+        BoardCommand {
+            user: user.map(|s| s.to_string()),
+            category: None,
+            project: project.map(|s| s.to_string()),
+            days: 7,
+            limit,
+        }
+    }
+
+    #[test]
+    fn test_handle_boardcmd() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let first = insert_task(&conn, "work", "write the spec", "tomorrow");
+        insert_task(&conn, "work", "review the spec", "in 2 days");
+        let shelved = insert_task(&conn, "work", "rewrite the parser", "in 3 days");
+        let done = insert_task(&conn, "work", "set up the repo", "yesterday");
+        update_status(&conn, shelved, 4);
+        update_status(&conn, done, 1);
+        conn.execute("UPDATE items SET project = 'spec' WHERE id IN (?1, ?2)", [first, done]).unwrap();
+
+        // Two ongoing tasks but one card, the second one is only counted
+        handle_boardcmd(&conn, &ctx, &board(None, None, 1)).unwrap();
+        assert_eq!(cache::read(&conn, 1).unwrap(), Some(first));
+        assert_eq!(cache::read(&conn, 2).unwrap(), Some(shelved));
+        assert_eq!(cache::read(&conn, 3).unwrap(), Some(done));
+        assert_eq!(cache::read(&conn, 4).unwrap(), None);
+
+        handle_boardcmd(&conn, &ctx, &board(None, Some("spec"), 20)).unwrap();
+        assert_eq!(cache::read(&conn, 1).unwrap(), Some(first));
+        assert_eq!(cache::read(&conn, 2).unwrap(), Some(done));
+        assert_eq!(cache::read(&conn, 3).unwrap(), None);
+
+        let err = handle_boardcmd(&conn, &ctx, &board(Some("nobody"), None, 20)).unwrap_err();
+        assert_eq!(err, "User 'nobody' not found");
+    }
+
+    #[test]
+    fn test_empty_columns() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+
+        let columns = board_columns(&conn, &ctx, &board(None, None, 20)).unwrap();
+        assert_eq!(counts(&columns), vec![("pending", 0), ("ongoing", 0), ("suspended", 0), ("done", 0)]);
+        handle_boardcmd(&conn, &ctx, &board(None, None, 20)).unwrap();
+        assert_eq!(cache::read(&conn, 1).unwrap(), None);
+
+        // Cards are numbered across the empty columns
+        let done = insert_task(&conn, "work", "set up the repo", "yesterday");
+        update_status(&conn, done, 1);
+        let columns = board_columns(&conn, &ctx, &board(None, None, 20)).unwrap();
+        assert_eq!(counts(&columns), vec![("pending", 0), ("ongoing", 0), ("suspended", 0), ("done", 1)]);
+        handle_boardcmd(&conn, &ctx, &board(None, None, 20)).unwrap();
+        assert_eq!(cache::read(&conn, 1).unwrap(), Some(done));
+    }
+
+    #[test]
+    fn test_board_namespaces() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let work_ns = create_namespace(&conn, "work", None, ctx.current_user_id).unwrap();
+        let home = insert_task(&conn, "work", "default task", "tomorrow");
+        let work = insert_task(&conn, "work", "work task", "in 2 days");
+        move_to(&conn, work, work_ns);
+
+        // A namespace the default user is not a member of
+        create_user(&conn, "eve", None, None).unwrap();
+        let eve = get_user_by_name(&conn, "eve").unwrap().unwrap();
+        let eve_ns = create_namespace(&conn, "eve-private", None, eve.id).unwrap();
+        let private = insert_task(&conn, "work", "Eve's task", "today");
+        move_to(&conn, private, eve_ns);
+
+        let cards = |ctx: &Context| -> Vec<i64> {
+            board_columns(&conn, ctx, &board(None, None, 20))
+                .unwrap()
+                .iter()
+                .flat_map(|column| column.cards.iter().map(|card| card.id.unwrap()))
+                .collect()
+        };
+        assert_eq!(cards(&ctx), vec![home]);
+        assert_eq!(cards(&ctx.clone().with_all_namespaces(true)), vec![home, work]);
+    }
+
+    #[test]
+    fn test_trashed_and_archived_are_left_out() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let kept = insert_task(&conn, "work", "write the spec", "tomorrow");
+        let trashed = insert_task(&conn, "work", "drop me", "tomorrow");
+        let archived = insert_task(&conn, "work", "set up the repo", "yesterday");
+        let shipped = insert_task(&conn, "work", "ship it", "yesterday");
+        trash_item(&conn, trashed, None).unwrap();
+        update_status(&conn, archived, 1);
+        let later = get_item(&conn, archived).unwrap().modify_time.unwrap() + 1;
+        archive_items(&conn, later, None).unwrap();
+        update_status(&conn, shipped, 1);
+
+        let columns = board_columns(&conn, &ctx, &board(None, None, 20)).unwrap();
+        assert_eq!(counts(&columns), vec![("pending", 0), ("ongoing", 1), ("suspended", 0), ("done", 1)]);
+        assert_eq!(columns[1].cards[0].id, Some(kept));
+        assert_eq!(columns[3].cards[0].id, Some(shipped));
+        std::fs::remove_file(archive_path(&conn).unwrap()).unwrap();
+    }
+}
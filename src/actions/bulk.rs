@@ -0,0 +1,181 @@
+use std::{
+    io,
+    io::Write,
+};
+
+use rusqlite::Connection;
+
+use crate::{
+    actions::{
+        display,
+        list::{compile_filter, OPEN_STATUS_CODES},
+    },
+    args::{
+        filter::{parse_filter, Field},
+        itemref::{expand_ranges, resolve_rowid, ItemRange},
+    },
+    context::Context,
+    db::{
+        crud::{get_item, query_items},
+        item::{Item, ItemQuery, RECORD, RECURRING_TASK, RECURRING_TASK_RECORD, TASK},
+    },
+};
+
+/// Resolves the items a command acts on, either the given indexes, ranges and ids
+/// in that order and without repeats, or the tasks matching a `--where` filter. Like `list task`,
+/// a filter only matches open tasks unless it has a term on status.
+pub(crate) fn resolve_targets(
+    conn: &Connection,
+    ctx: &Context,
+    ranges: &[ItemRange],
+    filter: Option<&str>,
+) -> Result<Vec<Item>, String> {
+    let Some(expr) = filter else {
+        if ranges.is_empty() {
+            return Err("Give the indexes of the items, or --where with a filter".to_string());
+        }
+        let mut items: Vec<Item> = Vec::new();
+        for item_ref in expand_ranges(ranges) {
+            let row_id = resolve_rowid(conn, ctx, item_ref)?;
+            // An index and an id may point at the same item
+            if items.iter().all(|item| item.id != Some(row_id)) {
+                items.push(get_item(conn, row_id).map_err(|e| format!("Failed to get item: {:?}", e))?);
+            }
+        }
+        return Ok(items);
+    };
+    if !ranges.is_empty() {
+        return Err("Give either indexes or --where, not both".to_string());
+    }
+
+    let filter = parse_filter(expr)?;
+    let condition = compile_filter(conn, ctx, &filter)?;
+    let mut query = ItemQuery::new()
+        .with_actions(vec![TASK, RECURRING_TASK])
+        .with_condition(&condition)
+        .with_namespace_ids(ctx.namespace_filter(conn)?);
+    if !filter.mentions(Field::Status) {
+        query = query.with_statuses(OPEN_STATUS_CODES.to_vec());
+    }
+    let items = query_items(conn, &query).map_err(|e| e.to_string())?;
+    if items.is_empty() {
+        return Err(format!("No tasks match '{}'", expr));
+    }
+    Ok(items)
+}
+
+/// Whether a command changes more than a single, explicitly given item
+/// and should preview them and ask first.
+pub(crate) fn is_bulk(items: &[Item], filter: Option<&str>) -> bool {
+    items.len() > 1 || filter.is_some()
+}
+
+/// Shows the items about to be changed and asks whether to go ahead, `yes` skips the question.
+/// Without a table to read the preview from, `--yes` is required.
+pub(crate) fn confirm(ctx: &Context, items: &[Item], question: &str, yes: bool) -> Result<bool, String> {
+    if yes {
+        return Ok(true);
+    }
+    if !ctx.output.is_table() {
+        return Err(format!("{} Add --yes to go ahead without a prompt", question.trim()));
+    }
+
+    let (records, tasks): (Vec<Item>, Vec<Item>) = items
+        .iter()
+        .cloned()
+        .partition(|item| item.action == RECORD || item.action == RECURRING_TASK_RECORD);
+    if !tasks.is_empty() {
+        display::print_items(&tasks, false, false);
+    }
+    if !records.is_empty() {
+        display::print_items(&records, true, false);
+    }
+    Ok(prompt_yes_no(question))
+}
+
+/// Applies `change` to every item in one transaction,
+/// the first failure rolls back the items changed before it.
+pub(crate) fn apply_all<F>(conn: &Connection, items: Vec<Item>, mut change: F) -> Result<(), String>
+where
+    F: FnMut(&Connection, Item) -> Result<(), String>,
+{
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for item in items {
+        let item_id = item.id.unwrap();
+        change(&tx, item).map_err(|e| format!("#{}: {}, nothing was changed", item_id, e))?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+pub(crate) fn prompt_yes_no(question: &str) -> bool {
+    print!("{} (y/n): ", question);
+    io::stdout().flush().unwrap();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+
+    matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        args::itemref::ItemRef,
+        db::{cache, crud::update_item},
+        tests::{get_test_conn, insert_task, update_status},
+    };
+
+    #[test]
+    fn test_resolve_targets() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let ids: Vec<i64> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|content| insert_task(&conn, "work", content, "tomorrow"))
+            .collect();
+        let items: Vec<Item> = ids.iter().map(|&id| get_item(&conn, id).unwrap()).collect();
+        cache::store(&conn, &items).unwrap();
+
+        let targets = |ranges: &[ItemRange], filter: Option<&str>| -> Result<Vec<i64>, String> {
+            resolve_targets(&conn, &ctx, ranges, filter).map(|items| items.iter().map(|i| i.id.unwrap()).collect())
+        };
+        let ranges = [
+            ItemRange::Indexes(2, 3),
+            ItemRange::One(ItemRef::Index(1)),
+            ItemRange::One(ItemRef::Id(ids[2])),
+        ];
+        assert_eq!(targets(&ranges, None).unwrap(), vec![ids[1], ids[2], ids[0]]);
+        assert!(targets(&[ItemRange::Indexes(3, 6)], None).unwrap_err().contains("index 5"));
+        assert!(targets(&[], None).is_err());
+
+        // Filters skip closed tasks unless they ask for a status
+        update_status(&conn, ids[3], 1);
+        let mut other = get_item(&conn, ids[0]).unwrap();
+        other.category = "home".to_string();
+        update_item(&conn, &other, None).unwrap();
+        assert_eq!(targets(&[], Some("category:work")).unwrap(), vec![ids[1], ids[2]]);
+        assert_eq!(targets(&[], Some("category:work status:done")).unwrap(), vec![ids[3]]);
+        assert!(targets(&[], Some("category:none")).unwrap_err().contains("No tasks match"));
+        assert!(targets(&ranges, Some("category:work")).is_err());
+    }
+
+    #[test]
+    fn test_apply_all_rolls_back() {
+        let (conn, _temp_file) = get_test_conn();
+        let a = insert_task(&conn, "work", "a", "tomorrow");
+        let b = insert_task(&conn, "work", "b", "tomorrow");
+        let items = vec![get_item(&conn, a).unwrap(), get_item(&conn, b).unwrap()];
+
+        let err = apply_all(&conn, items, |conn, mut item| {
+            if item.content == "b" {
+                return Err("boom".to_string());
+            }
+            item.category = "moved".to_string();
+            update_item(conn, &item, None).map_err(|e| e.to_string())
+        })
+        .unwrap_err();
+        assert_eq!(err, format!("#{}: boom, nothing was changed", b));
+        assert_eq!(get_item(&conn, a).unwrap().category, "work");
+    }
+}
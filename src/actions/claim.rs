@@ -1,21 +1,43 @@
 use rusqlite::Connection;
 
 use crate::{
-    actions::display,
+    actions::{bulk, display},
+    args::parser::ClaimCommand,
     context::Context,
     db::{
-        cache,
-        crud::{get_item, update_item},
-        item::{RECORD, RECURRING_TASK_RECORD},
+        crud::update_item,
+        item::{Item, RECORD, RECURRING_TASK_RECORD},
     },
 };
 
-/// Handles the claim command - takes ownership of an unassigned task
-pub fn handle_claimcmd(conn: &Connection, ctx: &Context, index: usize) -> Result<(), String> {
-    validate_cache(conn)?;
-    let row_id = get_rowid_from_cache(conn, index)?;
+/// Handles the claim command - takes ownership of unassigned tasks
+pub fn handle_claimcmd(conn: &Connection, ctx: &Context, cmd: &ClaimCommand) -> Result<(), String> {
+    let items = bulk::resolve_targets(conn, ctx, &cmd.indexes, cmd.filter.as_deref())?;
+    for item in &items {
+        check_claimable(conn, ctx, item)?;
+    }
+
+    let header = format!("Claimed task (assigned to {}):", ctx.current_user_name);
+    if !bulk::is_bulk(&items, cmd.filter.as_deref()) {
+        let item = claim(conn, ctx, items.into_iter().next().unwrap())?;
+        return display::output_items(conn, ctx, &header, &[item], false, false);
+    }
 
-    let mut item = get_item(conn, row_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+    if !bulk::confirm(ctx, &items, &format!("Claim these {} tasks?", items.len()), cmd.yes)? {
+        return Err("Not claiming the tasks".to_string());
+    }
+    let mut claimed = Vec::new();
+    bulk::apply_all(conn, items, |conn, item| {
+        claimed.push(claim(conn, ctx, item)?);
+        Ok(())
+    })?;
+
+    let header = format!("Claimed {} tasks (assigned to {}):", claimed.len(), ctx.current_user_name);
+    display::output_items(conn, ctx, &header, &claimed, false, false)
+}
+
+fn check_claimable(conn: &Connection, ctx: &Context, item: &Item) -> Result<(), String> {
+    ctx.authorize_item(conn, item)?;
 
     if item.action == RECORD || item.action == RECURRING_TASK_RECORD {
         return Err("Cannot claim a record".to_string());
@@ -30,37 +52,20 @@ pub fn handle_claimcmd(conn: &Connection, ctx: &Context, index: usize) -> Result
             "Task is already assigned. Use update command to reassign."
         ));
     }
-
-    // Claim the task
-    item.assignee_id = Some(ctx.current_user_id);
-    update_item(conn, &item).map_err(|e| format!("Failed to update item: {:?}", e))?;
-
-    display::print_bold(&format!("Claimed task (assigned to {}):", ctx.current_user_name));
-    display::print_items(&[item], false, false);
-
     Ok(())
 }
 
-fn validate_cache(conn: &Connection) -> Result<(), String> {
-    match cache::validate_cache(conn) {
-        Ok(true) => Ok(()),
-        Ok(false) => Err("Cache is not valid, consider running list command first".to_string()),
-        Err(_) => Err("Cannot connect to cache".to_string()),
-    }
-}
-
-fn get_rowid_from_cache(conn: &Connection, index: usize) -> Result<i64, String> {
-    let index = index as i64;
-    match cache::read(conn, index).map_err(|e| format!("Failed to read cache table: {:?}", e))? {
-        Some(id) => Ok(id),
-        None => Err(format!("index {} does not exist", index)),
-    }
+fn claim(conn: &Connection, ctx: &Context, mut item: Item) -> Result<Item, String> {
+    item.assignee_id = Some(ctx.current_user_id);
+    update_item(conn, &item, Some(ctx.current_user_id)).map_err(|e| format!("Failed to update item: {:?}", e))?;
+    Ok(item)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
+        args::itemref::ItemRef,
         context::Context,
         db::{
             cache,
@@ -71,6 +76,14 @@ mod tests {
         tests::{get_test_conn, insert_task},
     };
 
+    fn claim_cmd(index: usize) -> ClaimCommand {
+        ClaimCommand {
+            indexes: vec![ItemRef::Index(index).into()],
+            filter: None,
+            yes: false,
+        }
+    }
+
     #[test]
     fn test_handle_claimcmd() {
         let (conn, _temp_file) = get_test_conn();
@@ -85,7 +98,7 @@ mod tests {
         cache::store(&conn, &items).unwrap();
 
         // Claim the task
-        handle_claimcmd(&conn, &ctx, 1).unwrap();
+        handle_claimcmd(&conn, &ctx, &claim_cmd(1)).unwrap();
 
         // Verify task is now assigned
         let item = get_item(&conn, task_id).unwrap();
@@ -101,12 +114,12 @@ mod tests {
         // Manually assign to self
         let mut item = get_item(&conn, task_id).unwrap();
         item.assignee_id = Some(ctx.current_user_id);
-        update_item(&conn, &item).unwrap();
+        update_item(&conn, &item, None).unwrap();
 
         let items = query_items(&conn, &ItemQuery::new().with_action(TASK)).unwrap();
         cache::store(&conn, &items).unwrap();
 
-        let result = handle_claimcmd(&conn, &ctx, 1);
+        let result = handle_claimcmd(&conn, &ctx, &claim_cmd(1));
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("already assigned to this task"));
     }
@@ -121,12 +134,12 @@ mod tests {
         let other_user_id = create_user(&conn, "other", None, None).unwrap();
         let mut item = get_item(&conn, task_id).unwrap();
         item.assignee_id = Some(other_user_id);
-        update_item(&conn, &item).unwrap();
+        update_item(&conn, &item, None).unwrap();
 
         let items = query_items(&conn, &ItemQuery::new().with_action(TASK)).unwrap();
         cache::store(&conn, &items).unwrap();
 
-        let result = handle_claimcmd(&conn, &ctx, 1);
+        let result = handle_claimcmd(&conn, &ctx, &claim_cmd(1));
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("already assigned"));
     }
@@ -142,7 +155,7 @@ mod tests {
         let items = query_items(&conn, &ItemQuery::new().with_action(RECORD)).unwrap();
         cache::store(&conn, &items).unwrap();
 
-        let result = handle_claimcmd(&conn, &ctx, 1);
+        let result = handle_claimcmd(&conn, &ctx, &claim_cmd(1));
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("Cannot claim a record"));
     }
@@ -156,7 +169,7 @@ mod tests {
         let items = query_items(&conn, &ItemQuery::new().with_action(TASK)).unwrap();
         cache::store(&conn, &items).unwrap();
 
-        let result = handle_claimcmd(&conn, &ctx, 99);
+        let result = handle_claimcmd(&conn, &ctx, &claim_cmd(99));
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("does not exist"));
     }
@@ -0,0 +1,123 @@
+use terminal_size::{
+    terminal_size,
+    Width,
+};
+
+use crate::{
+    actions::display::{
+        table::{pad_string, wrap},
+        DisplayRow,
+    },
+    db::item::Item,
+};
+
+// Below this width columns are stacked instead of side by side
+const MIN_COLUMN_WIDTH: usize = 24;
+const COLUMN_GAP: &str = " │ ";
+
+/// A column of the board, `count` includes the tasks beyond the cards shown.
+pub struct BoardColumn {
+    pub title: String,
+    pub count: usize,
+    pub cards: Vec<Item>,
+}
+
+// A card line, dimmed lines carry the details under the content
+type Line = (String, bool);
+
+/// Prints the columns side by side, or one after the other on a narrow terminal.
+/// Cards are numbered across the columns in order, like the indexes of a listing.
+pub fn print_board(columns: &[BoardColumn]) {
+    let terminal_width = if let Some((Width(w), _)) = terminal_size() {
+        w as usize
+    } else {
+        120 // Default if unable to detect
+    };
+
+    let gaps = COLUMN_GAP.chars().count() * columns.len().saturating_sub(1);
+    let column_width = terminal_width.saturating_sub(gaps) / columns.len().max(1);
+    let stacked = column_width < MIN_COLUMN_WIDTH;
+    let width = if stacked { terminal_width } else { column_width };
+
+    let mut index = 0;
+    let rendered: Vec<Vec<Line>> = columns
+        .iter()
+        .map(|column| {
+            let mut lines: Vec<Line> = Vec::new();
+            for card in &column.cards {
+                index += 1;
+                lines.extend(card_lines(index, card, width));
+                lines.push((String::new(), false));
+            }
+            let hidden = column.count - column.cards.len();
+            if hidden > 0 {
+                lines.push((format!("+{} more", hidden), true));
+            }
+            lines
+        })
+        .collect();
+
+    if stacked {
+        for (column, lines) in columns.iter().zip(&rendered) {
+            println!("\x1b[1m{}\x1b[0m", header(column));
+            println!("{}", "─".repeat(width));
+            for (text, dim) in lines {
+                println!("{}", styled(text, *dim));
+            }
+            if lines.last().is_none_or(|(text, _)| !text.is_empty()) {
+                println!();
+            }
+        }
+        return;
+    }
+
+    let headers: Vec<String> = columns
+        .iter()
+        .map(|column| format!("\x1b[1m{}\x1b[0m", pad_string(&header(column), width)))
+        .collect();
+    println!("{}", headers.join(COLUMN_GAP));
+    println!("{}", vec!["─".repeat(width); columns.len()].join(COLUMN_GAP));
+
+    let height = rendered.iter().map(Vec::len).max().unwrap_or(0);
+    for row in 0..height {
+        let cells: Vec<String> = rendered
+            .iter()
+            .map(|lines| match lines.get(row) {
+                // Padding first, escape codes would count towards the width
+                Some((text, dim)) => styled(&pad_string(text, width), *dim),
+                None => " ".repeat(width),
+            })
+            .collect();
+        println!("{}", cells.join(COLUMN_GAP).trim_end());
+    }
+}
+
+// Title with the number of tasks in the column, e.g. "ONGOING (3)"
+fn header(column: &BoardColumn) -> String {
+    format!("{} ({})", column.title.to_uppercase(), column.count)
+}
+
+fn card_lines(index: usize, item: &Item, width: usize) -> Vec<Line> {
+    // The column already tells the status
+    let mut shown = item.clone();
+    shown.status = 0;
+    let row = DisplayRow::from_task(format!("{} #{}", index, item.id.unwrap_or_default()), &shown);
+    let mut lines: Vec<Line> = wrap(&format!("{} {}", row.index, row.content), width)
+        .into_iter()
+        .map(|line| (line, false))
+        .collect();
+    lines.extend(
+        wrap(&format!("{} · {}", row.category, row.timestr), width)
+            .into_iter()
+            .map(|line| (line, true)),
+    );
+    lines
+}
+
+fn styled(text: &str, dim: bool) -> String {
+    if dim {
+        format!("\x1b[90m{}\x1b[0m", text)
+    } else {
+        text.to_string()
+    }
+}
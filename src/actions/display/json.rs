@@ -0,0 +1,230 @@
+use rusqlite::Connection;
+use serde_json::{json, Value};
+
+use crate::{
+    actions::{
+        display::{print_bold, print_items},
+        show::format_status,
+        timetrack::spent_minutes,
+    },
+    args::{format::OutputFormat, priority::format_priority, timezone},
+    context::Context,
+    db::{
+        crud::mark_subtask_progress,
+        dependency::mark_blocked,
+        item::{Item, RECORD_SKIPPED, RECORD_SNOOZED, RECURRING_TASK_RECORD},
+        link::get_links_for_item,
+        namespace::get_namespace_by_id,
+        note::get_notes_for_item,
+        tag::mark_tags,
+        user::get_user_by_id,
+    },
+};
+
+/// Version of the item schema below, bumped on breaking changes only.
+/// New keys may be added without a bump.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Prints items as a table under `header`, or as JSON when --json / --format is set.
+/// `is_list` marks a listing, whose indexes can be used by later commands.
+pub fn output_items(
+    conn: &Connection,
+    ctx: &Context,
+    header: &str,
+    items: &[Item],
+    is_record: bool,
+    is_list: bool,
+) -> Result<(), String> {
+    if ctx.output.is_table() {
+        print_bold(header);
+        print_items(items, is_record, is_list);
+        return Ok(());
+    }
+    print_items_json(conn, items, is_list, ctx.output)
+}
+
+fn print_items_json(
+    conn: &Connection,
+    items: &[Item],
+    is_list: bool,
+    format: OutputFormat,
+) -> Result<(), String> {
+    // Runtime fields are only filled in by list, compute them for every command
+    let mut items = items.to_vec();
+    mark_blocked(conn, &mut items)?;
+    mark_subtask_progress(conn, &mut items).map_err(|e| e.to_string())?;
+    mark_tags(conn, &mut items)?;
+
+    let values = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| item_to_json(conn, item, is_list.then_some(i + 1)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if format == OutputFormat::Ndjson {
+        for value in &values {
+            println!("{}", value);
+        }
+    } else {
+        let output = json!({
+            "schema_version": JSON_SCHEMA_VERSION,
+            "items": values,
+        });
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    }
+    Ok(())
+}
+
+/// Builds the JSON representation of an item, see "JSON Output" in the README.
+pub fn item_to_json(conn: &Connection, item: &Item, index: Option<usize>) -> Result<Value, String> {
+    let links = match item.id {
+        Some(id) => get_links_for_item(conn, id)?,
+        None => Vec::new(),
+    };
+    let notes = match item.id {
+        Some(id) => get_notes_for_item(conn, id)?,
+        None => Vec::new(),
+    };
+    let spent = match item.id {
+        Some(id) => spent_minutes(conn, id)?.0,
+        None => 0,
+    };
+    let namespace = match item.namespace_id {
+        Some(id) => get_namespace_by_id(conn, id)?.map(|ns| ns.name),
+        None => None,
+    };
+
+    Ok(json!({
+        "id": item.id,
+        "index": index,
+        "type": item.action,
+        "category": item.category,
+        "tags": item.tags,
+        "content": item.content,
+        "status": status_name(item),
+        // Unset priority is normal, the way the filter treats it
+        "priority": format_priority(item.priority.or(Some(1))).to_lowercase(),
+        "due": item.target_time.map(to_rfc3339),
+        "schedule": item.human_schedule,
+        "created": to_rfc3339(item.create_time),
+        "modified": item.modify_time.map(to_rfc3339),
+        "estimate_minutes": item.estimate_minutes,
+        "spent_minutes": spent,
+        "reminder_days": item.reminder_days,
+        "project": item.project,
+        "owner": user_name(conn, item.owner_id),
+        "assignee": user_name(conn, item.assignee_id),
+        "namespace": namespace,
+        "parent_id": item.parent_id,
+        "recurring_task_id": item.recurring_task_id,
+        "recur_until": item.recur_until.and_then(to_rfc3339),
+        "recur_count": item.recur_count,
+        "blocked": item.blocked,
+        "subtasks": item.subtask_progress.map(|(closed, total)| json!({
+            "closed": closed,
+            "total": total,
+        })),
+        "github_issue": item.github_issue,
+        "links": links.iter().map(|link| json!({
+            "type": link.link_type,
+            "reference": link.reference,
+            "title": link.title,
+        })).collect::<Vec<_>>(),
+        "notes": notes.iter().map(|note| json!({
+            "content": note.content,
+            "created": to_rfc3339(note.created_at),
+            "author": user_name(conn, note.created_by),
+        })).collect::<Vec<_>>(),
+    }))
+}
+
+// Recurring task records tell how the iteration was handled instead
+fn status_name(item: &Item) -> &'static str {
+    if item.action != RECURRING_TASK_RECORD {
+        return format_status(item.status);
+    }
+    match item.status {
+        RECORD_SKIPPED => "skipped",
+        RECORD_SNOOZED => "snoozed",
+        _ => "completed",
+    }
+}
+
+fn user_name(conn: &Connection, user_id: Option<i64>) -> Option<String> {
+    user_id
+        .and_then(|id| get_user_by_id(conn, id).ok().flatten())
+        .map(|user| user.name)
+}
+
+fn to_rfc3339(timestamp: i64) -> Option<String> {
+    timezone::datetime(timestamp).map(|dt| dt.to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        args::{parser::parse_status, priority::parse_priority},
+        db::{crud::get_item, link::add_link, note::add_note},
+        tests::{get_test_conn, insert_subtask, insert_task},
+    };
+
+    #[test]
+    fn test_item_to_json() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let id = insert_task(&conn, "work", "Ship 1.0", "2030-01-15");
+        insert_subtask(&conn, id, "changelog");
+        add_note(&conn, id, "waiting on QA", Some(ctx.current_user_id)).unwrap();
+        add_link(&conn, id, "pr", "owner/repo#7", Some("Release PR"), None).unwrap();
+
+        let value = item_to_json(&conn, &get_item(&conn, id).unwrap(), Some(2)).unwrap();
+        assert_eq!(value["id"], id);
+        assert_eq!(value["index"], 2);
+        assert_eq!(value["type"], "task");
+        assert_eq!(value["status"], "ongoing");
+        assert_eq!(value["priority"], "normal");
+        assert!(value["due"].as_str().unwrap().starts_with("2030-01-15T23:59:59"));
+        assert_eq!(value["namespace"], "default");
+        assert_eq!(value["notes"][0]["content"], "waiting on QA");
+        assert_eq!(value["notes"][0]["author"], ctx.current_user_name.as_str());
+        assert_eq!(value["links"][0]["type"], "pr");
+        assert_eq!(value["links"][0]["title"], "Release PR");
+        // Keys are always present, unset ones are null
+        assert!(value["estimate_minutes"].is_null());
+        assert!(value["assignee"].is_null());
+        assert!(value["subtasks"].is_null());
+    }
+
+    #[test]
+    fn test_runtime_fields() {
+        let (conn, _temp_file) = get_test_conn();
+        let id = insert_task(&conn, "work", "Ship 1.0", "today");
+        insert_subtask(&conn, id, "changelog");
+
+        // item_to_json reports what it is given, print_items_json computes it
+        let mut items = vec![get_item(&conn, id).unwrap()];
+        mark_subtask_progress(&conn, &mut items).unwrap();
+        let value = item_to_json(&conn, &items[0], None).unwrap();
+        assert!(value["index"].is_null());
+        assert_eq!(value["subtasks"], json!({"closed": 0, "total": 1}));
+        assert_eq!(value["blocked"], false);
+    }
+
+    #[test]
+    fn test_names_match_the_filter() {
+        let (conn, _temp_file) = get_test_conn();
+        let id = insert_task(&conn, "work", "Ship 1.0", "today");
+        let mut item = get_item(&conn, id).unwrap();
+        for status in 0..=6 {
+            item.status = status;
+            let value = item_to_json(&conn, &item, None).unwrap();
+            assert_eq!(parse_status(value["status"].as_str().unwrap()).unwrap(), status);
+        }
+        for priority in 0..=2 {
+            item.priority = Some(priority);
+            let value = item_to_json(&conn, &item, None).unwrap();
+            assert_eq!(parse_priority(value["priority"].as_str().unwrap()).unwrap(), priority);
+        }
+    }
+}
@@ -1,8 +1,12 @@
+mod board;
+mod json;
 mod print;
 mod row;
 mod table;
 
 pub use crate::actions::display::{
+    board::{print_board, BoardColumn},
+    json::{item_to_json, output_items, JSON_SCHEMA_VERSION},
     print::{
         print_bold,
         print_items,
@@ -27,10 +27,12 @@ pub fn print_red(text: &str) {
 pub fn print_items(items: &[Item], is_record: bool, is_list: bool) {
     let mut results: Vec<DisplayRow> = Vec::with_capacity(items.len());
     for (index, item) in items.iter().enumerate() {
-        let indexstr = if is_list {
-            format!("{}", index + 1)
-        } else {
-            "N/A".to_string()
+        // The stable id goes under the list index so it can be used as #id
+        let indexstr = match (is_list, item.id) {
+            (true, Some(id)) => format!("{}\n#{}", index + 1, id),
+            (true, None) => format!("{}", index + 1),
+            (false, Some(id)) => format!("#{}", id),
+            (false, None) => "N/A".to_string(),
         };
         if is_record {
             results.push(DisplayRow::from_record(indexstr, item));
@@ -1,12 +1,10 @@
 use chrono::{
     Datelike,
-    Local,
-    TimeZone,
     Timelike,
     Weekday,
 };
 
-use crate::db::item::Item;
+use crate::{actions::show::format_status, args::timezone, db::item::Item};
 
 pub struct DisplayRow {
     pub index: String,
@@ -18,7 +16,13 @@ pub struct DisplayRow {
 impl DisplayRow {
     pub fn from_task(index: String, task: &Item) -> Self {
         let mut category = task.category.clone();
-        let content = task.content.clone();
+        let mut content = task.content.clone();
+        if let Some((closed, total)) = task.subtask_progress {
+            content.push_str(&format!(" [{}/{}]", closed, total));
+        }
+        for tag in &task.tags {
+            content.push_str(&format!(" +{}", tag));
+        }
 
         let mut timestr = if task.action == "recurring_task" {
             category.push_str(" (Recurring)");
@@ -30,10 +34,12 @@ impl DisplayRow {
         };
 
         if task.status != 0 {
-            let status_str = translate_status(task.status);
+            let status_str = format_status(task.status);
             timestr.push_str(&format!(" ({})", status_str));
         } else if task.recurring_interval_complete {
             timestr.push_str(" (fulfilled)");
+        } else if task.blocked {
+            timestr.push_str(" (blocked)");
         }
 
         DisplayRow {
@@ -62,12 +68,12 @@ impl DisplayRow {
 }
 
 fn timestamp_to_display_string(timestamp: i64, is_record: bool) -> String {
-    let dt = match Local.timestamp_opt(timestamp, 0) {
-        chrono::LocalResult::Single(dt) => dt,
-        _ => return "Invalid timestamp".to_string(),
+    let dt = match timezone::datetime(timestamp) {
+        Some(dt) => dt,
+        None => return "Invalid timestamp".to_string(),
     };
 
-    let now = Local::now();
+    let now = timezone::now();
     let is_end_of_day = dt.hour() == 23 && dt.minute() == 59 && dt.second() == 59;
 
     // If timestamp is today, use "Today" string.
@@ -158,16 +164,3 @@ fn format_hour(hour: u32, minute: u32) -> String {
     let period = if hour < 12 { "AM" } else { "PM" };
     format!("{}:{:02}{}", hour12, minute, period)
 }
-
-fn translate_status(status: u8) -> String {
-    match status {
-        0 => "ongoing".to_string(),
-        1 => "completed".to_string(),
-        2 => "cancelled".to_string(),
-        3 => "duplicate".to_string(),
-        4 => "suspended".to_string(),
-        5 => "removed".to_string(),
-        6 => "pending".to_string(),
-        _ => "unknown".to_string(),
-    }
-}
@@ -96,7 +96,7 @@ pub fn print_table(rows: &[DisplayRow], is_record: bool) {
     }
 }
 
-fn pad_string(s: &str, width: usize) -> String {
+pub(super) fn pad_string(s: &str, width: usize) -> String {
     let term_width = UnicodeWidthStr::width(s);
     if term_width >= width {
         s.to_string()
@@ -108,7 +108,7 @@ fn pad_string(s: &str, width: usize) -> String {
 }
 
 // Wraps text with consideration for unicode characters and word continuity.
-fn wrap(text: &str, max_length: usize) -> Vec<String> {
+pub(super) fn wrap(text: &str, max_length: usize) -> Vec<String> {
     if max_length == 0 || text.is_empty() {
         return vec![];
     }
@@ -3,15 +3,26 @@ use rusqlite::Connection;
 use crate::{
     actions::{
         addition,
+        archive,
+        block,
+        board,
         claim,
+        history,
         link,
         list,
         modify,
         namespace,
         note,
+        recurrence,
         reporting,
+        search,
         show,
+        template,
+        timetrack,
+        trash,
+        undo,
         user,
+        view,
     },
     args::parser::{
         Action,
@@ -24,23 +35,48 @@ use crate::{
 pub fn handle_commands(conn: &Connection, ctx: &Context, args: CliArgs) -> Result<(), String> {
     match args.arguments {
         Action::Task(cmd) => addition::handle_taskcmd(conn, ctx, &cmd),
-        Action::Record(cmd) => addition::handle_recordcmd(conn, &cmd),
-        Action::Done(cmd) => modify::handle_donecmd(conn, &cmd),
-        Action::Delete(cmd) => modify::handle_deletecmd(conn, &cmd),
-        Action::Update(cmd) => modify::handle_updatecmd(conn, &cmd),
+        Action::Record(cmd) => addition::handle_recordcmd(conn, ctx, &cmd),
+        Action::Done(cmd) => modify::handle_donecmd(conn, ctx, &cmd),
+        Action::Skip(cmd) => recurrence::handle_skipcmd(conn, ctx, &cmd),
+        Action::Snooze(cmd) => recurrence::handle_snoozecmd(conn, ctx, &cmd),
+        Action::Delete(cmd) => modify::handle_deletecmd(conn, ctx, &cmd),
+        Action::Update(cmd) => modify::handle_updatecmd(conn, ctx, &cmd),
         Action::List(list_cmd) => match list_cmd {
-            ListCommand::Task(cmd) => list::handle_listtasks(conn, cmd),
-            ListCommand::Record(cmd) => list::handle_listrecords(conn, cmd),
-            ListCommand::Show(cmd) => list::handle_showcontent(conn, cmd),
+            ListCommand::Task(cmd) => list::handle_listtasks(conn, ctx, cmd),
+            ListCommand::Record(cmd) => list::handle_listrecords(conn, ctx, cmd),
+            ListCommand::Show(cmd) => list::handle_showcontent(conn, ctx, cmd),
         },
         Action::Note(cmd) => note::handle_notecmd(conn, ctx, &cmd),
-        Action::Show(cmd) => show::handle_showcmd(conn, cmd.index),
-        Action::Claim(cmd) => claim::handle_claimcmd(conn, ctx, cmd.index),
+        Action::Show(cmd) => show::handle_showcmd(conn, ctx, cmd.index),
+        Action::Claim(cmd) => claim::handle_claimcmd(conn, ctx, &cmd),
         Action::Link(cmd) => link::handle_linkcmd(conn, ctx, &cmd),
+        Action::Block(cmd) => block::handle_blockcmd(conn, ctx, &cmd),
+        Action::Unblock(cmd) => block::handle_unblockcmd(conn, ctx, &cmd),
         Action::User(cmd) => user::handle_user_cmd(conn, ctx, cmd),
         Action::Ns(cmd) => namespace::handle_namespace_cmd(conn, ctx, cmd),
-        Action::Team(cmd) => reporting::handle_team(conn, ctx, &cmd),
-        Action::Workload(cmd) => reporting::handle_workload(conn, ctx, &cmd),
-        Action::Stats(cmd) => reporting::handle_stats(conn, ctx, &cmd),
+        Action::Team(mut cmd) => {
+            cmd.json = !ctx.output.is_table();
+            reporting::handle_team(conn, ctx, &cmd)
+        }
+        Action::Workload(mut cmd) => {
+            cmd.json = !ctx.output.is_table();
+            reporting::handle_workload(conn, ctx, &cmd)
+        }
+        Action::Stats(mut cmd) => {
+            cmd.json = !ctx.output.is_table();
+            reporting::handle_stats(conn, ctx, &cmd)
+        }
+        Action::History(cmd) => history::handle_historycmd(conn, ctx, cmd.index),
+        Action::Log(cmd) => history::handle_logcmd(conn, ctx, &cmd),
+        Action::Search(cmd) => search::handle_searchcmd(conn, ctx, &cmd),
+        Action::Start(cmd) => timetrack::handle_startcmd(conn, ctx, &cmd),
+        Action::Stop => timetrack::handle_stopcmd(conn, ctx),
+        Action::LogTime(cmd) => timetrack::handle_logtimecmd(conn, ctx, &cmd),
+        Action::View(cmd) => view::handle_viewcmd(conn, ctx, cmd),
+        Action::Undo(cmd) => undo::handle_undocmd(conn, ctx, &cmd),
+        Action::Trash(cmd) => trash::handle_trashcmd(conn, ctx, &cmd),
+        Action::Archive(cmd) => archive::handle_archivecmd(conn, ctx, &cmd),
+        Action::Template(cmd) => template::handle_templatecmd(conn, ctx, &cmd),
+        Action::Board(cmd) => board::handle_boardcmd(conn, ctx, &cmd),
     }
 }
@@ -0,0 +1,210 @@
+use rusqlite::Connection;
+
+use crate::{
+    actions::show::format_status,
+    args::{itemref::{resolve_any_rowid, ItemRef}, parser::LogCommand, priority::format_priority, timestr, timezone},
+    context::Context,
+    db::{
+        audit::{get_history_for_item, get_log_since, AuditEntry},
+        crud::get_item,
+        user::get_user_by_id,
+    },
+};
+
+const MAX_VALUE_WIDTH: usize = 40;
+
+/// Handles the history command - displays the audit trail of a single item
+pub fn handle_historycmd(conn: &Connection, ctx: &Context, item_ref: ItemRef) -> Result<(), String> {
+    let row_id = resolve_any_rowid(conn, ctx, item_ref)?;
+
+    // The item may have been deleted since it was listed, its history survives it.
+    match get_item(conn, row_id) {
+        Ok(item) => {
+            ctx.check_namespace(conn, &item)?;
+            println!(
+                "\x1b[1mHistory of #{}: {}\x1b[0m",
+                row_id,
+                item.content.lines().next().unwrap_or(&item.content)
+            )
+        }
+        Err(_) => println!("\x1b[1mHistory of #{} (deleted)\x1b[0m", row_id),
+    }
+    println!("{}", "━".repeat(50));
+
+    let entries = get_history_for_item(conn, row_id)?;
+    if entries.is_empty() {
+        println!("  No recorded changes");
+        return Ok(());
+    }
+
+    for entry in &entries {
+        println!("  {}", format_entry(conn, entry, false));
+    }
+    Ok(())
+}
+
+/// Handles the log command - displays recent changes across the current namespace
+pub fn handle_logcmd(conn: &Connection, ctx: &Context, cmd: &LogCommand) -> Result<(), String> {
+    let since = match &cmd.since {
+        Some(s) => timestr::to_unix_epoch(s)?,
+        None => timestr::days_before_to_unix_epoch(7),
+    };
+
+    let entries = get_log_since(conn, ctx.current_namespace_id, since, cmd.limit)?;
+    if entries.is_empty() {
+        println!("No changes in namespace '{}'", ctx.current_namespace_name);
+        return Ok(());
+    }
+
+    println!(
+        "\x1b[1mChanges in '{}' since {}\x1b[0m",
+        ctx.current_namespace_name,
+        format_timestamp(since)
+    );
+    println!("{}", "━".repeat(50));
+    for entry in &entries {
+        println!("  {}", format_entry(conn, entry, true));
+    }
+    Ok(())
+}
+
+fn format_entry(conn: &Connection, entry: &AuditEntry, with_item: bool) -> String {
+    let author = entry
+        .created_by
+        .and_then(|id| get_user_by_id(conn, id).ok().flatten())
+        .map(|user| user.name)
+        .unwrap_or_else(|| "-".to_string());
+
+    let item = match (with_item, entry.item_id) {
+        (true, Some(id)) => format!(" #{}", id),
+        _ => String::new(),
+    };
+
+    let action = match entry.action.as_str() {
+        "create" => "\x1b[32mcreated\x1b[0m",
+        "update" => "\x1b[33mupdated\x1b[0m",
+        "delete" => "\x1b[91mdeleted\x1b[0m",
+        "complete" => "\x1b[36mcompleted\x1b[0m",
+        other => other,
+    };
+
+    let change = match &entry.field_name {
+        Some(field) => format!(
+            " {}: {} → {}",
+            field,
+            format_value(conn, field, entry.old_value.as_deref()),
+            format_value(conn, field, entry.new_value.as_deref())
+        ),
+        None => String::new(),
+    };
+
+    format!(
+        "\x1b[90m{} {}\x1b[0m{} {}{}",
+        format_timestamp(entry.created_at),
+        author,
+        item,
+        action,
+        change
+    )
+}
+
+/// Renders a raw audit value in a human readable form based on the field it belongs to.
+fn format_value(conn: &Connection, field: &str, value: Option<&str>) -> String {
+    let value = match value {
+        Some(v) => v,
+        None => return "-".to_string(),
+    };
+
+    match field {
+        "target_time" | "good_until" | "deleted_at" => value
+            .parse::<i64>()
+            .map(format_timestamp)
+            .unwrap_or_else(|_| value.to_string()),
+        "status" => value
+            .parse::<u8>()
+            .map(|s| format_status(s).to_string())
+            .unwrap_or_else(|_| value.to_string()),
+        "priority" => value
+            .parse::<u8>()
+            .map(|p| format_priority(Some(p)).to_string())
+            .unwrap_or_else(|_| value.to_string()),
+        "owner_id" | "assignee_id" => value
+            .parse::<i64>()
+            .ok()
+            .and_then(|id| get_user_by_id(conn, id).ok().flatten())
+            .map(|user| user.name)
+            .unwrap_or_else(|| value.to_string()),
+        _ => truncate(value.lines().next().unwrap_or(value)),
+    }
+}
+
+fn truncate(value: &str) -> String {
+    if value.chars().count() <= MAX_VALUE_WIDTH {
+        return format!("\"{}\"", value);
+    }
+    let truncated: String = value.chars().take(MAX_VALUE_WIDTH - 1).collect();
+    format!("\"{}…\"", truncated)
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    match timezone::datetime(timestamp) {
+        Some(dt) => dt.format("%Y-%m-%d %H:%M").to_string(),
+        None => "unknown".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        args::itemref::ItemRef,
+        db::{
+            cache,
+            crud::{delete_item, query_items},
+            item::{ItemQuery, TASK},
+        },
+        tests::{get_test_conn, insert_task},
+    };
+
+    #[test]
+    fn test_handle_historycmd() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let task_id = insert_task(&conn, "work", "Test task", "today");
+        let items = query_items(&conn, &ItemQuery::new().with_action(TASK)).unwrap();
+        cache::store(&conn, &items).unwrap();
+
+        assert!(handle_historycmd(&conn, &ctx, ItemRef::Index(1)).is_ok());
+
+        // Listing a deleted item does not fail
+        delete_item(&conn, task_id, None).unwrap();
+        assert!(handle_historycmd(&conn, &ctx, ItemRef::Index(1)).is_ok());
+
+        let result = handle_historycmd(&conn, &ctx, ItemRef::Index(99));
+        assert!(result.unwrap_err().contains("does not exist"));
+    }
+
+    #[test]
+    fn test_handle_logcmd() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        insert_task(&conn, "work", "Test task", "today");
+
+        let cmd = LogCommand {
+            since: Some("yesterday".to_string()),
+            limit: 100,
+        };
+        assert!(handle_logcmd(&conn, &ctx, &cmd).is_ok());
+    }
+
+    #[test]
+    fn test_format_value() {
+        let (conn, _temp_file) = get_test_conn();
+        assert_eq!(format_value(&conn, "status", Some("1")), "done");
+        assert_eq!(format_value(&conn, "priority", Some("0")), "HIGH");
+        assert_eq!(format_value(&conn, "category", None), "-");
+        assert_eq!(format_value(&conn, "content", Some("a\nb")), "\"a\"");
+        let long = "x".repeat(60);
+        assert!(format_value(&conn, "content", Some(&long)).ends_with("…\""));
+    }
+}
@@ -2,10 +2,9 @@ use rusqlite::Connection;
 
 use crate::{
     actions::display,
-    args::parser::LinkCommand,
+    args::{itemref::resolve_rowid, parser::LinkCommand},
     context::Context,
     db::{
-        cache,
         crud::get_item,
         item::{RECORD, RECURRING_TASK_RECORD},
         link::{add_link, link_exists},
@@ -14,10 +13,10 @@ use crate::{
 
 /// Handles the link command - attaches a commit, issue, PR, or URL to a task
 pub fn handle_linkcmd(conn: &Connection, ctx: &Context, cmd: &LinkCommand) -> Result<(), String> {
-    validate_cache(conn)?;
-    let row_id = get_rowid_from_cache(conn, cmd.index)?;
+    let row_id = resolve_rowid(conn, ctx, cmd.index)?;
 
     let item = get_item(conn, row_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+    ctx.authorize_item(conn, &item)?;
 
     if item.action == RECORD || item.action == RECURRING_TASK_RECORD {
         return Err("Cannot add links to records".to_string());
@@ -50,36 +49,23 @@ pub fn handle_linkcmd(conn: &Connection, ctx: &Context, cmd: &LinkCommand) -> Re
         Some(ctx.current_user_id),
     )?;
 
-    display::print_bold(&format!("Added {} link #{} to task:", link_type, link_id));
-    display::print_items(&[item], false, false);
-    match &cmd.title {
-        Some(title) => println!("  Link: [{}] {} - {}", link_type, reference, title),
-        None => println!("  Link: [{}] {}", link_type, reference),
+    let header = format!("Added {} link #{} to task:", link_type, link_id);
+    display::output_items(conn, ctx, &header, &[item], false, false)?;
+    if ctx.output.is_table() {
+        match &cmd.title {
+            Some(title) => println!("  Link: [{}] {} - {}", link_type, reference, title),
+            None => println!("  Link: [{}] {}", link_type, reference),
+        }
     }
 
     Ok(())
 }
 
-fn validate_cache(conn: &Connection) -> Result<(), String> {
-    match cache::validate_cache(conn) {
-        Ok(true) => Ok(()),
-        Ok(false) => Err("Cache is not valid, consider running list command first".to_string()),
-        Err(_) => Err("Cannot connect to cache".to_string()),
-    }
-}
-
-fn get_rowid_from_cache(conn: &Connection, index: usize) -> Result<i64, String> {
-    let index = index as i64;
-    match cache::read(conn, index).map_err(|e| format!("Failed to read cache table: {:?}", e))? {
-        Some(id) => Ok(id),
-        None => Err(format!("index {} does not exist", index)),
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
+        args::itemref::ItemRef,
         context::Context,
         db::{
             cache,
@@ -92,7 +78,7 @@ mod tests {
 
     fn make_link_cmd(index: usize) -> LinkCommand {
         LinkCommand {
-            index,
+            index: ItemRef::Index(index),
             commit: None,
             issue: None,
             pr: None,
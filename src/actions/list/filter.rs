@@ -0,0 +1,287 @@
+use chrono::{Duration, Local, NaiveTime, Timelike};
+use rusqlite::{types::Value, Connection};
+
+use crate::{
+    actions::list::{CLOSED_STATUS_CODES, OPEN_STATUS_CODES},
+    args::{
+        filter::{Field, Filter, Op, Term},
+        parser::parse_status,
+        priority::parse_priority,
+        timestr,
+        timezone,
+    },
+    context::Context,
+    db::{
+        dependency::ready_condition,
+        item::Condition,
+        tag::tag_condition,
+        user::get_user_by_name,
+    },
+};
+
+/// Compiles a filter expression into a parameterised SQL condition on the `items` table.
+/// Every term evaluates to true or false, never NULL, so `not` also matches unset fields.
+pub(crate) fn compile_filter(conn: &Connection, ctx: &Context, filter: &Filter) -> Result<Condition, String> {
+    let mut params = Vec::new();
+    let sql = compile(conn, ctx, filter, &mut params)?;
+    Ok(Condition { sql, params })
+}
+
+fn compile(conn: &Connection, ctx: &Context, filter: &Filter, params: &mut Vec<Value>) -> Result<String, String> {
+    Ok(match filter {
+        Filter::And(left, right) => format!(
+            "({} AND {})",
+            compile(conn, ctx, left, params)?,
+            compile(conn, ctx, right, params)?
+        ),
+        Filter::Or(left, right) => format!(
+            "({} OR {})",
+            compile(conn, ctx, left, params)?,
+            compile(conn, ctx, right, params)?
+        ),
+        Filter::Not(inner) => format!("NOT {}", compile(conn, ctx, inner, params)?),
+        Filter::Term(term) => compile_term(conn, ctx, term, params)?,
+    })
+}
+
+fn compile_term(conn: &Connection, ctx: &Context, term: &Term, params: &mut Vec<Value>) -> Result<String, String> {
+    let negate = term.op == Op::Ne;
+    let is = if negate { "IS NOT" } else { "IS" };
+    let none = term.value.eq_ignore_ascii_case("none");
+
+    let sql = match term.field {
+        Field::Priority => {
+            // Unset priority is normal
+            params.push(Value::from(parse_priority(&term.value)?));
+            format!("IFNULL(priority, 1) {} ?", if negate { "!=" } else { "=" })
+        }
+        Field::Status => {
+            let statuses = match parse_status(&term.value)? {
+                254 => OPEN_STATUS_CODES.to_vec(),
+                253 => CLOSED_STATUS_CODES.to_vec(),
+                255 => return Ok(if negate { "0" } else { "1" }.to_string()),
+                status => vec![status],
+            };
+            let placeholders = vec!["?"; statuses.len()].join(", ");
+            params.extend(statuses.into_iter().map(Value::from));
+            format!("status {}IN ({})", if negate { "NOT " } else { "" }, placeholders)
+        }
+        Field::Category => {
+            params.push(Value::from(term.value.clone()));
+            format!("category {} ?", is)
+        }
+        Field::Content => {
+            params.push(Value::from(format!("%{}%", term.value)));
+            format!("content {}LIKE ?", if negate { "NOT " } else { "" })
+        }
+        Field::Project => {
+            params.push(if none { Value::Null } else { Value::from(term.value.clone()) });
+            format!("project {} ?", is)
+        }
+        Field::Assignee | Field::Owner => {
+            params.push(match resolve_user(conn, ctx, &term.value)? {
+                Some(id) => Value::from(id),
+                None => Value::Null,
+            });
+            let column = if term.field == Field::Owner { "owner_id" } else { "assignee_id" };
+            format!("{} {} ?", column, is)
+        }
+        Field::Tag => {
+            params.push(Value::from(term.value.clone()));
+            tag_condition(negate)
+        }
+        Field::Due | Field::Created => {
+            let column = if term.field == Field::Due { "target_time" } else { "create_time" };
+            if none {
+                format!("{} {} NULL", column, is)
+            } else {
+                compare_time(column, term.op, &term.value, params)?
+            }
+        }
+        Field::Estimate => {
+            if none {
+                format!("estimate_minutes {} NULL", is)
+            } else {
+                let minutes: i64 = term.value.parse().map_err(|_| format!("Invalid estimate '{}'", term.value))?;
+                params.push(Value::from(minutes));
+                compare("estimate_minutes", term.op, "?")
+            }
+        }
+        Field::Unassigned => "assignee_id IS NULL".to_string(),
+        Field::Overdue => {
+            params.push(Value::from(Local::now().timestamp()));
+            params.extend(OPEN_STATUS_CODES.iter().map(|&s| Value::from(s)));
+            let placeholders = vec!["?"; OPEN_STATUS_CODES.len()].join(", ");
+            format!("(target_time IS NOT NULL AND target_time < ? AND status IN ({}))", placeholders)
+        }
+        Field::Blocked => format!("NOT {}", ready_condition()),
+        Field::Ready => ready_condition(),
+    };
+    Ok(sql)
+}
+
+// `column op value` that is false rather than NULL on unset columns,
+// `!=` matches them.
+fn compare(column: &str, op: Op, value: &str) -> String {
+    let symbol = match op {
+        Op::Eq | Op::Ne => "=",
+        Op::Lt => "<",
+        Op::Le => "<=",
+        Op::Gt => ">",
+        Op::Ge => ">=",
+    };
+    let positive = format!("({} IS NOT NULL AND {} {} {})", column, column, symbol, value);
+    if op == Op::Ne {
+        format!("NOT {}", positive)
+    } else {
+        positive
+    }
+}
+
+// A date without time ("friday") stands for the whole day: due<friday is before it starts,
+// due<=friday until it ends and due:friday anywhere on it. A time ("friday 3pm") is an instant.
+fn compare_time(column: &str, op: Op, value: &str, params: &mut Vec<Value>) -> Result<String, String> {
+    let instant = timestr::to_unix_epoch(value)?;
+    let Some((start, end)) = day_bounds(instant) else {
+        params.push(Value::from(instant));
+        return Ok(compare(column, op, "?"));
+    };
+
+    let sql = match op {
+        Op::Eq | Op::Ne => {
+            params.push(Value::from(start));
+            params.push(Value::from(end));
+            let positive = format!("({} IS NOT NULL AND {} >= ? AND {} < ?)", column, column, column);
+            if op == Op::Ne {
+                format!("NOT {}", positive)
+            } else {
+                positive
+            }
+        }
+        Op::Lt | Op::Ge => {
+            params.push(Value::from(start));
+            compare(column, op, "?")
+        }
+        Op::Le => {
+            params.push(Value::from(end));
+            compare(column, Op::Lt, "?")
+        }
+        Op::Gt => {
+            params.push(Value::from(end));
+            compare(column, Op::Ge, "?")
+        }
+    };
+    Ok(sql)
+}
+
+// Start of the day and start of the next day around an end of day timestamp,
+// None when the timestamp has a time of day.
+fn day_bounds(timestamp: i64) -> Option<(i64, i64)> {
+    let dt = timezone::datetime(timestamp)?;
+    if (dt.hour(), dt.minute(), dt.second()) != (23, 59, 59) {
+        return None;
+    }
+    let date = dt.date_naive();
+    let start = timezone::timestamp(&date.and_time(NaiveTime::MIN))?;
+    let end = timezone::timestamp(&(date + Duration::days(1)).and_time(NaiveTime::MIN))?;
+    Some((start, end))
+}
+
+// "me" is the current user and "none" nobody
+fn resolve_user(conn: &Connection, ctx: &Context, name: &str) -> Result<Option<i64>, String> {
+    if name.eq_ignore_ascii_case("me") {
+        return Ok(Some(ctx.current_user_id));
+    }
+    if name.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    let user = get_user_by_name(conn, name)?.ok_or_else(|| format!("User '{}' not found", name))?;
+    Ok(Some(user.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        args::filter::parse_filter,
+        db::{
+            crud::{get_item, query_items, update_item},
+            item::{ItemQuery, TASK},
+            tag::add_tags,
+            user::create_user,
+        },
+        tests::{get_test_conn, insert_task, update_status},
+    };
+
+    fn matching(conn: &Connection, ctx: &Context, expr: &str) -> Vec<String> {
+        let condition = compile_filter(conn, ctx, &parse_filter(expr).unwrap()).unwrap();
+        let query = ItemQuery::new().with_action(TASK).with_condition(&condition);
+        let mut contents: Vec<String> = query_items(conn, &query)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.content)
+            .collect();
+        contents.sort();
+        contents
+    }
+
+    #[test]
+    fn test_compile_filter() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let bob = create_user(&conn, "bob", None, None).unwrap();
+
+        let login = insert_task(&conn, "backend", "login", "today");
+        let mut item = get_item(&conn, login).unwrap();
+        item.priority = Some(0);
+        item.assignee_id = Some(ctx.current_user_id);
+        update_item(&conn, &item, None).unwrap();
+
+        let deploy = insert_task(&conn, "ops", "deploy", "today");
+        let mut item = get_item(&conn, deploy).unwrap();
+        item.priority = Some(0);
+        update_item(&conn, &item, None).unwrap();
+
+        let docs = insert_task(&conn, "backend", "docs", "in 10 days");
+        let mut item = get_item(&conn, docs).unwrap();
+        item.assignee_id = Some(bob);
+        item.estimate_minutes = Some(180);
+        update_item(&conn, &item, None).unwrap();
+        add_tags(&conn, docs, &["writing".to_string()]).unwrap();
+
+        let old = insert_task(&conn, "backend", "old", "today");
+        update_status(&conn, old, 1);
+
+        assert_eq!(
+            matching(&conn, &ctx, "priority:high and due<\"in 7 days\" and (assignee:me or unassigned) and not category:ops"),
+            vec!["login"]
+        );
+        assert_eq!(matching(&conn, &ctx, "priority:high"), vec!["deploy", "login"]);
+        // Unset priority is normal
+        assert_eq!(matching(&conn, &ctx, "priority:normal"), vec!["docs", "old"]);
+        assert_eq!(matching(&conn, &ctx, "status:open not priority:high"), vec!["docs"]);
+        assert_eq!(matching(&conn, &ctx, "status:closed"), vec!["old"]);
+        // not matches unset columns too
+        assert_eq!(matching(&conn, &ctx, "not assignee:bob"), vec!["deploy", "login", "old"]);
+        assert_eq!(matching(&conn, &ctx, "assignee!=none"), vec!["docs", "login"]);
+        assert_eq!(matching(&conn, &ctx, "estimate>=3h or +writing"), vec!["docs"]);
+        assert_eq!(matching(&conn, &ctx, "due:today"), vec!["deploy", "login", "old"]);
+        assert_eq!(matching(&conn, &ctx, "due>today"), vec!["docs"]);
+        assert_eq!(matching(&conn, &ctx, "content:ep status:all"), vec!["deploy"]);
+
+        let err = compile_filter(&conn, &ctx, &parse_filter("owner:nobody").unwrap()).unwrap_err();
+        assert_eq!(err, "User 'nobody' not found");
+    }
+
+    #[test]
+    fn test_compile_filter_binds_values() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        insert_task(&conn, "it's", "quoted", "today");
+
+        let condition = compile_filter(&conn, &ctx, &parse_filter("category:\"it's\" or status:open").unwrap()).unwrap();
+        assert_eq!(condition.sql, "(category IS ? OR status IN (?, ?, ?))");
+        assert_eq!(condition.params.len(), 4);
+        assert_eq!(matching(&conn, &ctx, "category:\"it's\""), vec!["quoted"]);
+    }
+}
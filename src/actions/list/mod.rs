@@ -1,12 +1,18 @@
+mod filter;
 mod records;
 mod tasks;
 
+pub(crate) use filter::compile_filter;
 pub use records::handle_listrecords;
 use rusqlite::Connection;
 pub use tasks::handle_listtasks;
 
 use crate::{
-    args::parser::ShowContentCommand,
+    args::{
+        itemref::resolve_rowid,
+        parser::ShowContentCommand,
+    },
+    context::Context,
     db::{
         cache,
         crud::get_item,
@@ -27,22 +33,13 @@ pub(crate) const OPEN_STATUS_CODES: &[u8] = &[0, 4, 6];
 pub(crate) const CLOSED_STATUS_CODES: &[u8] = &[1, 2, 3, 5];
 
 // Shared function for showing content
-pub fn handle_showcontent(conn: &Connection, cmd: ShowContentCommand) -> Result<(), String> {
-    if !cache::validate_cache(conn).map_err(|e| e.to_string())? {
-        return Err("No valid cache found. Please run a list command first.".to_string());
-    }
-
-    let item_id = match cache::read(conn, cmd.index as i64).map_err(|e| e.to_string())? {
-        Some(id) => id,
-        None => {
-            return Err(format!(
-                "Index {} not found in cache. Use a valid index from the previous list command.",
-                cmd.index
-            ))
-        }
-    };
-
-    let item = get_item(conn, item_id).map_err(|e| e.to_string())?;
+pub fn handle_showcontent(
+    conn: &Connection,
+    ctx: &Context,
+    cmd: ShowContentCommand,
+) -> Result<(), String> {
+    let item_id = resolve_rowid(conn, ctx, cmd.index)?;
+    let item = get_item(conn, item_id).map_err(|_| format!("Item #{} not found", item_id))?;
     println!("{}", item.content);
     Ok(())
 }
@@ -1,19 +1,23 @@
 use rusqlite::Connection;
 
 use super::{
+    filter::compile_filter,
     handle_next_page,
     CREATE_TIME_COL,
 };
 use crate::{
     actions::display,
     args::{
+        filter::parse_filter,
         parser::ListRecordCommand,
         timestr,
     },
+    context::Context,
     db::{
         cache,
         crud::query_items,
         item::{
+            Condition,
             Item,
             ItemQuery,
             Offset,
@@ -23,17 +27,29 @@ use crate::{
     },
 };
 
-pub fn handle_listrecords(conn: &Connection, cmd: ListRecordCommand) -> Result<(), String> {
-    let records = match query_records(conn, &cmd) {
+pub fn handle_listrecords(
+    conn: &Connection,
+    ctx: &Context,
+    cmd: ListRecordCommand,
+) -> Result<(), String> {
+    let condition = match &cmd.filter {
+        Some(expr) => Some(compile_filter(conn, ctx, &parse_filter(expr)?)?),
+        None => None,
+    };
+    let records = match query_records(conn, &cmd, Some(&ctx.namespace_filter(conn)?), condition.as_ref()) {
         Ok(records) => records,
-        Err(estr) => {
+        Err(estr) if ctx.output.is_table() => {
             display::print_bold(&estr);
             return Ok(());
         }
+        Err(estr) => return Err(estr),
     };
     if records.is_empty() {
-        display::print_bold("No records found");
-        return Ok(());
+        if ctx.output.is_table() {
+            display::print_bold("No records found");
+            return Ok(());
+        }
+        return display::output_items(conn, ctx, "", &[], true, true);
     }
 
     cache::clear(conn).map_err(|e| e.to_string())?;
@@ -44,13 +60,19 @@ pub fn handle_listrecords(conn: &Connection, cmd: ListRecordCommand) -> Result<(
     }
     .map_err(|e| e.to_string())?;
 
-    display::print_bold("Records List:");
-    display::print_items(&records, true, true);
-    Ok(())
+    display::output_items(conn, ctx, "Records List:", &records, true, true)
 }
 
-fn query_records(conn: &Connection, cmd: &ListRecordCommand) -> Result<Vec<Item>, String> {
+fn query_records(
+    conn: &Connection,
+    cmd: &ListRecordCommand,
+    namespace_ids: Option<&[i64]>,
+    condition: Option<&Condition>,
+) -> Result<Vec<Item>, String> {
     let mut record_query = ItemQuery::new().with_actions(vec![RECORD, RECURRING_TASK_RECORD]);
+    if let Some(ns) = namespace_ids {
+        record_query = record_query.with_namespace_ids(ns.to_vec());
+    }
     if let Some(cat) = &cmd.category {
         record_query = record_query.with_category(cat);
     }
@@ -69,6 +91,9 @@ fn query_records(conn: &Connection, cmd: &ListRecordCommand) -> Result<Vec<Item>
         let ending_timestamp = timestr::to_unix_epoch(ending_time)?;
         record_query = record_query.with_create_time_max(ending_timestamp);
     }
+    if let Some(condition) = condition {
+        record_query = record_query.with_condition(condition);
+    }
 
     let mut offset = Offset::None;
     if cmd.next_page {
@@ -98,6 +123,7 @@ mod tests {
     impl ListRecordCommand {
         fn default_test() -> Self {
             ListRecordCommand {
+                filter: None,
                 category: None,
                 days: None,
                 limit: 100,
@@ -160,17 +186,32 @@ mod tests {
         let list_timeframe_start_only =
             ListRecordCommand::default_test().with_starting_time("yesterday 8PM");
 
-        let results = query_records(&conn, &listfeeding).unwrap();
+        let results = query_records(&conn, &listfeeding, None, None).unwrap();
         assert_eq!(results.len(), 3);
-        let results = query_records(&conn, &list_all).unwrap();
+        let results = query_records(&conn, &list_all, None, None).unwrap();
         assert_eq!(results.len(), 4);
-        let results = query_records(&conn, &list_timeframe).unwrap();
+        let results = query_records(&conn, &list_timeframe, None, None).unwrap();
         assert_eq!(results.len(), 2);
-        let results = query_records(&conn, &list_timeframe_start_only).unwrap();
+        let results = query_records(&conn, &list_timeframe_start_only, None, None).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].category, "feeding")
     }
 
+    #[test]
+    fn test_query_records_filter() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        insert_record(&conn, "feeding", "100ML", "yesterday 2PM");
+        insert_record(&conn, "feeding", "110ML", "today 1AM");
+        insert_record(&conn, "FTP", "256W", "yesterday 7PM");
+
+        let filter = parse_filter("category:feeding and created>=today").unwrap();
+        let condition = compile_filter(&conn, &ctx, &filter).unwrap();
+        let results = query_records(&conn, &ListRecordCommand::default_test(), None, Some(&condition)).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content, "110ML");
+    }
+
     #[test]
     fn test_query_records_with_recurring() {
         let (conn, _temp_file) = get_test_conn();
@@ -188,7 +229,7 @@ mod tests {
 
         // Query all records (should include both record and recurring_task_record)
         let list_all = ListRecordCommand::default_test().with_days(2);
-        let results = query_records(&conn, &list_all).unwrap();
+        let results = query_records(&conn, &list_all, None, None).unwrap();
         assert_eq!(results.len(), 6); // 3 regular records + 3 recurring records
 
         // Verify we have both action types
@@ -204,7 +245,7 @@ mod tests {
         let list_feeding = ListRecordCommand::default_test()
             .with_days(2)
             .with_category("feeding");
-        let results = query_records(&conn, &list_feeding).unwrap();
+        let results = query_records(&conn, &list_feeding, None, None).unwrap();
         assert_eq!(results.len(), 4); // 2 regular feeding + 2 recurring feeding
         for record in &results {
             assert_eq!(record.category, "feeding");
@@ -215,7 +256,7 @@ mod tests {
         let list_bottle = ListRecordCommand::default_test()
             .with_days(2)
             .with_search("bottle");
-        let results = query_records(&conn, &list_bottle).unwrap();
+        let results = query_records(&conn, &list_bottle, None, None).unwrap();
         assert_eq!(results.len(), 2); // 2 recurring records with "bottle"
         for record in &results {
             assert!(record.content.contains("bottle"));
@@ -246,20 +287,20 @@ mod tests {
             .with_starting_time("2025/02/21")
             .with_ending_time("2025/02/27");
 
-        let results = query_records(&conn, &list_record).unwrap();
+        let results = query_records(&conn, &list_record, None, None).unwrap();
         cache::clear(&conn).unwrap();
         cache::store_with_next(&conn, &results).unwrap();
         assert_eq!(results.len(), 11);
         assert!(results.iter().all(|i| i.content.contains("A")));
 
         let list_record_next = list_record.with_next_page();
-        let results = query_records(&conn, &list_record_next).unwrap();
+        let results = query_records(&conn, &list_record_next, None, None).unwrap();
         cache::clear(&conn).unwrap();
         cache::store_with_next(&conn, &results).unwrap();
         assert_eq!(results.len(), 11);
         assert!(results.iter().all(|i| i.content.contains("B")));
 
-        let results = query_records(&conn, &list_record_next).unwrap();
+        let results = query_records(&conn, &list_record_next, None, None).unwrap();
         cache::clear(&conn).unwrap();
         cache::store(&conn, &results).unwrap();
         assert_eq!(results.len(), 0);
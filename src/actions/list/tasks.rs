@@ -2,34 +2,72 @@ use chrono::Local;
 use rusqlite::Connection;
 
 use super::{
+    filter::compile_filter,
     handle_next_page,
     CLOSED_STATUS_CODES,
     OPEN_STATUS_CODES,
     TARGET_TIME_COL,
 };
 use crate::{
-    actions::display,
+    actions::{
+        display,
+        recurrence::{is_iteration_handled, remaining_occurrences},
+    },
     args::{
         cron,
+        filter::{parse_filter, Field},
         parser::ListTaskCommand,
+        sort::SortKey,
         timestr,
     },
+    context::Context,
     db::{
         cache,
-        crud::query_items,
+        crud::{
+            mark_subtask_progress,
+            query_items,
+        },
+        dependency::mark_blocked,
         item::{
+            Condition,
             Item,
             ItemQuery,
             Offset,
             RECURRING_TASK,
-            RECURRING_TASK_RECORD,
             TASK,
         },
+        tag::mark_tags,
         user::get_user_by_name,
     },
 };
 
-pub fn handle_listtasks(conn: &Connection, cmd: ListTaskCommand) -> Result<(), String> {
+pub fn handle_listtasks(
+    conn: &Connection,
+    ctx: &Context,
+    mut cmd: ListTaskCommand,
+) -> Result<(), String> {
+    let namespace_ids = ctx.namespace_filter(conn)?;
+
+    // The positional is a filter expression when it isn't a time
+    let filter = match &cmd.timestr {
+        Some(t) if timestr::parse_flexible_timestr(t).is_err() => Some(parse_filter(t)?),
+        _ => None,
+    };
+    let condition = match &filter {
+        Some(filter) => {
+            cmd.timestr = None;
+            // A filter on status or due dates replaces the default open, not yet due listing
+            if filter.mentions(Field::Status) {
+                cmd.status = 255;
+            }
+            if filter.mentions(Field::Due) || filter.mentions(Field::Overdue) {
+                cmd.overdue = true;
+            }
+            Some(compile_filter(conn, ctx, filter)?)
+        }
+        None => None,
+    };
+
     // Resolve user filter to assignee_id (skip if all_users is true)
     let assignee_id = if cmd.all_users {
         None
@@ -41,7 +79,7 @@ pub fn handle_listtasks(conn: &Connection, cmd: ListTaskCommand) -> Result<(), S
         None
     };
 
-    let recurring_tasks = match query_recurring_tasks(conn, &cmd, assignee_id) {
+    let recurring_tasks = match query_recurring_tasks(conn, &cmd, assignee_id, Some(&namespace_ids), condition.as_ref()) {
         Ok(tasks) => tasks,
         Err(estr) => {
             display::print_bold(&estr);
@@ -75,13 +113,13 @@ pub fn handle_listtasks(conn: &Connection, cmd: ListTaskCommand) -> Result<(), S
     };
     let recurring_tasks = filter_recurring_task_by_time(recurring_tasks, &cmd)?;
 
-    let all_tasks = if recurring_hit_limit {
+    let mut all_tasks = if recurring_hit_limit {
         // If recurring tasks hit the limit, don't query regular tasks yet
         // There might be more recurring tasks on the next page
         recurring_tasks
     } else {
         // Recurring tasks didn't hit limit, safe to query and combine with regular tasks
-        let regular_tasks = match query_tasks(conn, &cmd, assignee_id) {
+        let regular_tasks = match query_tasks(conn, &cmd, assignee_id, Some(&namespace_ids), condition.as_ref()) {
             Ok(tasks) => tasks,
             Err(estr) => {
                 display::print_bold(&estr);
@@ -99,9 +137,15 @@ pub fn handle_listtasks(conn: &Connection, cmd: ListTaskCommand) -> Result<(), S
     };
 
     if all_tasks.is_empty() {
-        display::print_bold("No tasks found");
-        return Ok(());
+        if ctx.output.is_table() {
+            display::print_bold("No tasks found");
+            return Ok(());
+        }
+        return display::output_items(conn, ctx, "", &[], false, true);
     }
+    mark_blocked(conn, &mut all_tasks)?;
+    mark_subtask_progress(conn, &mut all_tasks).map_err(|e| e.to_string())?;
+    mark_tags(conn, &mut all_tasks)?;
 
     // given we have filtering, the cache must store
     // all items queried even if they had been filtered
@@ -114,27 +158,56 @@ pub fn handle_listtasks(conn: &Connection, cmd: ListTaskCommand) -> Result<(), S
         }
     }
 
+    let has_next = recurring_hit_limit || cache_items.len() == cmd.limit;
+
+    if cmd.sort.is_some() || cmd.reverse {
+        // Sorting applies to the page, the next one still continues
+        // from the last task in due order
+        let anchor = cache_items.last().cloned();
+        sort_tasks(&mut all_tasks, cmd.sort, cmd.reverse);
+        cache_items = all_tasks.clone();
+        if let Some(anchor) = anchor.filter(|_| has_next) {
+            if cache_items.last().map(|t| t.id) != Some(anchor.id) {
+                cache_items.push(anchor);
+            }
+        }
+    }
+
     cache::clear(conn).map_err(|e| e.to_string())?;
-    if recurring_hit_limit || cache_items.len() == cmd.limit {
+    if has_next {
         cache::store_with_next(conn, &cache_items)
     } else {
         cache::store(conn, &cache_items)
     }
     .map_err(|e| e.to_string())?;
 
-    display::print_bold("Tasks List:");
-    display::print_items(&all_tasks, false, true);
-    Ok(())
+    display::output_items(conn, ctx, "Tasks List:", &all_tasks, false, true)
+}
+
+// Stable, so tasks that compare equal keep their due order
+fn sort_tasks(tasks: &mut [Item], sort: Option<SortKey>, reverse: bool) {
+    if let Some(key) = sort {
+        tasks.sort_by(|a, b| key.compare(a, b));
+    }
+    if reverse {
+        tasks.reverse();
+    }
 }
 
 // Some cmd query argument do not apply - moved to application layer.
-// Skip query for status because recurring tasks do not have status.
+// Recurring tasks are closed when their last occurrence is completed or skipped,
+// those are left out while open ones are filtered on completion at handler level.
 fn query_recurring_tasks(
     conn: &Connection,
     cmd: &ListTaskCommand,
     assignee_id: Option<i64>,
+    namespace_ids: Option<&[i64]>,
+    condition: Option<&Condition>,
 ) -> Result<Vec<Item>, String> {
     let mut query = ItemQuery::new().with_action(RECURRING_TASK);
+    if let Some(ns) = namespace_ids {
+        query = query.with_namespace_ids(ns.to_vec());
+    }
     if let Some(cat) = &cmd.category {
         query = query.with_category(cat);
     }
@@ -144,6 +217,12 @@ fn query_recurring_tasks(
     if let Some(aid) = assignee_id {
         query = query.with_assignee_id(aid);
     }
+    query = query
+        .with_tags(cmd.tags.iter().map(String::as_str).collect())
+        .without_tags(cmd.not_tags.iter().map(String::as_str).collect());
+    if let Some(condition) = condition {
+        query = query.with_condition(condition);
+    }
     let mut offset = Offset::None;
     if cmd.next_page {
         offset = handle_next_page(conn);
@@ -154,9 +233,9 @@ fn query_recurring_tasks(
         }
     }
     match cmd.status {
-        // For open, done, closed and all we capture all items
+        // For open, done, closed and all we capture all open items
         // to be filtered at handler level.
-        1 | 253 | 254 | 255 => {}
+        1 | 253 | 254 | 255 => query = query.with_statuses(OPEN_STATUS_CODES.to_vec()),
         // retain other specific status query
         _ => query = query.with_statuses(vec![cmd.status]),
     }
@@ -196,17 +275,10 @@ fn mark_recurring_task_by_completion(
     mut recurring_tasks: Vec<Item>,
 ) -> Result<Vec<Item>, String> {
     for recurring_task in &mut recurring_tasks {
-        let cron_schedule = recurring_task.cron_schedule.as_ref().unwrap();
-        let last_occurrence = cron::get_last_occurrence(cron_schedule)?;
-        let recurring_task_id = recurring_task.id.unwrap();
-
-        // Query for recurring_task_record that covers this interval
-        let record_query = ItemQuery::new()
-            .with_action(RECURRING_TASK_RECORD)
-            .with_recurring_task_id(recurring_task_id)
-            .with_good_until_min(last_occurrence);
-        let records = query_items(conn, &record_query).map_err(|e| e.to_string())?;
-        recurring_task.recurring_interval_complete = !records.is_empty();
+        // Completed or skipped for this interval, or snoozed for now.
+        // A task past its end date is closed by the next done or skip, not by listing.
+        recurring_task.recurring_interval_complete = is_iteration_handled(conn, recurring_task)?
+            || remaining_occurrences(conn, recurring_task)? == Some(0);
     }
     Ok(recurring_tasks)
 }
@@ -215,10 +287,16 @@ fn query_tasks(
     conn: &Connection,
     cmd: &ListTaskCommand,
     assignee_id: Option<i64>,
+    namespace_ids: Option<&[i64]>,
+    condition: Option<&Condition>,
 ) -> Result<Vec<Item>, String> {
     let mut task_query = ItemQuery::new().with_action(TASK);
     let now = Local::now().timestamp();
 
+    if let Some(ns) = namespace_ids {
+        task_query = task_query.with_namespace_ids(ns.to_vec());
+    }
+
     // Apply assignee filter
     if let Some(aid) = assignee_id {
         task_query = task_query.with_assignee_id(aid);
@@ -250,6 +328,15 @@ fn query_tasks(
     if let Some(search_term) = &cmd.search {
         task_query = task_query.with_content_like(search_term);
     }
+    if cmd.ready {
+        task_query = task_query.with_ready_only();
+    }
+    task_query = task_query
+        .with_tags(cmd.tags.iter().map(String::as_str).collect())
+        .without_tags(cmd.not_tags.iter().map(String::as_str).collect());
+    if let Some(condition) = condition {
+        task_query = task_query.with_condition(condition);
+    }
 
     match cmd.status {
         // 255 status means we query all task items regardless of status.
@@ -309,12 +396,19 @@ fn query_tasks(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tests::{
-        get_test_conn,
-        insert_recurring_record,
-        insert_recurring_task,
-        insert_task,
-        update_status,
+    use crate::{
+        db::{
+            crud::{get_item, update_item},
+            namespace::create_namespace,
+            tag::add_tags,
+        },
+        tests::{
+            get_test_conn,
+            insert_recurring_record,
+            insert_recurring_task,
+            insert_task,
+            update_status,
+        },
     };
 
     impl ListTaskCommand {
@@ -330,6 +424,11 @@ mod tests {
                 search: None,
                 user: None,
                 all_users: false,
+                ready: false,
+                tags: Vec::new(),
+                not_tags: Vec::new(),
+                sort: None,
+                reverse: false,
             }
         }
 
@@ -372,17 +471,101 @@ mod tests {
         insert_task(&conn, "fun", "first_due", "yesterday");
 
         let list_tasks_default = ListTaskCommand::default_test();
-        let results = query_tasks(&conn, &list_tasks_default, None).unwrap();
+        let results = query_tasks(&conn, &list_tasks_default, None, None, None).unwrap();
         assert_eq!(results.len(), 2);
         assert_eq!(results.first().unwrap().content, "second_due");
         assert_eq!(results.last().unwrap().content, "third_due");
 
         let list_tasks_with_overdue = ListTaskCommand::default_test().with_overdue(true);
-        let results = query_tasks(&conn, &list_tasks_with_overdue, None).unwrap();
+        let results = query_tasks(&conn, &list_tasks_with_overdue, None, None, None).unwrap();
         assert_eq!(results.len(), 3);
         assert_eq!(results.first().unwrap().content, "first_due");
     }
 
+    #[test]
+    fn test_query_tasks_relative_timestr() {
+        let (conn, _temp_file) = get_test_conn();
+        insert_task(&conn, "work", "soon", "in 2 days");
+        insert_task(&conn, "work", "later", "+10d");
+
+        let cmd = ListTaskCommand {
+            timestr: Some("in 5 days".to_string()),
+            ..ListTaskCommand::default_test()
+        };
+        let results = query_tasks(&conn, &cmd, None, None, None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content, "soon");
+    }
+
+    #[test]
+    fn test_query_tasks_namespace() {
+        let (conn, _temp_file) = get_test_conn();
+        let default_ns = Context::default_from_db(&conn).unwrap().current_namespace_id;
+        let work_ns = create_namespace(&conn, "work", None, 1).unwrap();
+        insert_task(&conn, "life", "default task", "today");
+        let task_id = insert_task(&conn, "life", "work task", "today");
+        let mut item = get_item(&conn, task_id).unwrap();
+        item.namespace_id = Some(work_ns);
+        update_item(&conn, &item, None).unwrap();
+
+        let cmd = ListTaskCommand::default_test();
+        let results = query_tasks(&conn, &cmd, None, Some(&[default_ns]), None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content, "default task");
+
+        let results = query_tasks(&conn, &cmd, None, Some(&[work_ns]), None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content, "work task");
+
+        let results = query_tasks(&conn, &cmd, None, None, None).unwrap();
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn test_query_tasks_ready() {
+        let (conn, _temp_file) = get_test_conn();
+        let design = insert_task(&conn, "work", "design", "today");
+        let build = insert_task(&conn, "work", "build", "tomorrow");
+        crate::db::dependency::add_dependency(&conn, build, design, None).unwrap();
+
+        let mut cmd = ListTaskCommand::default_test();
+        assert_eq!(query_tasks(&conn, &cmd, None, None, None).unwrap().len(), 2);
+
+        cmd.ready = true;
+        let results = query_tasks(&conn, &cmd, None, None, None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content, "design");
+
+        update_status(&conn, design, 1);
+        let results = query_tasks(&conn, &cmd, None, None, None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content, "build");
+    }
+
+    #[test]
+    fn test_query_tasks_by_tag() {
+        let (conn, _temp_file) = get_test_conn();
+        let login = insert_task(&conn, "work", "login", "today");
+        let audit = insert_task(&conn, "work", "audit", "tomorrow");
+        insert_task(&conn, "work", "docs", "tomorrow");
+        add_tags(&conn, login, &["backend".to_string(), "security".to_string()]).unwrap();
+        add_tags(&conn, audit, &["security".to_string()]).unwrap();
+
+        let mut cmd = ListTaskCommand::default_test();
+        cmd.tags = vec!["security".to_string()];
+        assert_eq!(query_tasks(&conn, &cmd, None, None, None).unwrap().len(), 2);
+
+        cmd.not_tags = vec!["backend".to_string()];
+        let results = query_tasks(&conn, &cmd, None, None, None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content, "audit");
+
+        cmd.tags.clear();
+        let results = query_tasks(&conn, &cmd, None, None, None).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|t| t.content != "login"));
+    }
+
     #[test]
     fn test_query_tasks_pagination() {
         let (conn, _temp_file) = get_test_conn();
@@ -405,13 +588,13 @@ mod tests {
             .with_category("test")
             .with_limit(10);
 
-        let results = query_tasks(&conn, &list_task, None).unwrap();
+        let results = query_tasks(&conn, &list_task, None, None, None).unwrap();
         cache::store_with_next(&conn, &results).unwrap();
         assert_eq!(results.len(), 10);
         assert!(results.iter().all(|i| i.content.contains("AM")));
 
         let list_task_next = list_task.with_next_page();
-        let results = query_tasks(&conn, &list_task_next, None).unwrap();
+        let results = query_tasks(&conn, &list_task_next, None, None, None).unwrap();
 
         cache::clear(&conn).unwrap();
         cache::store_with_next(&conn, &results).unwrap();
@@ -419,7 +602,7 @@ mod tests {
         assert_eq!(results.first().unwrap().content, "index 11AM");
         assert_eq!(results.last().unwrap().content, "index 9PM");
 
-        let results = query_tasks(&conn, &list_task_next, None).unwrap();
+        let results = query_tasks(&conn, &list_task_next, None, None, None).unwrap();
 
         cache::clear(&conn).unwrap();
         cache::store(&conn, &results).unwrap();
@@ -427,7 +610,7 @@ mod tests {
         assert_eq!(results.first().unwrap().content, "index 10PM");
         assert_eq!(results.last().unwrap().content, "index 11PM");
 
-        let results = query_tasks(&conn, &list_task_next, None);
+        let results = query_tasks(&conn, &list_task_next, None, None, None);
         assert_eq!(results.unwrap_err(), "No next page available".to_string());
     }
 
@@ -451,12 +634,12 @@ mod tests {
         let list_open = ListTaskCommand::default_test().with_status(254);
         let list_closed = ListTaskCommand::default_test().with_status(253);
 
-        let results = query_tasks(&conn, &list_open, None).expect("Unable to query");
+        let results = query_tasks(&conn, &list_open, None, None, None).expect("Unable to query");
         assert_eq!(results.len(), 6);
         assert!(results
             .iter()
             .all(|t| t.category == "ongoing" || t.category == "pending"));
-        let results = query_tasks(&conn, &list_closed, None).expect("Unable to query");
+        let results = query_tasks(&conn, &list_closed, None, None, None).expect("Unable to query");
         assert_eq!(results.len(), 4);
         assert!(results
             .iter()
@@ -474,12 +657,12 @@ mod tests {
 
         // Test basic query
         let list_all = ListTaskCommand::default_test();
-        let results = query_recurring_tasks(&conn, &list_all, None).unwrap();
+        let results = query_recurring_tasks(&conn, &list_all, None, None, None).unwrap();
         assert_eq!(results.len(), 3);
 
         // Test category filter
         let list_work = ListTaskCommand::default_test().with_category("work");
-        let results = query_recurring_tasks(&conn, &list_work, None).unwrap();
+        let results = query_recurring_tasks(&conn, &list_work, None, None, None).unwrap();
         assert_eq!(results.len(), 2);
         for task in &results {
             assert_eq!(task.category, "work");
@@ -487,13 +670,13 @@ mod tests {
 
         // Test search filter
         let list_search = ListTaskCommand::default_test().with_search("standup");
-        let results = query_recurring_tasks(&conn, &list_search, None).unwrap();
+        let results = query_recurring_tasks(&conn, &list_search, None, None, None).unwrap();
         assert_eq!(results.len(), 1);
         assert!(results[0].content.contains("standup"));
 
         // Test limit
         let list_limited = ListTaskCommand::default_test().with_limit(2);
-        let results = query_recurring_tasks(&conn, &list_limited, None).unwrap();
+        let results = query_recurring_tasks(&conn, &list_limited, None, None, None).unwrap();
         assert_eq!(results.len(), 2);
     }
 
@@ -508,7 +691,7 @@ mod tests {
 
         // Query all recurring tasks
         let cmd = ListTaskCommand::default_test();
-        let all_tasks = query_recurring_tasks(&conn, &cmd, None).unwrap();
+        let all_tasks = query_recurring_tasks(&conn, &cmd, None, None, None).unwrap();
         assert_eq!(all_tasks.len(), 3);
 
         // Test with no time filter (should return all)
@@ -543,7 +726,7 @@ mod tests {
 
         // Query all recurring tasks
         let cmd = ListTaskCommand::default_test();
-        let all_tasks = query_recurring_tasks(&conn, &cmd, None).unwrap();
+        let all_tasks = query_recurring_tasks(&conn, &cmd, None, None, None).unwrap();
         assert_eq!(all_tasks.len(), 3);
 
         // Mark completion status
@@ -572,6 +755,7 @@ mod tests {
     #[test]
     fn test_handle_listtasks_with_recurring() {
         let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
 
         // Insert recurring tasks
         insert_recurring_task(&conn, "work", "Daily standup", "Daily 9AM");
@@ -586,16 +770,70 @@ mod tests {
             status: 255,
             ..ListTaskCommand::default_test()
         };
-        let result = handle_listtasks(&conn, cmd);
+        let result = handle_listtasks(&conn, &ctx, cmd);
         assert!(result.is_ok());
 
         // Verify cache was populated
         assert!(cache::validate_cache(&conn).unwrap());
     }
 
+    #[test]
+    fn test_list_tasks_filter_expression() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let late = insert_task(&conn, "work", "late report", "yesterday");
+        let done = insert_task(&conn, "work", "shipped", "tomorrow");
+        update_status(&conn, done, 1);
+        insert_task(&conn, "ops", "rotate keys", "tomorrow");
+        insert_recurring_task(&conn, "work", "standup", "Daily 9AM");
+
+        let listed = |expr: &str| -> Vec<i64> {
+            let cmd = ListTaskCommand {
+                timestr: Some(expr.to_string()),
+                ..ListTaskCommand::default_test()
+            };
+            handle_listtasks(&conn, &ctx, cmd).unwrap();
+            (1..).map_while(|i| cache::read(&conn, i).unwrap()).collect()
+        };
+
+        // Filters on status or due dates lift the default open, not yet due listing
+        assert_eq!(listed("category:work status:done"), vec![done]);
+        assert_eq!(listed("category:work and due<today"), vec![late]);
+        assert_eq!(listed("not category:ops and (overdue or status:closed)"), vec![late, done]);
+    }
+
+    #[test]
+    fn test_list_tasks_sorted() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let soon = insert_task(&conn, "work", "soon", "tomorrow");
+        let later = insert_task(&conn, "work", "later", "in 3 days");
+        let last = insert_task(&conn, "work", "last", "in 5 days");
+        let mut item = get_item(&conn, later).unwrap();
+        item.priority = Some(0);
+        update_item(&conn, &item, None).unwrap();
+
+        let listed = |cmd: ListTaskCommand| -> Vec<i64> {
+            handle_listtasks(&conn, &ctx, cmd).unwrap();
+            (1..).map_while(|i| cache::read(&conn, i).unwrap()).collect()
+        };
+
+        let by_priority = || ListTaskCommand {
+            sort: Some(SortKey::Priority),
+            ..ListTaskCommand::default_test()
+        };
+        assert_eq!(listed(by_priority()), vec![later, soon, last]);
+        assert_eq!(listed(ListTaskCommand { reverse: true, ..by_priority() }), vec![last, soon, later]);
+
+        // The next page continues after the last task in due order, not the last one shown
+        assert_eq!(listed(by_priority().with_limit(2)), vec![later, soon, later]);
+        assert_eq!(listed(by_priority().with_limit(2).with_next_page()), vec![last]);
+    }
+
     #[test]
     fn test_list_tasks_pagination_with_recurring() {
         let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
 
         // Insert 5 recurring tasks
         insert_recurring_task(&conn, "work", "Task 1", "Daily 9AM");
@@ -617,7 +855,7 @@ mod tests {
             ..ListTaskCommand::default_test()
         };
 
-        let result = handle_listtasks(&conn, cmd);
+        let result = handle_listtasks(&conn, &ctx, cmd);
         assert!(result.is_ok());
         assert!(cache::validate_cache(&conn).unwrap());
 
@@ -629,7 +867,7 @@ mod tests {
             ..ListTaskCommand::default_test()
         };
 
-        let result = handle_listtasks(&conn, cmd_next);
+        let result = handle_listtasks(&conn, &ctx, cmd_next);
         assert!(result.is_ok());
         assert!(cache::validate_cache(&conn).unwrap());
     }
@@ -637,6 +875,7 @@ mod tests {
     #[test]
     fn test_pagination_transition_recurring_to_regular() {
         let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
 
         // Insert 3 recurring tasks and 5 regular tasks
         insert_recurring_task(&conn, "work", "Recurring 1", "Daily 9AM");
@@ -653,7 +892,7 @@ mod tests {
             status: 255, // all
             ..ListTaskCommand::default_test()
         };
-        let result = handle_listtasks(&conn, cmd);
+        let result = handle_listtasks(&conn, &ctx, cmd);
         assert!(result.is_ok());
 
         // Second page: should get last recurring + first regular (transition page)
@@ -663,8 +902,8 @@ mod tests {
             next_page: true,
             ..ListTaskCommand::default_test()
         };
-        let recurring_and_regular = query_recurring_tasks(&conn, &cmd_next, None).unwrap();
-        let regular_tasks = query_tasks(&conn, &cmd_next, None).unwrap();
+        let recurring_and_regular = query_recurring_tasks(&conn, &cmd_next, None, None, None).unwrap();
+        let regular_tasks = query_tasks(&conn, &cmd_next, None, None, None).unwrap();
 
         // Should have 1 recurring task left (Recurring 3)
         assert_eq!(recurring_and_regular.len(), 1);
@@ -680,13 +919,14 @@ mod tests {
             next_page: true,
             ..ListTaskCommand::default_test()
         };
-        let result = handle_listtasks(&conn, cmd_next);
+        let result = handle_listtasks(&conn, &ctx, cmd_next);
         assert!(result.is_ok()); // Should succeed and show regular tasks
     }
 
     #[test]
     fn test_handle_listtasks_status_filtering() {
         let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
 
         // Insert recurring tasks
         let task1_id = insert_recurring_task(&conn, "work", "Daily standup", "Daily 9AM");
@@ -701,7 +941,7 @@ mod tests {
             status: 253,
             ..ListTaskCommand::default_test()
         };
-        let result = handle_listtasks(&conn, cmd_closed);
+        let result = handle_listtasks(&conn, &ctx, cmd_closed);
         assert!(result.is_ok());
         // Should show completed recurring tasks
         assert!(cache::validate_cache(&conn).unwrap());
@@ -713,7 +953,7 @@ mod tests {
             status: 0,
             ..ListTaskCommand::default_test()
         };
-        let result = handle_listtasks(&conn, cmd_open);
+        let result = handle_listtasks(&conn, &ctx, cmd_open);
         assert!(result.is_ok());
         // Should show incomplete recurring tasks plus any regular tasks
         assert!(cache::validate_cache(&conn).unwrap());
@@ -722,6 +962,7 @@ mod tests {
     #[test]
     fn test_pagination_mixed_task_types() {
         let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
 
         // Insert 3 recurring tasks (won't hit limit of 10)
         insert_recurring_task(&conn, "work", "Recurring 1", "Daily 9AM");
@@ -752,7 +993,7 @@ mod tests {
             status: 255, // Show all
             ..ListTaskCommand::default_test()
         };
-        let result = handle_listtasks(&conn, cmd);
+        let result = handle_listtasks(&conn, &ctx, cmd);
         assert!(result.is_ok());
         assert!(cache::validate_cache(&conn).unwrap());
 
@@ -765,7 +1006,7 @@ mod tests {
             next_page: true,
             ..ListTaskCommand::default_test()
         };
-        let result = handle_listtasks(&conn, cmd_page2);
+        let result = handle_listtasks(&conn, &ctx, cmd_page2);
         assert!(result.is_ok());
         assert!(cache::validate_cache(&conn).unwrap());
     }
@@ -785,7 +1026,7 @@ mod tests {
         let search_meeting_tasks = ListTaskCommand::default_test()
             .with_overdue(true)
             .with_search("meeting");
-        let results = query_tasks(&conn, &search_meeting_tasks, None).unwrap();
+        let results = query_tasks(&conn, &search_meeting_tasks, None, None, None).unwrap();
         assert_eq!(results.len(), 3);
         for task in &results {
             assert!(task.content.contains("meeting"));
@@ -796,11 +1037,24 @@ mod tests {
             .with_category("work")
             .with_overdue(true)
             .with_search("meeting");
-        let results = query_tasks(&conn, &search_work_meeting, None).unwrap();
+        let results = query_tasks(&conn, &search_work_meeting, None, None, None).unwrap();
         assert_eq!(results.len(), 2);
         for task in &results {
             assert!(task.content.contains("meeting"));
             assert_eq!(task.category, "work");
         }
     }
+
+    #[test]
+    fn test_listing_leaves_exhausted_tasks_open() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let yesterday = (Local::now() - chrono::Duration::days(1)).format("%Y-%m-%d");
+        let expired = insert_recurring_task(&conn, "work", "Old ritual", &format!("Weekly until {}", yesterday));
+
+        // Hidden from the open tasks, but listing does not write
+        handle_listtasks(&conn, &ctx, ListTaskCommand::default_test()).unwrap();
+        assert_eq!(cache::read(&conn, 1).unwrap(), None);
+        assert_eq!(get_item(&conn, expired).unwrap().status, 0);
+    }
 }
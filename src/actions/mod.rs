@@ -1,12 +1,24 @@
 pub mod addition;
+pub mod archive;
+pub mod block;
+pub mod board;
+pub mod bulk;
 pub mod claim;
 pub mod display;
 pub mod handler;
+pub mod history;
 pub mod link;
 pub mod list;
 pub mod modify;
 pub mod namespace;
 pub mod note;
+pub mod recurrence;
 pub mod reporting;
+pub mod search;
 pub mod show;
+pub mod template;
+pub mod timetrack;
+pub mod trash;
+pub mod undo;
 pub mod user;
+pub mod view;
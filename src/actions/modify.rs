@@ -1,12 +1,13 @@
-use std::{
-    io,
-    io::Write,
-};
-
+use chrono::Local;
 use rusqlite::Connection;
 
 use crate::{
-    actions::display,
+    actions::{
+        bulk,
+        display,
+        list::CLOSED_STATUS_CODES,
+        recurrence::{check_iteration_open, close_if_exhausted, remaining_occurrences},
+    },
     args::{
         cron,
         parser::{
@@ -14,66 +15,113 @@ use crate::{
             DoneCommand,
             UpdateCommand,
         },
+        tag::extract_tags,
         timestr,
     },
     config::get_project,
+    context::Context,
     db::{
-        cache,
+        audit,
         crud::{
-            delete_item,
+            complete_item,
             get_item,
+            get_subtasks,
             insert_item,
-            query_items,
+            trash_item,
             update_item,
         },
         item::{
             Item,
-            ItemQuery,
             RECORD,
             RECURRING_TASK,
             RECURRING_TASK_RECORD,
         },
+        tag::{add_tags, get_tags_for_item, remove_tags},
+        time_entry::stop_timers_on_item,
     },
     github::{close_issue, is_gh_available, parse_issue_ref},
 };
 
-pub fn handle_donecmd(conn: &Connection, cmd: &DoneCommand) -> Result<(), String> {
-    validate_cache(conn)?;
-    let row_id = get_rowid_from_cache(conn, cmd.index)?;
-    let status = cmd.status;
+pub fn handle_donecmd(conn: &Connection, ctx: &Context, cmd: &DoneCommand) -> Result<(), String> {
+    let items = bulk::resolve_targets(conn, ctx, &cmd.indexes, cmd.filter.as_deref())?;
+    for item in &items {
+        check_completable(conn, ctx, item)?;
+    }
 
-    let mut item = get_item(conn, row_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
-    if item.action == RECORD || item.action == RECURRING_TASK_RECORD {
-        return Err("Cannot complete a record".to_string());
+    if !bulk::is_bulk(&items, cmd.filter.as_deref()) {
+        let item = items.into_iter().next().unwrap();
+        // Handle --close-issue flag before completing the task
+        if cmd.close_issue {
+            close_linked_issue(ctx, &item)?;
+        }
+        let header = if item.action == RECURRING_TASK {
+            "Completed Recurring Task:"
+        } else {
+            "Completed Task:"
+        };
+        let completion = complete(conn, ctx, cmd, item)?;
+        display::output_items(conn, ctx, header, &completion.items, false, false)?;
+        print_completion_notes(ctx, &completion);
+        return Ok(());
     }
 
-    // Handle --close-issue flag before completing the task
     if cmd.close_issue {
-        close_linked_issue(&item)?;
+        return Err("--close-issue works on one task at a time".to_string());
+    }
+    if !bulk::confirm(ctx, &items, &format!("Complete these {} tasks?", items.len()), cmd.yes)? {
+        return Err("Not completing the tasks".to_string());
+    }
+
+    let mut completions: Vec<Completion> = Vec::new();
+    bulk::apply_all(conn, items, |conn, item| {
+        // A parent closed along with its last subtask is done already
+        if completions.iter().any(|c| c.items.iter().any(|done| done.id == item.id)) {
+            return Ok(());
+        }
+        completions.push(complete(conn, ctx, cmd, item)?);
+        Ok(())
+    })?;
+
+    let completed: Vec<Item> = completions.iter().flat_map(|c| c.items.clone()).collect();
+    let header = format!("Completed {} Tasks:", completed.len());
+    display::output_items(conn, ctx, &header, &completed, false, false)?;
+    for completion in &completions {
+        print_completion_notes(ctx, completion);
     }
+    Ok(())
+}
+
+fn check_completable(conn: &Connection, ctx: &Context, item: &Item) -> Result<(), String> {
+    ctx.authorize_item(conn, item)?;
+    if item.action == RECORD || item.action == RECURRING_TASK_RECORD {
+        return Err("Cannot complete a record".to_string());
+    }
+    Ok(())
+}
+
+// What completing a task did, printed once the change is through
+struct Completion {
+    // The task, and its parent when closed along with it
+    items: Vec<Item>,
+    // A recurring task that had its last occurrence done
+    ended: bool,
+    // A parent left open though all its subtasks are closed
+    ready_parent: Option<i64>,
+}
+
+fn complete(conn: &Connection, ctx: &Context, cmd: &DoneCommand, mut item: Item) -> Result<Completion, String> {
+    let status = cmd.status;
 
     if item.action == RECURRING_TASK {
+        if CLOSED_STATUS_CODES.contains(&item.status) {
+            return Err("This recurring task has ended".to_string());
+        }
         let cron_schedule = item
             .cron_schedule
             .as_ref()
             .ok_or_else(|| "Recurring task missing cron schedule".to_string())?;
 
-        let last_occurrence = cron::get_last_occurrence(cron_schedule)?;
-
-        let existing_records = query_items(
-            conn,
-            &ItemQuery::new()
-                .with_action(RECURRING_TASK_RECORD)
-                .with_recurring_task_id(item.id.unwrap())
-                .with_good_until_min(last_occurrence),
-        )
-        .map_err(|e| format!("Failed to query existing records: {:?}", e))?;
-
-        if !existing_records.is_empty() {
-            return Err(
-                "This recurring task has already been completed for this iteration".to_string(),
-            );
-        }
+        check_iteration_open(conn, &item)?;
 
         let next_occurrence = cron::get_next_occurrence(cron_schedule)?;
 
@@ -83,18 +131,25 @@ pub fn handle_donecmd(conn: &Connection, cmd: &DoneCommand) -> Result<(), String
             record_content.push_str(comment);
         }
 
-        let completion_record = Item::create_recurring_record(
+        let mut completion_record = Item::create_recurring_record(
             item.category.clone(),
             record_content,
             item.id.unwrap(),
             next_occurrence,
         );
-        insert_item(conn, &completion_record)
+        completion_record.owner_id = Some(ctx.current_user_id);
+        completion_record.namespace_id = item.namespace_id;
+        insert_item(conn, &completion_record, Some(ctx.current_user_id))
             .map_err(|e| format!("Failed to create completion record: {:?}", e))?;
-
-        display::print_bold("Completed Recurring Task:");
-        display::print_items(&[item], false, false);
-        return Ok(());
+        complete_item(conn, &item, Some(ctx.current_user_id))
+            .map_err(|e| format!("Failed to update item: {:?}", e))?;
+        let ended = close_if_exhausted(conn, &mut item, Some(ctx.current_user_id))?;
+
+        return Ok(Completion {
+            items: vec![item],
+            ended,
+            ready_parent: None,
+        });
     }
 
     if let Some(comment) = &cmd.comment {
@@ -102,24 +157,84 @@ pub fn handle_donecmd(conn: &Connection, cmd: &DoneCommand) -> Result<(), String
         item.content.push_str(comment);
     }
 
+    let ready_parent = match item.parent_id {
+        Some(parent_id) if CLOSED_STATUS_CODES.contains(&status) => {
+            parent_ready_to_close(conn, parent_id, item.id)?
+        }
+        _ => None,
+    };
+    let mut completed = vec![complete_task(conn, ctx, item, status)?];
+
+    match ready_parent {
+        Some(parent) if cmd.close_parent => {
+            ctx.authorize_item(conn, &parent)?;
+            completed.push(complete_task(conn, ctx, parent, 1)?);
+            Ok(Completion {
+                items: completed,
+                ended: false,
+                ready_parent: None,
+            })
+        }
+        parent => Ok(Completion {
+            items: completed,
+            ended: false,
+            ready_parent: parent.and_then(|parent| parent.id),
+        }),
+    }
+}
+
+fn print_completion_notes(ctx: &Context, completion: &Completion) {
+    if !ctx.output.is_table() {
+        return;
+    }
+    if completion.ended {
+        println!("That was the last occurrence, the recurring task has ended.");
+    }
+    if let Some(parent_id) = completion.ready_parent {
+        println!(
+            "All subtasks of #{} are closed, complete it with: ctm done '#{}'",
+            parent_id, parent_id
+        );
+    }
+}
+
+// Writes the completion record of a regular task and closes it with `status`.
+fn complete_task(conn: &Connection, ctx: &Context, mut item: Item, status: u8) -> Result<Item, String> {
     let completion_content = format!("Completed Task: {}", item.content);
-    let completion_record = Item::new(
+    let mut completion_record = Item::new(
         RECORD.to_string(),
         item.category.clone(),
         completion_content,
     );
-    insert_item(conn, &completion_record)
+    completion_record.owner_id = Some(ctx.current_user_id);
+    completion_record.namespace_id = item.namespace_id;
+    insert_item(conn, &completion_record, Some(ctx.current_user_id))
         .map_err(|e| format!("Failed to create completion record: {:?}", e))?;
 
     item.status = status;
-    update_item(conn, &item).map_err(|e| format!("Failed to update item: {:?}", e))?;
-    display::print_bold("Completed Task:");
-    display::print_items(&[item], false, false);
-    Ok(())
+    complete_item(conn, &item, Some(ctx.current_user_id)).map_err(|e| format!("Failed to update item: {:?}", e))?;
+    Ok(item)
+}
+
+// Returns the parent when it is still open and `closing` is its last open subtask.
+fn parent_ready_to_close(
+    conn: &Connection,
+    parent_id: i64,
+    closing: Option<i64>,
+) -> Result<Option<Item>, String> {
+    let parent = get_item(conn, parent_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+    if CLOSED_STATUS_CODES.contains(&parent.status) {
+        return Ok(None);
+    }
+    let subtasks = get_subtasks(conn, parent_id).map_err(|e| format!("Failed to get subtasks: {:?}", e))?;
+    let ready = subtasks
+        .iter()
+        .all(|t| t.id == closing || CLOSED_STATUS_CODES.contains(&t.status));
+    Ok(ready.then_some(parent))
 }
 
 /// Close the linked GitHub issue for a task
-fn close_linked_issue(item: &Item) -> Result<(), String> {
+fn close_linked_issue(ctx: &Context, item: &Item) -> Result<(), String> {
     let issue_str = item.github_issue.as_ref().ok_or(
         "Task has no linked GitHub issue. Use 'ctm link --issue owner/repo#N' first.".to_string(),
     )?;
@@ -134,34 +249,109 @@ fn close_linked_issue(item: &Item) -> Result<(), String> {
     let issue_ref = parse_issue_ref(issue_str)?;
     close_issue(&issue_ref)?;
 
-    println!("Closed GitHub issue: {}", issue_str);
+    if ctx.output.is_table() {
+        println!("Closed GitHub issue: {}", issue_str);
+    }
     Ok(())
 }
 
-pub fn handle_deletecmd(conn: &Connection, cmd: &DeleteCommand) -> Result<(), String> {
-    validate_cache(conn)?;
-    let row_id = get_rowid_from_cache(conn, cmd.index)?;
-    let item = get_item(conn, row_id).map_err(|e| format!("Failed to find item: {:?}", e))?;
-    let item_type = item.action.clone();
-    let is_record = item_type == RECORD || item_type == RECURRING_TASK_RECORD;
-    display::print_items(&[item], is_record, false);
-    let accept = prompt_yes_no(&format!(
-        "Are you sure you want to delete this {}? ",
-        &item_type
-    ));
-
-    if !accept {
-        return Err(format!("Not deleting the {}", &item_type));
-    }
-    delete_item(conn, row_id).map_err(|e| format!("Failed to update item: {:?}", e))?;
-    display::print_bold("Deletion success");
+pub fn handle_deletecmd(conn: &Connection, ctx: &Context, cmd: &DeleteCommand) -> Result<(), String> {
+    let items = bulk::resolve_targets(conn, ctx, &cmd.indexes, cmd.filter.as_deref())?;
+    for item in &items {
+        ctx.authorize_item(conn, item)?;
+    }
+
+    // Deleting always asks, even for a single item
+    let (question, refusal) = match items.as_slice() {
+        [item] => (
+            format!("Are you sure you want to delete this {}? ", item.action),
+            format!("Not deleting the {}", item.action),
+        ),
+        _ => (
+            format!("Are you sure you want to delete these {} items?", items.len()),
+            "Not deleting the items".to_string(),
+        ),
+    };
+    if !bulk::confirm(ctx, &items, &question, cmd.yes)? {
+        return Err(refusal);
+    }
+
+    // Deleted items go to the trash until `ctm trash purge`
+    let ids: Vec<i64> = items.iter().map(|item| item.id.unwrap()).collect();
+    let count = ids.len();
+    // Time stops counting on a trashed task, undoing the delete starts its timers again
+    let now = Local::now().timestamp();
+    bulk::apply_all(conn, items, |conn, item| {
+        stop_timers_on_item(conn, item.id.unwrap(), now)?;
+        trash_item(conn, item.id.unwrap(), Some(ctx.current_user_id))
+            .map_err(|e| format!("Failed to update item: {:?}", e))
+    })?;
+    if !ctx.output.is_table() {
+        println!("{}", serde_json::json!({ "trashed": ids }));
+        return Ok(());
+    }
+    if count == 1 {
+        display::print_bold("Deletion success, moved to the trash");
+    } else {
+        display::print_bold(&format!("Deleted {} items, moved to the trash", count));
+    }
+    println!("Bring them back with ctm trash restore, see ctm trash list");
     Ok(())
 }
 
-pub fn handle_updatecmd(conn: &Connection, cmd: &UpdateCommand) -> Result<(), String> {
-    validate_cache(conn)?;
-    let row_id = get_rowid_from_cache(conn, cmd.index)?;
-    let mut item = get_item(conn, row_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+pub fn handle_updatecmd(conn: &Connection, ctx: &Context, cmd: &UpdateCommand) -> Result<(), String> {
+    let items = bulk::resolve_targets(conn, ctx, &cmd.indexes(), cmd.filter.as_deref())?;
+    for item in &items {
+        ctx.authorize_item(conn, item)?;
+    }
+
+    if !bulk::is_bulk(&items, cmd.filter.as_deref()) {
+        let item = update(conn, ctx, cmd, items.into_iter().next().unwrap())?;
+        let header = match item.action.as_str() {
+            RECURRING_TASK => "Updated Recurring Task:",
+            RECORD | RECURRING_TASK_RECORD => "Updated Record:",
+            _ => "Updated Task:",
+        };
+        let is_record = item.action == RECORD || item.action == RECURRING_TASK_RECORD;
+        return display::output_items(conn, ctx, header, &[item], is_record, false);
+    }
+
+    if !bulk::confirm(ctx, &items, &format!("Update these {} items?", items.len()), cmd.yes)? {
+        return Err("Not updating the items".to_string());
+    }
+    let mut updated = Vec::new();
+    bulk::apply_all(conn, items, |conn, item| {
+        updated.push(update(conn, ctx, cmd, item)?);
+        Ok(())
+    })?;
+
+    let all_records = updated
+        .iter()
+        .all(|item| item.action == RECORD || item.action == RECURRING_TASK_RECORD);
+    let header = format!("Updated {} Items:", updated.len());
+    display::output_items(conn, ctx, &header, &updated, all_records, false)
+}
+
+// Applies the changes of an update command to one item.
+fn update(conn: &Connection, ctx: &Context, cmd: &UpdateCommand, mut item: Item) -> Result<Item, String> {
+    let cmd_tags = cmd.tags();
+    let is_record = item.action == RECORD || item.action == RECURRING_TASK_RECORD;
+    if is_record && (!cmd_tags.is_empty() || !cmd.untags.is_empty()) {
+        return Err("Records cannot be tagged".to_string());
+    }
+    // +tag words in new content of a task are tags, not text
+    let mut tags = cmd_tags.clone();
+    let content = match &cmd.content {
+        Some(content) if !is_record => {
+            let (content, content_tags) = extract_tags(content);
+            if content.is_empty() {
+                return Err("Task content cannot be only tags".to_string());
+            }
+            tags.extend(content_tags.into_iter().filter(|tag| !cmd_tags.contains(tag)));
+            Some(content)
+        }
+        other => other.clone(),
+    };
 
     if item.action == RECURRING_TASK {
         if cmd.status.is_some() {
@@ -174,10 +364,14 @@ pub fn handle_updatecmd(conn: &Connection, cmd: &UpdateCommand) -> Result<(), St
         }
 
         if let Some(schedule_str) = &cmd.target_time {
-            match timestr::parse_recurring_timestr(schedule_str) {
-                Ok(cron_schedule) => {
-                    item.cron_schedule = Some(cron_schedule);
+            match timestr::parse_recurring_schedule(schedule_str) {
+                Ok(schedule) => {
+                    item.cron_schedule = Some(schedule.cron);
                     item.human_schedule = Some(schedule_str.clone());
+                    item.recur_until = schedule.until;
+                    item.recur_count = schedule.count;
+                    // A new bound may extend an ended task, or end it right away
+                    item.status = if remaining_occurrences(conn, &item)? == Some(0) { 1 } else { 0 };
                 }
                 Err(_) => {
                     return Err("Cannot parse schedule".to_string());
@@ -189,15 +383,14 @@ pub fn handle_updatecmd(conn: &Connection, cmd: &UpdateCommand) -> Result<(), St
             item.category = category.clone();
         }
 
-        if let Some(content) = &cmd.content {
-            item.content = content.clone();
+        if let Some(content) = content {
+            item.content = content;
         }
 
-        update_item(conn, &item).map_err(|e| format!("Failed to update item: {:?}", e))?;
+        update_item(conn, &item, Some(ctx.current_user_id)).map_err(|e| format!("Failed to update item: {:?}", e))?;
+        update_tags(conn, ctx, &mut item, &tags, &cmd.untags)?;
 
-        display::print_bold("Updated Recurring Task:");
-        display::print_items(&[item], false, false);
-        return Ok(());
+        return Ok(item);
     }
 
     if let Some(target) = &cmd.target_time {
@@ -209,8 +402,8 @@ pub fn handle_updatecmd(conn: &Connection, cmd: &UpdateCommand) -> Result<(), St
         item.category = category.clone();
     }
 
-    if let Some(content) = &cmd.content {
-        item.content = content.clone();
+    if let Some(content) = content {
+        item.content = content;
     }
 
     if let Some(add) = &cmd.add_content {
@@ -236,77 +429,91 @@ pub fn handle_updatecmd(conn: &Connection, cmd: &UpdateCommand) -> Result<(), St
         item.project = Some(project.clone());
     }
 
-    update_item(conn, &item).map_err(|e| format!("Failed to update item: {:?}", e))?;
-
-    let is_record = item.action == RECORD || item.action == RECURRING_TASK_RECORD;
-    let action = if is_record { "Record" } else { "Task" };
-    display::print_bold(&format!("Updated {}:", action));
-    display::print_items(&[item], is_record, false);
-    Ok(())
+    update_item(conn, &item, Some(ctx.current_user_id)).map_err(|e| format!("Failed to update item: {:?}", e))?;
+    update_tags(conn, ctx, &mut item, &tags, &cmd.untags)?;
+    Ok(item)
 }
 
-fn validate_cache(conn: &Connection) -> Result<(), String> {
-    match cache::validate_cache(conn) {
-        Ok(true) => Ok(()),
-        Ok(false) => Err("Cache is not valid, considering running list command first".to_string()),
-        Err(_) => Err("Cannot connect to cache".to_string()),
-    }
-}
-
-fn get_rowid_from_cache(conn: &Connection, index: usize) -> Result<i64, String> {
-    let index = index as i64;
-    match cache::read(conn, index).map_err(|e| format!("Failed to read cache table: {:?}", e))? {
-        Some(id) => Ok(id),
-        None => Err(format!("index {} does not exist", index)),
+// Adds and removes tags of an item, recording the change in the audit log.
+fn update_tags(
+    conn: &Connection,
+    ctx: &Context,
+    item: &mut Item,
+    add: &[String],
+    remove: &[String],
+) -> Result<(), String> {
+    let item_id = item.id.unwrap();
+    let old_tags = get_tags_for_item(conn, item_id)?;
+    add_tags(conn, item_id, add)?;
+    remove_tags(conn, item_id, remove)?;
+    item.tags = get_tags_for_item(conn, item_id)?;
+
+    if item.tags != old_tags {
+        audit::log_change(
+            conn,
+            item_id,
+            item.namespace_id,
+            audit::AUDIT_UPDATE,
+            Some("tags"),
+            Some(&old_tags.join(" ")),
+            Some(&item.tags.join(" ")),
+            Some(ctx.current_user_id),
+        )
+        .map_err(|e| e.to_string())?;
     }
-}
-
-fn prompt_yes_no(question: &str) -> bool {
-    print!("{} (y/n): ", question);
-    io::stdout().flush().unwrap();
-
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
-
-    matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
+        args::{
+            format::OutputFormat,
+            itemref::{ItemRange, ItemRef},
+            parser::UpdateTarget,
+        },
         db::{
             cache,
             crud::{
                 get_item,
                 query_items,
+                update_item,
             },
             item::{
                 ItemQuery,
                 TASK,
             },
+            namespace::create_namespace,
         },
         tests::{
             get_test_conn,
+            insert_record,
             insert_recurring_task,
+            insert_subtask,
             insert_task,
+            update_status,
         },
     };
 
     #[test]
     fn test_handle_donecmd() {
         let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
         insert_task(&conn, "work", "finish report", "tomorrow");
         let items = query_items(&conn, &ItemQuery::new().with_action(TASK)).unwrap();
         cache::store(&conn, &items).unwrap();
 
         let done_cmd = DoneCommand {
-            index: 1,
+            indexes: vec![ItemRef::Index(1).into()],
+            filter: None,
+            yes: false,
             status: 1,
             comment: None,
             close_issue: false,
+            close_parent: false,
         };
-        handle_donecmd(&conn, &done_cmd).unwrap();
+        handle_donecmd(&conn, &ctx, &done_cmd).unwrap();
         let item_id = cache::read(&conn, 1).unwrap().unwrap();
         let updated_item = get_item(&conn, item_id).unwrap();
         assert_eq!(updated_item.status, 1);
@@ -317,12 +524,15 @@ mod tests {
         assert_eq!(records[0].category, "work");
 
         let done_cmd = DoneCommand {
-            index: 1,
+            indexes: vec![ItemRef::Index(1).into()],
+            filter: None,
+            yes: false,
             status: 2,
             comment: None,
             close_issue: false,
+            close_parent: false,
         };
-        handle_donecmd(&conn, &done_cmd).unwrap();
+        handle_donecmd(&conn, &ctx, &done_cmd).unwrap();
         let updated_item = get_item(&conn, item_id).unwrap();
         assert_eq!(updated_item.status, 2);
 
@@ -330,20 +540,134 @@ mod tests {
         assert_eq!(records.len(), 2);
     }
 
+    #[test]
+    fn test_handle_donecmd_other_namespace() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let work_ns = create_namespace(&conn, "work", None, ctx.current_user_id).unwrap();
+        let task_id = insert_task(&conn, "work", "finish report", "tomorrow");
+        let mut item = get_item(&conn, task_id).unwrap();
+        item.namespace_id = Some(work_ns);
+        update_item(&conn, &item, None).unwrap();
+        let items = query_items(&conn, &ItemQuery::new().with_action(TASK)).unwrap();
+        cache::store(&conn, &items).unwrap();
+
+        let done_cmd = DoneCommand {
+            indexes: vec![ItemRef::Index(1).into()],
+            filter: None,
+            yes: false,
+            status: 1,
+            comment: None,
+            close_issue: false,
+            close_parent: false,
+        };
+        let result = handle_donecmd(&conn, &ctx, &done_cmd);
+        assert!(result.unwrap_err().contains("not in namespace"));
+        assert_eq!(get_item(&conn, task_id).unwrap().status, 0);
+
+        let ctx = ctx.with_all_namespaces(true);
+        handle_donecmd(&conn, &ctx, &done_cmd).unwrap();
+        assert_eq!(get_item(&conn, task_id).unwrap().status, 1);
+
+        // The completion record stays with the task's namespace
+        let records = query_items(&conn, &ItemQuery::new().with_action(RECORD)).unwrap();
+        assert_eq!(records[0].namespace_id, Some(work_ns));
+    }
+
+    #[test]
+    fn test_handle_donecmd_by_id() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        insert_task(&conn, "work", "first", "today");
+        let task_id = insert_task(&conn, "work", "second", "tomorrow");
+
+        // No list was run, the id still resolves
+        let done_cmd = DoneCommand {
+            indexes: vec![ItemRef::Id(task_id).into()],
+            filter: None,
+            yes: false,
+            status: 1,
+            comment: None,
+            close_issue: false,
+            close_parent: false,
+        };
+        handle_donecmd(&conn, &ctx, &done_cmd).unwrap();
+        assert_eq!(get_item(&conn, task_id).unwrap().status, 1);
+
+        let done_cmd = DoneCommand {
+            indexes: vec![ItemRef::Id(task_id + 100).into()],
+            filter: None,
+            yes: false,
+            status: 1,
+            comment: None,
+            close_issue: false,
+            close_parent: false,
+        };
+        let err = handle_donecmd(&conn, &ctx, &done_cmd).unwrap_err();
+        assert_eq!(err, format!("item #{} does not exist", task_id + 100));
+
+        // Indexes still require a fresh list
+        let done_cmd = DoneCommand {
+            indexes: vec![ItemRef::Index(1).into()],
+            filter: None,
+            yes: false,
+            status: 1,
+            comment: None,
+            close_issue: false,
+            close_parent: false,
+        };
+        assert!(handle_donecmd(&conn, &ctx, &done_cmd).unwrap_err().contains("Cache is not valid"));
+    }
+
+    #[test]
+    fn test_viewer_cannot_modify() {
+        let (conn, _temp_file) = get_test_conn();
+        crate::db::user::create_user(&conn, "vic", None, None).unwrap();
+        crate::db::namespace::add_user_to_namespace(&conn, "default", "vic", "viewer").unwrap();
+        let viewer = Context::resolve_with_config(&conn, Some("vic"), None, None).unwrap();
+        let task_id = insert_task(&conn, "work", "finish report", "tomorrow");
+        let items = query_items(&conn, &ItemQuery::new().with_action(TASK)).unwrap();
+        cache::store(&conn, &items).unwrap();
+
+        let done_cmd = DoneCommand {
+            indexes: vec![ItemRef::Index(1).into()],
+            filter: None,
+            yes: false,
+            status: 1,
+            comment: None,
+            close_issue: false,
+            close_parent: false,
+        };
+        let err = handle_donecmd(&conn, &viewer, &done_cmd).unwrap_err();
+        assert!(err.contains("role 'viewer'"), "{}", err);
+
+        let delete_cmd = DeleteCommand {
+            indexes: vec![ItemRef::Index(1).into()],
+            filter: None,
+            yes: false,
+        };
+        assert!(handle_deletecmd(&conn, &viewer, &delete_cmd).is_err());
+        assert_eq!(get_item(&conn, task_id).unwrap().status, 0);
+    }
+
     #[test]
     fn test_handle_donecmd_with_comment() {
         let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
         insert_task(&conn, "work", "finish report", "tomorrow");
         let items = query_items(&conn, &ItemQuery::new().with_action(TASK)).unwrap();
         cache::store(&conn, &items).unwrap();
 
         let done_cmd = DoneCommand {
-            index: 1,
+            indexes: vec![ItemRef::Index(1).into()],
+            filter: None,
+            yes: false,
             status: 1,
             comment: Some("Added extra analysis section".to_string()),
             close_issue: false,
+            close_parent: false,
         };
-        handle_donecmd(&conn, &done_cmd).unwrap();
+        handle_donecmd(&conn, &ctx, &done_cmd).unwrap();
         let item_id = cache::read(&conn, 1).unwrap().unwrap();
         let updated_item = get_item(&conn, item_id).unwrap();
 
@@ -365,13 +689,16 @@ mod tests {
     #[test]
     fn test_handle_updatecmd() {
         let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
         insert_task(&conn, "home", "clean garage", "saturday");
         let items = query_items(&conn, &ItemQuery::new().with_action(TASK)).unwrap();
         cache::store(&conn, &items).unwrap();
         let item_id = cache::read(&conn, 1).unwrap().unwrap();
 
         let update_cmd = UpdateCommand {
-            index: 1,
+            targets: vec![UpdateTarget::Items(ItemRef::Index(1).into())],
+            filter: None,
+            yes: false,
             target_time: None,
             category: None,
             content: Some("reorganize garage thoroughly".to_string()),
@@ -379,13 +706,16 @@ mod tests {
             status: None,
             reminder: None,
             project: None,
+            untags: Vec::new(),
         };
-        handle_updatecmd(&conn, &update_cmd).unwrap();
+        handle_updatecmd(&conn, &ctx, &update_cmd).unwrap();
         let updated_item = get_item(&conn, item_id).unwrap();
         assert_eq!(updated_item.content, "reorganize garage thoroughly");
 
         let update_cmd = UpdateCommand {
-            index: 1,
+            targets: vec![UpdateTarget::Items(ItemRef::Index(1).into())],
+            filter: None,
+            yes: false,
             target_time: None,
             category: None,
             content: None,
@@ -393,8 +723,9 @@ mod tests {
             status: None,
             reminder: None,
             project: None,
+            untags: Vec::new(),
         };
-        handle_updatecmd(&conn, &update_cmd).unwrap();
+        handle_updatecmd(&conn, &ctx, &update_cmd).unwrap();
         let updated_item = get_item(&conn, item_id).unwrap();
         assert_eq!(
             updated_item.content,
@@ -402,7 +733,9 @@ mod tests {
         );
 
         let update_cmd = UpdateCommand {
-            index: 1,
+            targets: vec![UpdateTarget::Items(ItemRef::Index(1).into())],
+            filter: None,
+            yes: false,
             target_time: None,
             category: None,
             content: None,
@@ -410,13 +743,16 @@ mod tests {
             status: Some(3),
             reminder: None,
             project: None,
+            untags: Vec::new(),
         };
-        handle_updatecmd(&conn, &update_cmd).unwrap();
+        handle_updatecmd(&conn, &ctx, &update_cmd).unwrap();
         let updated_item = get_item(&conn, item_id).unwrap();
         assert_eq!(updated_item.status, 3);
 
         let update_cmd = UpdateCommand {
-            index: 1,
+            targets: vec![UpdateTarget::Items(ItemRef::Index(1).into())],
+            filter: None,
+            yes: false,
             target_time: Some("eow".to_string()),
             category: Some("chore".to_string()),
             content: None,
@@ -424,26 +760,71 @@ mod tests {
             status: None,
             reminder: None,
             project: None,
+            untags: Vec::new(),
         };
-        handle_updatecmd(&conn, &update_cmd).unwrap();
+        handle_updatecmd(&conn, &ctx, &update_cmd).unwrap();
         let got_item = get_item(&conn, item_id).unwrap();
         assert_eq!(got_item.category, "chore");
     }
 
+    #[test]
+    fn test_handle_updatecmd_tags() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let task_id = insert_task(&conn, "work", "fix login", "today");
+        let record_id = insert_record(&conn, "work", "standup notes", "today");
+        let update = |index: i64, content: Option<&str>, tags: &[&str], untags: &[&str]| UpdateCommand {
+            targets: std::iter::once(UpdateTarget::Items(ItemRef::Id(index).into()))
+                .chain(tags.iter().map(|tag| UpdateTarget::Tag(tag.to_string())))
+                .collect(),
+            filter: None,
+            yes: false,
+            target_time: None,
+            category: None,
+            content: content.map(str::to_string),
+            add_content: None,
+            status: None,
+            reminder: None,
+            project: None,
+            untags: untags.iter().map(|tag| tag.to_string()).collect(),
+        };
+
+        handle_updatecmd(&conn, &ctx, &update(task_id, None, &["backend", "security"], &[])).unwrap();
+        handle_updatecmd(&conn, &ctx, &update(task_id, Some("fix login flow +auth"), &[], &["backend"])).unwrap();
+        assert_eq!(get_item(&conn, task_id).unwrap().content, "fix login flow");
+        assert_eq!(get_tags_for_item(&conn, task_id).unwrap(), vec!["auth", "security"]);
+
+        // Every tag change is audited
+        let history = audit::get_history_for_item(&conn, task_id).unwrap();
+        let tag_changes: Vec<_> = history
+            .iter()
+            .filter(|entry| entry.field_name.as_deref() == Some("tags"))
+            .collect();
+        assert_eq!(tag_changes.len(), 2);
+        assert_eq!(tag_changes[1].new_value.as_deref(), Some("auth security"));
+
+        let err = handle_updatecmd(&conn, &ctx, &update(record_id, None, &["backend"], &[])).unwrap_err();
+        assert_eq!(err, "Records cannot be tagged");
+    }
+
     #[test]
     fn test_handle_donecmd_recurring_task() {
         let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
         let task_id = insert_recurring_task(&conn, "work", "Daily standup", "Daily 9AM");
         let items = query_items(&conn, &ItemQuery::new().with_action(RECURRING_TASK)).unwrap();
         cache::store(&conn, &items).unwrap();
 
         let done_cmd = DoneCommand {
-            index: 1,
+            indexes: vec![ItemRef::Index(1).into()],
+            filter: None,
+            yes: false,
             status: 1,
             comment: Some("Discussed sprint goals".to_string()),
             close_issue: false,
+            close_parent: false,
         };
-        let result = handle_donecmd(&conn, &done_cmd);
+        let result = handle_donecmd(&conn, &ctx, &done_cmd);
         assert!(result.is_ok());
 
         let records =
@@ -458,12 +839,15 @@ mod tests {
         assert!(records[0].good_until.is_some());
 
         let done_cmd2 = DoneCommand {
-            index: 1,
+            indexes: vec![ItemRef::Index(1).into()],
+            filter: None,
+            yes: false,
             status: 1,
             comment: None,
             close_issue: false,
+            close_parent: false,
         };
-        let result = handle_donecmd(&conn, &done_cmd2);
+        let result = handle_donecmd(&conn, &ctx, &done_cmd2);
         assert!(result.is_err());
         assert_eq!(
             result.unwrap_err(),
@@ -475,15 +859,61 @@ mod tests {
         assert_eq!(records.len(), 1);
     }
 
+    #[test]
+    fn test_handle_donecmd_bounded_recurring_task() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let task_id = insert_recurring_task(&conn, "work", "Retro", "Daily 9AM for 1 time");
+        cache::store(&conn, &[get_item(&conn, task_id).unwrap()]).unwrap();
+
+        let done_cmd = DoneCommand {
+            indexes: vec![ItemRef::Index(1).into()],
+            filter: None,
+            yes: false,
+            status: 1,
+            comment: None,
+            close_issue: false,
+            close_parent: false,
+        };
+        handle_donecmd(&conn, &ctx, &done_cmd).unwrap();
+        assert_eq!(get_item(&conn, task_id).unwrap().status, 1);
+        assert_eq!(
+            handle_donecmd(&conn, &ctx, &done_cmd).unwrap_err(),
+            "This recurring task has ended"
+        );
+
+        // Extending the bound reopens it
+        let update_cmd = UpdateCommand {
+            targets: vec![UpdateTarget::Items(ItemRef::Index(1).into())],
+            filter: None,
+            yes: false,
+            target_time: Some("Daily 9AM for 3 times".to_string()),
+            category: None,
+            content: None,
+            add_content: None,
+            status: None,
+            reminder: None,
+            project: None,
+            untags: Vec::new(),
+        };
+        handle_updatecmd(&conn, &ctx, &update_cmd).unwrap();
+        let item = get_item(&conn, task_id).unwrap();
+        assert_eq!(item.status, 0);
+        assert_eq!(item.recur_count, Some(3));
+    }
+
     #[test]
     fn test_handle_updatecmd_recurring_task() {
         let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
         let task_id = insert_recurring_task(&conn, "work", "Daily standup", "Daily 9AM");
         let items = query_items(&conn, &ItemQuery::new().with_action(RECURRING_TASK)).unwrap();
         cache::store(&conn, &items).unwrap();
 
         let update_cmd = UpdateCommand {
-            index: 1,
+            targets: vec![UpdateTarget::Items(ItemRef::Index(1).into())],
+            filter: None,
+            yes: false,
             target_time: None,
             category: Some("meetings".to_string()),
             content: Some("Daily team sync".to_string()),
@@ -491,8 +921,9 @@ mod tests {
             status: None,
             reminder: None,
             project: None,
+            untags: Vec::new(),
         };
-        let result = handle_updatecmd(&conn, &update_cmd);
+        let result = handle_updatecmd(&conn, &ctx, &update_cmd);
         assert!(result.is_ok());
 
         let updated_item = get_item(&conn, task_id).unwrap();
@@ -501,7 +932,9 @@ mod tests {
 
         // Test updating schedule
         let update_cmd = UpdateCommand {
-            index: 1,
+            targets: vec![UpdateTarget::Items(ItemRef::Index(1).into())],
+            filter: None,
+            yes: false,
             target_time: Some("Daily 3PM".to_string()),
             category: None,
             content: None,
@@ -509,15 +942,18 @@ mod tests {
             status: None,
             reminder: None,
             project: None,
+            untags: Vec::new(),
         };
-        let result = handle_updatecmd(&conn, &update_cmd);
+        let result = handle_updatecmd(&conn, &ctx, &update_cmd);
         assert!(result.is_ok());
         let updated_item = get_item(&conn, task_id).unwrap();
         assert_eq!(updated_item.cron_schedule, Some("0 15 * * *".to_string()));
         assert_eq!(updated_item.human_schedule, Some("Daily 3PM".to_string()));
 
         let update_cmd = UpdateCommand {
-            index: 1,
+            targets: vec![UpdateTarget::Items(ItemRef::Index(1).into())],
+            filter: None,
+            yes: false,
             target_time: None,
             category: None,
             content: None,
@@ -525,8 +961,9 @@ mod tests {
             status: Some(1),
             reminder: None,
             project: None,
+            untags: Vec::new(),
         };
-        let result = handle_updatecmd(&conn, &update_cmd);
+        let result = handle_updatecmd(&conn, &ctx, &update_cmd);
         assert!(result.is_err());
         assert_eq!(
             result.unwrap_err(),
@@ -534,7 +971,9 @@ mod tests {
         );
 
         let update_cmd = UpdateCommand {
-            index: 1,
+            targets: vec![UpdateTarget::Items(ItemRef::Index(1).into())],
+            filter: None,
+            yes: false,
             target_time: None,
             category: None,
             content: None,
@@ -542,8 +981,9 @@ mod tests {
             status: None,
             reminder: None,
             project: None,
+            untags: Vec::new(),
         };
-        let result = handle_updatecmd(&conn, &update_cmd);
+        let result = handle_updatecmd(&conn, &ctx, &update_cmd);
         assert!(result.is_err());
         assert_eq!(
             result.unwrap_err(),
@@ -554,6 +994,7 @@ mod tests {
     #[test]
     fn test_block_task_conversions() {
         let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
 
         // Test blocking regular task to recurring conversion
         insert_task(&conn, "work", "finish report", "tomorrow");
@@ -561,7 +1002,9 @@ mod tests {
         cache::store(&conn, &items).unwrap();
 
         let update_cmd = UpdateCommand {
-            index: 1,
+            targets: vec![UpdateTarget::Items(ItemRef::Index(1).into())],
+            filter: None,
+            yes: false,
             target_time: Some("Daily 9AM".to_string()),
             category: None,
             content: None,
@@ -569,8 +1012,9 @@ mod tests {
             status: None,
             reminder: None,
             project: None,
+            untags: Vec::new(),
         };
-        let result = handle_updatecmd(&conn, &update_cmd);
+        let result = handle_updatecmd(&conn, &ctx, &update_cmd);
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("Couldn't parse"));
 
@@ -581,7 +1025,9 @@ mod tests {
         cache::store(&conn, &items).unwrap();
 
         let update_cmd = UpdateCommand {
-            index: 1,
+            targets: vec![UpdateTarget::Items(ItemRef::Index(1).into())],
+            filter: None,
+            yes: false,
             target_time: Some("tomorrow".to_string()),
             category: None,
             content: None,
@@ -589,8 +1035,133 @@ mod tests {
             status: None,
             reminder: None,
             project: None,
+            untags: Vec::new(),
         };
-        let result = handle_updatecmd(&conn, &update_cmd);
+        let result = handle_updatecmd(&conn, &ctx, &update_cmd);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_handle_donecmd_bulk() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let ids: Vec<i64> = (1..=5)
+            .map(|i| insert_task(&conn, "work", &format!("task {}", i), "tomorrow"))
+            .collect();
+        let items: Vec<Item> = ids.iter().map(|&id| get_item(&conn, id).unwrap()).collect();
+        cache::store(&conn, &items).unwrap();
+
+        let done_cmd = DoneCommand {
+            indexes: vec![ItemRef::Index(1).into(), ItemRange::Indexes(3, 4)],
+            filter: None,
+            yes: true,
+            status: 1,
+            comment: None,
+            close_issue: false,
+            close_parent: false,
+        };
+        handle_donecmd(&conn, &ctx, &done_cmd).unwrap();
+        let statuses: Vec<u8> = ids.iter().map(|&id| get_item(&conn, id).unwrap().status).collect();
+        assert_eq!(statuses, vec![1, 0, 1, 1, 0]);
+        let records = query_items(&conn, &ItemQuery::new().with_action(RECORD)).unwrap();
+        assert_eq!(records.len(), 3);
+
+        // JSON output can't show a prompt
+        let json_ctx = Context::default_from_db(&conn).unwrap().with_output(OutputFormat::Json);
+        let done_cmd = DoneCommand {
+            indexes: vec![ItemRange::Indexes(2, 5)],
+            yes: false,
+            ..done_cmd
+        };
+        assert!(handle_donecmd(&conn, &json_ctx, &done_cmd).unwrap_err().contains("--yes"));
+        assert_eq!(get_item(&conn, ids[1]).unwrap().status, 0);
+    }
+
+    #[test]
+    fn test_handle_updatecmd_where() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let a = insert_task(&conn, "old", "a", "tomorrow");
+        let b = insert_task(&conn, "old", "b", "tomorrow");
+        let closed = insert_task(&conn, "old", "closed", "tomorrow");
+        update_status(&conn, closed, 1);
+        let other = insert_task(&conn, "keep", "other", "tomorrow");
+
+        let update_cmd = |filter: &str, add_content: Option<&str>| UpdateCommand {
+            targets: vec![UpdateTarget::Tag("infra".to_string())],
+            filter: Some(filter.to_string()),
+            yes: true,
+            target_time: None,
+            category: Some("new".to_string()),
+            content: None,
+            add_content: add_content.map(str::to_string),
+            status: None,
+            reminder: None,
+            project: None,
+            untags: Vec::new(),
+        };
+        handle_updatecmd(&conn, &ctx, &update_cmd("category:old", None)).unwrap();
+        for id in [a, b] {
+            assert_eq!(get_item(&conn, id).unwrap().category, "new");
+            assert_eq!(get_tags_for_item(&conn, id).unwrap(), vec!["infra"]);
+        }
+        // Closed tasks are left out unless the filter asks for them
+        assert_eq!(get_item(&conn, closed).unwrap().category, "old");
+        assert_eq!(get_item(&conn, other).unwrap().category, "keep");
+
+        // One failing item leaves every item as it was
+        insert_recurring_task(&conn, "new", "standup", "Daily 9AM");
+        let err = handle_updatecmd(&conn, &ctx, &update_cmd("category:new", Some("more"))).unwrap_err();
+        assert!(err.contains("nothing was changed"), "{}", err);
+        assert_eq!(get_item(&conn, a).unwrap().content, "a");
+    }
+
+    #[test]
+    fn test_handle_donecmd_close_parent() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let parent_id = insert_task(&conn, "work", "release", "tomorrow");
+        let subtask_ids = [
+            insert_subtask(&conn, parent_id, "changelog"),
+            insert_subtask(&conn, parent_id, "tag"),
+        ];
+
+        let done = |id: i64, close_parent: bool| DoneCommand {
+            indexes: vec![ItemRef::Id(id).into()],
+            filter: None,
+            yes: false,
+            status: 1,
+            comment: None,
+            close_issue: false,
+            close_parent,
+        };
+
+        // Parent stays open while a subtask is open
+        handle_donecmd(&conn, &ctx, &done(subtask_ids[0], true)).unwrap();
+        assert_eq!(get_item(&conn, parent_id).unwrap().status, 0);
+
+        handle_donecmd(&conn, &ctx, &done(subtask_ids[1], true)).unwrap();
+        assert_eq!(get_item(&conn, parent_id).unwrap().status, 1);
+    }
+
+    #[test]
+    fn test_handle_donecmd_keeps_parent_open() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let parent_id = insert_task(&conn, "work", "release", "tomorrow");
+        let subtask_id = insert_subtask(&conn, parent_id, "tag");
+
+        let done_cmd = DoneCommand {
+            indexes: vec![ItemRef::Id(subtask_id).into()],
+            filter: None,
+            yes: false,
+            status: 1,
+            comment: None,
+            close_issue: false,
+            close_parent: false,
+        };
+        handle_donecmd(&conn, &ctx, &done_cmd).unwrap();
+        assert_eq!(get_item(&conn, subtask_id).unwrap().status, 1);
+        assert_eq!(get_item(&conn, parent_id).unwrap().status, 0);
+    }
 }
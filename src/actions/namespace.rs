@@ -4,12 +4,18 @@ use crate::{
     args::parser::{
         NamespaceAddUserCommand, NamespaceCommand, NamespaceCreateCommand,
         NamespaceDeleteCommand, NamespaceMembersCommand, NamespaceRemoveUserCommand,
-        NamespaceSwitchCommand,
+        NamespaceSwitchCommand, TimeZoneCommand,
     },
-    context::Context,
-    db::namespace::{
-        add_user_to_namespace, create_namespace, delete_namespace,
-        list_namespace_members, list_namespaces, remove_user_from_namespace,
+    config::set_active_namespace,
+    context::{Context, Permission},
+    db::{
+        namespace::{
+            add_user_to_namespace, create_namespace, delete_namespace, get_namespace_by_name,
+            get_namespace_time_zone,
+            get_user_role, list_namespace_members, list_namespaces, remove_user_from_namespace,
+            set_namespace_time_zone,
+        },
+        user::get_user_by_name,
     },
 };
 
@@ -21,11 +27,14 @@ pub fn handle_namespace_cmd(
     match cmd {
         NamespaceCommand::Create(create_cmd) => handle_ns_create(conn, ctx, create_cmd),
         NamespaceCommand::List => handle_ns_list(conn),
-        NamespaceCommand::Delete(delete_cmd) => handle_ns_delete(conn, delete_cmd),
-        NamespaceCommand::Switch(switch_cmd) => handle_ns_switch(switch_cmd),
-        NamespaceCommand::AddUser(add_cmd) => handle_ns_add_user(conn, add_cmd),
-        NamespaceCommand::RemoveUser(remove_cmd) => handle_ns_remove_user(conn, remove_cmd),
+        NamespaceCommand::Delete(delete_cmd) => handle_ns_delete(conn, ctx, delete_cmd),
+        NamespaceCommand::Switch(switch_cmd) => handle_ns_switch(conn, ctx, switch_cmd),
+        NamespaceCommand::AddUser(add_cmd) => handle_ns_add_user(conn, ctx, add_cmd),
+        NamespaceCommand::RemoveUser(remove_cmd) => {
+            handle_ns_remove_user(conn, ctx, remove_cmd)
+        }
         NamespaceCommand::Members(members_cmd) => handle_ns_members(conn, ctx, members_cmd),
+        NamespaceCommand::Tz(tz_cmd) => handle_ns_tz(conn, ctx, tz_cmd),
     }
 }
 
@@ -65,26 +74,101 @@ fn handle_ns_list(conn: &Connection) -> Result<(), String> {
     Ok(())
 }
 
-fn handle_ns_delete(conn: &Connection, cmd: NamespaceDeleteCommand) -> Result<(), String> {
+/// Checks the current user holds `permission` in the named namespace.
+fn require_in_namespace(
+    conn: &Connection,
+    ctx: &Context,
+    name: &str,
+    permission: Permission,
+) -> Result<(), String> {
+    let namespace = get_namespace_by_name(conn, name)?
+        .ok_or_else(|| format!("Namespace '{}' not found", name))?;
+    ctx.require_in(conn, namespace.id, permission)
+}
+
+/// Checks the current user may change the membership of `user`: granting the owner
+/// role, or changing or removing an owner, is left to owners.
+fn require_membership_change(
+    conn: &Connection,
+    ctx: &Context,
+    namespace: &str,
+    user: &str,
+    role: Option<&str>,
+) -> Result<(), String> {
+    require_in_namespace(conn, ctx, namespace, Permission::ManageMembers)?;
+    let is_owner = match (get_namespace_by_name(conn, namespace)?, get_user_by_name(conn, user)?) {
+        (Some(ns), Some(user)) => get_user_role(conn, user.id, ns.id)?.as_deref() == Some("owner"),
+        _ => false,
+    };
+    if is_owner || role == Some("owner") {
+        require_in_namespace(conn, ctx, namespace, Permission::ManageOwners)?;
+    }
+    Ok(())
+}
+
+fn handle_ns_delete(
+    conn: &Connection,
+    ctx: &Context,
+    cmd: NamespaceDeleteCommand,
+) -> Result<(), String> {
+    require_in_namespace(conn, ctx, &cmd.name, Permission::DeleteNamespace)?;
     delete_namespace(conn, &cmd.name)?;
     println!("Deleted namespace '{}'", cmd.name);
     Ok(())
 }
 
-fn handle_ns_switch(cmd: NamespaceSwitchCommand) -> Result<(), String> {
-    // For now, just tell the user to use the --ns flag or set CTM_NAMESPACE env
-    // Full config file support would require modifying config module
-    println!(
-        "To switch to namespace '{}', either:",
-        cmd.name
-    );
-    println!("  - Use: ctm --ns {} <command>", cmd.name);
-    println!("  - Set: export CTM_NAMESPACE={}", cmd.name);
-    println!("\nPersistent config support coming in a future update.");
+fn handle_ns_switch(
+    conn: &Connection,
+    ctx: &Context,
+    cmd: NamespaceSwitchCommand,
+) -> Result<(), String> {
+    validate_switch(conn, ctx, &cmd)?;
+    set_active_namespace(&cmd.name, cmd.user.as_deref())?;
+
+    match &cmd.user {
+        Some(user) => println!("Switched to namespace '{}' as user '{}'", cmd.name, user),
+        None => println!("Switched to namespace '{}'", cmd.name),
+    }
+    if std::env::var("CTM_NAMESPACE").is_ok() {
+        println!("Note: CTM_NAMESPACE is set and still takes precedence");
+    }
+    Ok(())
+}
+
+/// Refuses to persist a namespace the (new) default user cannot resolve,
+/// which would otherwise break every following command.
+fn validate_switch(
+    conn: &Connection,
+    ctx: &Context,
+    cmd: &NamespaceSwitchCommand,
+) -> Result<(), String> {
+    let namespace = get_namespace_by_name(conn, &cmd.name)?
+        .ok_or_else(|| format!("Namespace '{}' not found", cmd.name))?;
+
+    let (user_id, user_name) = match &cmd.user {
+        Some(name) => {
+            let user = get_user_by_name(conn, name)?
+                .ok_or_else(|| format!("User '{}' not found", name))?;
+            (user.id, user.name)
+        }
+        None => (ctx.current_user_id, ctx.current_user_name.clone()),
+    };
+
+    if get_user_role(conn, user_id, namespace.id)?.is_none() {
+        return Err(format!(
+            "User '{}' is not a member of namespace '{}'",
+            user_name, cmd.name
+        ));
+    }
     Ok(())
 }
 
-fn handle_ns_add_user(conn: &Connection, cmd: NamespaceAddUserCommand) -> Result<(), String> {
+fn handle_ns_add_user(
+    conn: &Connection,
+    ctx: &Context,
+    cmd: NamespaceAddUserCommand,
+) -> Result<(), String> {
+    require_membership_change(conn, ctx, &cmd.namespace, &cmd.user, Some(&cmd.role))?;
     add_user_to_namespace(conn, &cmd.namespace, &cmd.user, &cmd.role)?;
     println!(
         "Added user '{}' to namespace '{}' with role '{}'",
@@ -95,8 +179,10 @@ fn handle_ns_add_user(conn: &Connection, cmd: NamespaceAddUserCommand) -> Result
 
 fn handle_ns_remove_user(
     conn: &Connection,
+    ctx: &Context,
     cmd: NamespaceRemoveUserCommand,
 ) -> Result<(), String> {
+    require_membership_change(conn, ctx, &cmd.namespace, &cmd.user, None)?;
     remove_user_from_namespace(conn, &cmd.namespace, &cmd.user)?;
     println!(
         "Removed user '{}' from namespace '{}'",
@@ -129,3 +215,204 @@ fn handle_ns_members(
 
     Ok(())
 }
+
+fn handle_ns_tz(conn: &Connection, ctx: &Context, cmd: TimeZoneCommand) -> Result<(), String> {
+    let ns_name = &ctx.current_namespace_name;
+    let Some(zone) = cmd.zone else {
+        match get_namespace_time_zone(conn, ctx.current_namespace_id)? {
+            Some(zone) => println!("Time zone of namespace '{}': {}", ns_name, zone),
+            None => println!("Namespace '{}' has no time zone, members use the system zone", ns_name),
+        }
+        return Ok(());
+    };
+
+    ctx.require(Permission::ManageSettings)?;
+    if zone == "none" {
+        set_namespace_time_zone(conn, ctx.current_namespace_id, None)?;
+        println!("Unset time zone of namespace '{}'", ns_name);
+    } else {
+        set_namespace_time_zone(conn, ctx.current_namespace_id, Some(&zone))?;
+        println!("Set time zone of namespace '{}' to {}", ns_name, zone);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{namespace::get_user_role, user::create_user},
+        tests::get_test_conn,
+    };
+
+    fn setup_team(conn: &Connection) -> Context {
+        let owner = Context::default_from_db(conn).unwrap();
+        create_namespace(conn, "team", None, owner.current_user_id).unwrap();
+        for (name, role) in [("ann", "admin"), ("mel", "member"), ("vic", "viewer")] {
+            create_user(conn, name, None, None).unwrap();
+            add_user_to_namespace(conn, "team", name, role).unwrap();
+        }
+        create_user(conn, "new", None, None).unwrap();
+        owner
+    }
+
+    fn as_user(conn: &Connection, name: &str) -> Context {
+        Context::resolve_with_config(conn, Some(name), Some("team"), None).unwrap()
+    }
+
+    fn add_cmd(user: &str) -> NamespaceAddUserCommand {
+        NamespaceAddUserCommand {
+            namespace: "team".to_string(),
+            user: user.to_string(),
+            role: "member".to_string(),
+        }
+    }
+
+    fn remove_cmd(user: &str) -> NamespaceRemoveUserCommand {
+        NamespaceRemoveUserCommand {
+            namespace: "team".to_string(),
+            user: user.to_string(),
+        }
+    }
+
+    fn delete_cmd() -> NamespaceDeleteCommand {
+        NamespaceDeleteCommand {
+            name: "team".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_member_management_by_role() {
+        let (conn, _temp_file) = get_test_conn();
+        setup_team(&conn);
+
+        for name in ["mel", "vic"] {
+            let ctx = as_user(&conn, name);
+            let err = handle_ns_add_user(&conn, &ctx, add_cmd("new")).unwrap_err();
+            assert!(err.contains("cannot manage members"), "{}", err);
+            let err = handle_ns_remove_user(&conn, &ctx, remove_cmd("mel")).unwrap_err();
+            assert!(err.contains("cannot manage members"), "{}", err);
+        }
+
+        let admin = as_user(&conn, "ann");
+        handle_ns_add_user(&conn, &admin, add_cmd("new")).unwrap();
+        handle_ns_remove_user(&conn, &admin, remove_cmd("vic")).unwrap();
+
+        let team = get_namespace_by_name(&conn, "team").unwrap().unwrap();
+        let new_user = get_user_by_name(&conn, "new").unwrap().unwrap();
+        assert_eq!(
+            get_user_role(&conn, new_user.id, team.id).unwrap().as_deref(),
+            Some("member")
+        );
+    }
+
+    #[test]
+    fn test_owner_role_by_role() {
+        let (conn, _temp_file) = get_test_conn();
+        let owner = setup_team(&conn);
+        let owner_name = owner.current_user_name.clone();
+        let team = get_namespace_by_name(&conn, "team").unwrap().unwrap();
+        let role_of = |name: &str| {
+            let user = get_user_by_name(&conn, name).unwrap().unwrap();
+            get_user_role(&conn, user.id, team.id).unwrap()
+        };
+        let grant = |user: &str, role: &str| NamespaceAddUserCommand {
+            role: role.to_string(),
+            ..add_cmd(user)
+        };
+
+        // Admins manage members, but not owners
+        let admin = as_user(&conn, "ann");
+        let err = handle_ns_add_user(&conn, &admin, grant("new", "owner")).unwrap_err();
+        assert!(err.contains("cannot manage owners"), "{}", err);
+        let err = handle_ns_add_user(&conn, &admin, grant(&owner_name, "viewer")).unwrap_err();
+        assert!(err.contains("cannot manage owners"), "{}", err);
+        let err = handle_ns_remove_user(&conn, &admin, remove_cmd(&owner_name)).unwrap_err();
+        assert!(err.contains("cannot manage owners"), "{}", err);
+        assert_eq!(role_of(&owner_name).as_deref(), Some("owner"));
+
+        // The last owner can't step down
+        let err = handle_ns_add_user(&conn, &owner, grant(&owner_name, "admin")).unwrap_err();
+        assert!(err.contains("only owner"), "{}", err);
+        let err = handle_ns_remove_user(&conn, &owner, remove_cmd(&owner_name)).unwrap_err();
+        assert!(err.contains("only owner"), "{}", err);
+
+        handle_ns_add_user(&conn, &owner, grant("ann", "owner")).unwrap();
+        handle_ns_add_user(&conn, &owner, grant(&owner_name, "admin")).unwrap();
+        assert_eq!(role_of(&owner_name).as_deref(), Some("admin"));
+        assert_eq!(role_of("ann").as_deref(), Some("owner"));
+    }
+
+    #[test]
+    fn test_namespace_delete_by_role() {
+        let (conn, _temp_file) = get_test_conn();
+        let owner = setup_team(&conn);
+
+        for name in ["mel", "vic"] {
+            let ctx = as_user(&conn, name);
+            let err = handle_ns_delete(&conn, &ctx, delete_cmd()).unwrap_err();
+            assert!(err.contains("cannot delete the namespace"), "{}", err);
+        }
+
+        // Outsiders are rejected even when acting from another namespace
+        create_user(&conn, "out", None, None).unwrap();
+        add_user_to_namespace(&conn, "default", "out", "admin").unwrap();
+        let outsider = Context::resolve_with_config(&conn, Some("out"), None, None).unwrap();
+        let err = handle_ns_delete(&conn, &outsider, delete_cmd()).unwrap_err();
+        assert!(err.contains("not a member"), "{}", err);
+
+        handle_ns_delete(&conn, &owner, delete_cmd()).unwrap();
+        assert!(get_namespace_by_name(&conn, "team").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_validate_switch() {
+        let (conn, _temp_file) = get_test_conn();
+        let owner = setup_team(&conn);
+        let switch = |name: &str, user: Option<&str>| NamespaceSwitchCommand {
+            name: name.to_string(),
+            user: user.map(|u| u.to_string()),
+        };
+
+        assert!(validate_switch(&conn, &owner, &switch("team", None)).is_ok());
+        assert!(validate_switch(&conn, &owner, &switch("team", Some("vic"))).is_ok());
+
+        let err = validate_switch(&conn, &owner, &switch("missing", None)).unwrap_err();
+        assert!(err.contains("not found"));
+        let err = validate_switch(&conn, &owner, &switch("team", Some("new"))).unwrap_err();
+        assert!(err.contains("not a member"));
+        let err = validate_switch(&conn, &owner, &switch("team", Some("ghost"))).unwrap_err();
+        assert!(err.contains("User 'ghost' not found"));
+    }
+
+    #[test]
+    fn test_namespace_delete_not_found() {
+        let (conn, _temp_file) = get_test_conn();
+        let owner = Context::default_from_db(&conn).unwrap();
+        let cmd = NamespaceDeleteCommand {
+            name: "missing".to_string(),
+        };
+        let err = handle_ns_delete(&conn, &owner, cmd).unwrap_err();
+        assert!(err.contains("not found"));
+    }
+
+    #[test]
+    fn test_namespace_tz_by_role() {
+        let (conn, _temp_file) = get_test_conn();
+        setup_team(&conn);
+        let set = |zone: &str| TimeZoneCommand {
+            zone: Some(zone.to_string()),
+        };
+
+        let member = as_user(&conn, "mel");
+        let err = handle_ns_tz(&conn, &member, set("Europe/Paris")).unwrap_err();
+        assert!(err.contains("cannot change namespace settings"), "{}", err);
+
+        let admin = as_user(&conn, "ann");
+        handle_ns_tz(&conn, &admin, set("Europe/Paris")).unwrap();
+        assert_eq!(as_user(&conn, "mel").time_zone.name(), "Europe/Paris");
+
+        handle_ns_tz(&conn, &admin, set("none")).unwrap();
+        assert_eq!(get_namespace_time_zone(&conn, admin.current_namespace_id).unwrap(), None);
+    }
+}
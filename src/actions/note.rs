@@ -2,10 +2,9 @@ use rusqlite::Connection;
 
 use crate::{
     actions::display,
-    args::parser::NoteCommand,
+    args::{itemref::resolve_rowid, parser::NoteCommand},
     context::Context,
     db::{
-        cache,
         crud::get_item,
         item::{RECORD, RECURRING_TASK_RECORD},
         note::add_note,
@@ -13,43 +12,30 @@ use crate::{
 };
 
 pub fn handle_notecmd(conn: &Connection, ctx: &Context, cmd: &NoteCommand) -> Result<(), String> {
-    validate_cache(conn)?;
-    let row_id = get_rowid_from_cache(conn, cmd.index)?;
+    let row_id = resolve_rowid(conn, ctx, cmd.index)?;
 
     let item = get_item(conn, row_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+    ctx.authorize_item(conn, &item)?;
     if item.action == RECORD || item.action == RECURRING_TASK_RECORD {
         return Err("Cannot add notes to records".to_string());
     }
 
     let note_id = add_note(conn, row_id, &cmd.content, Some(ctx.current_user_id))?;
 
-    display::print_bold(&format!("Added note #{} to task:", note_id));
-    display::print_items(&[item], false, false);
-    println!("  Note: {}", cmd.content);
-
-    Ok(())
-}
-
-fn validate_cache(conn: &Connection) -> Result<(), String> {
-    match cache::validate_cache(conn) {
-        Ok(true) => Ok(()),
-        Ok(false) => Err("Cache is not valid, consider running list command first".to_string()),
-        Err(_) => Err("Cannot connect to cache".to_string()),
+    let header = format!("Added note #{} to task:", note_id);
+    display::output_items(conn, ctx, &header, &[item], false, false)?;
+    if ctx.output.is_table() {
+        println!("  Note: {}", cmd.content);
     }
-}
 
-fn get_rowid_from_cache(conn: &Connection, index: usize) -> Result<i64, String> {
-    let index = index as i64;
-    match cache::read(conn, index).map_err(|e| format!("Failed to read cache table: {:?}", e))? {
-        Some(id) => Ok(id),
-        None => Err(format!("index {} does not exist", index)),
-    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
+        args::itemref::ItemRef,
         context::Context,
         db::{
             cache,
@@ -70,7 +56,7 @@ mod tests {
         cache::store(&conn, &items).unwrap();
 
         let note_cmd = NoteCommand {
-            index: 1,
+            index: ItemRef::Index(1),
             content: "This is a test note".to_string(),
         };
 
@@ -93,14 +79,14 @@ mod tests {
 
         // Add first note
         let note_cmd1 = NoteCommand {
-            index: 1,
+            index: ItemRef::Index(1),
             content: "First note".to_string(),
         };
         handle_notecmd(&conn, &ctx, &note_cmd1).unwrap();
 
         // Add second note
         let note_cmd2 = NoteCommand {
-            index: 1,
+            index: ItemRef::Index(1),
             content: "Second note".to_string(),
         };
         handle_notecmd(&conn, &ctx, &note_cmd2).unwrap();
@@ -124,7 +110,7 @@ mod tests {
         cache::store(&conn, &items).unwrap();
 
         let note_cmd = NoteCommand {
-            index: 1,
+            index: ItemRef::Index(1),
             content: "This should fail".to_string(),
         };
 
@@ -143,7 +129,7 @@ mod tests {
         cache::store(&conn, &items).unwrap();
 
         let note_cmd = NoteCommand {
-            index: 99,
+            index: ItemRef::Index(99),
             content: "This should fail".to_string(),
         };
 
@@ -0,0 +1,391 @@
+use chrono::Local;
+use rusqlite::Connection;
+
+use crate::{
+    actions::{
+        display,
+        list::CLOSED_STATUS_CODES,
+    },
+    args::{
+        cron,
+        itemref::{resolve_rowid, ItemRef},
+        parser::{SkipCommand, SnoozeCommand},
+        timestr,
+        timezone,
+    },
+    context::Context,
+    db::{
+        crud::{complete_item, get_item, insert_item, query_items},
+        item::{
+            Item,
+            ItemQuery,
+            RECORD_COMPLETED,
+            RECORD_SKIPPED,
+            RECORD_SNOOZED,
+            RECURRING_TASK,
+            RECURRING_TASK_RECORD,
+        },
+    },
+};
+
+// Occurrences counted at most when a recurring task is bounded by a date
+const MAX_COUNTED_OCCURRENCES: i64 = 10_000;
+
+/// Handles the skip command - marks the current occurrence of a recurring task
+/// as skipped, without counting it as a completion.
+pub fn handle_skipcmd(conn: &Connection, ctx: &Context, cmd: &SkipCommand) -> Result<(), String> {
+    let mut item = get_recurring_task(conn, ctx, cmd.index)?;
+    let cron_schedule = item.cron_schedule.clone().unwrap_or_default();
+    let next_occurrence = cron::get_next_occurrence(&cron_schedule)?;
+
+    let mut content = format!("Skipped Recurring Task: {}", item.content);
+    if let Some(comment) = &cmd.comment {
+        content.push('\n');
+        content.push_str(comment);
+    }
+    insert_iteration_record(conn, ctx, &item, content, next_occurrence, RECORD_SKIPPED)?;
+    let ended = close_if_exhausted(conn, &mut item, Some(ctx.current_user_id))?;
+
+    display::output_items(conn, ctx, "Skipped Recurring Task:", &[item], false, false)?;
+    if ended && ctx.output.is_table() {
+        println!("That was the last occurrence, the recurring task has ended.");
+    }
+    Ok(())
+}
+
+/// Handles the snooze command - hides the current occurrence of a recurring task
+/// until the given time, after which it is due again.
+pub fn handle_snoozecmd(conn: &Connection, ctx: &Context, cmd: &SnoozeCommand) -> Result<(), String> {
+    let item = get_recurring_task(conn, ctx, cmd.index)?;
+    let cron_schedule = item.cron_schedule.clone().unwrap_or_default();
+    let next_occurrence = cron::get_next_occurrence(&cron_schedule)?;
+
+    let snooze_until = timestr::to_unix_epoch(&cmd.timestr)?;
+    if snooze_until <= Local::now().timestamp() {
+        return Err("Snooze time must be in the future".to_string());
+    }
+    if snooze_until >= next_occurrence {
+        return Err("Snooze time is past the next occurrence, use skip instead".to_string());
+    }
+
+    let content = format!(
+        "Snoozed Recurring Task: {} until {}",
+        item.content,
+        format_time(snooze_until)
+    );
+    insert_iteration_record(conn, ctx, &item, content, snooze_until, RECORD_SNOOZED)?;
+
+    display::output_items(conn, ctx, "Snoozed Recurring Task:", &[item], false, false)?;
+    if ctx.output.is_table() {
+        println!("Back on the list at {}", format_time(snooze_until));
+    }
+    Ok(())
+}
+
+// Resolves an open recurring task whose current iteration is not completed or skipped yet
+fn get_recurring_task(conn: &Connection, ctx: &Context, item_ref: ItemRef) -> Result<Item, String> {
+    let row_id = resolve_rowid(conn, ctx, item_ref)?;
+    let item = get_item(conn, row_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+    ctx.authorize_item(conn, &item)?;
+    if item.action != RECURRING_TASK {
+        return Err("Only recurring tasks can be skipped or snoozed".to_string());
+    }
+    if CLOSED_STATUS_CODES.contains(&item.status) {
+        return Err("This recurring task has ended".to_string());
+    }
+    check_iteration_open(conn, &item)?;
+    Ok(item)
+}
+
+fn insert_iteration_record(
+    conn: &Connection,
+    ctx: &Context,
+    item: &Item,
+    content: String,
+    good_until: i64,
+    status: u8,
+) -> Result<(), String> {
+    let mut record = Item::create_recurring_record(item.category.clone(), content, item.id.unwrap(), good_until);
+    record.status = status;
+    record.owner_id = Some(ctx.current_user_id);
+    record.namespace_id = item.namespace_id;
+    insert_item(conn, &record, Some(ctx.current_user_id))
+        .map_err(|e| format!("Failed to create record: {:?}", e))?;
+    Ok(())
+}
+
+fn format_time(timestamp: i64) -> String {
+    timezone::datetime(timestamp)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// Records of a recurring task covering its current iteration.
+pub fn current_iteration_records(conn: &Connection, item: &Item) -> Result<Vec<Item>, String> {
+    let cron_schedule = item
+        .cron_schedule
+        .as_ref()
+        .ok_or_else(|| "Recurring task missing cron schedule".to_string())?;
+    let last_occurrence = cron::get_last_occurrence(cron_schedule)?;
+    query_items(
+        conn,
+        &ItemQuery::new()
+            .with_action(RECURRING_TASK_RECORD)
+            .with_recurring_task_id(item.id.unwrap_or_default())
+            .with_good_until_min(last_occurrence),
+    )
+    .map_err(|e| format!("Failed to query existing records: {:?}", e))
+}
+
+/// Fails when the current iteration was already completed or skipped,
+/// a snoozed iteration can still be completed.
+pub fn check_iteration_open(conn: &Connection, item: &Item) -> Result<(), String> {
+    let records = current_iteration_records(conn, item)?;
+    if records.iter().any(|r| r.status == RECORD_COMPLETED) {
+        return Err("This recurring task has already been completed for this iteration".to_string());
+    }
+    if records.iter().any(|r| r.status == RECORD_SKIPPED) {
+        return Err("This recurring task has been skipped for this iteration".to_string());
+    }
+    Ok(())
+}
+
+/// Whether the current iteration needs no attention:
+/// completed, skipped, or snoozed to a time still ahead.
+pub fn is_iteration_handled(conn: &Connection, item: &Item) -> Result<bool, String> {
+    let now = Local::now().timestamp();
+    Ok(current_iteration_records(conn, item)?
+        .iter()
+        .any(|r| r.status != RECORD_SNOOZED || r.good_until.is_some_and(|until| until > now)))
+}
+
+/// Occurrences left to complete for a bounded recurring task, None when unbounded.
+/// Skipped occurrences use up the end date but not the number of times.
+pub fn remaining_occurrences(conn: &Connection, item: &Item) -> Result<Option<i64>, String> {
+    if item.recur_until.is_none() && item.recur_count.is_none() {
+        return Ok(None);
+    }
+    let item_id = item.id.ok_or_else(|| "Recurring task has no id".to_string())?;
+    let cron_schedule = item
+        .cron_schedule
+        .as_ref()
+        .ok_or_else(|| "Recurring task missing cron schedule".to_string())?;
+    let records = query_items(
+        conn,
+        &ItemQuery::new()
+            .with_action(RECURRING_TASK_RECORD)
+            .with_recurring_task_id(item_id),
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut remaining = i64::MAX;
+    if let Some(count) = item.recur_count {
+        let completions = records.iter().filter(|r| r.status == RECORD_COMPLETED).count();
+        remaining = remaining.min(count - completions as i64);
+    }
+    if let Some(until) = item.recur_until {
+        // Each occurrence is the deadline of the interval before it,
+        // the current one is covered when a record reaches past the last occurrence
+        let last_occurrence = cron::get_last_occurrence(cron_schedule)?;
+        let current_done = records.iter().any(|r| {
+            r.status != RECORD_SNOOZED && r.good_until.is_some_and(|good_until| good_until >= last_occurrence)
+        });
+
+        let mut occurrences = 0;
+        let mut next = cron::get_next_occurrence(cron_schedule)?;
+        while next <= until && occurrences < MAX_COUNTED_OCCURRENCES {
+            occurrences += 1;
+            next = cron::get_next_occurrence_after(cron_schedule, next)?;
+        }
+        if current_done {
+            occurrences -= 1;
+        }
+        remaining = remaining.min(occurrences);
+    }
+    Ok(Some(remaining.max(0)))
+}
+
+/// Closes a bounded recurring task without occurrences left.
+/// Returns true when the task was closed by this call.
+pub fn close_if_exhausted(conn: &Connection, item: &mut Item, actor: Option<i64>) -> Result<bool, String> {
+    if CLOSED_STATUS_CODES.contains(&item.status) || remaining_occurrences(conn, item)? != Some(0) {
+        return Ok(false);
+    }
+    item.status = 1;
+    complete_item(conn, item, actor).map_err(|e| format!("Failed to update item: {:?}", e))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Duration};
+
+    use super::*;
+    use crate::tests::{get_test_conn, insert_recurring_record, insert_recurring_task, update_status};
+
+    fn date_in_days(days: i64) -> String {
+        (Local::now() + Duration::days(days)).format("%Y-%m-%d").to_string()
+    }
+
+    // A yearly schedule whose next occurrence is at least two weeks away
+    fn far_schedule() -> String {
+        format!("Yearly {}/15", Local::now().month() % 12 + 1)
+    }
+
+    #[test]
+    fn test_handle_skipcmd() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let id = insert_recurring_task(&conn, "work", "Standup", "Daily 9AM");
+        let cmd = SkipCommand {
+            index: ItemRef::Id(id),
+            comment: Some("team offsite".to_string()),
+        };
+        handle_skipcmd(&conn, &ctx, &cmd).unwrap();
+
+        let item = get_item(&conn, id).unwrap();
+        let records = current_iteration_records(&conn, &item).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].status, RECORD_SKIPPED);
+        assert!(records[0].content.contains("team offsite"));
+        assert!(is_iteration_handled(&conn, &item).unwrap());
+        assert_eq!(
+            check_iteration_open(&conn, &item).unwrap_err(),
+            "This recurring task has been skipped for this iteration"
+        );
+        assert!(handle_skipcmd(&conn, &ctx, &cmd).is_err());
+
+        let task = crate::tests::insert_task(&conn, "work", "Regular", "today");
+        let cmd = SkipCommand {
+            index: ItemRef::Id(task),
+            comment: None,
+        };
+        assert!(handle_skipcmd(&conn, &ctx, &cmd).is_err());
+    }
+
+    #[test]
+    fn test_skip_is_not_a_completion() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let id = insert_recurring_task(&conn, "work", "Check-in", "Daily 9AM for 2 times");
+        let cmd = SkipCommand {
+            index: ItemRef::Id(id),
+            comment: None,
+        };
+        handle_skipcmd(&conn, &ctx, &cmd).unwrap();
+        let item = get_item(&conn, id).unwrap();
+        assert_eq!(item.status, 0);
+        assert_eq!(remaining_occurrences(&conn, &item).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_handle_snoozecmd() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let id = insert_recurring_task(&conn, "work", "Renew certificates", &far_schedule());
+        let later = (Local::now() + Duration::hours(1)).format("%Y-%m-%d %H:%M").to_string();
+        let cmd = SnoozeCommand {
+            index: ItemRef::Id(id),
+            timestr: later,
+        };
+        handle_snoozecmd(&conn, &ctx, &cmd).unwrap();
+
+        let item = get_item(&conn, id).unwrap();
+        let records = current_iteration_records(&conn, &item).unwrap();
+        assert_eq!(records[0].status, RECORD_SNOOZED);
+        assert!(is_iteration_handled(&conn, &item).unwrap());
+        // A snoozed iteration can still be completed or snoozed again
+        assert!(check_iteration_open(&conn, &item).is_ok());
+
+        // Once the snooze time passed the iteration is due again
+        let mut record = records[0].clone();
+        record.good_until = Some(Local::now().timestamp() - 60);
+        crate::db::crud::update_item(&conn, &record, None).unwrap();
+        assert!(!is_iteration_handled(&conn, &item).unwrap());
+
+        let past = SnoozeCommand {
+            index: ItemRef::Id(id),
+            timestr: "2020-01-01".to_string(),
+        };
+        assert!(handle_snoozecmd(&conn, &ctx, &past).is_err());
+        let too_late = SnoozeCommand {
+            index: ItemRef::Id(id),
+            timestr: (Local::now() + Duration::days(400)).format("%Y-%m-%d").to_string(),
+        };
+        assert_eq!(
+            handle_snoozecmd(&conn, &ctx, &too_late).unwrap_err(),
+            "Snooze time is past the next occurrence, use skip instead"
+        );
+    }
+
+    #[test]
+    fn test_snooze_after_skip() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let id = insert_recurring_task(&conn, "work", "Renew certificates", &far_schedule());
+        let record = insert_recurring_record(&conn, "work", "skipped", id, Local::now().timestamp() + 60);
+        update_status(&conn, record, RECORD_SKIPPED);
+
+        let cmd = SnoozeCommand {
+            index: ItemRef::Id(id),
+            timestr: (Local::now() + Duration::hours(1)).format("%Y-%m-%d %H:%M").to_string(),
+        };
+        assert!(handle_snoozecmd(&conn, &ctx, &cmd).is_err());
+    }
+
+    #[test]
+    fn test_remaining_occurrences_by_count() {
+        let (conn, _temp_file) = get_test_conn();
+        let unbounded = insert_recurring_task(&conn, "work", "Standup", "Daily 9AM");
+        assert_eq!(remaining_occurrences(&conn, &get_item(&conn, unbounded).unwrap()).unwrap(), None);
+
+        let id = insert_recurring_task(&conn, "work", "Sprint review", "Weekly Friday for 2 times");
+        assert_eq!(remaining_occurrences(&conn, &get_item(&conn, id).unwrap()).unwrap(), Some(2));
+        insert_recurring_record(&conn, "work", "done", id, 0);
+        assert_eq!(remaining_occurrences(&conn, &get_item(&conn, id).unwrap()).unwrap(), Some(1));
+        insert_recurring_record(&conn, "work", "done", id, 0);
+        assert_eq!(remaining_occurrences(&conn, &get_item(&conn, id).unwrap()).unwrap(), Some(0));
+    }
+
+    #[test]
+    fn test_remaining_occurrences_by_date() {
+        let (conn, _temp_file) = get_test_conn();
+        let schedule = format!("Daily until {}", date_in_days(9));
+        let id = insert_recurring_task(&conn, "work", "On-call handover", &schedule);
+        // Today's 11:59PM plus the 9 days after
+        let remaining = remaining_occurrences(&conn, &get_item(&conn, id).unwrap()).unwrap();
+        assert_eq!(remaining, Some(10));
+
+        // Completing the current interval uses up today's occurrence
+        let next = cron::get_next_occurrence("59 23 * * *").unwrap();
+        insert_recurring_record(&conn, "work", "done", id, next);
+        let remaining = remaining_occurrences(&conn, &get_item(&conn, id).unwrap()).unwrap();
+        assert_eq!(remaining, Some(9));
+
+        // The lower of both bounds wins
+        let schedule = format!("Daily until {} for 3 times", date_in_days(9));
+        let id = insert_recurring_task(&conn, "work", "Handover", &schedule);
+        assert_eq!(remaining_occurrences(&conn, &get_item(&conn, id).unwrap()).unwrap(), Some(3));
+    }
+
+    #[test]
+    fn test_skip_closes_exhausted_task() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let past = format!("Weekly until {}", date_in_days(-1));
+        let expired = insert_recurring_task(&conn, "work", "Old ritual", &past);
+        let ongoing = insert_recurring_task(&conn, "work", "Standup", "Daily for 5 times");
+
+        let cmd = SkipCommand {
+            index: ItemRef::Id(expired),
+            comment: None,
+        };
+        handle_skipcmd(&conn, &ctx, &cmd).unwrap();
+        assert_eq!(get_item(&conn, expired).unwrap().status, 1);
+        assert_eq!(get_item(&conn, ongoing).unwrap().status, 0);
+
+        // Already closed tasks are left alone
+        let mut item = get_item(&conn, expired).unwrap();
+        assert!(!close_if_exhausted(&conn, &mut item, None).unwrap());
+    }
+}
@@ -5,13 +5,16 @@ use rusqlite::Connection;
 use serde_json::json;
 
 use crate::{
+    actions::list::OPEN_STATUS_CODES,
     args::estimate::format_estimate,
     args::parser::{StatsCommand, TeamCommand, WorkloadCommand},
     context::Context,
     db::{
         crud::query_items,
-        item::{ItemQuery, TASK},
-        user::{get_user_by_name, list_users, User},
+        item::{Item, ItemQuery, TASK},
+        tag::mark_tags,
+        time_entry::spent_minutes_by_item,
+        user::{get_user_by_id, get_user_by_name, list_namespace_users, User},
     },
 };
 
@@ -35,13 +38,69 @@ struct WorkloadStats {
     total_minutes: i64,
 }
 
+/// Estimated vs actual effort of completed tasks that have both
+#[derive(Debug, Default, PartialEq)]
+struct EstimateStats {
+    tasks: usize,
+    estimated_minutes: i64,
+    spent_minutes: i64,
+    // Mean of min(estimate, spent) / max(estimate, spent) in percent, 100 is spot on
+    accuracy: Option<i64>,
+}
+
+/// Open and done task counts of a tag
+#[derive(Debug, PartialEq)]
+struct TagStats {
+    tag: String,
+    open: usize,
+    done: usize,
+}
+
+/// Base task query for reports, scoped to the current namespace, or to
+/// the namespaces the user is a member of with --all-namespaces.
+fn task_query(conn: &Connection, ctx: &Context) -> Result<ItemQuery<'static>, String> {
+    Ok(ItemQuery::new()
+        .with_action(TASK)
+        .with_namespace_ids(ctx.namespace_filter(conn)?))
+}
+
+/// Members of the namespaces reports are scoped to, and the assignees of
+/// `tasks` that are not, e.g. after leaving a namespace, sorted by name.
+fn report_users(conn: &Connection, ctx: &Context, tasks: &[Item]) -> Result<Vec<User>, String> {
+    let mut users = list_namespace_users(conn, &ctx.namespace_filter(conn)?)?;
+    for assignee_id in tasks.iter().filter_map(|task| task.assignee_id) {
+        if users.iter().any(|user| user.id == assignee_id) {
+            continue;
+        }
+        if let Some(user) = get_user_by_id(conn, assignee_id)? {
+            users.push(user);
+        }
+    }
+    users.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(users)
+}
+
 /// Handles the team command - shows task distribution by user
-pub fn handle_team(conn: &Connection, _ctx: &Context, cmd: &TeamCommand) -> Result<(), String> {
-    let users = list_users(conn)?;
+pub fn handle_team(conn: &Connection, ctx: &Context, cmd: &TeamCommand) -> Result<(), String> {
+    let active_stats = team_stats(conn, ctx)?;
+
+    if cmd.json {
+        print_team_json(&active_stats);
+    } else if cmd.md {
+        print_team_markdown(&active_stats);
+    } else {
+        print_team_text(&active_stats);
+    }
+
+    Ok(())
+}
 
+/// Open and done task counts of the users that have tasks in scope, unassigned last
+fn team_stats(conn: &Connection, ctx: &Context) -> Result<Vec<UserStats>, String> {
     // Query all tasks (open and done)
-    let all_tasks = query_items(conn, &ItemQuery::new().with_action(TASK))
+    let all_tasks = query_items(conn, &task_query(conn, ctx)?)
         .map_err(|e| format!("Failed to query tasks: {:?}", e))?;
+    let users = report_users(conn, ctx, &all_tasks)?;
 
     // Group by assignee
     let mut stats: HashMap<Option<i64>, UserStats> = HashMap::new();
@@ -99,19 +158,9 @@ pub fn handle_team(conn: &Connection, _ctx: &Context, cmd: &TeamCommand) -> Resu
     });
 
     // Filter out users with no tasks
-    let active_stats: Vec<_> = sorted_stats.into_iter()
+    Ok(sorted_stats.into_iter()
         .filter(|s| s.open_count > 0 || s.done_count > 0)
-        .collect();
-
-    if cmd.json {
-        print_team_json(&active_stats);
-    } else if cmd.md {
-        print_team_markdown(&active_stats);
-    } else {
-        print_team_text(&active_stats);
-    }
-
-    Ok(())
+        .collect())
 }
 
 fn print_team_text(stats: &[UserStats]) {
@@ -189,22 +238,21 @@ fn print_team_markdown(stats: &[UserStats]) {
 }
 
 /// Handles the workload command - shows estimated hours per user
-pub fn handle_workload(conn: &Connection, _ctx: &Context, cmd: &WorkloadCommand) -> Result<(), String> {
+pub fn handle_workload(conn: &Connection, ctx: &Context, cmd: &WorkloadCommand) -> Result<(), String> {
+    // Query open tasks only
+    let open_tasks = query_items(conn, &task_query(conn, ctx)?
+        .with_statuses(vec![0, 4, 6]))  // ongoing, suspended, pending
+        .map_err(|e| format!("Failed to query tasks: {:?}", e))?;
+
     // Get users to filter by
     let users: Vec<User> = if let Some(ref username) = cmd.user {
         let user = get_user_by_name(conn, username)?
             .ok_or_else(|| format!("User '{}' not found", username))?;
         vec![user]
     } else {
-        list_users(conn)?
+        report_users(conn, ctx, &open_tasks)?
     };
 
-    // Query open tasks only
-    let open_tasks = query_items(conn, &ItemQuery::new()
-        .with_action(TASK)
-        .with_statuses(vec![0, 4, 6]))  // ongoing, suspended, pending
-        .map_err(|e| format!("Failed to query tasks: {:?}", e))?;
-
     // Calculate workload per user
     let mut workload_stats: Vec<WorkloadStats> = Vec::new();
 
@@ -313,13 +361,14 @@ fn print_workload_markdown(stats: &[WorkloadStats]) {
 }
 
 /// Handles the stats command - shows completion rates and overdue analysis
-pub fn handle_stats(conn: &Connection, _ctx: &Context, cmd: &StatsCommand) -> Result<(), String> {
+pub fn handle_stats(conn: &Connection, ctx: &Context, cmd: &StatsCommand) -> Result<(), String> {
     let now = Local::now().timestamp();
     let cutoff = now - (cmd.days * 86400);
 
     // Query all tasks
-    let all_tasks = query_items(conn, &ItemQuery::new().with_action(TASK))
+    let mut all_tasks = query_items(conn, &task_query(conn, ctx)?)
         .map_err(|e| format!("Failed to query tasks: {:?}", e))?;
+    mark_tags(conn, &mut all_tasks)?;
 
     // Calculate stats
     let created_in_period: usize = all_tasks.iter()
@@ -352,22 +401,29 @@ pub fn handle_stats(conn: &Connection, _ctx: &Context, cmd: &StatsCommand) -> Re
         })
         .count();
 
+    // Estimate accuracy over completed tasks with tracked time
+    let completed: Vec<&Item> = all_tasks.iter()
+        .filter(|t| t.status == 1 && t.create_time >= cutoff)
+        .collect();
+    let estimates = estimate_stats(&completed, &spent_minutes_by_item(conn)?);
+
     // Status breakdown
     let ongoing: usize = all_tasks.iter().filter(|t| t.status == 0).count();
     let pending: usize = all_tasks.iter().filter(|t| t.status == 6).count();
     let suspended: usize = all_tasks.iter().filter(|t| t.status == 4).count();
     let done: usize = all_tasks.iter().filter(|t| t.status == 1).count();
     let cancelled: usize = all_tasks.iter().filter(|t| t.status == 2).count();
+    let tags = tag_stats(&all_tasks);
 
     if cmd.json {
         print_stats_json(cmd.days, created_in_period, completed_in_period, completion_rate,
-                         overdue, high_priority, ongoing, pending, suspended, done, cancelled);
+                         overdue, high_priority, ongoing, pending, suspended, done, cancelled, &estimates, &tags);
     } else if cmd.md {
         print_stats_markdown(cmd.days, created_in_period, completed_in_period, completion_rate,
-                            overdue, high_priority, ongoing, pending, suspended, done, cancelled);
+                            overdue, high_priority, ongoing, pending, suspended, done, cancelled, &estimates, &tags);
     } else {
         print_stats_text(cmd.days, created_in_period, completed_in_period, completion_rate,
-                        overdue, high_priority, ongoing, pending, suspended, done, cancelled);
+                        overdue, high_priority, ongoing, pending, suspended, done, cancelled, &estimates, &tags);
     }
 
     Ok(())
@@ -386,6 +442,8 @@ fn print_stats_text(
     suspended: usize,
     done: usize,
     cancelled: usize,
+    estimates: &EstimateStats,
+    tags: &[TagStats],
 ) {
     println!();
     println!("\x1b[1mTask Statistics (last {} days)\x1b[0m", days);
@@ -414,6 +472,22 @@ fn print_stats_text(
     println!("  suspended     {}", suspended);
     println!("  done          {}", done);
     println!("  cancelled     {}", cancelled);
+
+    if let Some(accuracy) = estimates.accuracy {
+        println!("{}", "━".repeat(40));
+        println!("Estimates ({} tracked tasks):", estimates.tasks);
+        println!("  estimated     {}", format_estimate(Some(estimates.estimated_minutes)));
+        println!("  spent         {}", format_estimate(Some(estimates.spent_minutes)));
+        println!("  accuracy      {}%", accuracy);
+    }
+
+    if !tags.is_empty() {
+        println!("{}", "━".repeat(40));
+        println!("By Tag:         open  done");
+        for stat in tags {
+            println!("  {:<12} {:>5} {:>5}", format!("+{}", stat.tag), stat.open, stat.done);
+        }
+    }
     println!();
 }
 
@@ -430,6 +504,8 @@ fn print_stats_json(
     suspended: usize,
     done: usize,
     cancelled: usize,
+    estimates: &EstimateStats,
+    tags: &[TagStats],
 ) {
     let output = json!({
         "period_days": days,
@@ -444,7 +520,18 @@ fn print_stats_json(
             "suspended": suspended,
             "done": done,
             "cancelled": cancelled
-        }
+        },
+        "estimates": {
+            "tasks": estimates.tasks,
+            "estimated_minutes": estimates.estimated_minutes,
+            "spent_minutes": estimates.spent_minutes,
+            "accuracy": estimates.accuracy
+        },
+        "by_tag": tags.iter().map(|t| json!({
+            "tag": t.tag,
+            "open": t.open,
+            "done": t.done
+        })).collect::<Vec<_>>()
     });
 
     println!("{}", serde_json::to_string_pretty(&output).unwrap());
@@ -463,6 +550,8 @@ fn print_stats_markdown(
     suspended: usize,
     done: usize,
     cancelled: usize,
+    estimates: &EstimateStats,
+    tags: &[TagStats],
 ) {
     println!("# Task Statistics (last {} days)\n", days);
     println!("| Metric | Value |");
@@ -480,12 +569,85 @@ fn print_stats_markdown(
     println!("| suspended | {} |", suspended);
     println!("| done | {} |", done);
     println!("| cancelled | {} |", cancelled);
+
+    if let Some(accuracy) = estimates.accuracy {
+        println!("\n## Estimates\n");
+        println!("| Metric | Value |");
+        println!("|--------|-------|");
+        println!("| Tracked Tasks | {} |", estimates.tasks);
+        println!("| Estimated | {} |", format_estimate(Some(estimates.estimated_minutes)));
+        println!("| Spent | {} |", format_estimate(Some(estimates.spent_minutes)));
+        println!("| Accuracy | {}% |", accuracy);
+    }
+
+    if !tags.is_empty() {
+        println!("\n## By Tag\n");
+        println!("| Tag | Open | Done |");
+        println!("|-----|------|------|");
+        for stat in tags {
+            println!("| +{} | {} | {} |", stat.tag, stat.open, stat.done);
+        }
+    }
+}
+
+fn estimate_stats(tasks: &[&Item], spent: &HashMap<i64, i64>) -> EstimateStats {
+    let mut stats = EstimateStats::default();
+    let mut ratio_sum = 0.0;
+    for task in tasks {
+        let estimate = task.estimate_minutes.unwrap_or(0);
+        let actual = task.id.and_then(|id| spent.get(&id).copied()).unwrap_or(0);
+        if estimate <= 0 || actual <= 0 {
+            continue;
+        }
+        stats.tasks += 1;
+        stats.estimated_minutes += estimate;
+        stats.spent_minutes += actual;
+        ratio_sum += estimate.min(actual) as f64 / estimate.max(actual) as f64;
+    }
+    if stats.tasks > 0 {
+        stats.accuracy = Some((ratio_sum / stats.tasks as f64 * 100.0).round() as i64);
+    }
+    stats
+}
+
+// Counts open and done tasks per tag, busiest tags first.
+fn tag_stats(tasks: &[Item]) -> Vec<TagStats> {
+    let mut by_tag: HashMap<&str, TagStats> = HashMap::new();
+    for task in tasks {
+        let open = OPEN_STATUS_CODES.contains(&task.status);
+        if !open && task.status != 1 {
+            continue;
+        }
+        for tag in &task.tags {
+            let stat = by_tag.entry(tag).or_insert_with(|| TagStats {
+                tag: tag.clone(),
+                open: 0,
+                done: 0,
+            });
+            if open {
+                stat.open += 1;
+            } else {
+                stat.done += 1;
+            }
+        }
+    }
+
+    let mut stats: Vec<TagStats> = by_tag.into_values().collect();
+    stats.sort_by(|a, b| (b.open + b.done).cmp(&(a.open + a.done)).then_with(|| a.tag.cmp(&b.tag)));
+    stats
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tests::{get_test_conn, insert_task};
+    use crate::{
+        db::{
+            crud::{get_item, update_item},
+            namespace::{add_user_to_namespace, create_namespace, get_namespace_by_name},
+            user::create_user,
+        },
+        tests::{get_test_conn, insert_task},
+    };
 
     #[test]
     fn test_handle_team() {
@@ -586,6 +748,114 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_task_query_namespace_isolation() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let work_ns = create_namespace(&conn, "work", None, ctx.current_user_id).unwrap();
+
+        insert_task(&conn, "work", "Default task", "today");
+        let work_task = insert_task(&conn, "work", "Work task", "today");
+        let mut item = get_item(&conn, work_task).unwrap();
+        item.namespace_id = Some(work_ns);
+        update_item(&conn, &item, None).unwrap();
+
+        // A namespace the default user is not a member of
+        create_user(&conn, "eve", None, None).unwrap();
+        let eve = get_user_by_name(&conn, "eve").unwrap().unwrap();
+        let eve_ns = create_namespace(&conn, "eve-private", None, eve.id).unwrap();
+        let mut item = get_item(&conn, insert_task(&conn, "work", "Eve's task", "today")).unwrap();
+        item.namespace_id = Some(eve_ns);
+        update_item(&conn, &item, None).unwrap();
+
+        let tasks = query_items(&conn, &task_query(&conn, &ctx).unwrap()).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].content, "Default task");
+
+        let ctx = ctx.with_all_namespaces(true);
+        let tasks = query_items(&conn, &task_query(&conn, &ctx).unwrap()).unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert!(tasks.iter().all(|task| task.namespace_id != Some(eve_ns)));
+    }
+
+    #[test]
+    fn test_team_stats_scoped_to_members() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        create_namespace(&conn, "team", None, ctx.current_user_id).unwrap();
+        let ann = create_user(&conn, "ann", Some("Ann"), None).unwrap();
+        let bob = create_user(&conn, "bob", Some("Bob"), None).unwrap();
+        add_user_to_namespace(&conn, "team", "ann", "member").unwrap();
+
+        // Neither is a member of default, the tasks they are assigned there still count
+        let assign = |content: &str, assignee_id: i64| {
+            let mut item = get_item(&conn, insert_task(&conn, "work", content, "today")).unwrap();
+            item.assignee_id = Some(assignee_id);
+            update_item(&conn, &item, None).unwrap();
+        };
+        assign("Bob's task", bob);
+        assign("Ann's task", ann);
+        let mut item = get_item(&conn, insert_task(&conn, "work", "Team task", "today")).unwrap();
+        item.namespace_id = get_namespace_by_name(&conn, "team").unwrap().map(|ns| ns.id);
+        item.assignee_id = Some(ann);
+        update_item(&conn, &item, None).unwrap();
+
+        let names = |ctx: &Context| -> Vec<(String, i64)> {
+            let stats = team_stats(&conn, ctx).unwrap();
+            stats.into_iter().map(|s| (s.display_name, s.open_count)).collect()
+        };
+        assert_eq!(names(&ctx), vec![("Ann".to_string(), 1), ("Bob".to_string(), 1)]);
+        let team = Context::resolve_with_config(&conn, None, Some("team"), None).unwrap();
+        assert_eq!(names(&team), vec![("Ann".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_estimate_stats() {
+        let task = |id: i64, estimate: Option<i64>| {
+            let mut item = Item::new("task".to_string(), "work".to_string(), "t".to_string());
+            item.id = Some(id);
+            item.estimate_minutes = estimate;
+            item
+        };
+        let tasks = [task(1, Some(60)), task(2, Some(60)), task(3, None), task(4, Some(30))];
+        let refs: Vec<&Item> = tasks.iter().collect();
+        // Task 3 has no estimate and task 4 no tracked time, both are left out
+        let spent = HashMap::from([(1, 60), (2, 120), (3, 45)]);
+
+        let stats = estimate_stats(&refs, &spent);
+        assert_eq!(stats, EstimateStats {
+            tasks: 2,
+            estimated_minutes: 120,
+            spent_minutes: 180,
+            accuracy: Some(75),
+        });
+        assert_eq!(estimate_stats(&[], &spent).accuracy, None);
+    }
+
+    #[test]
+    fn test_tag_stats() {
+        let task = |status: u8, tags: &[&str]| {
+            let mut item = Item::new("task".to_string(), "work".to_string(), "t".to_string());
+            item.status = status;
+            item.tags = tags.iter().map(|tag| tag.to_string()).collect();
+            item
+        };
+        // The cancelled task is left out
+        let tasks = [
+            task(0, &["backend", "security"]),
+            task(1, &["backend"]),
+            task(6, &["docs"]),
+            task(2, &["docs"]),
+        ];
+
+        let stats = tag_stats(&tasks);
+        assert_eq!(stats, vec![
+            TagStats { tag: "backend".to_string(), open: 1, done: 1 },
+            TagStats { tag: "docs".to_string(), open: 1, done: 0 },
+            TagStats { tag: "security".to_string(), open: 1, done: 0 },
+        ]);
+    }
+
     #[test]
     fn test_handle_stats_custom_days() {
         let (conn, _temp_file) = get_test_conn();
@@ -0,0 +1,168 @@
+use rusqlite::Connection;
+
+use crate::{
+    actions::{display, show::format_status},
+    args::parser::SearchCommand,
+    context::Context,
+    db::{
+        archive::open_archive,
+        cache,
+        item::{Item, RECORD, RECURRING_TASK, RECURRING_TASK_RECORD, TASK},
+        search::{search, SearchHit},
+    },
+};
+
+/// Handles the search command - full-text search across the current namespace.
+/// Results are cached like a listing so their indexes can be used right after.
+pub fn handle_searchcmd(conn: &Connection, ctx: &Context, cmd: &SearchCommand) -> Result<(), String> {
+    let query = cmd.query.join(" ");
+    if cmd.archived {
+        return handle_archived_search(conn, ctx, cmd, &query);
+    }
+    let hits = search(conn, &query, Some(&ctx.namespace_filter(conn)?), cmd.limit)?;
+    // Indexes of an earlier listing must not outlive the results
+    cache::clear(conn).map_err(|e| e.to_string())?;
+
+    if hits.is_empty() && ctx.output.is_table() {
+        println!("No matches for '{}'", query);
+        return Ok(());
+    }
+
+    let items: Vec<Item> = hits.iter().map(|hit| hit.item.clone()).collect();
+    cache::store(conn, &items).map_err(|e| format!("Failed to store cache: {:?}", e))?;
+    if !ctx.output.is_table() {
+        return display::output_items(conn, ctx, "", &items, false, true);
+    }
+
+    println!("\x1b[1mSearch results for '{}'\x1b[0m", query);
+    println!("{}", "━".repeat(50));
+    for (index, hit) in hits.iter().enumerate() {
+        print_hit(Some(index + 1), hit);
+    }
+    Ok(())
+}
+
+// Archived items can't be changed, so their results get no indexes
+fn handle_archived_search(conn: &Connection, ctx: &Context, cmd: &SearchCommand, query: &str) -> Result<(), String> {
+    let archive = open_archive(conn)?;
+    let hits = search(&archive, query, Some(&ctx.namespace_filter(conn)?), cmd.limit)?;
+    if !ctx.output.is_table() {
+        let items: Vec<Item> = hits.iter().map(|hit| hit.item.clone()).collect();
+        return display::output_items(&archive, ctx, "", &items, false, false);
+    }
+
+    if hits.is_empty() {
+        println!("No archived matches for '{}'", query);
+        return Ok(());
+    }
+    println!("\x1b[1mArchived results for '{}'\x1b[0m", query);
+    println!("{}", "━".repeat(50));
+    for hit in &hits {
+        print_hit(None, hit);
+    }
+    Ok(())
+}
+
+fn print_hit(index: Option<usize>, hit: &SearchHit) {
+    let item = &hit.item;
+    let kind = match item.action.as_str() {
+        TASK => "task",
+        RECURRING_TASK => "recurring task",
+        RECORD => "record",
+        RECURRING_TASK_RECORD => "recurring record",
+        other => other,
+    };
+    let status = match item.action.as_str() {
+        TASK | RECURRING_TASK => format!(" ({})", format_status(item.status)),
+        _ => String::new(),
+    };
+
+    println!(
+        "{:>3}  \x1b[90m#{} {} · {}{}\x1b[0m  {}",
+        index.map(|index| index.to_string()).unwrap_or_default(),
+        item.id.unwrap(),
+        kind,
+        item.category,
+        status,
+        item.content.lines().next().unwrap_or(&item.content)
+    );
+    println!(
+        "     \x1b[90m[{}]\x1b[0m {}",
+        hit.fields.join(", "),
+        hit.snippet.replace('\n', " ")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actions::list::handle_listtasks,
+        args::parser::ListTaskCommand,
+        db::note::add_note,
+        tests::{get_test_conn, insert_task},
+    };
+
+    #[test]
+    fn test_handle_searchcmd_caches_results() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        insert_task(&conn, "work", "Unrelated", "today");
+        let id = insert_task(&conn, "work", "Review pull requests", "today");
+        add_note(&conn, id, "waiting on the billing migration", None).unwrap();
+
+        let cmd = SearchCommand {
+            query: vec!["billing".to_string()],
+            limit: 20,
+            archived: false,
+        };
+        handle_searchcmd(&conn, &ctx, &cmd).unwrap();
+        assert_eq!(cache::read(&conn, 1).unwrap(), Some(id));
+        assert_eq!(cache::read(&conn, 2).unwrap(), None);
+
+        let cmd = SearchCommand {
+            query: vec!["\"".to_string()],
+            limit: 20,
+            archived: false,
+        };
+        assert!(handle_searchcmd(&conn, &ctx, &cmd).is_err());
+    }
+
+    #[test]
+    fn test_handle_searchcmd_replaces_listing_cache() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let id = insert_task(&conn, "work", "Fix the billing export", "today");
+        insert_task(&conn, "work", "Plan the offsite", "tomorrow");
+        insert_task(&conn, "work", "Renew the certificates", "in 2 days");
+
+        let list = ListTaskCommand {
+            timestr: None,
+            category: None,
+            days: None,
+            status: 0,
+            overdue: false,
+            limit: 100,
+            next_page: false,
+            search: None,
+            user: None,
+            all_users: false,
+            ready: false,
+            tags: Vec::new(),
+            not_tags: Vec::new(),
+            sort: None,
+            reverse: false,
+        };
+        handle_listtasks(&conn, &ctx, list).unwrap();
+        assert!(cache::read(&conn, 3).unwrap().is_some());
+
+        let cmd = SearchCommand {
+            query: vec!["billing".to_string()],
+            limit: 20,
+            archived: false,
+        };
+        handle_searchcmd(&conn, &ctx, &cmd).unwrap();
+        assert_eq!(cache::read(&conn, 1).unwrap(), Some(id));
+        assert_eq!(cache::read(&conn, 2).unwrap(), None);
+    }
+}
@@ -1,31 +1,42 @@
-use chrono::{Local, TimeZone};
 use rusqlite::Connection;
 
 use crate::{
-    args::estimate::format_estimate,
+    actions::{
+        display,
+        list::CLOSED_STATUS_CODES,
+        recurrence::remaining_occurrences,
+        timetrack::{format_spent, spent_minutes},
+    },
+    args::{estimate::format_estimate, itemref::{resolve_rowid, ItemRef}, timezone},
+    context::Context,
     db::{
-        cache,
-        crud::get_item,
+        crud::{get_item, get_stored_item, get_subtasks},
+        dependency::{get_dependencies, get_dependents},
         item::{Item, RECORD, RECURRING_TASK, RECURRING_TASK_RECORD, TASK},
         link::get_links_for_item,
         note::get_notes_for_item,
+        tag::get_tags_for_item,
         user::get_user_by_id,
     },
 };
 
 /// Handles the show command - displays detailed view of a task
-pub fn handle_showcmd(conn: &Connection, index: usize) -> Result<(), String> {
-    validate_cache(conn)?;
-    let row_id = get_rowid_from_cache(conn, index)?;
+pub fn handle_showcmd(conn: &Connection, ctx: &Context, item_ref: ItemRef) -> Result<(), String> {
+    let row_id = resolve_rowid(conn, ctx, item_ref)?;
 
     let item = get_item(conn, row_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+    ctx.check_namespace(conn, &item)?;
 
-    print_detailed_view(conn, &item, index)?;
+    if !ctx.output.is_table() {
+        let is_record = item.action == RECORD || item.action == RECURRING_TASK_RECORD;
+        return display::output_items(conn, ctx, "", &[item], is_record, false);
+    }
+    print_detailed_view(conn, &item, row_id)?;
 
     Ok(())
 }
 
-fn print_detailed_view(conn: &Connection, item: &Item, index: usize) -> Result<(), String> {
+fn print_detailed_view(conn: &Connection, item: &Item, item_id: i64) -> Result<(), String> {
     let action_label = match item.action.as_str() {
         TASK => "Task",
         RECURRING_TASK => "Recurring Task",
@@ -36,13 +47,18 @@ fn print_detailed_view(conn: &Connection, item: &Item, index: usize) -> Result<(
 
     // Header
     println!();
-    println!("\x1b[1m{} #{}: {}\x1b[0m", action_label, index, item.content.lines().next().unwrap_or(&item.content));
+    println!("\x1b[1m{} #{}: {}\x1b[0m", action_label, item_id, item.content.lines().next().unwrap_or(&item.content));
     println!("{}", "━".repeat(50));
 
     // Basic fields
     println!("  \x1b[90mPriority:\x1b[0m   {}", format_priority_colored(item.priority));
     println!("  \x1b[90mStatus:\x1b[0m     {}", format_status(item.status));
     println!("  \x1b[90mCategory:\x1b[0m   {}", item.category);
+    let tags = get_tags_for_item(conn, item_id)?;
+    if !tags.is_empty() {
+        let tags = tags.iter().map(|tag| format!("+{}", tag)).collect::<Vec<_>>();
+        println!("  \x1b[90mTags:\x1b[0m       {}", tags.join(" "));
+    }
 
     // Owner
     if let Some(owner_id) = item.owner_id {
@@ -62,6 +78,17 @@ fn print_detailed_view(conn: &Connection, item: &Item, index: usize) -> Result<(
         println!("  \x1b[90mAssignee:\x1b[0m   \x1b[33munassigned\x1b[0m");
     }
 
+    // Parent
+    if let Some(parent_id) = item.parent_id {
+        let parent = get_stored_item(conn, parent_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+        println!(
+            "  \x1b[90mParent:\x1b[0m     #{} {}{}",
+            parent_id,
+            parent.content.lines().next().unwrap_or(&parent.content),
+            if parent.deleted_at.is_some() { " (in the trash)" } else { "" }
+        );
+    }
+
     // Project
     if let Some(ref project) = item.project {
         println!("  \x1b[90mProject:\x1b[0m    {}", project);
@@ -72,6 +99,12 @@ fn print_detailed_view(conn: &Connection, item: &Item, index: usize) -> Result<(
         if let Some(ref schedule) = item.human_schedule {
             println!("  \x1b[90mSchedule:\x1b[0m   {}", schedule);
         }
+        match remaining_occurrences(conn, item)? {
+            Some(0) => println!("  \x1b[90mRemaining:\x1b[0m  none, ended"),
+            Some(1) => println!("  \x1b[90mRemaining:\x1b[0m  1 occurrence"),
+            Some(n) => println!("  \x1b[90mRemaining:\x1b[0m  {} occurrences", n),
+            None => {}
+        }
     } else if let Some(target_time) = item.target_time {
         println!("  \x1b[90mDue:\x1b[0m        {}", format_timestamp_relative(target_time));
     }
@@ -82,6 +115,13 @@ fn print_detailed_view(conn: &Connection, item: &Item, index: usize) -> Result<(
         println!("  \x1b[90mEstimate:\x1b[0m   {}", estimate_str);
     }
 
+    // Time spent
+    let (spent, running) = spent_minutes(conn, item_id)?;
+    if spent > 0 || running {
+        let running_str = if running { " \x1b[32m(timer running)\x1b[0m" } else { "" };
+        println!("  \x1b[90mSpent:\x1b[0m      {}{}", format_spent(spent, item.estimate_minutes), running_str);
+    }
+
     // Reminder
     if let Some(reminder) = item.reminder_days {
         println!("  \x1b[90mReminder:\x1b[0m   {} days before", reminder);
@@ -105,6 +145,13 @@ fn print_detailed_view(conn: &Connection, item: &Item, index: usize) -> Result<(
         }
     }
 
+    // Subtasks
+    print_subtasks(conn, item_id)?;
+
+    // Dependencies
+    print_dependencies(conn, "Blocked by", &get_dependencies(conn, item_id)?)?;
+    print_dependencies(conn, "Blocks", &get_dependents(conn, item_id)?)?;
+
     // Notes
     let notes = get_notes_for_item(conn, item.id.unwrap())?;
     if !notes.is_empty() {
@@ -158,7 +205,7 @@ fn format_priority_colored(priority: Option<u8>) -> String {
     }
 }
 
-fn format_status(status: u8) -> &'static str {
+pub fn format_status(status: u8) -> &'static str {
     match status {
         0 => "ongoing",
         1 => "done",
@@ -172,16 +219,16 @@ fn format_status(status: u8) -> &'static str {
 }
 
 fn format_timestamp(timestamp: i64) -> String {
-    match Local.timestamp_opt(timestamp, 0) {
-        chrono::LocalResult::Single(dt) => dt.format("%Y-%m-%d %H:%M").to_string(),
-        _ => "unknown".to_string(),
+    match timezone::datetime(timestamp) {
+        Some(dt) => dt.format("%Y-%m-%d %H:%M").to_string(),
+        None => "unknown".to_string(),
     }
 }
 
 fn format_timestamp_short(timestamp: i64) -> String {
-    match Local.timestamp_opt(timestamp, 0) {
-        chrono::LocalResult::Single(dt) => dt.format("%b %d, %H:%M").to_string(),
-        _ => "unknown".to_string(),
+    match timezone::datetime(timestamp) {
+        Some(dt) => dt.format("%b %d, %H:%M").to_string(),
+        None => "unknown".to_string(),
     }
 }
 
@@ -194,9 +241,9 @@ fn format_timestamp_relative(timestamp: i64) -> String {
     let diff = timestamp - now;
     let days = diff / 86400;
 
-    let date_str = match Local.timestamp_opt(timestamp, 0) {
-        chrono::LocalResult::Single(dt) => dt.format("%Y-%m-%d").to_string(),
-        _ => "unknown".to_string(),
+    let date_str = match timezone::datetime(timestamp) {
+        Some(dt) => dt.format("%Y-%m-%d").to_string(),
+        None => "unknown".to_string(),
     };
 
     if diff < 0 {
@@ -219,20 +266,42 @@ fn format_timestamp_relative(timestamp: i64) -> String {
     }
 }
 
-fn validate_cache(conn: &Connection) -> Result<(), String> {
-    match cache::validate_cache(conn) {
-        Ok(true) => Ok(()),
-        Ok(false) => Err("Cache is not valid, consider running list command first".to_string()),
-        Err(_) => Err("Cannot connect to cache".to_string()),
+fn print_subtasks(conn: &Connection, item_id: i64) -> Result<(), String> {
+    let subtasks = get_subtasks(conn, item_id).map_err(|e| format!("Failed to get subtasks: {:?}", e))?;
+    if subtasks.is_empty() {
+        return Ok(());
+    }
+    let closed = subtasks.iter().filter(|t| CLOSED_STATUS_CODES.contains(&t.status)).count();
+    println!();
+    println!("\x1b[90mSubtasks ({}/{}):\x1b[0m", closed, subtasks.len());
+    for subtask in &subtasks {
+        let mark = if subtask.status == 1 { "x" } else if CLOSED_STATUS_CODES.contains(&subtask.status) { "-" } else { " " };
+        println!(
+            "  [{}] #{} {}",
+            mark,
+            subtask.id.unwrap(),
+            subtask.content.lines().next().unwrap_or(&subtask.content)
+        );
     }
+    Ok(())
 }
 
-fn get_rowid_from_cache(conn: &Connection, index: usize) -> Result<i64, String> {
-    let index = index as i64;
-    match cache::read(conn, index).map_err(|e| format!("Failed to read cache table: {:?}", e))? {
-        Some(id) => Ok(id),
-        None => Err(format!("index {} does not exist", index)),
+fn print_dependencies(conn: &Connection, label: &str, ids: &[i64]) -> Result<(), String> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    println!();
+    println!("\x1b[90m{}:\x1b[0m", label);
+    for id in ids {
+        let dep = get_item(conn, *id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+        println!(
+            "  #{} {} ({})",
+            id,
+            dep.content.lines().next().unwrap_or(&dep.content),
+            format_status(dep.status)
+        );
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -252,19 +321,21 @@ mod tests {
     #[test]
     fn test_handle_showcmd() {
         let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
         insert_task(&conn, "work", "Test task for show", "today");
 
         let items = query_items(&conn, &ItemQuery::new().with_action(TASK)).unwrap();
         cache::store(&conn, &items).unwrap();
 
         // Should succeed
-        let result = handle_showcmd(&conn, 1);
+        let result = handle_showcmd(&conn, &ctx, ItemRef::Index(1));
         assert!(result.is_ok());
     }
 
     #[test]
     fn test_handle_showcmd_with_notes_and_links() {
         let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
         let task_id = insert_task(&conn, "work", "Complex task", "tomorrow");
 
         // Add notes
@@ -278,19 +349,20 @@ mod tests {
         let items = query_items(&conn, &ItemQuery::new().with_action(TASK)).unwrap();
         cache::store(&conn, &items).unwrap();
 
-        let result = handle_showcmd(&conn, 1);
+        let result = handle_showcmd(&conn, &ctx, ItemRef::Index(1));
         assert!(result.is_ok());
     }
 
     #[test]
     fn test_handle_showcmd_invalid_index() {
         let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
         insert_task(&conn, "work", "Test task", "today");
 
         let items = query_items(&conn, &ItemQuery::new().with_action(TASK)).unwrap();
         cache::store(&conn, &items).unwrap();
 
-        let result = handle_showcmd(&conn, 99);
+        let result = handle_showcmd(&conn, &ctx, ItemRef::Index(99));
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("does not exist"));
     }
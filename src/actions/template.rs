@@ -0,0 +1,384 @@
+use std::collections::{BTreeSet, HashMap};
+
+use rusqlite::Connection;
+
+use crate::{
+    actions::display,
+    args::{
+        estimate::parse_estimate,
+        parser::{TemplateApplyCommand, TemplateCommand},
+        priority::parse_priority,
+        tag::extract_tags,
+        timestr,
+    },
+    config::{get_project, get_template, list_templates, TemplateConfig},
+    context::{Context, Permission},
+    db::{
+        crud::insert_item,
+        dependency::{add_dependency, mark_blocked},
+        item::{Item, TASK},
+        tag::add_tags,
+        user::get_user_by_name,
+    },
+};
+
+/// Handles the template command - lists the templates in the config or applies one
+pub fn handle_templatecmd(conn: &Connection, ctx: &Context, cmd: &TemplateCommand) -> Result<(), String> {
+    match cmd {
+        TemplateCommand::List => handle_template_list(ctx),
+        TemplateCommand::Apply(cmd) => handle_template_apply(conn, ctx, cmd),
+    }
+}
+
+fn handle_template_list(ctx: &Context) -> Result<(), String> {
+    let templates = list_templates();
+
+    if !ctx.output.is_table() {
+        let templates: Vec<_> = templates
+            .iter()
+            .map(|(name, template)| {
+                serde_json::json!({
+                    "name": name,
+                    "description": template.description,
+                    "tasks": template.tasks.len(),
+                    "variables": template_vars(template),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::json!({ "templates": templates }));
+        return Ok(());
+    }
+
+    if templates.is_empty() {
+        println!("No templates yet, add them under \"templates\" in ~/.config/ctm/config.json");
+        return Ok(());
+    }
+
+    display::print_bold("Templates");
+    println!("{:<16} {:<6} {:<24} Description", "Name", "Tasks", "Variables");
+    println!("{}", "-".repeat(72));
+    for (name, template) in &templates {
+        println!(
+            "{:<16} {:<6} {:<24} {}",
+            name,
+            template.tasks.len(),
+            template_vars(template).join(", "),
+            template.description.as_deref().unwrap_or("")
+        );
+    }
+    Ok(())
+}
+
+fn handle_template_apply(conn: &Connection, ctx: &Context, cmd: &TemplateApplyCommand) -> Result<(), String> {
+    ctx.require(Permission::EditItems)?;
+    let template = get_template(&cmd.name).ok_or_else(|| {
+        format!(
+            "Template '{}' not found in config. Add it under \"templates\" in ~/.config/ctm/config.json",
+            cmd.name
+        )
+    })?;
+
+    let items = apply_template(conn, ctx, &cmd.name, &template, &cmd.vars, cmd.assignee.as_deref())?;
+    display::output_items(conn, ctx, &format!("Applied template '{}':", cmd.name), &items, false, false)
+}
+
+/// Creates the tasks of a template in one transaction, preceded by their parent task
+/// when the template has a title. `assignee` wins over the assignees in the template.
+pub fn apply_template(
+    conn: &Connection,
+    ctx: &Context,
+    name: &str,
+    template: &TemplateConfig,
+    vars: &[(String, String)],
+    assignee: Option<&str>,
+) -> Result<Vec<Item>, String> {
+    if template.tasks.is_empty() {
+        return Err(format!("Template '{}' has no tasks", name));
+    }
+
+    let mut values: HashMap<String, String> = template.vars.clone().unwrap_or_default();
+    if let Some(user) = assignee {
+        values.insert("for".to_string(), user.to_string());
+    }
+    values.extend(vars.iter().cloned());
+    let fill = |text: &str| {
+        fill_vars(text, &values)
+            .map_err(|var| format!("Template '{}' uses {{{}}}, give it with --var {}=...", name, var, var))
+    };
+    let assignee_id = resolve_user(conn, assignee)?;
+
+    // Every task is built before any is inserted, so a mistake in the template changes nothing
+    let mut tasks: Vec<(Item, Vec<String>)> = Vec::with_capacity(template.tasks.len());
+    for (i, task) in template.tasks.iter().enumerate() {
+        let context = |e: String| format!("Task {} of template '{}': {}", i + 1, name, e);
+
+        let (content, tags) = extract_tags(&fill(&task.content)?);
+        if content.is_empty() {
+            return Err(context("content cannot be empty".to_string()));
+        }
+        let category = fill(field(&task.category, &template.category).unwrap_or("default"))?;
+        let due = fill(field(&task.due, &template.due).unwrap_or("today"))?;
+        let target_time = timestr::to_unix_epoch(&due).map_err(context)?;
+
+        let mut item = Item::with_target_time(TASK.to_string(), category, content, Some(target_time));
+        item.owner_id = Some(ctx.current_user_id);
+        item.namespace_id = Some(ctx.current_namespace_id);
+        item.priority = match field(&task.priority, &template.priority) {
+            Some(priority) => Some(parse_priority(&fill(priority)?).map_err(context)?),
+            None => None,
+        };
+        item.estimate_minutes = match field(&task.estimate, &template.estimate) {
+            Some(estimate) => Some(parse_estimate(&fill(estimate)?).map_err(context)?),
+            None => None,
+        };
+        item.project = match field(&task.project, &template.project) {
+            Some(project) => Some(check_project(&fill(project)?).map_err(context)?),
+            None => None,
+        };
+        item.assignee_id = match (assignee_id, &task.assignee) {
+            (Some(id), _) => Some(id),
+            (None, Some(user)) => resolve_user(conn, Some(&fill(user)?)).map_err(context)?,
+            (None, None) => None,
+        };
+
+        // Only earlier tasks, which also rules out cycles
+        for &position in task.after.iter().flatten() {
+            if position < 1 || position > i {
+                return Err(context(format!(
+                    "after {} must be the position of an earlier task, from 1",
+                    position
+                )));
+            }
+        }
+        tasks.push((item, tags));
+    }
+
+    let parent = match &template.title {
+        Some(title) => {
+            let (content, tags) = extract_tags(&fill(title)?);
+            let category = fill(template.category.as_deref().unwrap_or("default"))?;
+            // Due when the last of its tasks is
+            let target_time = tasks.iter().filter_map(|(item, _)| item.target_time).max();
+            let mut item = Item::with_target_time(TASK.to_string(), category, content, target_time);
+            item.owner_id = Some(ctx.current_user_id);
+            item.namespace_id = Some(ctx.current_namespace_id);
+            item.assignee_id = assignee_id;
+            item.project = match &template.project {
+                Some(project) => Some(check_project(&fill(project)?)?),
+                None => None,
+            };
+            item.priority = tasks.iter().filter_map(|(item, _)| item.priority).min();
+            Some((item, tags))
+        }
+        None => None,
+    };
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut created: Vec<Item> = Vec::with_capacity(tasks.len() + 1);
+    let parent_id = match parent {
+        Some((item, tags)) => Some(insert_with_tags(&tx, ctx, item, tags, &mut created)?),
+        None => None,
+    };
+    let mut ids: Vec<i64> = Vec::with_capacity(tasks.len());
+    for (mut item, tags) in tasks {
+        item.parent_id = parent_id;
+        ids.push(insert_with_tags(&tx, ctx, item, tags, &mut created)?);
+    }
+    for (task, &id) in template.tasks.iter().zip(&ids) {
+        for &position in task.after.iter().flatten() {
+            add_dependency(&tx, id, ids[position - 1], Some(ctx.current_user_id))?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    mark_blocked(conn, &mut created)?;
+    Ok(created)
+}
+
+fn insert_with_tags(
+    conn: &Connection,
+    ctx: &Context,
+    mut item: Item,
+    tags: Vec<String>,
+    created: &mut Vec<Item>,
+) -> Result<i64, String> {
+    let id = insert_item(conn, &item, Some(ctx.current_user_id)).map_err(|e| e.to_string())?;
+    add_tags(conn, id, &tags)?;
+    item.id = Some(id);
+    item.tags = tags;
+    created.push(item);
+    Ok(id)
+}
+
+// The task's own value of a field, or the template's
+fn field<'a>(task: &'a Option<String>, template: &'a Option<String>) -> Option<&'a str> {
+    task.as_deref().or(template.as_deref())
+}
+
+fn resolve_user(conn: &Connection, name: Option<&str>) -> Result<Option<i64>, String> {
+    match name {
+        Some(name) => {
+            let user = get_user_by_name(conn, name)?.ok_or_else(|| format!("User '{}' not found", name))?;
+            Ok(Some(user.id))
+        }
+        None => Ok(None),
+    }
+}
+
+fn check_project(project: &str) -> Result<String, String> {
+    if get_project(project).is_none() {
+        return Err(format!(
+            "Project '{}' not found in config. Add it to ~/.config/ctm/config.json",
+            project
+        ));
+    }
+    Ok(project.to_string())
+}
+
+/// Replaces every `{name}` in `text` with its value, braces around anything
+/// that isn't a variable name are kept. Fails with the name of a variable without a value.
+fn fill_vars(text: &str, values: &HashMap<String, String>) -> Result<String, String> {
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        match rest.find('}').map(|end| (&rest[..end], end)) {
+            Some((name, end)) if is_var_name(name) => {
+                filled.push_str(values.get(name).ok_or_else(|| name.to_string())?);
+                rest = &rest[end + 1..];
+            }
+            _ => filled.push('{'),
+        }
+    }
+    filled.push_str(rest);
+    Ok(filled)
+}
+
+fn is_var_name(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+// The variables a template refers to, sorted
+fn template_vars(template: &TemplateConfig) -> Vec<String> {
+    let mut texts: Vec<&Option<String>> = vec![
+        &template.title,
+        &template.category,
+        &template.priority,
+        &template.estimate,
+        &template.project,
+        &template.due,
+    ];
+    for task in &template.tasks {
+        texts.extend([&task.category, &task.priority, &task.estimate, &task.project, &task.due, &task.assignee]);
+    }
+    let contents = template.tasks.iter().map(|task| task.content.as_str());
+
+    let mut vars: BTreeSet<String> = BTreeSet::new();
+    for text in texts.into_iter().flatten().map(String::as_str).chain(contents) {
+        // Collect the names fill_vars would ask for
+        let mut names = HashMap::new();
+        while let Err(name) = fill_vars(text, &names) {
+            names.insert(name.clone(), String::new());
+            vars.insert(name);
+        }
+    }
+    vars.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::TemplateTask,
+        db::{
+            crud::{get_item, query_items},
+            dependency::get_dependencies,
+            item::ItemQuery,
+            user::create_user,
+        },
+        tests::get_test_conn,
+    };
+
+    fn release_template() -> TemplateConfig {
+        let task = |content: &str, after: Option<Vec<usize>>| TemplateTask {
+            content: content.to_string(),
+            after,
+            ..Default::default()
+        };
+        TemplateConfig {
+            title: Some("Release {version}".to_string()),
+            category: Some("release".to_string()),
+            due: Some("in 2 days".to_string()),
+            vars: Some(HashMap::from([("channel".to_string(), "#general".to_string())])),
+            tasks: vec![
+                TemplateTask {
+                    priority: Some("high".to_string()),
+                    estimate: Some("1h".to_string()),
+                    ..task("Tag v{version} +release", None)
+                },
+                task("Announce {version} in {channel}", Some(vec![1])),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_apply_template() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let sarah = create_user(&conn, "sarah", None, None).unwrap();
+        let vars = vec![("version".to_string(), "1.4".to_string())];
+
+        let items = apply_template(&conn, &ctx, "release", &release_template(), &vars, Some("sarah")).unwrap();
+        let contents: Vec<&str> = items.iter().map(|item| item.content.as_str()).collect();
+        assert_eq!(contents, vec!["Release 1.4", "Tag v1.4", "Announce 1.4 in #general"]);
+
+        let (parent, tag, announce) = (&items[0], &items[1], &items[2]);
+        assert_eq!(tag.parent_id, parent.id);
+        assert_eq!(announce.parent_id, parent.id);
+        assert!(items.iter().all(|item| item.assignee_id == Some(sarah) && item.category == "release"));
+        assert_eq!(tag.tags, vec!["release"]);
+        assert_eq!(tag.estimate_minutes, Some(60));
+        // The parent takes the highest priority and the latest deadline of its tasks
+        assert_eq!(parent.priority, Some(0));
+        assert_eq!(parent.target_time, announce.target_time);
+        assert_eq!(get_dependencies(&conn, announce.id.unwrap()).unwrap(), vec![tag.id.unwrap()]);
+        assert!(announce.blocked);
+        assert_eq!(get_item(&conn, tag.id.unwrap()).unwrap().priority, Some(0));
+    }
+
+    #[test]
+    fn test_apply_template_errors_change_nothing() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+
+        let err = apply_template(&conn, &ctx, "release", &release_template(), &[], None).unwrap_err();
+        assert_eq!(err, "Template 'release' uses {version}, give it with --var version=...");
+
+        let mut template = release_template();
+        template.tasks[0].after = Some(vec![2]);
+        let vars = vec![("version".to_string(), "1.4".to_string())];
+        let err = apply_template(&conn, &ctx, "release", &template, &vars, None).unwrap_err();
+        assert!(err.starts_with("Task 1 of template 'release': after 2"));
+
+        let mut template = release_template();
+        template.tasks[1].content = String::new();
+        let err = apply_template(&conn, &ctx, "release", &template, &vars, None).unwrap_err();
+        assert!(err.starts_with("Task 2 of template 'release'"));
+        assert!(err.ends_with("content cannot be empty"));
+
+        let err = apply_template(&conn, &ctx, "release", &release_template(), &vars, Some("nobody")).unwrap_err();
+        assert_eq!(err, "User 'nobody' not found");
+        assert!(query_items(&conn, &ItemQuery::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fill_vars() {
+        let values = HashMap::from([("v".to_string(), "1.4".to_string())]);
+        assert_eq!(fill_vars("Tag v{v}", &values).unwrap(), "Tag v1.4");
+        assert_eq!(fill_vars("{ not a var } {}", &values).unwrap(), "{ not a var } {}");
+        assert_eq!(fill_vars("{v}{missing}", &values).unwrap_err(), "missing");
+        assert_eq!(template_vars(&release_template()), vec!["channel", "version"]);
+    }
+}
@@ -0,0 +1,213 @@
+use chrono::Local;
+use rusqlite::Connection;
+
+use crate::{
+    actions::{display, list::CLOSED_STATUS_CODES},
+    args::{
+        estimate::format_estimate,
+        itemref::resolve_rowid,
+        parser::{LogTimeCommand, StartCommand},
+    },
+    context::Context,
+    db::{
+        crud::{get_item, get_stored_item},
+        item::{Item, RECORD, RECURRING_TASK_RECORD},
+        time_entry::{get_running_timer, get_time_entries_for_item, log_time, start_timer, stop_timer},
+    },
+};
+
+/// Handles the start command - starts a timer on a task for the current user
+pub fn handle_startcmd(conn: &Connection, ctx: &Context, cmd: &StartCommand) -> Result<(), String> {
+    let row_id = resolve_rowid(conn, ctx, cmd.index)?;
+    let item = get_trackable_item(conn, ctx, row_id)?;
+    if CLOSED_STATUS_CODES.contains(&item.status) {
+        return Err("Cannot track time on a closed task".to_string());
+    }
+
+    let now = Local::now().timestamp();
+    if let Some(running) = get_running_timer(conn, ctx.current_user_id)? {
+        if running.item_id == row_id {
+            return Err("The timer is already running on this task".to_string());
+        }
+        stop_timer(conn, running.id, now)?;
+        if ctx.output.is_table() {
+            println!(
+                "Stopped timer on #{} after {}",
+                running.item_id,
+                format_estimate(Some(running.minutes(now)))
+            );
+        }
+    }
+    start_timer(conn, row_id, ctx.current_user_id, now)?;
+
+    display::output_items(conn, ctx, "Started timer on task:", &[item], false, false)
+}
+
+/// Handles the stop command - stops the running timer of the current user
+pub fn handle_stopcmd(conn: &Connection, ctx: &Context) -> Result<(), String> {
+    let running = get_running_timer(conn, ctx.current_user_id)?
+        .ok_or_else(|| "No timer is running".to_string())?;
+
+    // Fetched first so that nothing is stopped if it fails, the task may be in the trash
+    let item = get_stored_item(conn, running.item_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+    let now = Local::now().timestamp();
+    stop_timer(conn, running.id, now)?;
+
+    let header = format!("Stopped timer after {} on task:", format_estimate(Some(running.minutes(now))));
+    display::output_items(conn, ctx, &header, std::slice::from_ref(&item), false, false)?;
+    print_spent(conn, ctx, &item)
+}
+
+/// Handles the log-time command - records time spent on a task without a timer
+pub fn handle_logtimecmd(conn: &Connection, ctx: &Context, cmd: &LogTimeCommand) -> Result<(), String> {
+    let row_id = resolve_rowid(conn, ctx, cmd.index)?;
+    let item = get_trackable_item(conn, ctx, row_id)?;
+
+    log_time(conn, row_id, ctx.current_user_id, cmd.duration, Local::now().timestamp())?;
+
+    let header = format!("Logged {} on task:", format_estimate(Some(cmd.duration)));
+    display::output_items(conn, ctx, &header, std::slice::from_ref(&item), false, false)?;
+    print_spent(conn, ctx, &item)
+}
+
+/// Minutes spent on a task by everyone, running timers counted up to now.
+/// The flag tells whether a timer is still running.
+pub fn spent_minutes(conn: &Connection, item_id: i64) -> Result<(i64, bool), String> {
+    let now = Local::now().timestamp();
+    let entries = get_time_entries_for_item(conn, item_id)?;
+    let spent = entries.iter().map(|entry| entry.minutes(now)).sum();
+    let running = entries.iter().any(|entry| entry.ended_at.is_none());
+    Ok((spent, running))
+}
+
+/// Spent time next to the estimate, e.g. "1h30m of 2h estimate (75%)".
+pub fn format_spent(spent: i64, estimate: Option<i64>) -> String {
+    match estimate {
+        Some(estimate) if estimate > 0 => format!(
+            "{} of {} estimate ({}%)",
+            format_estimate(Some(spent)),
+            format_estimate(Some(estimate)),
+            spent * 100 / estimate
+        ),
+        _ => format_estimate(Some(spent)),
+    }
+}
+
+fn print_spent(conn: &Connection, ctx: &Context, item: &Item) -> Result<(), String> {
+    if ctx.output.is_table() {
+        let (spent, _) = spent_minutes(conn, item.id.unwrap())?;
+        println!("  Spent: {}", format_spent(spent, item.estimate_minutes));
+    }
+    Ok(())
+}
+
+fn get_trackable_item(conn: &Connection, ctx: &Context, row_id: i64) -> Result<Item, String> {
+    let item = get_item(conn, row_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+    ctx.authorize_item(conn, &item)?;
+    if item.action == RECORD || item.action == RECURRING_TASK_RECORD {
+        return Err("Cannot track time on records".to_string());
+    }
+    Ok(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actions::modify::handle_deletecmd,
+        args::{itemref::ItemRef, parser::DeleteCommand},
+        db::{crud::trash_item, time_entry::get_time_entries_for_item},
+        tests::{get_test_conn, insert_record, insert_task, update_status},
+    };
+
+    fn start(id: i64) -> StartCommand {
+        StartCommand {
+            index: ItemRef::Id(id),
+        }
+    }
+
+    #[test]
+    fn test_start_stop_timer() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let task1_id = insert_task(&conn, "work", "Task 1", "today");
+        let task2_id = insert_task(&conn, "work", "Task 2", "today");
+
+        handle_startcmd(&conn, &ctx, &start(task1_id)).unwrap();
+        let err = handle_startcmd(&conn, &ctx, &start(task1_id)).unwrap_err();
+        assert!(err.contains("already running"));
+
+        // Starting another task stops the running timer
+        handle_startcmd(&conn, &ctx, &start(task2_id)).unwrap();
+        let entries = get_time_entries_for_item(&conn, task1_id).unwrap();
+        assert!(entries[0].ended_at.is_some());
+        let running = get_running_timer(&conn, ctx.current_user_id).unwrap().unwrap();
+        assert_eq!(running.item_id, task2_id);
+
+        handle_stopcmd(&conn, &ctx).unwrap();
+        assert!(get_running_timer(&conn, ctx.current_user_id).unwrap().is_none());
+        let err = handle_stopcmd(&conn, &ctx).unwrap_err();
+        assert_eq!(err, "No timer is running");
+    }
+
+    #[test]
+    fn test_timer_on_trashed_task() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let task1_id = insert_task(&conn, "work", "Task 1", "today");
+        let task2_id = insert_task(&conn, "work", "Task 2", "today");
+
+        // A timer left running on a task trashed without stopping it can still be stopped
+        handle_startcmd(&conn, &ctx, &start(task1_id)).unwrap();
+        trash_item(&conn, task1_id, None).unwrap();
+        handle_stopcmd(&conn, &ctx).unwrap();
+        assert!(get_running_timer(&conn, ctx.current_user_id).unwrap().is_none());
+
+        // Deleting a task stops its timer
+        handle_startcmd(&conn, &ctx, &start(task2_id)).unwrap();
+        let delete = DeleteCommand {
+            indexes: vec![ItemRef::Id(task2_id).into()],
+            filter: None,
+            yes: true,
+        };
+        handle_deletecmd(&conn, &ctx, &delete).unwrap();
+        assert!(get_running_timer(&conn, ctx.current_user_id).unwrap().is_none());
+        assert!(get_time_entries_for_item(&conn, task2_id).unwrap()[0].ended_at.is_some());
+    }
+
+    #[test]
+    fn test_start_rejects_closed_tasks_and_records() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let task_id = insert_task(&conn, "work", "Task", "today");
+        update_status(&conn, task_id, 1);
+        let record_id = insert_record(&conn, "work", "Record", "today");
+
+        let err = handle_startcmd(&conn, &ctx, &start(task_id)).unwrap_err();
+        assert!(err.contains("closed task"));
+        let err = handle_startcmd(&conn, &ctx, &start(record_id)).unwrap_err();
+        assert!(err.contains("records"));
+    }
+
+    #[test]
+    fn test_log_time() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let task_id = insert_task(&conn, "work", "Task", "today");
+
+        let cmd = LogTimeCommand {
+            index: ItemRef::Id(task_id),
+            duration: 45,
+        };
+        handle_logtimecmd(&conn, &ctx, &cmd).unwrap();
+        handle_logtimecmd(&conn, &ctx, &cmd).unwrap();
+        assert_eq!(spent_minutes(&conn, task_id).unwrap(), (90, false));
+    }
+
+    #[test]
+    fn test_format_spent() {
+        assert_eq!(format_spent(45, None), "45m");
+        assert_eq!(format_spent(90, Some(120)), "1h30m of 2h estimate (75%)");
+        assert_eq!(format_spent(150, Some(120)), "2h30m of 2h estimate (125%)");
+    }
+}
@@ -0,0 +1,273 @@
+use rusqlite::Connection;
+
+use crate::{
+    actions::{bulk, display},
+    args::{
+        itemref::{expand_ranges, resolve_trashed_rowid, ItemRange},
+        parser::{TrashCommand, TrashPurgeCommand, TrashRestoreCommand},
+        timestr::to_unix_epoch,
+        timezone,
+    },
+    context::Context,
+    db::{
+        cache,
+        crud::{delete_item, get_item, get_stored_item, query_items, restore_item},
+        item::{Item, ItemQuery, RECORD, RECURRING_TASK, RECURRING_TASK_RECORD, TASK},
+        journal,
+    },
+};
+
+/// Handles the trash command - lists, restores and purges deleted items
+pub fn handle_trashcmd(conn: &Connection, ctx: &Context, cmd: &TrashCommand) -> Result<(), String> {
+    match cmd {
+        TrashCommand::List => handle_trash_list(conn, ctx),
+        TrashCommand::Restore(cmd) => handle_trash_restore(conn, ctx, cmd),
+        TrashCommand::Purge(cmd) => handle_trash_purge(conn, ctx, cmd),
+    }
+}
+
+/// Lists the trash like a listing, so `restore` and `purge` can use its indexes right after.
+fn handle_trash_list(conn: &Connection, ctx: &Context) -> Result<(), String> {
+    let items = trashed_items(conn, ctx)?;
+    cache::clear(conn).map_err(|e| format!("Failed to clear cache: {:?}", e))?;
+    cache::store(conn, &items).map_err(|e| format!("Failed to store cache: {:?}", e))?;
+    if !ctx.output.is_table() {
+        return display::output_items(conn, ctx, "", &items, false, true);
+    }
+
+    if items.is_empty() {
+        println!("The trash is empty");
+        return Ok(());
+    }
+
+    display::print_bold("Trash");
+    println!("{}", "━".repeat(50));
+    for (index, item) in items.iter().enumerate() {
+        let kind = match item.action.as_str() {
+            TASK => "task",
+            RECURRING_TASK => "recurring task",
+            RECORD => "record",
+            RECURRING_TASK_RECORD => "recurring record",
+            other => other,
+        };
+        println!(
+            "{:>3}  \x1b[90m#{} {} · {} · deleted {}\x1b[0m  {}",
+            index + 1,
+            item.id.unwrap(),
+            kind,
+            item.category,
+            format_timestamp(item.deleted_at.unwrap_or_default()),
+            item.content.lines().next().unwrap_or(&item.content)
+        );
+    }
+    Ok(())
+}
+
+fn handle_trash_restore(conn: &Connection, ctx: &Context, cmd: &TrashRestoreCommand) -> Result<(), String> {
+    let items = resolve_trashed(conn, ctx, &cmd.indexes)?;
+    let ids: Vec<i64> = items.iter().map(|item| item.id.unwrap()).collect();
+    bulk::apply_all(conn, items, |conn, item| {
+        restore_item(conn, item.id.unwrap(), Some(ctx.current_user_id))
+            .map_err(|e| format!("Failed to update item: {:?}", e))
+    })?;
+
+    let restored = ids
+        .iter()
+        .map(|&id| get_item(conn, id).map_err(|e| format!("Failed to get item: {:?}", e)))
+        .collect::<Result<Vec<Item>, String>>()?;
+    if !ctx.output.is_table() {
+        return display::output_items(conn, ctx, "", &restored, false, false);
+    }
+
+    display::print_bold("Restored:");
+    let (records, tasks): (Vec<Item>, Vec<Item>) = restored
+        .into_iter()
+        .partition(|item| item.action == RECORD || item.action == RECURRING_TASK_RECORD);
+    if !tasks.is_empty() {
+        display::print_items(&tasks, false, false);
+    }
+    if !records.is_empty() {
+        display::print_items(&records, true, false);
+    }
+    Ok(())
+}
+
+fn handle_trash_purge(conn: &Connection, ctx: &Context, cmd: &TrashPurgeCommand) -> Result<(), String> {
+    // Purging is for good, `ctm undo` can't bring the items back
+    journal::end();
+
+    let items = if !cmd.indexes.is_empty() {
+        resolve_trashed(conn, ctx, &cmd.indexes)?
+    } else {
+        let cutoff = match &cmd.older_than {
+            Some(age) => Some(to_unix_epoch(&format!("{} ago", age))?),
+            None => None,
+        };
+        let items: Vec<Item> = trashed_items(conn, ctx)?
+            .into_iter()
+            .filter(|item| cutoff.is_none_or(|cutoff| item.deleted_at.unwrap_or_default() < cutoff))
+            .collect();
+        for item in &items {
+            ctx.authorize_item(conn, item)?;
+        }
+        items
+    };
+    if items.is_empty() {
+        if ctx.output.is_table() {
+            println!("Nothing to purge");
+        } else {
+            println!("{}", serde_json::json!({ "purged": [] }));
+        }
+        return Ok(());
+    }
+
+    let question = format!("Delete these {} items for good?", items.len());
+    if !bulk::confirm(ctx, &items, &question, cmd.yes)? {
+        return Err("Not purging the trash".to_string());
+    }
+
+    let ids: Vec<i64> = items.iter().map(|item| item.id.unwrap()).collect();
+    let count = ids.len();
+    bulk::apply_all(conn, items, |conn, item| {
+        delete_item(conn, item.id.unwrap(), Some(ctx.current_user_id))
+            .map_err(|e| format!("Failed to delete item: {:?}", e))
+    })?;
+    cache::clear(conn).map_err(|e| format!("Failed to clear cache: {:?}", e))?;
+    if !ctx.output.is_table() {
+        println!("{}", serde_json::json!({ "purged": ids }));
+        return Ok(());
+    }
+    if count == 1 {
+        display::print_bold("Purged 1 item");
+    } else {
+        display::print_bold(&format!("Purged {} items", count));
+    }
+    Ok(())
+}
+
+// The trash of the current namespace, in the order the items were deleted
+fn trashed_items(conn: &Connection, ctx: &Context) -> Result<Vec<Item>, String> {
+    let query = ItemQuery::new()
+        .with_trash()
+        .with_order_by("deleted_at")
+        .with_namespace_ids(ctx.namespace_filter(conn)?);
+    query_items(conn, &query).map_err(|e| e.to_string())
+}
+
+// Resolves indexes of a trash listing, or ids, to items in the trash the user may change
+fn resolve_trashed(conn: &Connection, ctx: &Context, ranges: &[ItemRange]) -> Result<Vec<Item>, String> {
+    let mut items: Vec<Item> = Vec::new();
+    for item_ref in expand_ranges(ranges) {
+        let row_id = resolve_trashed_rowid(conn, ctx, item_ref)?;
+        if items.iter().any(|item| item.id == Some(row_id)) {
+            continue;
+        }
+        let item = get_stored_item(conn, row_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+        ctx.authorize_item(conn, &item)?;
+        items.push(item);
+    }
+    Ok(items)
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    match timezone::datetime(timestamp) {
+        Some(dt) => dt.format("%Y-%m-%d %H:%M").to_string(),
+        None => "unknown".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actions::modify::handle_deletecmd,
+        args::{itemref::ItemRef, parser::DeleteCommand},
+        db::{
+            audit::get_history_for_item,
+            note::{add_note, get_notes_for_item},
+        },
+        tests::{get_test_conn, insert_task},
+    };
+
+    fn delete(conn: &Connection, ctx: &Context, id: i64) {
+        let cmd = DeleteCommand {
+            indexes: vec![ItemRef::Id(id).into()],
+            filter: None,
+            yes: true,
+        };
+        handle_deletecmd(conn, ctx, &cmd).unwrap();
+    }
+
+    #[test]
+    fn test_delete_restore_and_purge() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let kept = insert_task(&conn, "work", "keep me", "tomorrow");
+        let gone = insert_task(&conn, "work", "drop me", "tomorrow");
+        add_note(&conn, kept, "some context", None).unwrap();
+
+        delete(&conn, &ctx, kept);
+        delete(&conn, &ctx, gone);
+        assert!(get_item(&conn, kept).is_err());
+        assert_eq!(query_items(&conn, &ItemQuery::new()).unwrap().len(), 0);
+        assert_eq!(trashed_items(&conn, &ctx).unwrap().len(), 2);
+
+        // Listing caches the trash, restore takes its indexes
+        handle_trashcmd(&conn, &ctx, &TrashCommand::List).unwrap();
+        let restore = TrashRestoreCommand {
+            indexes: vec![ItemRef::Index(1).into()],
+        };
+        handle_trashcmd(&conn, &ctx, &TrashCommand::Restore(restore)).unwrap();
+        assert_eq!(get_item(&conn, kept).unwrap().deleted_at, None);
+        assert_eq!(get_notes_for_item(&conn, kept).unwrap().len(), 1);
+
+        let restore = TrashRestoreCommand {
+            indexes: vec![ItemRef::Id(kept).into()],
+        };
+        let err = handle_trashcmd(&conn, &ctx, &TrashCommand::Restore(restore)).unwrap_err();
+        assert_eq!(err, format!("#{} is not in the trash", kept));
+
+        // Only items deleted long enough ago are purged
+        let purge = |older_than: Option<&str>| TrashPurgeCommand {
+            indexes: vec![],
+            older_than: older_than.map(|s| s.to_string()),
+            yes: true,
+        };
+        handle_trashcmd(&conn, &ctx, &TrashCommand::Purge(purge(Some("30d")))).unwrap();
+        assert!(get_stored_item(&conn, gone).is_ok());
+        handle_trashcmd(&conn, &ctx, &TrashCommand::Purge(purge(None))).unwrap();
+        assert!(get_stored_item(&conn, gone).is_err());
+        assert!(get_item(&conn, kept).is_ok());
+    }
+
+    #[test]
+    fn test_purge_removes_items_and_history() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let gone = insert_task(&conn, "work", "drop me", "tomorrow");
+        let kept = insert_task(&conn, "work", "keep me", "tomorrow");
+        add_note(&conn, gone, "some context", None).unwrap();
+        delete(&conn, &ctx, gone);
+        assert!(!get_history_for_item(&conn, gone).unwrap().is_empty());
+
+        let purge = TrashPurgeCommand {
+            indexes: vec![ItemRef::Id(gone).into()],
+            older_than: None,
+            yes: true,
+        };
+        handle_trashcmd(&conn, &ctx, &TrashCommand::Purge(purge)).unwrap();
+        assert!(get_stored_item(&conn, gone).is_err());
+        assert!(get_notes_for_item(&conn, gone).unwrap().is_empty());
+        assert!(get_history_for_item(&conn, gone).unwrap().is_empty());
+        assert!(trashed_items(&conn, &ctx).unwrap().is_empty());
+
+        // Only trashed items can be restored, purged ones are gone for good
+        for id in [kept, gone] {
+            let restore = TrashRestoreCommand {
+                indexes: vec![ItemRef::Id(id).into()],
+            };
+            assert!(handle_trashcmd(&conn, &ctx, &TrashCommand::Restore(restore)).is_err());
+        }
+        assert_eq!(get_item(&conn, kept).unwrap().deleted_at, None);
+    }
+}
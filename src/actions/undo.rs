@@ -0,0 +1,248 @@
+use rusqlite::Connection;
+
+use crate::{
+    actions::display::{self, print_bold},
+    args::{parser::UndoCommand, timezone},
+    context::{Context, Permission},
+    db::{
+        crud::{get_item, get_stored_item},
+        item::{Item, RECORD, RECURRING_TASK_RECORD},
+        journal::{self, changed_item_ids, list_entries, undo_entry, JournalEntry},
+        namespace::get_namespace_by_id,
+    },
+};
+
+/// Handles the undo command - reverses the last journaled command of the current user
+pub fn handle_undocmd(conn: &Connection, ctx: &Context, cmd: &UndoCommand) -> Result<(), String> {
+    // Undoing can't be undone in turn, the next undo goes further back
+    journal::end();
+
+    if cmd.list {
+        return handle_undo_list(conn, ctx);
+    }
+
+    let entry = list_entries(conn, ctx.current_user_id, 1)?
+        .pop()
+        .ok_or_else(|| "Nothing to undo".to_string())?;
+    authorize_entry(conn, ctx, &entry)?;
+    let item_ids = undo_entry(conn, &entry, ctx.current_user_id, cmd.force)?;
+
+    // Items the command created are gone again
+    let mut items: Vec<Item> = Vec::new();
+    let mut removed: Vec<i64> = Vec::new();
+    for item_id in item_ids {
+        match get_item(conn, item_id) {
+            Ok(item) => items.push(item),
+            Err(_) => removed.push(item_id),
+        }
+    }
+
+    if !ctx.output.is_table() {
+        return display::output_items(conn, ctx, "", &items, false, false);
+    }
+
+    print_bold(&format!(
+        "Undid '{}' from {}{}",
+        entry.command,
+        format_timestamp(entry.created_at),
+        namespace_note(conn, ctx, &entry)
+    ));
+    let (records, tasks): (Vec<Item>, Vec<Item>) = items
+        .into_iter()
+        .partition(|item| item.action == RECORD || item.action == RECURRING_TASK_RECORD);
+    if !tasks.is_empty() {
+        display::print_items(&tasks, false, false);
+    }
+    if !records.is_empty() {
+        display::print_items(&records, true, false);
+    }
+    if !removed.is_empty() {
+        let removed: Vec<String> = removed.iter().map(|id| format!("#{}", id)).collect();
+        println!("Removed {}", removed.join(", "));
+    }
+    Ok(())
+}
+
+// Undoing changes items again, which takes the role to modify them where they are now,
+// and where the command ran
+fn authorize_entry(conn: &Connection, ctx: &Context, entry: &JournalEntry) -> Result<(), String> {
+    ctx.require_in(conn, entry.namespace_id.unwrap_or(ctx.current_namespace_id), Permission::EditItems)?;
+    for item_id in changed_item_ids(conn, entry)? {
+        // Items the command deleted for good come back where the command ran
+        if let Ok(item) = get_stored_item(conn, item_id) {
+            if let Some(namespace_id) = item.namespace_id {
+                ctx.require_in(conn, namespace_id, Permission::EditItems)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn handle_undo_list(conn: &Connection, ctx: &Context) -> Result<(), String> {
+    let entries = list_entries(conn, ctx.current_user_id, usize::MAX)?;
+
+    if !ctx.output.is_table() {
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "command": entry.command,
+                    "created_at": entry.created_at,
+                    "changes": entry.changes,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::json!({ "commands": entries }));
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("Nothing to undo");
+        return Ok(());
+    }
+
+    print_bold("Commands ctm undo reverses, newest first");
+    println!("{:<17} {:<8} Command", "When", "Changes");
+    println!("{}", "-".repeat(64));
+    for entry in &entries {
+        println!(
+            "{:<17} {:<8} {}{}",
+            format_timestamp(entry.created_at),
+            entry.changes,
+            entry.command,
+            namespace_note(conn, ctx, entry)
+        );
+    }
+    Ok(())
+}
+
+// Where the command ran, when that isn't the current namespace
+fn namespace_note(conn: &Connection, ctx: &Context, entry: &JournalEntry) -> String {
+    match entry.namespace_id {
+        Some(id) if id != ctx.current_namespace_id => get_namespace_by_id(conn, id)
+            .ok()
+            .flatten()
+            .map(|ns| format!(" (in '{}')", ns.name))
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    match timezone::datetime(timestamp) {
+        Some(dt) => dt.format("%Y-%m-%d %H:%M").to_string(),
+        None => "unknown".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actions::{block::handle_blockcmd, modify::handle_donecmd, note::handle_notecmd, show::handle_showcmd},
+        args::{
+            itemref::ItemRef,
+            parser::{BlockCommand, DoneCommand, NoteCommand},
+        },
+        db::{
+            crud::query_items,
+            dependency::get_dependencies,
+            item::ItemQuery,
+            namespace::add_user_to_namespace,
+            note::get_notes_for_item,
+            user::create_user,
+        },
+        tests::{get_test_conn, insert_task},
+    };
+
+    fn undo_cmd(force: bool) -> UndoCommand {
+        UndoCommand { list: false, force }
+    }
+
+    #[test]
+    fn test_undo_done() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let task_id = insert_task(&conn, "work", "ship it", "tomorrow");
+
+        journal::begin(ctx.current_user_id, ctx.current_namespace_id, "ctm done #1");
+        let done_cmd = DoneCommand {
+            indexes: vec![ItemRef::Id(task_id).into()],
+            filter: None,
+            yes: false,
+            status: 1,
+            comment: None,
+            close_issue: false,
+            close_parent: false,
+        };
+        handle_donecmd(&conn, &ctx, &done_cmd).unwrap();
+        assert_eq!(query_items(&conn, &ItemQuery::new().with_action(RECORD)).unwrap().len(), 1);
+
+        // The task is open again and its completion record is gone
+        handle_undocmd(&conn, &ctx, &undo_cmd(false)).unwrap();
+        assert_eq!(get_item(&conn, task_id).unwrap().status, 0);
+        assert!(query_items(&conn, &ItemQuery::new().with_action(RECORD)).unwrap().is_empty());
+
+        let err = handle_undocmd(&conn, &ctx, &undo_cmd(false)).unwrap_err();
+        assert_eq!(err, "Nothing to undo");
+    }
+
+    #[test]
+    fn test_undo_note_and_block() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let task_id = insert_task(&conn, "work", "deploy", "tomorrow");
+        let other_id = insert_task(&conn, "work", "migrate", "tomorrow");
+
+        journal::begin(ctx.current_user_id, ctx.current_namespace_id, "ctm note #1 waiting");
+        let note_cmd = NoteCommand {
+            index: ItemRef::Id(task_id),
+            content: "waiting on the migration".to_string(),
+        };
+        handle_notecmd(&conn, &ctx, &note_cmd).unwrap();
+        journal::begin(ctx.current_user_id, ctx.current_namespace_id, "ctm block #1 --on #2");
+        let block_cmd = BlockCommand {
+            index: ItemRef::Id(task_id),
+            on: ItemRef::Id(other_id),
+        };
+        handle_blockcmd(&conn, &ctx, &block_cmd).unwrap();
+        // Read-only commands leave nothing to undo
+        journal::begin(ctx.current_user_id, ctx.current_namespace_id, "ctm show #1");
+        handle_showcmd(&conn, &ctx, ItemRef::Id(task_id)).unwrap();
+        assert_eq!(list_entries(&conn, ctx.current_user_id, 10).unwrap().len(), 2);
+
+        handle_undocmd(&conn, &ctx, &undo_cmd(false)).unwrap();
+        assert!(get_dependencies(&conn, task_id).unwrap().is_empty());
+        assert_eq!(get_notes_for_item(&conn, task_id).unwrap().len(), 1);
+
+        handle_undocmd(&conn, &ctx, &undo_cmd(false)).unwrap();
+        assert!(get_notes_for_item(&conn, task_id).unwrap().is_empty());
+        assert_eq!(handle_undocmd(&conn, &ctx, &undo_cmd(false)).unwrap_err(), "Nothing to undo");
+    }
+
+    #[test]
+    fn test_undo_needs_edit_role() {
+        let (conn, _temp_file) = get_test_conn();
+        let owner = Context::default_from_db(&conn).unwrap();
+        create_user(&conn, "bob", None, None).unwrap();
+        add_user_to_namespace(&conn, &owner.current_namespace_name, "bob", "member").unwrap();
+        let bob = Context::resolve_with_config(&conn, Some("bob"), None, None).unwrap();
+        let task_id = insert_task(&conn, "work", "ship it", "tomorrow");
+
+        journal::begin(bob.current_user_id, bob.current_namespace_id, "ctm note #1 shipping");
+        let note_cmd = NoteCommand {
+            index: ItemRef::Id(task_id),
+            content: "shipping friday".to_string(),
+        };
+        handle_notecmd(&conn, &bob, &note_cmd).unwrap();
+        journal::end();
+
+        // Demoted to viewer, bob can no longer reverse their own note
+        add_user_to_namespace(&conn, &owner.current_namespace_name, "bob", "viewer").unwrap();
+        let bob = Context::resolve_with_config(&conn, Some("bob"), None, None).unwrap();
+        let err = handle_undocmd(&conn, &bob, &undo_cmd(false)).unwrap_err();
+        assert!(err.contains("cannot modify items"), "{}", err);
+        assert_eq!(get_notes_for_item(&conn, task_id).unwrap().len(), 1);
+        assert_eq!(list_entries(&conn, bob.current_user_id, 10).unwrap().len(), 1);
+    }
+}
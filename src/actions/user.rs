@@ -1,9 +1,13 @@
 use rusqlite::Connection;
 
 use crate::{
-    args::parser::{UserCommand, UserCreateCommand, UserDeleteCommand},
-    context::Context,
-    db::user::{create_user, delete_user, list_users},
+    args::parser::{TimeZoneCommand, UserCommand, UserCreateCommand, UserDeleteCommand},
+    context::{Context, Permission},
+    db::{
+        namespace::{get_namespace_time_zone, get_user_role, list_user_namespace_ids},
+        user::get_user_by_name,
+        user::{create_user, delete_user, get_user_time_zone, list_users, set_user_time_zone},
+    },
 };
 
 pub fn handle_user_cmd(
@@ -14,7 +18,8 @@ pub fn handle_user_cmd(
     match cmd {
         UserCommand::Create(create_cmd) => handle_user_create(conn, ctx, create_cmd),
         UserCommand::List => handle_user_list(conn),
-        UserCommand::Delete(delete_cmd) => handle_user_delete(conn, delete_cmd),
+        UserCommand::Delete(delete_cmd) => handle_user_delete(conn, ctx, delete_cmd),
+        UserCommand::Tz(tz_cmd) => handle_user_tz(conn, ctx, tz_cmd),
     }
 }
 
@@ -55,8 +60,148 @@ fn handle_user_list(conn: &Connection) -> Result<(), String> {
     Ok(())
 }
 
-fn handle_user_delete(conn: &Connection, cmd: UserDeleteCommand) -> Result<(), String> {
+/// Deleting a user takes their memberships along, so it needs the right to manage
+/// the members of every namespace they belong to, and to manage owners where they own one.
+fn handle_user_delete(conn: &Connection, ctx: &Context, cmd: UserDeleteCommand) -> Result<(), String> {
+    let user = get_user_by_name(conn, &cmd.name)?
+        .ok_or_else(|| format!("User '{}' not found", cmd.name))?;
+    if user.id == ctx.current_user_id {
+        return Err("Cannot delete yourself".to_string());
+    }
+
+    ctx.require(Permission::ManageMembers)?;
+    for namespace_id in list_user_namespace_ids(conn, user.id)? {
+        ctx.require_in(conn, namespace_id, Permission::ManageMembers)?;
+        if get_user_role(conn, user.id, namespace_id)?.as_deref() == Some("owner") {
+            ctx.require_in(conn, namespace_id, Permission::ManageOwners)?;
+        }
+    }
+
     delete_user(conn, &cmd.name)?;
     println!("Deleted user '{}'", cmd.name);
     Ok(())
 }
+
+fn handle_user_tz(conn: &Connection, ctx: &Context, cmd: TimeZoneCommand) -> Result<(), String> {
+    let Some(zone) = cmd.zone else {
+        // Show where the effective zone comes from
+        let source = if get_user_time_zone(conn, ctx.current_user_id)?.is_some() {
+            "set for you"
+        } else if get_namespace_time_zone(conn, ctx.current_namespace_id)?.is_some() {
+            "default of the namespace"
+        } else {
+            "system zone"
+        };
+        println!("Time zone: {} ({})", ctx.time_zone.name(), source);
+        return Ok(());
+    };
+
+    if zone == "none" {
+        set_user_time_zone(conn, ctx.current_user_id, None)?;
+        println!("Unset time zone of user '{}'", ctx.current_user_name);
+    } else {
+        set_user_time_zone(conn, ctx.current_user_id, Some(&zone))?;
+        println!("Set time zone of user '{}' to {}", ctx.current_user_name, zone);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::namespace::{add_user_to_namespace, create_namespace},
+        tests::get_test_conn,
+    };
+
+    // The default user owns "team", where ann is admin, mel member and vic viewer
+    fn setup_team(conn: &Connection) -> Context {
+        let owner = Context::default_from_db(conn).unwrap();
+        create_namespace(conn, "team", None, owner.current_user_id).unwrap();
+        for (name, role) in [("ann", "admin"), ("mel", "member"), ("vic", "viewer")] {
+            create_user(conn, name, None, None).unwrap();
+            add_user_to_namespace(conn, "team", name, role).unwrap();
+        }
+        owner
+    }
+
+    fn as_user(conn: &Connection, name: &str) -> Context {
+        Context::resolve_with_config(conn, Some(name), Some("team"), None).unwrap()
+    }
+
+    fn delete_cmd(name: &str) -> UserDeleteCommand {
+        UserDeleteCommand {
+            name: name.to_string(),
+        }
+    }
+
+    fn exists(conn: &Connection, name: &str) -> bool {
+        get_user_by_name(conn, name).unwrap().is_some()
+    }
+
+    fn tz_cmd(zone: Option<&str>) -> TimeZoneCommand {
+        TimeZoneCommand {
+            zone: zone.map(|z| z.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_user_tz() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+
+        handle_user_tz(&conn, &ctx, tz_cmd(Some("America/Chicago"))).unwrap();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        assert_eq!(ctx.time_zone.name(), "America/Chicago");
+        handle_user_tz(&conn, &ctx, tz_cmd(None)).unwrap();
+
+        handle_user_tz(&conn, &ctx, tz_cmd(Some("none"))).unwrap();
+        assert_eq!(get_user_time_zone(&conn, ctx.current_user_id).unwrap(), None);
+    }
+
+    #[test]
+    fn test_user_delete_by_role() {
+        let (conn, _temp_file) = get_test_conn();
+        let owner = setup_team(&conn);
+
+        for name in ["mel", "vic"] {
+            let ctx = as_user(&conn, name);
+            let err = handle_user_delete(&conn, &ctx, delete_cmd("ann")).unwrap_err();
+            assert!(err.contains("cannot manage members"), "{}", err);
+        }
+        assert!(exists(&conn, "ann"));
+
+        // Admins can't delete an owner, nobody can delete themselves
+        add_user_to_namespace(&conn, "default", "ann", "admin").unwrap();
+        let admin = as_user(&conn, "ann");
+        let err = handle_user_delete(&conn, &admin, delete_cmd(&owner.current_user_name)).unwrap_err();
+        assert!(err.contains("cannot manage owners"), "{}", err);
+        let err = handle_user_delete(&conn, &admin, delete_cmd("ann")).unwrap_err();
+        assert_eq!(err, "Cannot delete yourself");
+
+        handle_user_delete(&conn, &admin, delete_cmd("vic")).unwrap();
+        assert!(!exists(&conn, "vic"));
+    }
+
+    #[test]
+    fn test_user_delete_needs_every_namespace() {
+        let (conn, _temp_file) = get_test_conn();
+        let owner = setup_team(&conn);
+
+        // mel also belongs to a namespace the owner of team is not a member of
+        create_user(&conn, "olga", None, None).unwrap();
+        let olga = get_user_by_name(&conn, "olga").unwrap().unwrap();
+        create_namespace(&conn, "side", None, olga.id).unwrap();
+        add_user_to_namespace(&conn, "side", "mel", "member").unwrap();
+        let err = handle_user_delete(&conn, &owner, delete_cmd("mel")).unwrap_err();
+        assert!(err.contains("not a member of namespace 'side'"), "{}", err);
+        assert!(exists(&conn, "mel"));
+
+        // The last owner of a namespace stays
+        add_user_to_namespace(&conn, "side", &owner.current_user_name, "admin").unwrap();
+        handle_user_delete(&conn, &owner, delete_cmd("mel")).unwrap();
+        let err = delete_user(&conn, "olga").unwrap_err();
+        assert!(err.contains("only owner of 'side'"), "{}", err);
+        assert!(exists(&conn, "olga"));
+    }
+}
@@ -0,0 +1,263 @@
+use clap::Parser;
+use rusqlite::Connection;
+
+use crate::{
+    actions::list,
+    args::parser::{
+        ListArgs,
+        ListCommand,
+        ViewAction,
+        ViewCommand,
+        ViewDeleteCommand,
+        ViewSaveCommand,
+    },
+    context::{Context, Permission},
+    db::view::{delete_view, get_view, list_views, save_view, ViewScope},
+};
+
+pub fn handle_viewcmd(conn: &Connection, ctx: &Context, cmd: ViewCommand) -> Result<(), String> {
+    match (cmd.action, cmd.name) {
+        (Some(ViewAction::Save(save_cmd)), _) => handle_view_save(conn, ctx, save_cmd),
+        (Some(ViewAction::List), _) | (None, None) => handle_view_list(conn, ctx),
+        (Some(ViewAction::Delete(delete_cmd)), _) => handle_view_delete(conn, ctx, delete_cmd),
+        (None, Some(name)) => handle_view_run(conn, ctx, &name, &cmd.args),
+    }
+}
+
+fn handle_view_save(conn: &Connection, ctx: &Context, cmd: ViewSaveCommand) -> Result<(), String> {
+    let args = normalize_args(cmd.args)?;
+    // Refuse what the view could never run
+    parse_list_args(&args)?;
+
+    let scope = if cmd.shared {
+        ctx.require(Permission::EditItems)?;
+        ViewScope::Shared(ctx.current_namespace_id)
+    } else {
+        ViewScope::Personal(ctx.current_user_id)
+    };
+    let replaced = save_view(conn, &cmd.name, &args, scope, ctx.current_user_id)?;
+
+    let verb = if replaced { "Updated" } else { "Saved" };
+    match scope {
+        ViewScope::Shared(_) => println!(
+            "{} view '{}' shared in namespace '{}': ctm list {}",
+            verb,
+            cmd.name,
+            ctx.current_namespace_name,
+            shell_words(&args)
+        ),
+        ViewScope::Personal(_) => println!("{} view '{}': ctm list {}", verb, cmd.name, shell_words(&args)),
+    }
+    Ok(())
+}
+
+fn handle_view_list(conn: &Connection, ctx: &Context) -> Result<(), String> {
+    let views = list_views(conn, ctx.current_user_id, ctx.current_namespace_id)?;
+
+    if !ctx.output.is_table() {
+        let views: Vec<_> = views
+            .iter()
+            .map(|view| {
+                serde_json::json!({
+                    "name": view.name,
+                    "shared": matches!(view.scope, ViewScope::Shared(_)),
+                    "args": view.args,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::json!({ "views": views }));
+        return Ok(());
+    }
+
+    if views.is_empty() {
+        println!("No views found. Save one with: ctm view save <name> -- task <filter>");
+        return Ok(());
+    }
+
+    println!("{:<20} {:<10} Arguments", "Name", "Scope");
+    println!("{}", "-".repeat(64));
+    for view in views {
+        let scope = match view.scope {
+            ViewScope::Personal(_) => "personal",
+            ViewScope::Shared(_) => "shared",
+        };
+        println!("{:<20} {:<10} {}", view.name, scope, shell_words(&view.args));
+    }
+    Ok(())
+}
+
+fn handle_view_delete(conn: &Connection, ctx: &Context, cmd: ViewDeleteCommand) -> Result<(), String> {
+    let scope = if cmd.shared {
+        ctx.require(Permission::EditItems)?;
+        ViewScope::Shared(ctx.current_namespace_id)
+    } else {
+        ViewScope::Personal(ctx.current_user_id)
+    };
+    if !delete_view(conn, &cmd.name, scope)? {
+        return Err(match scope {
+            ViewScope::Shared(_) => format!(
+                "No view '{}' is shared in namespace '{}'",
+                cmd.name, ctx.current_namespace_name
+            ),
+            ViewScope::Personal(_) => format!("You have no view '{}', add --shared for a shared one", cmd.name),
+        });
+    }
+    println!("Deleted view '{}'", cmd.name);
+    Ok(())
+}
+
+/// Runs a view as the list command it was saved from, `extra` arguments
+/// are appended and override the saved ones.
+fn handle_view_run(conn: &Connection, ctx: &Context, name: &str, extra: &[String]) -> Result<(), String> {
+    let view = get_view(conn, name, ctx.current_user_id, ctx.current_namespace_id)?
+        .ok_or_else(|| format!("View '{}' not found, see ctm view list", name))?;
+
+    let mut args = view.args;
+    args.extend(extra.iter().cloned());
+    match parse_list_args(&args)? {
+        ListCommand::Task(cmd) => list::handle_listtasks(conn, ctx, cmd),
+        ListCommand::Record(cmd) => list::handle_listrecords(conn, ctx, cmd),
+        ListCommand::Show(_) => Err(format!("View '{}' does not list anything", name)),
+    }
+}
+
+// Views list tasks unless they start with `record`
+fn normalize_args(mut args: Vec<String>) -> Result<Vec<String>, String> {
+    match args.first().map(String::as_str) {
+        Some("task" | "record") => {}
+        Some("show") => return Err("A view must list tasks or records".to_string()),
+        _ => args.insert(0, "task".to_string()),
+    }
+    Ok(args)
+}
+
+fn parse_list_args(args: &[String]) -> Result<ListCommand, String> {
+    let words = std::iter::once("ctm list").chain(args.iter().map(String::as_str));
+    match ListArgs::try_parse_from(words) {
+        Ok(list_args) => Ok(list_args.list),
+        Err(e) => {
+            let message = e.to_string();
+            let first_line = message.lines().next().unwrap_or_default();
+            Err(first_line.trim_start_matches("error: ").to_string())
+        }
+    }
+}
+
+/// Arguments as they would be typed in a shell.
+pub(crate) fn shell_words(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            if !arg.is_empty() && arg.chars().all(|c| c.is_alphanumeric() || "-_+./:#@".contains(c)) {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{
+            cache,
+            crud::get_item,
+            namespace::add_user_to_namespace,
+            user::create_user,
+        },
+        tests::{get_test_conn, insert_task},
+    };
+
+    fn words(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn save_cmd(name: &str, shared: bool, args: &[&str]) -> ViewSaveCommand {
+        ViewSaveCommand {
+            name: name.to_string(),
+            shared,
+            args: words(args),
+        }
+    }
+
+    fn listed(conn: &Connection) -> Vec<String> {
+        (1..)
+            .map_while(|i| cache::read(conn, i).unwrap())
+            .map(|id| get_item(conn, id).unwrap().content)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_list_args() {
+        let args = normalize_args(words(&["priority:high", "--sort", "priority"])).unwrap();
+        assert_eq!(args[0], "task");
+        match parse_list_args(&args).unwrap() {
+            ListCommand::Task(cmd) => assert_eq!(cmd.timestr.as_deref(), Some("priority:high")),
+            other => panic!("expected a task listing, got {:?}", other),
+        }
+
+        // Later arguments override earlier ones
+        let args = words(&["record", "-l", "5", "-l", "7"]);
+        match parse_list_args(&args).unwrap() {
+            ListCommand::Record(cmd) => assert_eq!(cmd.limit, 7),
+            other => panic!("expected a record listing, got {:?}", other),
+        }
+
+        assert!(normalize_args(words(&["show", "1"])).is_err());
+        let err = parse_list_args(&words(&["task", "--colour", "red"])).unwrap_err();
+        assert!(err.contains("--colour"), "{}", err);
+    }
+
+    #[test]
+    fn test_run_view() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        insert_task(&conn, "work", "b later", "in 3 days");
+        insert_task(&conn, "work", "a sooner", "tomorrow");
+        insert_task(&conn, "home", "c home", "tomorrow");
+
+        handle_view_save(&conn, &ctx, save_cmd("work", false, &["category:work", "--reverse"])).unwrap();
+        handle_view_run(&conn, &ctx, "work", &[]).unwrap();
+        assert_eq!(listed(&conn), vec!["b later", "a sooner"]);
+
+        // Extra arguments are applied on top
+        handle_view_run(&conn, &ctx, "work", &words(&["--limit", "1"])).unwrap();
+        assert_eq!(listed(&conn), vec!["a sooner"]);
+
+        let err = handle_view_run(&conn, &ctx, "missing", &[]).unwrap_err();
+        assert!(err.contains("not found"));
+        assert!(handle_view_save(&conn, &ctx, save_cmd("bad", false, &["--days", "x"])).is_err());
+    }
+
+    #[test]
+    fn test_shared_view_permissions() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        create_user(&conn, "vic", None, None).unwrap();
+        add_user_to_namespace(&conn, &ctx.current_namespace_name, "vic", "viewer").unwrap();
+        let viewer = Context::resolve_with_config(&conn, Some("vic"), Some(&ctx.current_namespace_name), None).unwrap();
+
+        handle_view_save(&conn, &ctx, save_cmd("team", true, &["--all-users"])).unwrap();
+        // Viewers can run shared views and keep their own, but not share
+        handle_view_run(&conn, &viewer, "team", &[]).unwrap();
+        assert!(handle_view_save(&conn, &viewer, save_cmd("mine", true, &["overdue"])).is_err());
+        handle_view_save(&conn, &viewer, save_cmd("mine", false, &["overdue"])).unwrap();
+
+        let delete = |ctx: &Context, shared| {
+            handle_view_delete(&conn, ctx, ViewDeleteCommand { name: "team".to_string(), shared })
+        };
+        assert!(delete(&viewer, false).is_err());
+        assert!(delete(&viewer, true).is_err());
+        delete(&ctx, true).unwrap();
+    }
+
+    #[test]
+    fn test_shell_words() {
+        assert_eq!(
+            shell_words(&words(&["task", "due<friday and +ops", "--sort", "due", "it's"])),
+            r"task 'due<friday and +ops' --sort due 'it'\''s'"
+        );
+    }
+}
@@ -1,26 +1,82 @@
 use chrono::{
+    DateTime,
     Datelike,
     Duration,
     Local,
+    NaiveDate,
     TimeZone,
-    Timelike,
+    Utc,
 };
 
+use crate::args::timezone::{self, Zone};
+
+// Days searched for an occurrence of an extended schedule,
+// enough for yearly patterns and the largest allowed interval.
+const SCAN_DAYS: i64 = 800;
+
 // Parse a cron string and return the next or last occurrence timestamp
 // The cron implementation is specific to this project
 // avoiding additional dependency while implementing specific
 // subset of cron functionalities.
+//
+// On top of "minute hour day month weekday" it understands:
+// - several schedules separated by ';', the closest occurrence wins
+// - 'L' as day: last day of the month
+// - 'D#N' as weekday: Nth weekday D of the month (1-5), 'D#L' for the last one
+// - a sixth field 'Nd@YYYYMMDD' or 'Nw@YYYYMMDD': every N days or weeks
+//   counted from the anchor date the schedule was created on
+//
+// Occurrences are evaluated on the wall clock of the current time zone.
 pub fn get_next_occurrence(cron_str: &str) -> Result<i64, String> {
-    get_occurrence_from(cron_str, Local::now(), true)
+    occurrence_in_current_zone(cron_str, Utc::now().timestamp(), true)
 }
 
 pub fn get_last_occurrence(cron_str: &str) -> Result<i64, String> {
-    get_occurrence_from(cron_str, Local::now(), false)
+    occurrence_in_current_zone(cron_str, Utc::now().timestamp(), false)
+}
+
+// Next occurrence strictly after the given timestamp
+pub fn get_next_occurrence_after(cron_str: &str, timestamp: i64) -> Result<i64, String> {
+    occurrence_in_current_zone(cron_str, timestamp, true)
+}
+
+fn occurrence_in_current_zone(cron_str: &str, timestamp: i64, forward: bool) -> Result<i64, String> {
+    let invalid = || format!("Invalid timestamp: {}", timestamp);
+    match timezone::current() {
+        Zone::Local => {
+            let from = Local.timestamp_opt(timestamp, 0).single().ok_or_else(invalid)?;
+            get_occurrence_from(cron_str, from, forward)
+        }
+        Zone::Named(tz) => {
+            let from = tz.timestamp_opt(timestamp, 0).single().ok_or_else(invalid)?;
+            get_occurrence_from(cron_str, from, forward)
+        }
+    }
 }
 
 // Underlying implementation to allow for testing
-fn get_occurrence_from(cron_str: &str, now: chrono::DateTime<Local>, forward: bool) -> Result<i64, String> {
+fn get_occurrence_from<Z: TimeZone>(cron_str: &str, now: DateTime<Z>, forward: bool) -> Result<i64, String> {
+    let mut closest: Option<i64> = None;
+    for single in cron_str.split(';') {
+        let ts = get_single_occurrence_from(single.trim(), now.clone(), forward)?;
+        closest = Some(match closest {
+            Some(c) if forward => c.min(ts),
+            Some(c) => c.max(ts),
+            None => ts,
+        });
+    }
+    closest.ok_or_else(|| format!("Invalid cron format: {}", cron_str))
+}
+
+fn get_single_occurrence_from<Z: TimeZone>(
+    cron_str: &str,
+    now: DateTime<Z>,
+    forward: bool,
+) -> Result<i64, String> {
     let parts: Vec<&str> = cron_str.split_whitespace().collect();
+    if parts.len() == 6 || (parts.len() == 5 && (parts[2] == "L" || parts[4].contains('#'))) {
+        return calculate_by_scan(&ExtendedSchedule::parse(&parts)?, now, forward);
+    }
     if parts.len() != 5 {
         return Err(format!("Invalid cron format: {}", cron_str));
     }
@@ -64,46 +120,232 @@ fn get_occurrence_from(cron_str: &str, now: chrono::DateTime<Local>, forward: bo
     }
 }
 
-fn calculate_daily(now: chrono::DateTime<Local>, minute: u32, hour: u32, forward: bool) -> Result<i64, String> {
-    let mut candidate = now
-        .with_hour(hour)
-        .ok_or("Invalid hour")?
-        .with_minute(minute)
-        .ok_or("Invalid minute")?
-        .with_second(0)
-        .unwrap()
-        .with_nanosecond(0)
-        .unwrap();
+#[derive(Debug, PartialEq)]
+enum DaySpec {
+    Day(u32),
+    Last,
+}
+
+#[derive(Debug, PartialEq)]
+enum Nth {
+    Nth(u32),
+    Last,
+}
+
+#[derive(Debug, PartialEq)]
+enum Interval {
+    Days(i64),
+    Weeks(i64),
+}
+
+// Schedule using one of the extensions, evaluated day by day
+#[derive(Debug)]
+struct ExtendedSchedule {
+    minute: u32,
+    hour: u32,
+    day: Option<DaySpec>,
+    month: Option<u32>,
+    // inclusive range of cron weekdays, 0 and 7 are Sunday
+    weekdays: Option<(u32, u32)>,
+    nth: Option<Nth>,
+    interval: Option<(Interval, NaiveDate)>,
+}
+
+impl ExtendedSchedule {
+    fn parse(parts: &[&str]) -> Result<Self, String> {
+        let minute: u32 = parts[0]
+            .parse()
+            .map_err(|_| format!("Invalid minute: {}", parts[0]))?;
+        let hour: u32 = parts[1]
+            .parse()
+            .map_err(|_| format!("Invalid hour: {}", parts[1]))?;
+        let day = match parts[2] {
+            "*" => None,
+            "L" => Some(DaySpec::Last),
+            d => Some(DaySpec::Day(
+                d.parse().map_err(|_| format!("Invalid day: {}", d))?,
+            )),
+        };
+        let month = match parts[3] {
+            "*" => None,
+            m => Some(m.parse().map_err(|_| format!("Invalid month: {}", m))?),
+        };
+
+        let (weekday_str, nth) = match parts[4].split_once('#') {
+            Some((wd, "L")) => (wd, Some(Nth::Last)),
+            Some((wd, n)) => {
+                let n: u32 = n.parse().map_err(|_| format!("Invalid nth weekday: {}", parts[4]))?;
+                if !(1..=5).contains(&n) {
+                    return Err(format!("Invalid nth weekday: {}", parts[4]));
+                }
+                (wd, Some(Nth::Nth(n)))
+            }
+            None => (parts[4], None),
+        };
+        let weekdays = match weekday_str {
+            "*" => None,
+            wd => Some(parse_weekday_range(wd)?),
+        };
+
+        let interval = match parts.get(5) {
+            Some(spec) => Some(parse_interval(spec)?),
+            None => None,
+        };
+
+        Ok(ExtendedSchedule {
+            minute,
+            hour,
+            day,
+            month,
+            weekdays,
+            nth,
+            interval,
+        })
+    }
+
+    fn matches(&self, date: NaiveDate) -> bool {
+        if self.month.is_some_and(|m| m != date.month()) {
+            return false;
+        }
+        let is_last_day = (date + Duration::days(1)).month() != date.month();
+        match self.day {
+            Some(DaySpec::Day(d)) if d != date.day() => return false,
+            Some(DaySpec::Last) if !is_last_day => return false,
+            _ => {}
+        }
+        if let Some((start, end)) = self.weekdays {
+            let wd = date.weekday().num_days_from_sunday();
+            if !((wd >= start && wd <= end) || (wd == 0 && end == 7)) {
+                return false;
+            }
+        }
+        match self.nth {
+            Some(Nth::Nth(n)) if (date.day() - 1) / 7 + 1 != n => return false,
+            Some(Nth::Last) if (date + Duration::days(7)).month() == date.month() => return false,
+            _ => {}
+        }
+        match &self.interval {
+            Some((Interval::Days(n), anchor)) => (date - *anchor).num_days().rem_euclid(*n) == 0,
+            Some((Interval::Weeks(n), anchor)) => {
+                let weeks = (week_start(date) - week_start(*anchor)).num_days() / 7;
+                weeks.rem_euclid(*n) == 0
+            }
+            None => true,
+        }
+    }
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+fn parse_weekday_range(s: &str) -> Result<(u32, u32), String> {
+    let parse = |wd: &str| -> Result<u32, String> {
+        wd.parse::<u32>()
+            .ok()
+            .filter(|wd| *wd <= 7)
+            .ok_or_else(|| format!("Invalid weekday: {}", wd))
+    };
+    match s.split_once('-') {
+        Some((start, end)) => Ok((parse(start)?, parse(end)?)),
+        None => {
+            // Normalize single weekday 7 to 0 (both mean Sunday)
+            let wd = parse(s)? % 7;
+            Ok((wd, wd))
+        }
+    }
+}
+
+// Parse "3d@20240315" or "2w@20240311"
+fn parse_interval(s: &str) -> Result<(Interval, NaiveDate), String> {
+    let (every, anchor) = s
+        .split_once('@')
+        .ok_or_else(|| format!("Invalid interval: {}", s))?;
+    let anchor = NaiveDate::parse_from_str(anchor, "%Y%m%d")
+        .map_err(|_| format!("Invalid interval anchor: {}", s))?;
+    let (count, unit) = every.split_at(every.len().saturating_sub(1));
+    let count: i64 = count
+        .parse()
+        .ok()
+        .filter(|c| *c > 0)
+        .ok_or_else(|| format!("Invalid interval: {}", s))?;
+    match unit {
+        "d" => Ok((Interval::Days(count), anchor)),
+        "w" => Ok((Interval::Weeks(count), anchor)),
+        _ => Err(format!("Invalid interval unit: {}", s)),
+    }
+}
+
+fn calculate_by_scan<Z: TimeZone>(
+    schedule: &ExtendedSchedule,
+    now: DateTime<Z>,
+    forward: bool,
+) -> Result<i64, String> {
+    let today = now.date_naive();
+    for offset in 0..=SCAN_DAYS {
+        let date = if forward {
+            today + Duration::days(offset)
+        } else {
+            today - Duration::days(offset)
+        };
+        if !schedule.matches(date) {
+            continue;
+        }
+        let Some(dt) = now.timezone()
+            .with_ymd_and_hms(date.year(), date.month(), date.day(), schedule.hour, schedule.minute, 0)
+            .earliest()
+        else {
+            continue;
+        };
+        let time_match = if forward { dt > now } else { dt < now };
+        if time_match {
+            return Ok(dt.timestamp());
+        }
+    }
+    Err("Could not find an occurrence for the schedule".to_string())
+}
+
+// The instant of a wall clock time on `date` in the zone of `now`.
+// Days are stepped on the calendar so the hour holds across DST changes,
+// a time skipped by a DST change moves an hour later.
+fn at_time<Z: TimeZone>(now: &DateTime<Z>, date: NaiveDate, hour: u32, minute: u32) -> Result<DateTime<Z>, String> {
+    let tz = now.timezone();
+    let time = date
+        .and_hms_opt(hour, minute, 0)
+        .ok_or_else(|| format!("Invalid time: {}:{}", hour, minute))?;
+    tz.from_local_datetime(&time)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(time + Duration::hours(1))).earliest())
+        .ok_or_else(|| format!("Time {} does not exist", time))
+}
+
+fn calculate_daily<Z: TimeZone>(now: DateTime<Z>, minute: u32, hour: u32, forward: bool) -> Result<i64, String> {
+    let today = now.date_naive();
+    let mut candidate = at_time(&now, today, hour, minute)?;
 
     if forward {
         // If we're past that time today, move to tomorrow
         if candidate <= now {
-            candidate += Duration::days(1);
+            candidate = at_time(&now, today + Duration::days(1), hour, minute)?;
         }
     } else {
         // If we're before that time today, move to yesterday
         if candidate >= now {
-            candidate -= Duration::days(1);
+            candidate = at_time(&now, today - Duration::days(1), hour, minute)?;
         }
     }
 
     Ok(candidate.timestamp())
 }
 
-fn calculate_weekly(
-    now: chrono::DateTime<Local>,
+fn calculate_weekly<Z: TimeZone>(
+    now: DateTime<Z>,
     minute: u32,
     hour: u32,
     weekday_str: &str,
     forward: bool,
 ) -> Result<i64, String> {
-    let mut candidate = now
-        .with_hour(hour)
-        .ok_or("Invalid hour")?
-        .with_minute(minute)
-        .ok_or("Invalid minute")?
-        .with_second(0)
-        .unwrap();
+    let mut date = now.date_naive();
 
     // Parse weekday - either single weekday or range between "1-7"
     let (start, end) = if weekday_str.contains('-') {
@@ -129,6 +371,7 @@ fn calculate_weekly(
 
     // Find next/last matching weekday
     for _ in 0..8 {
+        let candidate = at_time(&now, date, hour, minute)?;
         let wd = candidate.weekday().num_days_from_sunday();
         // Check if weekday matches range
         // Sunday is 0 from chrono, but also matches 7 in cron ranges
@@ -140,17 +383,17 @@ fn calculate_weekly(
         }
 
         if forward {
-            candidate += Duration::days(1);
+            date += Duration::days(1);
         } else {
-            candidate -= Duration::days(1);
+            date -= Duration::days(1);
         }
     }
 
     Err("Could not find valid weekday".to_string())
 }
 
-fn calculate_monthly(
-    now: chrono::DateTime<Local>,
+fn calculate_monthly<Z: TimeZone>(
+    now: DateTime<Z>,
     minute: u32,
     hour: u32,
     day: u32,
@@ -160,7 +403,7 @@ fn calculate_monthly(
     let mut month = now.month();
 
     // Try current month
-    if let Some(dt) = Local
+    if let Some(dt) = now.timezone()
         .with_ymd_and_hms(year, month, day, hour, minute, 0)
         .earliest()
     {
@@ -177,7 +420,7 @@ fn calculate_monthly(
             month = 1;
             year += 1;
         }
-        let dt = Local
+        let dt = now.timezone()
             .with_ymd_and_hms(year, month, day, hour, minute, 0)
             .earliest();
         match dt {
@@ -194,7 +437,7 @@ fn calculate_monthly(
                 month -= 1;
             }
 
-            if let Some(dt) = Local
+            if let Some(dt) = now.timezone()
                 .with_ymd_and_hms(year, month, day, hour, minute, 0)
                 .earliest()
             {
@@ -206,8 +449,8 @@ fn calculate_monthly(
     }
 }
 
-fn calculate_yearly(
-    now: chrono::DateTime<Local>,
+fn calculate_yearly<Z: TimeZone>(
+    now: DateTime<Z>,
     minute: u32,
     hour: u32,
     day: u32,
@@ -217,7 +460,7 @@ fn calculate_yearly(
     let mut year = now.year();
 
     // Try this year
-    if let Some(dt) = Local
+    if let Some(dt) = now.timezone()
         .with_ymd_and_hms(year, month, day, hour, minute, 0)
         .earliest()
     {
@@ -233,7 +476,7 @@ fn calculate_yearly(
     } else {
         year -= 1;
     }
-    let dt = Local
+    let dt = now.timezone()
         .with_ymd_and_hms(year, month, day, hour, minute, 0)
         .earliest();
     match dt {
@@ -285,6 +528,22 @@ mod tests {
             ("2024-12-26 10:00", "0 9 25 12 *", "2025-12-25 09:00"), // Next year
             ("2024-12-25 08:00", "0 9 25 12 *", "2024-12-25 09:00"), // Same day before time
             ("2024-12-25 10:00", "0 9 25 12 *", "2025-12-25 09:00"), // Same day after time
+            // Last day of month
+            ("2024-03-15 10:00", "0 9 L * *", "2024-03-31 09:00"),
+            ("2024-02-10 10:00", "0 9 L * *", "2024-02-29 09:00"), // Leap year
+            // Nth and last weekday of month
+            ("2024-03-15 10:00", "0 9 * * 1#1", "2024-04-01 09:00"), // First Monday of next month
+            ("2024-03-15 10:00", "0 9 * * 5#5", "2024-03-29 09:00"), // Fifth Friday
+            ("2024-03-15 10:00", "0 17 * * 5#L", "2024-03-29 17:00"), // Last Friday
+            ("2024-04-15 10:00", "0 9 * * 1#5", "2024-04-29 09:00"), // Fifth Monday, skips months without one
+            // Intervals counted from the anchor date
+            ("2024-03-15 10:00", "0 9 * * * 3d@20240314", "2024-03-17 09:00"),
+            ("2024-03-14 08:00", "0 9 * * * 3d@20240314", "2024-03-14 09:00"),
+            ("2024-03-15 10:00", "0 9 * * 1 2w@20240311", "2024-03-25 09:00"), // Skips the 18th
+            ("2024-03-15 10:00", "0 9 * * 1-5 2w@20240313", "2024-03-25 09:00"), // Anchor mid-week
+            // Several schedules
+            ("2024-03-15 10:00", "0 9 * * *;0 17 * * *", "2024-03-15 17:00"),
+            ("2024-03-15 18:00", "0 9 * * *;0 17 * * *", "2024-03-16 09:00"),
         ];
 
         for (now_str, cron, expected_str) in test_cases {
@@ -354,6 +613,13 @@ mod tests {
             ("2024-03-15 10:00", "0 9 25 12 *", "2023-12-25 09:00"), // Before date -> previous year
             ("2024-12-25 10:00", "0 9 25 12 *", "2024-12-25 09:00"), // Same day after time
             ("2024-12-25 08:00", "0 9 25 12 *", "2023-12-25 09:00"), // Same day before time
+            // Extended schedules
+            ("2024-03-15 10:00", "0 9 L * *", "2024-02-29 09:00"),
+            ("2024-03-15 10:00", "0 9 * * 1#1", "2024-03-04 09:00"),
+            ("2024-03-15 10:00", "0 9 * * * 3d@20240314", "2024-03-14 09:00"),
+            ("2024-03-20 10:00", "0 9 * * 1 2w@20240311", "2024-03-11 09:00"),
+            ("2024-03-15 10:00", "0 9 * * *;0 17 * * *", "2024-03-15 09:00"),
+            ("2024-03-15 18:00", "0 9 * * *;0 17 * * *", "2024-03-15 17:00"),
         ];
 
         for (now_str, cron, expected_str) in test_cases {
@@ -388,4 +654,38 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_invalid_extended_cron() {
+        let now = Local::now();
+        assert!(get_occurrence_from("0 9 * * 1#6", now, true).is_err());
+        assert!(get_occurrence_from("0 9 * * 1#x", now, true).is_err());
+        assert!(get_occurrence_from("0 9 * * * 0d@20240314", now, true).is_err());
+        assert!(get_occurrence_from("0 9 * * * 3m@20240314", now, true).is_err());
+        assert!(get_occurrence_from("0 9 * * * 3d@2024", now, true).is_err());
+        assert!(get_occurrence_from("0 9 * * *;", now, true).is_err());
+    }
+
+    #[test]
+    fn test_occurrence_in_named_zone() {
+        let tz: chrono_tz::Tz = "America/New_York".parse().unwrap();
+        // 7am in New York, "daily 9am" fires at 9am local time
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap().with_timezone(&tz);
+        let next = get_occurrence_from("0 9 * * *", now, true).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 15, 14, 0, 0).unwrap().timestamp());
+
+        // Still 9am local the day after the switch to daylight saving time
+        let now = Utc.with_ymd_and_hms(2025, 3, 8, 15, 0, 0).unwrap().with_timezone(&tz);
+        let next = get_occurrence_from("0 9 * * *", now, true).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 3, 9, 13, 0, 0).unwrap().timestamp());
+
+        // The current zone is used by the public API
+        timezone::set_current(Zone::Named(tz));
+        let ts = Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap().timestamp();
+        assert_eq!(
+            get_next_occurrence_after("0 9 * * *", ts).unwrap(),
+            Utc.with_ymd_and_hms(2025, 1, 15, 14, 0, 0).unwrap().timestamp()
+        );
+        timezone::set_current(Zone::Local);
+    }
 }
@@ -0,0 +1,362 @@
+use crate::args::{
+    estimate::parse_estimate,
+    parser::parse_status,
+    priority::parse_priority,
+    tag::parse_tag,
+    timestr::parse_flexible_timestr,
+};
+
+/// Filter expression of the list commands, e.g.
+/// `priority:high and due<friday and (assignee:me or unassigned) and not category:ops`.
+/// Terms next to each other without `and`/`or` are and-ed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Term(Term),
+}
+
+/// A single comparison, values are validated but kept as written
+/// since times and users can only be resolved once the context is known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub field: Field,
+    pub op: Op,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Priority,
+    Status,
+    Category,
+    Content,
+    Project,
+    Assignee,
+    Owner,
+    Tag,
+    Due,
+    Created,
+    Estimate,
+    // Bare keywords, they take no value
+    Unassigned,
+    Overdue,
+    Blocked,
+    Ready,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Filter {
+    /// Whether any term of the expression is on `field`.
+    pub fn mentions(&self, field: Field) -> bool {
+        match self {
+            Filter::And(left, right) | Filter::Or(left, right) => left.mentions(field) || right.mentions(field),
+            Filter::Not(inner) => inner.mentions(field),
+            Filter::Term(term) => term.field == field,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Word(String),
+}
+
+/// Parse a filter expression.
+pub fn parse_filter(s: &str) -> Result<Filter, String> {
+    let tokens = tokenize(s)?;
+    if tokens.is_empty() {
+        return Err("Filter expression cannot be empty".to_string());
+    }
+    let mut parser = FilterParser { tokens, pos: 0 };
+    let filter = parser.parse_or()?;
+    // Terms are and-ed up to the first unmatched ')'
+    match parser.tokens.get(parser.pos) {
+        None => Ok(filter),
+        Some(_) => Err("Unbalanced ')' in filter".to_string()),
+    }
+}
+
+// Splits on whitespace and parentheses, double or single quotes keep a value together:
+// due<"end of week" is the single word `due<end of week`.
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    chars.next();
+                    if c == '"' || c == '\'' {
+                        let mut closed = false;
+                        for q in chars.by_ref() {
+                            if q == c {
+                                closed = true;
+                                break;
+                            }
+                            word.push(q);
+                        }
+                        if !closed {
+                            return Err("Unterminated quote in filter".to_string());
+                        }
+                    } else {
+                        word.push(c);
+                    }
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct FilterParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl FilterParser {
+    fn parse_or(&mut self) -> Result<Filter, String> {
+        let mut left = self.parse_and()?;
+        while self.eat_keyword("or") {
+            let right = self.parse_and()?;
+            left = Filter::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, String> {
+        let mut left = self.parse_unary()?;
+        loop {
+            if self.eat_keyword("and") {
+                let right = self.parse_unary()?;
+                left = Filter::And(Box::new(left), Box::new(right));
+                continue;
+            }
+            // Implicit and between adjacent terms
+            match self.tokens.get(self.pos) {
+                Some(Token::Open) => {}
+                Some(Token::Word(word)) if !word.eq_ignore_ascii_case("or") => {}
+                _ => break,
+            }
+            let right = self.parse_unary()?;
+            left = Filter::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Filter, String> {
+        if self.eat_keyword("not") {
+            return Ok(Filter::Not(Box::new(self.parse_unary()?)));
+        }
+        match self.tokens.get(self.pos) {
+            Some(Token::Open) => {
+                self.pos += 1;
+                let inner = self.parse_or()?;
+                if self.tokens.get(self.pos) != Some(&Token::Close) {
+                    return Err("Missing ')' in filter".to_string());
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Some(Token::Word(word)) => {
+                if ["and", "or"].iter().any(|k| word.eq_ignore_ascii_case(k)) {
+                    return Err(format!("Expected a filter term before '{}'", word));
+                }
+                let term = parse_term(word)?;
+                self.pos += 1;
+                Ok(Filter::Term(term))
+            }
+            Some(Token::Close) => Err("Unexpected ')' in filter".to_string()),
+            None => Err("Filter expression ends unexpectedly".to_string()),
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        match self.tokens.get(self.pos) {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn parse_term(word: &str) -> Result<Term, String> {
+    let keyword = |field| Term { field, op: Op::Eq, value: String::new() };
+
+    if let Some(tag) = word.strip_prefix('+') {
+        return Ok(Term {
+            field: Field::Tag,
+            op: Op::Eq,
+            value: parse_tag(tag)?,
+        });
+    }
+
+    let split = word.find(|c: char| !(c.is_alphanumeric() || c == '_'));
+    let Some(split) = split else {
+        return match word.to_lowercase().as_str() {
+            "unassigned" => Ok(keyword(Field::Unassigned)),
+            "overdue" => Ok(keyword(Field::Overdue)),
+            "blocked" => Ok(keyword(Field::Blocked)),
+            "ready" => Ok(keyword(Field::Ready)),
+            _ => Err(format!(
+                "Unknown filter term '{}', expected field:value, +tag, unassigned, overdue, blocked or ready",
+                word
+            )),
+        };
+    };
+
+    let (name, rest) = word.split_at(split);
+    let (op, value) = [
+        ("<=", Op::Le),
+        (">=", Op::Ge),
+        ("!=", Op::Ne),
+        (":", Op::Eq),
+        ("=", Op::Eq),
+        ("<", Op::Lt),
+        (">", Op::Gt),
+    ]
+    .iter()
+    .find_map(|(symbol, op)| rest.strip_prefix(symbol).map(|value| (*op, value)))
+    .ok_or_else(|| format!("Invalid operator in filter term '{}'", word))?;
+
+    if value.is_empty() {
+        return Err(format!("Missing value in filter term '{}'", word));
+    }
+
+    let field = match name.to_lowercase().as_str() {
+        "priority" => Field::Priority,
+        "status" => Field::Status,
+        "category" | "cat" => Field::Category,
+        "content" | "text" => Field::Content,
+        "project" => Field::Project,
+        "assignee" => Field::Assignee,
+        "owner" => Field::Owner,
+        "tag" => Field::Tag,
+        "due" => Field::Due,
+        "created" => Field::Created,
+        "estimate" => Field::Estimate,
+        _ => return Err(format!("Unknown filter field '{}'", name)),
+    };
+
+    let ordered = matches!(field, Field::Due | Field::Created | Field::Estimate);
+    if !ordered && !matches!(op, Op::Eq | Op::Ne) {
+        return Err(format!("Field '{}' only supports ':' and '!='", name));
+    }
+
+    let none = value.eq_ignore_ascii_case("none");
+    let value = match field {
+        Field::Priority => parse_priority(value).map(|_| value.to_string())?,
+        Field::Status => parse_status(value).map(|_| value.to_string())?,
+        Field::Tag => parse_tag(value)?,
+        Field::Due | Field::Created if !none => parse_flexible_timestr(value).map(|_| value.to_string())?,
+        Field::Estimate if !none => parse_estimate(value).map(|minutes| minutes.to_string())?,
+        _ => value.to_string(),
+    };
+    if none && ordered && !matches!(op, Op::Eq | Op::Ne) {
+        return Err(format!("'{}' can only be compared to none with ':' or '!='", name));
+    }
+    if none && field == Field::Created {
+        return Err("Every item has a creation time".to_string());
+    }
+
+    Ok(Term { field, op, value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(field: Field, op: Op, value: &str) -> Filter {
+        Filter::Term(Term {
+            field,
+            op,
+            value: value.to_string(),
+        })
+    }
+
+    fn and(left: Filter, right: Filter) -> Filter {
+        Filter::And(Box::new(left), Box::new(right))
+    }
+
+    #[test]
+    fn test_parse_filter_precedence() {
+        let filter =
+            parse_filter("priority:high and due<friday and (assignee:me or unassigned) and not category:ops").unwrap();
+        let expected = and(
+            and(
+                and(term(Field::Priority, Op::Eq, "high"), term(Field::Due, Op::Lt, "friday")),
+                Filter::Or(
+                    Box::new(term(Field::Assignee, Op::Eq, "me")),
+                    Box::new(term(Field::Unassigned, Op::Eq, "")),
+                ),
+            ),
+            Filter::Not(Box::new(term(Field::Category, Op::Eq, "ops"))),
+        );
+        assert_eq!(filter, expected);
+
+        // and binds tighter than or, adjacent terms are and-ed
+        let filter = parse_filter("+backend status:done or blocked").unwrap();
+        let expected = Filter::Or(
+            Box::new(and(term(Field::Tag, Op::Eq, "backend"), term(Field::Status, Op::Eq, "done"))),
+            Box::new(term(Field::Blocked, Op::Eq, "")),
+        );
+        assert_eq!(filter, expected);
+    }
+
+    #[test]
+    fn test_parse_filter_values() {
+        assert_eq!(parse_filter("due<=\"end of week\"").unwrap(), term(Field::Due, Op::Le, "end of week"));
+        assert_eq!(parse_filter("estimate>2h").unwrap(), term(Field::Estimate, Op::Gt, "120"));
+        assert_eq!(parse_filter("project!=none").unwrap(), term(Field::Project, Op::Ne, "none"));
+        assert_eq!(parse_filter("tag:Security").unwrap(), term(Field::Tag, Op::Eq, "security"));
+        assert!(parse_filter("priority:high").unwrap().mentions(Field::Priority));
+        assert!(!parse_filter("priority:high").unwrap().mentions(Field::Status));
+    }
+
+    #[test]
+    fn test_parse_filter_errors() {
+        assert!(parse_filter("").unwrap_err().contains("empty"));
+        assert!(parse_filter("priority:urgent").unwrap_err().contains("Invalid priority"));
+        assert!(parse_filter("priority<high").unwrap_err().contains("only supports"));
+        assert!(parse_filter("due<someday").is_err());
+        assert!(parse_filter("colour:red").unwrap_err().contains("Unknown filter field"));
+        assert!(parse_filter("urgent").unwrap_err().contains("Unknown filter term"));
+        assert!(parse_filter("(priority:high").unwrap_err().contains("Missing ')'"));
+        assert!(parse_filter("priority:high)").unwrap_err().contains("Unbalanced"));
+        assert!(parse_filter("priority:high and").unwrap_err().contains("ends unexpectedly"));
+        assert!(parse_filter("or blocked").is_err());
+        assert!(parse_filter("category:").unwrap_err().contains("Missing value"));
+        assert!(parse_filter("due<\"end of week").unwrap_err().contains("Unterminated"));
+    }
+}
@@ -0,0 +1,44 @@
+/// How commands print the items they list or change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Box table for humans
+    #[default]
+    Table,
+    /// A single JSON document, `{"items": [...]}`
+    Json,
+    /// One JSON item per line
+    Ndjson,
+}
+
+impl OutputFormat {
+    pub fn is_table(&self) -> bool {
+        *self == OutputFormat::Table
+    }
+}
+
+/// Parse the value of --format.
+pub fn parse_format(s: &str) -> Result<OutputFormat, String> {
+    match s.to_lowercase().as_str() {
+        "table" => Ok(OutputFormat::Table),
+        "json" => Ok(OutputFormat::Json),
+        "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
+        _ => Err(format!(
+            "Invalid format '{}', expected table, json or ndjson",
+            s
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(parse_format("table").unwrap(), OutputFormat::Table);
+        assert_eq!(parse_format("JSON").unwrap(), OutputFormat::Json);
+        assert_eq!(parse_format("ndjson").unwrap(), OutputFormat::Ndjson);
+        assert_eq!(parse_format("jsonl").unwrap(), OutputFormat::Ndjson);
+        assert!(parse_format("yaml").is_err());
+    }
+}
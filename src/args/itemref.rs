@@ -0,0 +1,254 @@
+use std::fmt;
+
+use rusqlite::Connection;
+
+use crate::{
+    context::Context,
+    db::{cache, crud::get_stored_item},
+};
+
+/// Reference to an item on the command line.
+/// Either an index from the previous list command, which is only valid
+/// until the next list, or a stable `id:42` / `#42` that always points at the same item.
+/// A bare `#42` starts a comment in POSIX shells, so it has to be quoted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemRef {
+    Index(usize),
+    Id(i64),
+}
+
+/// Parse `3` as a list index, and `id:42` or `#42` as an item id.
+pub fn parse_item_ref(s: &str) -> Result<ItemRef, String> {
+    if let Some(id) = s.strip_prefix('#').or_else(|| s.strip_prefix("id:")) {
+        let id: i64 = id
+            .parse()
+            .map_err(|_| format!("Invalid item id '{}', expected e.g. id:42 or '#42'", s))?;
+        if id < 1 {
+            return Err("Item id must be greater than 0".to_string());
+        }
+        return Ok(ItemRef::Id(id));
+    }
+
+    let index: usize = s
+        .parse()
+        .map_err(|_| "Index must be a number, or an item id like id:42 or '#42' (quoted, # starts a shell comment)".to_string())?;
+    if index == 0 {
+        return Err("Index must be greater than 0".to_string());
+    }
+    if index > 65536 {
+        return Err("Index cannot exceed 65536".to_string());
+    }
+    Ok(ItemRef::Index(index))
+}
+
+/// One or more items on the command line: a reference, or a range of
+/// list indexes like `5-8` for bulk commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemRange {
+    One(ItemRef),
+    Indexes(usize, usize),
+}
+
+impl From<ItemRef> for ItemRange {
+    fn from(item_ref: ItemRef) -> Self {
+        ItemRange::One(item_ref)
+    }
+}
+
+/// Parse `3`, `#42` or an inclusive index range `5-8`.
+pub fn parse_item_range(s: &str) -> Result<ItemRange, String> {
+    let Some((start, end)) = s.split_once('-') else {
+        return parse_item_ref(s).map(ItemRange::One);
+    };
+    let bound = |part: &str| match parse_item_ref(part) {
+        Ok(ItemRef::Index(index)) => Ok(index),
+        _ => Err(format!("Invalid range '{}', expected list indexes like 5-8", s)),
+    };
+    let (start, end) = (bound(start)?, bound(end)?);
+    if start > end {
+        return Err(format!("Invalid range '{}', the start is after the end", s));
+    }
+    Ok(ItemRange::Indexes(start, end))
+}
+
+/// Flattens ranges into references in the order given, dropping repeats.
+pub fn expand_ranges(ranges: &[ItemRange]) -> Vec<ItemRef> {
+    let mut refs = Vec::new();
+    for range in ranges {
+        let expanded: Vec<ItemRef> = match *range {
+            ItemRange::One(item_ref) => vec![item_ref],
+            ItemRange::Indexes(start, end) => (start..=end).map(ItemRef::Index).collect(),
+        };
+        for item_ref in expanded {
+            if !refs.contains(&item_ref) {
+                refs.push(item_ref);
+            }
+        }
+    }
+    refs
+}
+
+/// Resolves a reference to the id of a live item in scope: an id as is, or an index
+/// through the cache of this session's last listing. Items in the trash, or in a
+/// namespace outside of the context, are refused.
+pub fn resolve_rowid(conn: &Connection, ctx: &Context, item_ref: ItemRef) -> Result<i64, String> {
+    resolve(conn, ctx, item_ref, Scope::Live)
+}
+
+/// Same as `resolve_rowid` for items in the trash, indexes are those of `ctm trash list`.
+pub fn resolve_trashed_rowid(conn: &Connection, ctx: &Context, item_ref: ItemRef) -> Result<i64, String> {
+    resolve(conn, ctx, item_ref, Scope::Trashed)
+}
+
+/// Same as `resolve_rowid`, also for items in the trash or deleted for good, whose history outlives them.
+pub fn resolve_any_rowid(conn: &Connection, ctx: &Context, item_ref: ItemRef) -> Result<i64, String> {
+    resolve(conn, ctx, item_ref, Scope::Any)
+}
+
+#[derive(PartialEq)]
+enum Scope {
+    Live,
+    Trashed,
+    Any,
+}
+
+fn resolve(conn: &Connection, ctx: &Context, item_ref: ItemRef, scope: Scope) -> Result<i64, String> {
+    let id = match item_ref {
+        ItemRef::Id(id) => id,
+        ItemRef::Index(index) => {
+            let listing = if scope == Scope::Trashed { "trash list" } else { "list" };
+            match cache::validate_cache(conn) {
+                Ok(true) => {}
+                Ok(false) => {
+                    return Err(format!("Cache is not valid, consider running {} command first", listing))
+                }
+                Err(_) => return Err("Cannot connect to cache".to_string()),
+            }
+            cache::read(conn, index as i64)
+                .map_err(|e| format!("Failed to read cache table: {:?}", e))?
+                .ok_or_else(|| format!("index {} does not exist", index))?
+        }
+    };
+
+    let item = match get_stored_item(conn, id) {
+        Ok(item) => item,
+        Err(_) if scope == Scope::Any => return Ok(id),
+        Err(_) => return Err(format!("item #{} does not exist", id)),
+    };
+    match (scope, item.deleted_at.is_some()) {
+        (Scope::Live, true) => return Err(format!("item #{} is in the trash, see ctm trash list", id)),
+        (Scope::Trashed, false) => return Err(format!("#{} is not in the trash", id)),
+        _ => {}
+    }
+    ctx.check_namespace(conn, &item)?;
+    Ok(id)
+}
+
+impl fmt::Display for ItemRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemRef::Index(index) => write!(f, "{}", index),
+            ItemRef::Id(id) => write!(f, "#{}", id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{
+            crud::{delete_item, get_item, trash_item, update_item},
+            namespace::create_namespace,
+        },
+        tests::{get_test_conn, insert_task},
+    };
+
+    #[test]
+    fn test_parse_item_ref() {
+        assert_eq!(parse_item_ref("3").unwrap(), ItemRef::Index(3));
+        assert_eq!(parse_item_ref("65536").unwrap(), ItemRef::Index(65536));
+        assert_eq!(parse_item_ref("#42").unwrap(), ItemRef::Id(42));
+        assert_eq!(parse_item_ref("#100000").unwrap(), ItemRef::Id(100000));
+        assert_eq!(parse_item_ref("id:42").unwrap(), ItemRef::Id(42));
+
+        assert!(parse_item_ref("0").is_err());
+        assert!(parse_item_ref("65537").is_err());
+        assert!(parse_item_ref("#0").is_err());
+        assert!(parse_item_ref("#").is_err());
+        assert!(parse_item_ref("#abc").unwrap_err().contains("id:42"));
+        assert!(parse_item_ref("id:").is_err());
+        assert!(parse_item_ref("abc").is_err());
+    }
+
+    #[test]
+    fn test_parse_item_range() {
+        assert_eq!(parse_item_range("3").unwrap(), ItemRange::One(ItemRef::Index(3)));
+        assert_eq!(parse_item_range("#42").unwrap(), ItemRange::One(ItemRef::Id(42)));
+        assert_eq!(parse_item_range("5-8").unwrap(), ItemRange::Indexes(5, 8));
+        assert_eq!(parse_item_range("4-4").unwrap(), ItemRange::Indexes(4, 4));
+
+        assert!(parse_item_range("8-5").unwrap_err().contains("start is after"));
+        assert!(parse_item_range("#3-#5").is_err());
+        assert!(parse_item_range("0-2").is_err());
+        assert!(parse_item_range("3-").is_err());
+    }
+
+    #[test]
+    fn test_expand_ranges() {
+        let ranges = [
+            ItemRange::One(ItemRef::Index(1)),
+            ItemRange::Indexes(3, 5),
+            ItemRange::One(ItemRef::Index(4)),
+            ItemRange::One(ItemRef::Id(42)),
+        ];
+        assert_eq!(
+            expand_ranges(&ranges),
+            vec![
+                ItemRef::Index(1),
+                ItemRef::Index(3),
+                ItemRef::Index(4),
+                ItemRef::Index(5),
+                ItemRef::Id(42),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_display_item_ref() {
+        assert_eq!(ItemRef::Index(3).to_string(), "3");
+        assert_eq!(ItemRef::Id(42).to_string(), "#42");
+    }
+
+    #[test]
+    fn test_resolve_rowid() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let first = insert_task(&conn, "work", "first", "tomorrow");
+        let second = insert_task(&conn, "work", "second", "tomorrow");
+
+        assert!(resolve_rowid(&conn, &ctx, ItemRef::Index(1)).unwrap_err().contains("running list command"));
+        cache::store(&conn, &[get_item(&conn, first).unwrap(), get_item(&conn, second).unwrap()]).unwrap();
+        assert_eq!(resolve_rowid(&conn, &ctx, ItemRef::Index(2)).unwrap(), second);
+        assert_eq!(resolve_rowid(&conn, &ctx, ItemRef::Id(first)).unwrap(), first);
+        assert_eq!(resolve_rowid(&conn, &ctx, ItemRef::Index(3)).unwrap_err(), "index 3 does not exist");
+        assert_eq!(resolve_rowid(&conn, &ctx, ItemRef::Id(99)).unwrap_err(), "item #99 does not exist");
+
+        // Another namespace needs --all-namespaces
+        let mut item = get_item(&conn, second).unwrap();
+        item.namespace_id = Some(create_namespace(&conn, "ops", None, ctx.current_user_id).unwrap());
+        update_item(&conn, &item, None).unwrap();
+        assert!(resolve_rowid(&conn, &ctx, ItemRef::Index(2)).unwrap_err().contains("--all-namespaces"));
+        let all = ctx.clone().with_all_namespaces(true);
+        assert_eq!(resolve_rowid(&conn, &all, ItemRef::Index(2)).unwrap(), second);
+
+        // Trashed items only resolve for the trash, purged ones only for their history
+        trash_item(&conn, first, None).unwrap();
+        assert!(resolve_rowid(&conn, &ctx, ItemRef::Id(first)).unwrap_err().contains("in the trash"));
+        assert_eq!(resolve_trashed_rowid(&conn, &ctx, ItemRef::Id(first)).unwrap(), first);
+        assert!(resolve_trashed_rowid(&conn, &all, ItemRef::Id(second)).unwrap_err().contains("not in the trash"));
+        delete_item(&conn, first, None).unwrap();
+        assert!(resolve_trashed_rowid(&conn, &ctx, ItemRef::Id(first)).is_err());
+        assert_eq!(resolve_any_rowid(&conn, &ctx, ItemRef::Index(1)).unwrap(), first);
+    }
+}
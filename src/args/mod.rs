@@ -1,5 +1,11 @@
 pub mod cron;
 pub mod estimate;
+pub mod filter;
+pub mod format;
+pub mod itemref;
 pub mod parser;
 pub mod priority;
+pub mod sort;
+pub mod tag;
 pub mod timestr;
+pub mod timezone;
@@ -5,8 +5,14 @@ use clap::{
 };
 use crate::args::{
     estimate::parse_estimate,
+    filter::parse_filter,
+    format::{parse_format, OutputFormat},
+    itemref::{parse_item_range, parse_item_ref, ItemRange, ItemRef},
     priority::parse_priority,
-    timestr::{parse_flexible_timestr, parse_recurring_timestr},
+    sort::{parse_sort, SortKey},
+    tag::parse_tag,
+    timestr::{parse_flexible_timestr, parse_recurring_schedule},
+    timezone::parse_zone,
 };
 
 /// Claude-first task management CLI with multi-tenant support.
@@ -24,10 +30,32 @@ pub struct CliArgs {
     #[arg(long = "ns", global = true)]
     pub namespace: Option<String>,
 
+    /// Lift namespace scoping, operate on items of every namespace you are a member of
+    #[arg(long, global = true)]
+    pub all_namespaces: bool,
+
+    /// Print JSON instead of tables, same as --format json
+    #[arg(long, global = true, conflicts_with = "format")]
+    pub json: bool,
+
+    /// Output format: table (default), json, or ndjson with one item per line
+    #[arg(long, global = true, value_parser = parse_format)]
+    pub format: Option<OutputFormat>,
+
     #[command(subcommand)]
     pub arguments: Action,
 }
 
+impl CliArgs {
+    pub fn output_format(&self) -> OutputFormat {
+        match self.format {
+            Some(format) => format,
+            None if self.json => OutputFormat::Json,
+            None => OutputFormat::Table,
+        }
+    }
+}
+
 #[derive(Debug, Subcommand)]
 pub enum Action {
     /// add task
@@ -36,6 +64,10 @@ pub enum Action {
     Record(RecordCommand),
     /// complete task and generates a corresponding record entry
     Done(DoneCommand),
+    /// skip the current occurrence of a recurring task
+    Skip(SkipCommand),
+    /// hide the current occurrence of a recurring task until a later time
+    Snooze(SnoozeCommand),
     /// update task and record entries
     Update(UpdateCommand),
     /// delete task or record
@@ -51,6 +83,10 @@ pub enum Action {
     Claim(ClaimCommand),
     /// attach a link (commit, issue, PR, URL) to a task
     Link(LinkCommand),
+    /// mark a task as blocked until another task is done
+    Block(BlockCommand),
+    /// remove a dependency added by block
+    Unblock(BlockCommand),
     /// manage users
     #[command(subcommand)]
     User(UserCommand),
@@ -63,11 +99,37 @@ pub enum Action {
     Workload(WorkloadCommand),
     /// show task statistics
     Stats(StatsCommand),
+    /// show the change history of a task or record
+    History(HistoryCommand),
+    /// show recent changes across the current namespace
+    Log(LogCommand),
+    /// full-text search over tasks, records, notes and link titles
+    Search(SearchCommand),
+    /// start a timer on a task, stopping the one already running
+    Start(StartCommand),
+    /// stop the running timer
+    Stop,
+    /// record time spent on a task without a timer
+    LogTime(LogTimeCommand),
+    /// run a saved list view, or save, list and delete views
+    View(ViewCommand),
+    /// undo your last command that changed tasks or records
+    Undo(UndoCommand),
+    /// list, restore or purge deleted tasks and records
+    #[command(subcommand)]
+    Trash(TrashCommand),
+    /// move closed tasks and old records to the archive
+    Archive(ArchiveCommand),
+    /// create sets of tasks from templates in the config
+    #[command(subcommand)]
+    Template(TemplateCommand),
+    /// show tasks as a kanban board with a column per status
+    Board(BoardCommand),
 }
 
 #[derive(Debug, Args)]
 pub struct TaskCommand {
-    /// description of the task
+    /// description of the task, +tag words are taken off it as tags
     #[arg(value_parser = |s: &str| syntax_helper("task", s))]
     pub content: String,
     /// time the task is due for completion, default to EOD,
@@ -96,6 +158,9 @@ pub struct TaskCommand {
     /// create task from GitHub issue (e.g., owner/repo#42)
     #[arg(long)]
     pub from_issue: Option<String>,
+    /// create the task as a subtask of another task (index, or id like id:42)
+    #[arg(long, value_parser = parse_item_ref, conflicts_with = "from_issue")]
+    pub parent: Option<ItemRef>,
 }
 
 #[derive(Debug, Args)]
@@ -114,9 +179,15 @@ pub struct RecordCommand {
 
 #[derive(Debug, Args)]
 pub struct DoneCommand {
-    /// index from previous list command
-    #[arg(value_parser = validate_index)]
-    pub index: usize,
+    /// indexes from previous list command, ranges like 5-8, or stable item ids like id:42 or '#42'
+    #[arg(value_name = "INDEX", value_parser = parse_item_range, required_unless_present = "filter")]
+    pub indexes: Vec<ItemRange>,
+    /// act on every open task matching a filter expression instead
+    #[arg(long = "where", value_name = "FILTER", value_parser = validate_filter, conflicts_with = "indexes")]
+    pub filter: Option<String>,
+    /// don't ask before changing several items
+    #[arg(short, long, default_value_t = false)]
+    pub yes: bool,
     /// optional status, default to done.
     #[arg(short, long, value_parser = parse_status, default_value_t = 1)]
     pub status: u8,
@@ -126,20 +197,56 @@ pub struct DoneCommand {
     /// close linked GitHub issue when completing task
     #[arg(long)]
     pub close_issue: bool,
+    /// also complete the parent task once its last open subtask is closed
+    #[arg(long)]
+    pub close_parent: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SkipCommand {
+    /// index from previous list command, or a stable item id like id:42 or '#42'
+    #[arg(value_parser = parse_item_ref)]
+    pub index: ItemRef,
+    /// reason for skipping, kept on the skip record
+    #[arg(short, long)]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct SnoozeCommand {
+    /// index from previous list command, or a stable item id like id:42 or '#42'
+    #[arg(value_parser = parse_item_ref)]
+    pub index: ItemRef,
+    /// when the occurrence shows up again, e.g. "3pm" or "tomorrow 9am"
+    #[arg(value_parser = validate_flexible_timestr)]
+    pub timestr: String,
 }
 
 #[derive(Debug, Args)]
 pub struct DeleteCommand {
-    /// index from previous list command
-    #[arg(value_parser = validate_index)]
-    pub index: usize,
+    /// indexes from previous list command, ranges like 5-8, or stable item ids like id:42 or '#42'
+    #[arg(value_name = "INDEX", value_parser = parse_item_range, required_unless_present = "filter")]
+    pub indexes: Vec<ItemRange>,
+    /// delete every open task matching a filter expression instead
+    #[arg(long = "where", value_name = "FILTER", value_parser = validate_filter, conflicts_with = "indexes")]
+    pub filter: Option<String>,
+    /// don't ask for confirmation
+    #[arg(short, long, default_value_t = false)]
+    pub yes: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct UpdateCommand {
-    /// index from previous list command
-    #[arg(value_parser = validate_index)]
-    pub index: usize,
+    /// indexes from previous list command, ranges like 5-8, or stable item ids like id:42 or '#42',
+    /// followed by tags to add, e.g. 2-6 +backend +security
+    #[arg(value_name = "INDEX|+TAG", value_parser = parse_update_target, required_unless_present = "filter")]
+    pub targets: Vec<UpdateTarget>,
+    /// update every open task matching a filter expression instead of indexes
+    #[arg(long = "where", value_name = "FILTER", value_parser = validate_filter)]
+    pub filter: Option<String>,
+    /// don't ask before changing several items
+    #[arg(short, long, default_value_t = false)]
+    pub yes: bool,
     /// update the target time of task,
     /// or event time of record,
     /// or schedule of a recurring task
@@ -148,7 +255,8 @@ pub struct UpdateCommand {
     /// update category of the task/record
     #[arg(short, long)]
     pub category: Option<String>,
-    /// replace the content of the task/record
+    /// replace the content of the task/record,
+    /// +tag words are taken off it as tags
     #[arg(short='w', long)]
     pub content: Option<String>,
     /// add to entry content in a newline
@@ -164,6 +272,40 @@ pub struct UpdateCommand {
     /// update project association (must be defined in config)
     #[arg(short = 'p', long)]
     pub project: Option<String>,
+    /// tag to remove, can be repeated
+    #[arg(long = "untag", value_name = "TAG", value_parser = parse_tag)]
+    pub untags: Vec<String>,
+}
+
+/// A positional word of `update`, items and tags can't be told apart by position.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateTarget {
+    Items(ItemRange),
+    Tag(String),
+}
+
+impl UpdateCommand {
+    /// The items to update, in the order given.
+    pub fn indexes(&self) -> Vec<ItemRange> {
+        self.targets
+            .iter()
+            .filter_map(|target| match target {
+                UpdateTarget::Items(range) => Some(*range),
+                UpdateTarget::Tag(_) => None,
+            })
+            .collect()
+    }
+
+    /// The tags to add.
+    pub fn tags(&self) -> Vec<String> {
+        self.targets
+            .iter()
+            .filter_map(|target| match target {
+                UpdateTarget::Tag(tag) => Some(tag.clone()),
+                UpdateTarget::Items(_) => None,
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Subcommand)]
@@ -180,7 +322,11 @@ pub enum ListCommand {
 pub struct ListTaskCommand {
     /// task due time. e.g. today,
     /// when present it restrict the task listed to be those,
-    /// that are marked for completion prior to this time
+    /// that are marked for completion prior to this time,
+    /// relative times like "in 3 days" or "end of week" work too.
+    /// Or a filter expression such as
+    /// 'priority:high and due<friday and (assignee:me or unassigned) and not category:ops'
+    #[arg(value_name = "TIME|FILTER", value_parser = validate_timestr_or_filter)]
     pub timestr: Option<String>,
     /// category of the task
     #[arg(short, long)]
@@ -212,17 +358,36 @@ pub struct ListTaskCommand {
     /// show tasks for all users (ignores current user filter)
     #[arg(long, default_value_t = false)]
     pub all_users: bool,
+    /// only show tasks whose dependencies are all closed
+    #[arg(long, default_value_t = false)]
+    pub ready: bool,
+    /// only show tasks carrying this tag, can be repeated to require several
+    #[arg(long = "tag", value_name = "TAG", value_parser = parse_tag)]
+    pub tags: Vec<String>,
+    /// hide tasks carrying this tag, can be repeated
+    #[arg(long = "not-tag", value_name = "TAG", value_parser = parse_tag)]
+    pub not_tags: Vec<String>,
+    /// order the listed tasks by due, priority, created, category or estimate
+    /// instead of due time with recurring tasks first
+    #[arg(long, value_parser = parse_sort)]
+    pub sort: Option<SortKey>,
+    /// reverse the order of the listed tasks
+    #[arg(long, default_value_t = false)]
+    pub reverse: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct ListRecordCommand {
+    /// filter expression, e.g. 'category:work and created>monday'
+    #[arg(value_parser = validate_filter)]
+    pub filter: Option<String>,
     /// category of the record
     #[arg(short, long)]
     pub category: Option<String>,
     /// days of records to retrieve,
     /// e.g. 1 shows record made in the last 24 hours,
     /// value of 7 would show record made in the past week
-    #[arg(short, long, conflicts_with_all = ["starting_date", "ending_date"])]
+    #[arg(short, long, conflicts_with_all = ["starting_time", "ending_time"])]
     pub days: Option<usize>,
     /// limit the amount of records returned
     #[arg(short, long, default_value_t = 100, value_parser = validate_limit)]
@@ -243,11 +408,65 @@ pub struct ListRecordCommand {
     pub search: Option<String>,
 }
 
+/// The words following `ctm list`, as kept by a saved view.
+#[derive(Debug, Parser)]
+#[command(name = "ctm list", args_override_self = true)]
+pub struct ListArgs {
+    #[command(subcommand)]
+    pub list: ListCommand,
+}
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct ViewCommand {
+    #[command(subcommand)]
+    pub action: Option<ViewAction>,
+    /// name of the view to run, lists the views when omitted
+    pub name: Option<String>,
+    /// more list arguments after --, they override the saved ones,
+    /// e.g. ctm view backlog -- --limit 5
+    #[arg(last = true)]
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ViewAction {
+    /// save list arguments under a name,
+    /// e.g. ctm view save backlog -- task 'priority:high' --sort due
+    Save(ViewSaveCommand),
+    /// list your views and the ones shared in the namespace
+    List,
+    /// delete a view
+    Delete(ViewDeleteCommand),
+}
+
+#[derive(Debug, Args)]
+pub struct ViewSaveCommand {
+    /// name of the view
+    #[arg(value_parser = validate_view_name)]
+    pub name: String,
+    /// share the view with every member of the current namespace
+    #[arg(long, default_value_t = false)]
+    pub shared: bool,
+    /// arguments of `ctm list` after --, starting with task (the default) or record
+    #[arg(last = true, required = true)]
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ViewDeleteCommand {
+    /// name of the view
+    pub name: String,
+    /// delete the view shared in the current namespace instead of your own
+    #[arg(long, default_value_t = false)]
+    pub shared: bool,
+}
+
 #[derive(Debug, Args)]
 pub struct ShowContentCommand {
-    /// index from previous list command
-    #[arg(value_parser = validate_index)]
-    pub index: usize,
+    /// index from previous list command, or a stable item id like id:42 or '#42'
+    #[arg(value_parser = parse_item_ref)]
+    pub index: ItemRef,
 }
 
 #[derive(Debug, Subcommand)]
@@ -258,6 +477,8 @@ pub enum UserCommand {
     List,
     /// delete a user
     Delete(UserDeleteCommand),
+    /// show or set your time zone
+    Tz(TimeZoneCommand),
 }
 
 #[derive(Debug, Args)]
@@ -275,6 +496,13 @@ pub struct UserDeleteCommand {
     pub name: String,
 }
 
+#[derive(Debug, Args)]
+pub struct TimeZoneCommand {
+    /// IANA time zone like Europe/Berlin, "local" for the system zone, "none" to unset
+    #[arg(value_parser = validate_time_zone)]
+    pub zone: Option<String>,
+}
+
 #[derive(Debug, Subcommand)]
 pub enum NamespaceCommand {
     /// create a new namespace
@@ -291,6 +519,8 @@ pub enum NamespaceCommand {
     RemoveUser(NamespaceRemoveUserCommand),
     /// list members of a namespace
     Members(NamespaceMembersCommand),
+    /// show or set the default time zone of the current namespace
+    Tz(TimeZoneCommand),
 }
 
 #[derive(Debug, Args)]
@@ -312,6 +542,9 @@ pub struct NamespaceDeleteCommand {
 pub struct NamespaceSwitchCommand {
     /// namespace name to switch to
     pub name: String,
+    /// also make this user the default identity
+    #[arg(short = 'u', long)]
+    pub user: Option<String>,
 }
 
 #[derive(Debug, Args)]
@@ -341,32 +574,136 @@ pub struct NamespaceMembersCommand {
 
 #[derive(Debug, Args)]
 pub struct NoteCommand {
-    /// index from previous list command
-    #[arg(value_parser = validate_index)]
-    pub index: usize,
+    /// index from previous list command, or a stable item id like id:42 or '#42'
+    #[arg(value_parser = parse_item_ref)]
+    pub index: ItemRef,
     /// note content to add
     pub content: String,
 }
 
 #[derive(Debug, Args)]
 pub struct ShowCommand {
-    /// index from previous list command
-    #[arg(value_parser = validate_index)]
-    pub index: usize,
+    /// index from previous list command, or a stable item id like id:42 or '#42'
+    #[arg(value_parser = parse_item_ref)]
+    pub index: ItemRef,
+}
+
+#[derive(Debug, Args)]
+pub struct StartCommand {
+    /// index from previous list command, or a stable item id like id:42 or '#42'
+    #[arg(value_parser = parse_item_ref)]
+    pub index: ItemRef,
+}
+
+#[derive(Debug, Args)]
+pub struct LogTimeCommand {
+    /// index from previous list command, or a stable item id like id:42 or '#42'
+    #[arg(value_parser = parse_item_ref)]
+    pub index: ItemRef,
+    /// time spent: 45m, 2h, 1h30m, 1.5h
+    #[arg(value_parser = validate_duration)]
+    pub duration: i64,
+}
+
+#[derive(Debug, Args)]
+pub struct UndoCommand {
+    /// list the commands that can be undone instead, newest first
+    #[arg(short, long)]
+    pub list: bool,
+    /// undo even if the items were changed since by someone else
+    #[arg(long, conflicts_with = "list")]
+    pub force: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TrashCommand {
+    /// list deleted items in the current namespace, in the order they were deleted
+    List,
+    /// bring deleted items back
+    Restore(TrashRestoreCommand),
+    /// delete items in the trash for good, with their notes and links
+    Purge(TrashPurgeCommand),
+}
+
+#[derive(Debug, Args)]
+pub struct TrashRestoreCommand {
+    /// indexes from previous trash list command, ranges like 5-8, or stable item ids like id:42 or '#42'
+    #[arg(value_name = "INDEX", value_parser = parse_item_range, required = true)]
+    pub indexes: Vec<ItemRange>,
+}
+
+#[derive(Debug, Args)]
+pub struct TrashPurgeCommand {
+    /// indexes from previous trash list command, ranges like 5-8, or stable item ids like id:42 or '#42',
+    /// the whole trash of the namespace when omitted
+    #[arg(value_name = "INDEX", value_parser = parse_item_range)]
+    pub indexes: Vec<ItemRange>,
+    /// only purge items deleted longer ago than this, e.g. 30d or 2w
+    #[arg(long, value_name = "AGE", value_parser = validate_age, conflicts_with = "indexes")]
+    pub older_than: Option<String>,
+    /// don't ask for confirmation
+    #[arg(short, long, default_value_t = false)]
+    pub yes: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct BoardCommand {
+    /// only tasks assigned to this user
+    #[arg(short, long)]
+    pub user: Option<String>,
+    /// only tasks in this category
+    #[arg(short, long)]
+    pub category: Option<String>,
+    /// only tasks of this project
+    #[arg(short, long)]
+    pub project: Option<String>,
+    /// days back the done column goes
+    #[arg(short, long, default_value_t = 7)]
+    pub days: usize,
+    /// most cards shown per column, the counts include the rest
+    #[arg(short, long, default_value_t = 20, value_parser = validate_limit)]
+    pub limit: usize,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TemplateCommand {
+    /// list the templates in the config
+    List,
+    /// create the tasks of a template,
+    /// e.g. ctm template apply release --var version=1.4 --for sarah
+    Apply(TemplateApplyCommand),
+}
+
+#[derive(Debug, Args)]
+pub struct TemplateApplyCommand {
+    /// name of the template
+    pub name: String,
+    /// value of a template variable, may be repeated
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var)]
+    pub vars: Vec<(String, String)>,
+    /// assign every task to this user, the template can refer to them as {for}
+    #[arg(long = "for", value_name = "USER")]
+    pub assignee: Option<String>,
 }
 
 #[derive(Debug, Args)]
 pub struct ClaimCommand {
-    /// index from previous list command
-    #[arg(value_parser = validate_index)]
-    pub index: usize,
+    /// indexes from previous list command, ranges like 5-8, or stable item ids like id:42 or '#42'
+    #[arg(value_name = "INDEX", value_parser = parse_item_range, required_unless_present = "filter")]
+    pub indexes: Vec<ItemRange>,
+    /// claim every open task matching a filter expression instead, e.g. 'unassigned and +backend'
+    #[arg(long = "where", value_name = "FILTER", value_parser = validate_filter, conflicts_with = "indexes")]
+    pub filter: Option<String>,
+    /// don't ask before claiming several tasks
+    #[arg(short, long, default_value_t = false)]
+    pub yes: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct LinkCommand {
-    /// index from previous list command
-    #[arg(value_parser = validate_index)]
-    pub index: usize,
+    /// index from previous list command, or a stable item id like id:42 or '#42'
+    #[arg(value_parser = parse_item_ref)]
+    pub index: ItemRef,
     /// attach a commit hash
     #[arg(long)]
     pub commit: Option<String>,
@@ -386,8 +723,8 @@ pub struct LinkCommand {
 
 #[derive(Debug, Args)]
 pub struct TeamCommand {
-    /// output as JSON
-    #[arg(long)]
+    /// output as JSON, set from the global --json / --format
+    #[arg(skip)]
     pub json: bool,
     /// output as Markdown
     #[arg(long)]
@@ -399,8 +736,8 @@ pub struct WorkloadCommand {
     /// filter to specific user
     #[arg(short, long)]
     pub user: Option<String>,
-    /// output as JSON
-    #[arg(long)]
+    /// output as JSON, set from the global --json / --format
+    #[arg(skip)]
     pub json: bool,
     /// output as Markdown
     #[arg(long)]
@@ -412,14 +749,64 @@ pub struct StatsCommand {
     /// time period in days (default 30)
     #[arg(short, long, default_value_t = 30)]
     pub days: i64,
-    /// output as JSON
-    #[arg(long)]
+    /// output as JSON, set from the global --json / --format
+    #[arg(skip)]
     pub json: bool,
     /// output as Markdown
     #[arg(long)]
     pub md: bool,
 }
 
+#[derive(Debug, Args)]
+pub struct BlockCommand {
+    /// index from previous list command, or a stable item id like id:42 or '#42'
+    #[arg(value_parser = parse_item_ref)]
+    pub index: ItemRef,
+    /// the task it depends on, as an index, or an id like id:42
+    #[arg(long, value_parser = parse_item_ref)]
+    pub on: ItemRef,
+}
+
+#[derive(Debug, Args)]
+pub struct HistoryCommand {
+    /// index from previous list command, or a stable item id like id:42 or '#42'
+    #[arg(value_parser = parse_item_ref)]
+    pub index: ItemRef,
+}
+
+#[derive(Debug, Args)]
+pub struct LogCommand {
+    /// only show changes made after this time, default to the last 7 days
+    #[arg(short, long, value_parser = validate_timestr)]
+    pub since: Option<String>,
+    /// limit the amount of changes returned
+    #[arg(short, long, default_value_t = 100, value_parser = validate_limit)]
+    pub limit: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct SearchCommand {
+    /// words to search for, "quoted phrase" for exact phrases, word* for prefixes
+    #[arg(required = true, num_args = 1..)]
+    pub query: Vec<String>,
+    /// limit the amount of items returned
+    #[arg(short, long, default_value_t = 20, value_parser = validate_limit)]
+    pub limit: usize,
+    /// search the archive instead, see ctm archive
+    #[arg(long, default_value_t = false)]
+    pub archived: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ArchiveCommand {
+    /// archive tasks closed and records made before this time, e.g. "1 year ago" or 2025-01-01
+    #[arg(long, value_parser = validate_flexible_timestr)]
+    pub before: String,
+    /// don't ask for confirmation
+    #[arg(short, long, default_value_t = false)]
+    pub yes: bool,
+}
+
 fn syntax_helper(cmd: &str, s: &str) -> Result<String, String> {
     if s == "list" {
         return Err(format!("Do you mean 'list {}' instead of '{} list'", cmd, cmd));
@@ -430,6 +817,26 @@ fn syntax_helper(cmd: &str, s: &str) -> Result<String, String> {
     Ok(s.to_string())
 }
 
+fn parse_update_target(s: &str) -> Result<UpdateTarget, String> {
+    if s.starts_with('+') {
+        return parse_tag(s).map(UpdateTarget::Tag);
+    }
+    parse_item_range(s)
+        .map(UpdateTarget::Items)
+        .map_err(|e| format!("{}, or a tag like +backend", e))
+}
+
+// Subcommand names of `view` can't name a view
+fn validate_view_name(s: &str) -> Result<String, String> {
+    if ["save", "list", "delete", "help"].contains(&s) {
+        return Err(format!("'{}' is reserved, pick another view name", s));
+    }
+    if s.is_empty() || s.starts_with('-') || s.contains(char::is_whitespace) {
+        return Err(format!("Invalid view name '{}'", s));
+    }
+    Ok(s.to_string())
+}
+
 fn validate_limit(s: &str) -> Result<usize, String> {
     let limit: usize = s.parse().map_err(|_| "Must be a number".to_string())?;
     if limit < 1 {
@@ -441,22 +848,55 @@ fn validate_limit(s: &str) -> Result<usize, String> {
     Ok(limit)
 }
 
-fn validate_index(s: &str) -> Result<usize, String> {
-    let index: usize = s.parse().map_err(|_| "Index must be a number".to_string())?;
-    if index == 0 {
-        return Err("Index must be greater than 0".to_string());
+fn validate_duration(s: &str) -> Result<i64, String> {
+    let minutes = parse_estimate(s)?;
+    if minutes < 1 {
+        return Err("Duration must be at least 1 minute".to_string());
     }
-    if index > 65536 {
-        return Err("Index cannot exceed 65536".to_string());
+    Ok(minutes)
+}
+
+fn parse_var(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => Ok((name.trim().to_string(), value.to_string())),
+        _ => Err(format!("Expected NAME=VALUE, got '{}'", s)),
+    }
+}
+
+// An age like "30d" reads as "30d ago"
+fn validate_age(s: &str) -> Result<String, String> {
+    parse_flexible_timestr(&format!("{} ago", s))
+        .map(|_| s.to_string())
+        .map_err(|_| format!("Couldn't parse '{}' as an age, e.g. 30d or 2w", s))
+}
+
+fn validate_flexible_timestr(s: &str) -> Result<String, String> {
+    parse_flexible_timestr(s).map(|_| s.to_string())
+}
+
+fn validate_filter(s: &str) -> Result<String, String> {
+    parse_filter(s).map(|_| s.to_string())
+}
+
+// Anything that doesn't read as a time is taken for a filter expression,
+// the time error is kept for plain words that are neither.
+fn validate_timestr_or_filter(s: &str) -> Result<String, String> {
+    let time_err = match parse_flexible_timestr(s) {
+        Ok(_) => return Ok(s.to_string()),
+        Err(e) => e,
+    };
+    match parse_filter(s) {
+        Ok(_) => Ok(s.to_string()),
+        Err(e) if s.contains(|c: char| ":<>=!()+\"'".contains(c)) => Err(e),
+        Err(_) => Err(time_err),
     }
-    Ok(index)
 }
 
 fn validate_timestr(s: &str) -> Result<String, String> {
     match parse_flexible_timestr(s) {
         Ok(_) => Ok(s.to_string()),
         Err(_) => {
-            match parse_recurring_timestr(s) {
+            match parse_recurring_schedule(s) {
                 Ok(_) => Ok(s.to_string()),
                 Err(e) => Err(e)
             }
@@ -464,7 +904,14 @@ fn validate_timestr(s: &str) -> Result<String, String> {
     }
 }
 
-fn parse_status(s: &str) -> Result<u8, String> {
+fn validate_time_zone(s: &str) -> Result<String, String> {
+    if s.eq_ignore_ascii_case("none") {
+        return Ok("none".to_string());
+    }
+    parse_zone(s).map(|zone| zone.name())
+}
+
+pub(crate) fn parse_status(s: &str) -> Result<u8, String> {
     match s.to_lowercase().as_str() {
         "ongoing" => Ok(0),
         "done" | "complete" | "completed" => Ok(1),
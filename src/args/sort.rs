@@ -0,0 +1,64 @@
+use std::cmp::Ordering;
+
+use crate::db::item::Item;
+
+/// Order of listed tasks other than the default by due time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Due,
+    Priority,
+    Created,
+    Category,
+    Estimate,
+}
+
+impl SortKey {
+    /// Compares two items on the key, unset due times and estimates go last
+    /// and an unset priority counts as normal.
+    pub fn compare(&self, a: &Item, b: &Item) -> Ordering {
+        match self {
+            SortKey::Due => last_if_none(a.target_time, b.target_time),
+            SortKey::Priority => a.priority.unwrap_or(1).cmp(&b.priority.unwrap_or(1)),
+            SortKey::Created => a.create_time.cmp(&b.create_time),
+            SortKey::Category => a.category.cmp(&b.category),
+            SortKey::Estimate => last_if_none(a.estimate_minutes, b.estimate_minutes),
+        }
+    }
+}
+
+fn last_if_none(a: Option<i64>, b: Option<i64>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Parse the value of --sort.
+pub fn parse_sort(s: &str) -> Result<SortKey, String> {
+    match s.to_lowercase().as_str() {
+        "due" => Ok(SortKey::Due),
+        "priority" => Ok(SortKey::Priority),
+        "created" => Ok(SortKey::Created),
+        "category" | "cat" => Ok(SortKey::Category),
+        "estimate" => Ok(SortKey::Estimate),
+        _ => Err(format!(
+            "Invalid sort '{}', expected due, priority, created, category or estimate",
+            s
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sort() {
+        assert_eq!(parse_sort("due").unwrap(), SortKey::Due);
+        assert_eq!(parse_sort("Priority").unwrap(), SortKey::Priority);
+        assert_eq!(parse_sort("cat").unwrap(), SortKey::Category);
+        assert!(parse_sort("name").is_err());
+    }
+}
@@ -0,0 +1,63 @@
+/// Parse a tag name, with or without its leading '+'.
+/// Tags are lowercase, start with a letter and may contain letters, digits, '-', '_', '/' and '.'.
+pub fn parse_tag(s: &str) -> Result<String, String> {
+    let name = s.trim().trim_start_matches('+').to_lowercase();
+
+    if !name.starts_with(|c: char| c.is_alphabetic()) {
+        return Err(format!("Invalid tag '{}', tags must start with a letter", s));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '/' | '.')))
+    {
+        return Err(format!("Invalid tag '{}', unexpected character '{}'", s, c));
+    }
+    Ok(name)
+}
+
+/// Split `+tag` words off task content.
+/// Returns the remaining content and the tags in order of appearance, without duplicates.
+/// Words like "+1" or a lone "+" are not tags and stay in the content.
+pub fn extract_tags(content: &str) -> (String, Vec<String>) {
+    let mut words = Vec::new();
+    let mut tags: Vec<String> = Vec::new();
+
+    for word in content.split_whitespace() {
+        match word.strip_prefix('+').map(parse_tag) {
+            Some(Ok(tag)) => {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            _ => words.push(word),
+        }
+    }
+
+    (words.join(" "), tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tag() {
+        assert_eq!(parse_tag("backend").unwrap(), "backend");
+        assert_eq!(parse_tag("+Security").unwrap(), "security");
+        assert_eq!(parse_tag("team/infra-2").unwrap(), "team/infra-2");
+        assert!(parse_tag("2fa").is_err());
+        assert!(parse_tag("+").is_err());
+        assert!(parse_tag("a,b").is_err());
+    }
+
+    #[test]
+    fn test_extract_tags() {
+        let (content, tags) = extract_tags("Fix login +backend  +Security bug +backend");
+        assert_eq!(content, "Fix login bug");
+        assert_eq!(tags, vec!["backend", "security"]);
+
+        let (content, tags) = extract_tags("Vote +1 on 2 + 2");
+        assert_eq!(content, "Vote +1 on 2 + 2");
+        assert!(tags.is_empty());
+    }
+}
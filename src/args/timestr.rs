@@ -1,15 +1,45 @@
+use std::cell::RefCell;
+
 use chrono::{
     Datelike,
     Duration,
     Local,
+    Months,
     NaiveDate,
     NaiveDateTime,
     NaiveTime,
-    TimeZone,
     Timelike,
     Weekday,
 };
 
+use crate::args::timezone;
+
+// Upper bounds of "Every <n> days/weeks", kept within the range cron scans
+const MAX_INTERVAL_DAYS: u32 = 365;
+const MAX_INTERVAL_WEEKS: u32 = 52;
+
+// Upper bound of the count in relative expressions like "in 3 days"
+const MAX_OFFSET: i64 = 100_000;
+
+// Company holidays skipped by business day arithmetic, set once at startup from the config.
+// Thread local so tests running in parallel don't see each other's holidays.
+thread_local! {
+    static HOLIDAYS: RefCell<Vec<NaiveDate>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Set the holidays (YYYY-MM-DD) that "business days" skip besides weekends.
+pub fn set_holidays(dates: &[String]) -> Result<(), String> {
+    let holidays = dates
+        .iter()
+        .map(|d| {
+            NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d")
+                .map_err(|_| format!("Invalid holiday '{}', expected YYYY-MM-DD", d))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    HOLIDAYS.with(|h| *h.borrow_mut() = holidays);
+    Ok(())
+}
+
 pub fn days_before_to_unix_epoch(d: usize) -> i64 {
     let now = Local::now();
     let past_date = now - Duration::days(d as i64);
@@ -22,18 +52,15 @@ pub fn days_after_to_unix_epoch(d: usize) -> i64 {
     future_date.timestamp()
 }
 
+// Wall clock times are read in the current user's time zone
 pub fn to_unix_epoch(s: &str) -> Result<i64, String> {
     let dt = parse_flexible_timestr(s)?;
-    Local
-        .from_local_datetime(&dt)
-        .earliest()
-        .ok_or_else(|| String::from("cannot parse timestr into unix epoch"))
-        .map(|dt| dt.timestamp())
+    timezone::timestamp(&dt).ok_or_else(|| String::from("cannot parse timestr into unix epoch"))
 }
 
 pub fn parse_flexible_timestr(s: &str) -> Result<NaiveDateTime, String> {
     let s = s.trim();
-    let now = Local::now().naive_local();
+    let now = timezone::now().naive_local();
 
     // Default time when only date is specified (end of day)
     let default_time = NaiveTime::from_hms_opt(23, 59, 59).unwrap();
@@ -41,6 +68,10 @@ pub fn parse_flexible_timestr(s: &str) -> Result<NaiveDateTime, String> {
     // Default date when only time is specified (today)
     let default_date = now.date();
 
+    if let Some(dt) = parse_relative(s, now)? {
+        return Ok(dt);
+    }
+
     let parts: Vec<&str> = s.split_whitespace().collect();
     if parts.len() > 2 {
         return Err(format!("There are too many parts in timestr {}", s));
@@ -70,6 +101,195 @@ pub fn parse_flexible_timestr(s: &str) -> Result<NaiveDateTime, String> {
     Err(format!("Couldn't parse '{}' as a valid date/time", s))
 }
 
+#[derive(Debug, Clone, Copy)]
+enum Unit {
+    Minutes,
+    Hours,
+    Days,
+    BusinessDays,
+    Weeks,
+    Months,
+    Years,
+}
+
+// Parse a unit at the start of `words`, returns it with the number of words used
+fn parse_unit(words: &[&str]) -> Option<(Unit, usize)> {
+    let unit = match *words.first()? {
+        "m" | "min" | "mins" | "minute" | "minutes" => Unit::Minutes,
+        "h" | "hr" | "hrs" | "hour" | "hours" => Unit::Hours,
+        "d" | "day" | "days" => Unit::Days,
+        "bd" | "workday" | "workdays" => Unit::BusinessDays,
+        "business" | "working" if matches!(words.get(1), Some(&"day") | Some(&"days")) => {
+            return Some((Unit::BusinessDays, 2));
+        }
+        "w" | "wk" | "wks" | "week" | "weeks" => Unit::Weeks,
+        "mo" | "month" | "months" => Unit::Months,
+        "y" | "yr" | "year" | "years" => Unit::Years,
+        _ => return None,
+    };
+    Some((unit, 1))
+}
+
+// Split "3" or "3d" into the count and the attached unit
+fn split_count(word: &str) -> Option<(i64, &str)> {
+    let digits = word.find(|c: char| !c.is_ascii_digit()).unwrap_or(word.len());
+    if digits == 0 {
+        return None;
+    }
+    let count = word[..digits].parse().ok()?;
+    Some((count, &word[digits..]))
+}
+
+// Parse "<n><unit>" or "<n> <unit>" at the start of `words`,
+// returns the count, the unit and the number of words used
+fn parse_offset(words: &[&str]) -> Result<Option<(i64, Unit, usize)>, String> {
+    let Some((count, suffix)) = words.first().and_then(|w| split_count(w)) else {
+        return Ok(None);
+    };
+    let (unit, used) = if suffix.is_empty() {
+        match parse_unit(&words[1..]) {
+            Some((unit, used)) => (unit, used + 1),
+            None => return Ok(None),
+        }
+    } else {
+        match parse_unit(&[suffix]) {
+            Some((unit, _)) => (unit, 1),
+            None => return Ok(None),
+        }
+    };
+    if count > MAX_OFFSET {
+        return Err(format!("Offset cannot exceed {}", MAX_OFFSET));
+    }
+    Ok(Some((count, unit, used)))
+}
+
+// Relative expressions: "in 3 days", "+2h", "2 weeks ago", "in 5 business days",
+// "end of week", "next quarter", "next business day".
+// Expressions landing on a day take an optional time, e.g. "in 3 days 9am".
+// Returns None when `s` is not a relative expression.
+fn parse_relative(s: &str, now: NaiveDateTime) -> Result<Option<NaiveDateTime>, String> {
+    let lower = s.to_lowercase();
+    let words: Vec<&str> = lower.split_whitespace().collect();
+    let today = now.date();
+
+    let (date, rest) = match words.as_slice() {
+        ["in", offset @ ..] => match parse_offset(offset)? {
+            Some((count, unit, used)) => match shift(now, count, unit)? {
+                Shifted::Instant(dt) if used == offset.len() => return Ok(Some(dt)),
+                Shifted::Instant(_) => return Ok(None),
+                Shifted::Day(date) => (date, &offset[used..]),
+            },
+            None => return Ok(None),
+        },
+        [first, ..] if first.starts_with(['+', '-']) => {
+            let sign = if first.starts_with('-') { -1 } else { 1 };
+            let mut offset = words.clone();
+            offset[0] = &first[1..];
+            match parse_offset(&offset)? {
+                Some((count, unit, used)) => match shift(now, sign * count, unit)? {
+                    Shifted::Instant(dt) if used == offset.len() => return Ok(Some(dt)),
+                    Shifted::Instant(_) => return Ok(None),
+                    Shifted::Day(date) => (date, &words[used..]),
+                },
+                None => return Ok(None),
+            }
+        }
+        ["end", "of", period, rest @ ..] => {
+            let date = match *period {
+                "day" => today,
+                "week" => next_weekday(today, Weekday::Sun),
+                "month" => last_day_of_month(today),
+                "quarter" => last_day_of_quarter(today),
+                "year" => today.with_month(12).unwrap().with_day(31).unwrap(),
+                _ => return Ok(None),
+            };
+            (date, rest)
+        }
+        ["next", "business" | "working", "day", rest @ ..] => (add_business_days(today, 1), rest),
+        ["next", period, rest @ ..] => {
+            let date = match *period {
+                "week" => next_weekday(today, Weekday::Sun) + Duration::days(7),
+                "month" => last_day_of_month(last_day_of_month(today) + Duration::days(1)),
+                "quarter" => last_day_of_quarter(last_day_of_quarter(today) + Duration::days(1)),
+                "year" => NaiveDate::from_ymd_opt(today.year() + 1, 12, 31).unwrap(),
+                weekday => match weekday.parse::<Weekday>() {
+                    Ok(weekday) => next_weekday(today, weekday),
+                    Err(_) => return Ok(None),
+                },
+            };
+            (date, rest)
+        }
+        _ => match parse_offset(&words)? {
+            Some((count, unit, used)) if words.get(used) == Some(&"ago") => {
+                match shift(now, -count, unit)? {
+                    Shifted::Instant(dt) if used + 1 == words.len() => return Ok(Some(dt)),
+                    Shifted::Instant(_) => return Ok(None),
+                    Shifted::Day(date) => (date, &words[used + 1..]),
+                }
+            }
+            _ => return Ok(None),
+        },
+    };
+
+    // Optional time of day, end of day by default
+    let rest = match rest {
+        ["at", rest @ ..] => rest,
+        rest => rest,
+    };
+    if rest.is_empty() {
+        return Ok(Some(date.and_time(NaiveTime::from_hms_opt(23, 59, 59).unwrap())));
+    }
+    let time = parse_time_portion(&rest.join(" "))?;
+    Ok(Some(date.and_time(time)))
+}
+
+enum Shifted {
+    // Minutes and hours move the exact time
+    Instant(NaiveDateTime),
+    // Larger units land on a day
+    Day(NaiveDate),
+}
+
+fn shift(now: NaiveDateTime, count: i64, unit: Unit) -> Result<Shifted, String> {
+    let today = now.date();
+    let months = |n: i64| {
+        let shifted = if n < 0 {
+            today.checked_sub_months(Months::new(n.unsigned_abs() as u32))
+        } else {
+            today.checked_add_months(Months::new(n as u32))
+        };
+        shifted.map(Shifted::Day)
+    };
+    let shifted = match unit {
+        Unit::Minutes => now.checked_add_signed(Duration::minutes(count)).map(Shifted::Instant),
+        Unit::Hours => now.checked_add_signed(Duration::hours(count)).map(Shifted::Instant),
+        Unit::Days => today.checked_add_signed(Duration::days(count)).map(Shifted::Day),
+        Unit::BusinessDays => Some(Shifted::Day(add_business_days(today, count))),
+        Unit::Weeks => today.checked_add_signed(Duration::weeks(count)).map(Shifted::Day),
+        Unit::Months => months(count),
+        Unit::Years => months(count * 12),
+    };
+    shifted.ok_or_else(|| String::from("Relative time is out of range"))
+}
+
+fn is_business_day(date: NaiveDate) -> bool {
+    !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+        && !HOLIDAYS.with(|h| h.borrow().contains(&date))
+}
+
+// Move `count` business days (backwards when negative), skipping weekends and holidays
+fn add_business_days(mut date: NaiveDate, count: i64) -> NaiveDate {
+    let step = Duration::days(count.signum());
+    let mut left = count.abs();
+    while left > 0 {
+        date += step;
+        if is_business_day(date) {
+            left -= 1;
+        }
+    }
+    date
+}
+
 fn parse_date_portion(s: &str, today: NaiveDate) -> Result<NaiveDate, String> {
     match s.to_lowercase().as_str() {
         "today" | "eod" => return Ok(today),
@@ -84,6 +304,7 @@ fn parse_date_portion(s: &str, today: NaiveDate) -> Result<NaiveDate, String> {
         "sunday" | "eow" | "week" => return Ok(next_weekday(today, Weekday::Sun)),
         "year" | "eoy" => return Ok(today.with_month(12).unwrap().with_day(31).unwrap()),
         "month" | "eom" => return Ok(last_day_of_month(today)),
+        "quarter" | "eoq" => return Ok(last_day_of_quarter(today)),
         _ => {}
     }
 
@@ -163,6 +384,11 @@ fn last_day_of_month(date: NaiveDate) -> NaiveDate {
     first_of_next - Duration::days(1)
 }
 
+fn last_day_of_quarter(date: NaiveDate) -> NaiveDate {
+    let last_month = (date.month() - 1) / 3 * 3 + 3;
+    last_day_of_month(date.with_day(1).unwrap().with_month(last_month).unwrap())
+}
+
 fn next_weekday(from_date: NaiveDate, weekday: Weekday) -> NaiveDate {
     let days_from_today =
         weekday.num_days_from_monday() as i64 - from_date.weekday().num_days_from_monday() as i64;
@@ -177,6 +403,12 @@ fn next_weekday(from_date: NaiveDate, weekday: Weekday) -> NaiveDate {
 
 // Helper: Get time from parts starting at index, or default to 11:59PM
 fn get_time_or_default(parts: &[&str], start_idx: usize) -> Result<String, String> {
+    // Allow "at 9AM" as well as "9AM"
+    let start_idx = if parts.get(start_idx).is_some_and(|p| p.eq_ignore_ascii_case("at")) {
+        start_idx + 1
+    } else {
+        start_idx
+    };
     if parts.len() > start_idx {
         let time_str = parts[start_idx..].join(" ");
         let time = parse_time_portion(&time_str)
@@ -187,8 +419,129 @@ fn get_time_or_default(parts: &[&str], start_idx: usize) -> Result<String, Strin
     }
 }
 
+// Helper: Get several times like "9AM and 5PM" or "9AM, 5PM", or default to 11:59PM
+fn get_times_or_default(parts: &[&str], start_idx: usize) -> Result<Vec<String>, String> {
+    let rest = parts.get(start_idx..).unwrap_or(&[]).join(" ").replace(',', " and ");
+    let mut times = Vec::new();
+    let mut group: Vec<&str> = Vec::new();
+    for word in rest.split_whitespace().chain(["and"]) {
+        if word.eq_ignore_ascii_case("and") {
+            if !group.is_empty() {
+                times.push(get_time_or_default(&group, 0)?);
+                group.clear();
+            }
+        } else {
+            group.push(word);
+        }
+    }
+    if times.is_empty() {
+        times.push("59 23".to_string());
+    }
+    Ok(times)
+}
+
+// Parse "<ordinal> <weekday> [of [the] month] [time]" or "last day [of [the] month] [time]"
+// Returns None when the parts don't start with that shape
+fn parse_nth_of_month(parts: &[&str]) -> Result<Option<String>, String> {
+    if parts.len() < 2 {
+        return Ok(None);
+    }
+    let nth = match parts[0].to_lowercase().as_str() {
+        "first" => "1".to_string(),
+        "second" => "2".to_string(),
+        "third" => "3".to_string(),
+        "fourth" => "4".to_string(),
+        "fifth" => "5".to_string(),
+        "last" => "L".to_string(),
+        other => match parse_ordinal_day(other) {
+            Some(n) if n <= 5 => n.to_string(),
+            _ => return Ok(None),
+        },
+    };
+
+    let is_last_day = nth == "L" && parts[1].eq_ignore_ascii_case("day");
+    let weekday = match parse_weekday(parts[1]) {
+        Ok(weekday) => Some(weekday),
+        Err(_) if is_last_day => None,
+        Err(_) => return Ok(None),
+    };
+
+    // "of the month" / "of month" are optional
+    let mut idx = 2;
+    if parts.get(idx).is_some_and(|p| p.eq_ignore_ascii_case("of")) {
+        idx += 1;
+        if parts.get(idx).is_some_and(|p| p.eq_ignore_ascii_case("the")) {
+            idx += 1;
+        }
+        if !parts.get(idx).is_some_and(|p| p.eq_ignore_ascii_case("month")) {
+            return Err(format!("Expected 'of the Month' in '{}'", parts.join(" ")));
+        }
+        idx += 1;
+    }
+
+    let time = get_time_or_default(parts, idx)?;
+    Ok(Some(match weekday {
+        Some(weekday) => format!("{} * * {}#{}", time, weekday, nth),
+        None => format!("{} L * *", time),
+    }))
+}
+
+// Parse "<n> days [time]" or "<n> weeks [on <weekday>] [time]" after "Every"
+// Returns None when the parts don't start with that shape
+fn parse_interval(parts: &[&str], today: NaiveDate) -> Result<Option<String>, String> {
+    let Some(count) = parts.first().and_then(|p| p.parse::<u32>().ok()) else {
+        return Ok(None);
+    };
+    let anchor = today.format("%Y%m%d");
+    match parts.get(1).map(|p| p.to_lowercase()).as_deref() {
+        Some("day") | Some("days") => {
+            if !(1..=MAX_INTERVAL_DAYS).contains(&count) {
+                return Err(format!("Interval must be between 1 and {} days", MAX_INTERVAL_DAYS));
+            }
+            let time = get_time_or_default(parts, 2)?;
+            if count == 1 {
+                return Ok(Some(format!("{} * * *", time)));
+            }
+            Ok(Some(format!("{} * * * {}d@{}", time, count, anchor)))
+        }
+        Some("week") | Some("weeks") => {
+            if !(1..=MAX_INTERVAL_WEEKS).contains(&count) {
+                return Err(format!("Interval must be between 1 and {} weeks", MAX_INTERVAL_WEEKS));
+            }
+            let mut idx = 2;
+            if parts.get(idx).is_some_and(|p| p.eq_ignore_ascii_case("on")) {
+                idx += 1;
+            }
+            // Defaults to Sunday like "Weekly"
+            let days = match parts.get(idx) {
+                Some(p) if p.contains('-') => {
+                    idx += 1;
+                    parse_day_range(p)?
+                }
+                Some(p) if parse_weekday(p).is_ok() => {
+                    idx += 1;
+                    parse_weekday(p)?.to_string()
+                }
+                _ => "0".to_string(),
+            };
+            let time = get_time_or_default(parts, idx)?;
+            if count == 1 {
+                return Ok(Some(format!("{} * * {}", time, days)));
+            }
+            Ok(Some(format!("{} * * {} {}w@{}", time, days, count, anchor)))
+        }
+        _ => Ok(None),
+    }
+}
+
 // Parse a human readable schedule into cron.
+// Several schedules (e.g. "Daily 9AM and 5PM") are joined by ';'.
 pub fn parse_recurring_timestr(s: &str) -> Result<String, String> {
+    parse_recurring_timestr_on(s, timezone::now().date_naive())
+}
+
+// Underlying implementation, `today` anchors "Every <n> days/weeks"
+fn parse_recurring_timestr_on(s: &str, today: NaiveDate) -> Result<String, String> {
     let s = s.trim();
     let parts: Vec<&str> = s.split_whitespace().collect();
 
@@ -198,9 +551,21 @@ pub fn parse_recurring_timestr(s: &str) -> Result<String, String> {
 
     match parts[0].to_lowercase().as_str() {
         "daily" => {
-            let time = get_time_or_default(&parts, 1)?;
-            Ok(format!("{} * * *", time))
+            let times = get_times_or_default(&parts, 1)?;
+            Ok(daily_schedules(&times))
         }
+        "twice" => {
+            if !parts.get(1).is_some_and(|p| p.eq_ignore_ascii_case("daily")) {
+                return Err(format!("Unrecognized recurring time format: '{}'", s));
+            }
+            let times = get_times_or_default(&parts, 2)?;
+            if times.len() != 2 {
+                return Err(format!("'Twice Daily' needs two times, e.g. 'Twice Daily at 9AM and 5PM', got '{}'", s));
+            }
+            Ok(daily_schedules(&times))
+        }
+        "first" | "second" | "third" | "fourth" | "fifth" | "last" => parse_nth_of_month(&parts)?
+            .ok_or_else(|| format!("Unrecognized recurring time format: '{}'", s)),
         "weekly" => {
             if parts.len() == 1 {
                 return Ok(String::from("59 23 * * 0"));
@@ -221,6 +586,11 @@ pub fn parse_recurring_timestr(s: &str) -> Result<String, String> {
                 return Ok(String::from("59 23 1 * *"));
             }
 
+            // "Monthly last Friday", "Monthly last day"
+            if let Some(cron) = parse_nth_of_month(&parts[1..])? {
+                return Ok(cron);
+            }
+
             let day = parse_ordinal_day(parts[1])
                 .ok_or_else(|| format!("Invalid day format in '{}'", s))?;
             let time = get_time_or_default(&parts, 2)?;
@@ -240,6 +610,16 @@ pub fn parse_recurring_timestr(s: &str) -> Result<String, String> {
                 return Err(String::from("'Every' requires additional specification"));
             }
 
+            // "Every 3 days", "Every 2 weeks on Monday"
+            if let Some(cron) = parse_interval(&parts[1..], today)? {
+                return Ok(cron);
+            }
+
+            // "Every first Monday of the Month", "Every last day of the Month"
+            if let Some(cron) = parse_nth_of_month(&parts[1..])? {
+                return Ok(cron);
+            }
+
             // Check if it's a time pattern (e.g., "Every 9PM")
             if parse_time_portion(parts[1]).is_ok() {
                 let time = get_time_or_default(&parts, 1)?;
@@ -296,6 +676,67 @@ pub fn parse_recurring_timestr(s: &str) -> Result<String, String> {
     }
 }
 
+/// A recurring schedule with its optional bound,
+/// e.g. "Weekly until 2026-12-31" or "Daily for 10 times".
+#[derive(Debug, PartialEq)]
+pub struct RecurringSchedule {
+    pub cron: String,
+    // No occurrence after this timestamp
+    pub until: Option<i64>,
+    // Number of completions after which the task ends
+    pub count: Option<i64>,
+}
+
+// Parse a schedule optionally followed by "until <date>" and/or "for <n> times".
+pub fn parse_recurring_schedule(s: &str) -> Result<RecurringSchedule, String> {
+    let mut rest = s.trim().to_string();
+    let mut until = None;
+    let mut count = None;
+
+    // The bounds may come in either order
+    for _ in 0..2 {
+        let parts: Vec<&str> = rest.split_whitespace().collect();
+        let n = parts.len();
+        if count.is_none()
+            && n >= 3
+            && parts[n - 3].eq_ignore_ascii_case("for")
+            && matches!(parts[n - 1].to_lowercase().as_str(), "time" | "times" | "occurrence" | "occurrences")
+        {
+            let times: i64 = parts[n - 2]
+                .parse()
+                .ok()
+                .filter(|times| *times > 0)
+                .ok_or_else(|| format!("Invalid number of times '{}'", parts[n - 2]))?;
+            count = Some(times);
+            rest = parts[..n - 3].join(" ");
+            continue;
+        }
+        if until.is_none() {
+            if let Some(pos) = parts.iter().rposition(|p| p.eq_ignore_ascii_case("until")) {
+                let date_str = parts[pos + 1..].join(" ");
+                if date_str.is_empty() {
+                    return Err(format!("Missing date after 'until' in '{}'", s));
+                }
+                until = Some(to_unix_epoch(&date_str)?);
+                rest = parts[..pos].join(" ");
+                continue;
+            }
+        }
+        break;
+    }
+
+    let cron = parse_recurring_timestr(&rest)?;
+    Ok(RecurringSchedule { cron, until, count })
+}
+
+fn daily_schedules(times: &[String]) -> String {
+    times
+        .iter()
+        .map(|time| format!("{} * * *", time))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
 // Parse weekday names to cron weekday numbers (0=Sunday, 1=Monday, etc.)
 fn parse_weekday(s: &str) -> Result<u8, String> {
     match s.to_lowercase().as_str() {
@@ -397,6 +838,12 @@ mod tests {
             "friday",
             "friday 3PM",
             "3/24",
+            "in 3 days",
+            "+2h",
+            "end of week",
+            "next quarter",
+            "in 5 business days",
+            "eoq",
         ];
 
         for input in valid_inputs {
@@ -430,6 +877,10 @@ mod tests {
             "20PM",
             "13AM",
             "monday 0AM",
+            "in 3 bananas",
+            "in 200000 days",
+            "next fortnight",
+            "+2h 5PM",
         ];
 
         for input in invalid_inputs {
@@ -506,6 +957,23 @@ mod tests {
             // Every - month/day patterns (maps to Yearly)
             ("Every 6/12", "59 23 12 6 *"),
             ("Every 2/14 5PM", "0 17 14 2 *"),
+            // "at" before the time
+            ("Daily at 5PM", "0 17 * * *"),
+            ("Every Monday at 9AM", "0 9 * * 1"),
+            // Several times a day
+            ("Daily 9AM and 5PM", "0 9 * * *;0 17 * * *"),
+            ("Daily at 9AM, 1PM and 5PM", "0 9 * * *;0 13 * * *;0 17 * * *"),
+            ("Twice Daily at 9AM and 5PM", "0 9 * * *;0 17 * * *"),
+            // Nth and last weekday of the month
+            ("Every first Monday of the Month", "59 23 * * 1#1"),
+            ("First Monday of the Month 9AM", "0 9 * * 1#1"),
+            ("Every 2nd Tuesday of Month", "59 23 * * 2#2"),
+            ("Last Friday of the Month at 5PM", "0 17 * * 5#L"),
+            ("Monthly last Friday", "59 23 * * 5#L"),
+            // Last day of the month
+            ("Last day of the Month", "59 23 L * *"),
+            ("Every last day of Month 6PM", "0 18 L * *"),
+            ("Monthly last day", "59 23 L * *"),
         ];
 
         for (input, expected) in test_cases {
@@ -518,6 +986,116 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_relative_expressions() {
+        // Friday
+        let now = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap().and_hms_opt(10, 30, 0).unwrap();
+        let at = |m: u32, d: u32, h: u32, min: u32, sec: u32| {
+            NaiveDate::from_ymd_opt(2024, m, d).unwrap().and_hms_opt(h, min, sec).unwrap()
+        };
+        let test_cases = [
+            ("in 3 days", at(3, 18, 23, 59, 59)),
+            ("in 3 days 9AM", at(3, 18, 9, 0, 0)),
+            ("In 1 month", at(4, 15, 23, 59, 59)),
+            ("in 90 minutes", at(3, 15, 12, 0, 0)),
+            ("+2h", at(3, 15, 12, 30, 0)),
+            ("-2h", at(3, 15, 8, 30, 0)),
+            ("+1w", at(3, 22, 23, 59, 59)),
+            ("2 days ago", at(3, 13, 23, 59, 59)),
+            ("end of week", at(3, 17, 23, 59, 59)),
+            ("end of month 5PM", at(3, 31, 17, 0, 0)),
+            ("end of quarter", at(3, 31, 23, 59, 59)),
+            ("next week", at(3, 24, 23, 59, 59)),
+            ("next month", at(4, 30, 23, 59, 59)),
+            ("next quarter", at(6, 30, 23, 59, 59)),
+            ("next monday at 9AM", at(3, 18, 9, 0, 0)),
+            ("in 5 business days", at(3, 22, 23, 59, 59)),
+            ("next business day", at(3, 18, 23, 59, 59)),
+            ("+2bd 5PM", at(3, 19, 17, 0, 0)),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(
+                parse_relative(input, now).unwrap(),
+                Some(expected),
+                "Failed for input: '{}'",
+                input
+            );
+        }
+        assert_eq!(
+            parse_relative("next year", now).unwrap(),
+            Some(NaiveDate::from_ymd_opt(2025, 12, 31).unwrap().and_hms_opt(23, 59, 59).unwrap())
+        );
+        assert_eq!(parse_relative("tomorrow", now).unwrap(), None);
+        assert_eq!(parse_relative("3PM", now).unwrap(), None);
+    }
+
+    #[test]
+    fn test_business_days_skip_holidays() {
+        let now = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap().and_hms_opt(10, 30, 0).unwrap();
+        set_holidays(&["2024-03-18".to_string(), "2024-03-19".to_string()]).unwrap();
+        let result = parse_relative("in 2 business days", now).unwrap().unwrap();
+        assert_eq!(result.date(), NaiveDate::from_ymd_opt(2024, 3, 21).unwrap());
+        let result = parse_relative("next business day", now).unwrap().unwrap();
+        assert_eq!(result.date(), NaiveDate::from_ymd_opt(2024, 3, 20).unwrap());
+        set_holidays(&[]).unwrap();
+
+        assert!(set_holidays(&["christmas".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_recurring_intervals() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let test_cases = [
+            ("Every 3 days", "59 23 * * * 3d@20240315"),
+            ("Every 3 days at 8AM", "0 8 * * * 3d@20240315"),
+            ("Every 1 day", "59 23 * * *"),
+            ("Every 2 weeks", "59 23 * * 0 2w@20240315"),
+            ("Every 2 weeks on Monday", "59 23 * * 1 2w@20240315"),
+            ("Every 2 weeks on Monday 10AM", "0 10 * * 1 2w@20240315"),
+            ("Every 3 weeks Mon-Fri at 9AM", "0 9 * * 1-5 3w@20240315"),
+            ("Every 1 week on Friday", "59 23 * * 5"),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(
+                parse_recurring_timestr_on(input, today).unwrap(),
+                expected,
+                "Failed for input: '{}'",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_recurring_schedule_bounds() {
+        let unbounded = parse_recurring_schedule("Weekly Monday").unwrap();
+        assert_eq!(unbounded.cron, "59 23 * * 1");
+        assert_eq!((unbounded.until, unbounded.count), (None, None));
+
+        let until = parse_recurring_schedule("Weekly until 2026-12-31").unwrap();
+        assert_eq!(until.cron, "59 23 * * 0");
+        assert_eq!(until.until, Some(to_unix_epoch("2026-12-31").unwrap()));
+        assert_eq!(until.count, None);
+
+        let count = parse_recurring_schedule("Daily 9AM for 10 times").unwrap();
+        assert_eq!(count.cron, "0 9 * * *");
+        assert_eq!((count.until, count.count), (None, Some(10)));
+
+        let both = parse_recurring_schedule("Every Monday until 2026-12-31 for 5 occurrences").unwrap();
+        assert_eq!(both.cron, "59 23 * * 1");
+        assert_eq!(both.until, Some(to_unix_epoch("2026-12-31").unwrap()));
+        assert_eq!(both.count, Some(5));
+        let reversed = parse_recurring_schedule("Every Monday for 5 times until 2026-12-31").unwrap();
+        assert_eq!(reversed, both);
+
+        assert!(parse_recurring_schedule("Daily for 0 times").is_err());
+        assert!(parse_recurring_schedule("Daily for many times").is_err());
+        assert!(parse_recurring_schedule("Daily until").is_err());
+        assert!(parse_recurring_schedule("Daily until someday").is_err());
+        assert!(parse_recurring_schedule("until 2026-12-31").is_err());
+    }
+
     #[test]
     fn test_recurring_invalid_inputs() {
         assert!(parse_recurring_timestr("").is_err());
@@ -544,5 +1122,17 @@ mod tests {
         assert!(parse_recurring_timestr("Every InvalidDay").is_err());
         assert!(parse_recurring_timestr("Every 32nd of the Month").is_err());
         assert!(parse_recurring_timestr("Every 2/30").is_err()); // Invalid date
+
+        // Invalid extended patterns
+        assert!(parse_recurring_timestr("Every 0 days").is_err());
+        assert!(parse_recurring_timestr("Every 400 days").is_err());
+        assert!(parse_recurring_timestr("Every 60 weeks").is_err());
+        assert!(parse_recurring_timestr("Every 2 weeks on Someday").is_err());
+        assert!(parse_recurring_timestr("Every 3 months").is_err());
+        assert!(parse_recurring_timestr("Sixth Monday of the Month").is_err());
+        assert!(parse_recurring_timestr("Last Monday of the Year").is_err());
+        assert!(parse_recurring_timestr("Twice Daily at 9AM").is_err());
+        assert!(parse_recurring_timestr("Twice Weekly").is_err());
+        assert!(parse_recurring_timestr("Daily 9AM and later").is_err());
     }
 }
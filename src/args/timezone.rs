@@ -0,0 +1,111 @@
+use std::cell::Cell;
+
+use chrono::{
+    DateTime,
+    FixedOffset,
+    Local,
+    NaiveDateTime,
+    TimeZone,
+    Utc,
+};
+use chrono_tz::Tz;
+
+/// Time zone that deadlines are parsed in, schedules fire in and times are shown in.
+/// Timestamps in the db stay absolute instants, only their wall clock time depends on it.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Zone {
+    /// The system time zone
+    #[default]
+    Local,
+    /// An IANA time zone such as "Europe/Berlin"
+    Named(Tz),
+}
+
+// Zone of the current user, set once at startup.
+// Thread local so tests running in parallel don't see each other's zone.
+thread_local! {
+    static CURRENT: Cell<Zone> = const { Cell::new(Zone::Local) };
+}
+
+/// Parse an IANA time zone name, "local" stands for the system time zone.
+pub fn parse_zone(s: &str) -> Result<Zone, String> {
+    if s.eq_ignore_ascii_case("local") {
+        return Ok(Zone::Local);
+    }
+    s.parse::<Tz>()
+        .map(Zone::Named)
+        .map_err(|_| format!("Unknown time zone '{}', expected a name like 'Europe/Berlin'", s))
+}
+
+pub fn set_current(zone: Zone) {
+    CURRENT.with(|current| current.set(zone));
+}
+
+pub fn current() -> Zone {
+    CURRENT.with(|current| current.get())
+}
+
+/// Now on the wall clock of the current zone.
+pub fn now() -> DateTime<FixedOffset> {
+    match current() {
+        Zone::Local => Local::now().fixed_offset(),
+        Zone::Named(tz) => Utc::now().with_timezone(&tz).fixed_offset(),
+    }
+}
+
+/// The instant `timestamp` on the wall clock of the current zone.
+pub fn datetime(timestamp: i64) -> Option<DateTime<FixedOffset>> {
+    match current() {
+        Zone::Local => Local.timestamp_opt(timestamp, 0).single().map(|dt| dt.fixed_offset()),
+        Zone::Named(tz) => tz.timestamp_opt(timestamp, 0).single().map(|dt| dt.fixed_offset()),
+    }
+}
+
+/// The instant of a wall clock time in the current zone,
+/// the earlier one when the time is ambiguous around a DST change.
+pub fn timestamp(naive: &NaiveDateTime) -> Option<i64> {
+    match current() {
+        Zone::Local => Local.from_local_datetime(naive).earliest().map(|dt| dt.timestamp()),
+        Zone::Named(tz) => tz.from_local_datetime(naive).earliest().map(|dt| dt.timestamp()),
+    }
+}
+
+impl Zone {
+    pub fn name(&self) -> String {
+        match self {
+            Zone::Local => "local".to_string(),
+            Zone::Named(tz) => tz.name().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, Timelike};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_zone() {
+        assert_eq!(parse_zone("local").unwrap(), Zone::Local);
+        assert_eq!(parse_zone("Europe/Berlin").unwrap(), Zone::Named(chrono_tz::Europe::Berlin));
+        assert_eq!(parse_zone("UTC").unwrap().name(), "UTC");
+        assert!(parse_zone("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn test_conversions_in_named_zone() {
+        set_current(parse_zone("America/New_York").unwrap());
+        let naive = NaiveDate::from_ymd_opt(2025, 1, 15).unwrap().and_hms_opt(9, 0, 0).unwrap();
+        // 9am EST is 14:00 UTC
+        let ts = timestamp(&naive).unwrap();
+        assert_eq!(ts, Utc.with_ymd_and_hms(2025, 1, 15, 14, 0, 0).unwrap().timestamp());
+        assert_eq!(datetime(ts).unwrap().hour(), 9);
+        assert_eq!(datetime(ts).unwrap().offset().local_minus_utc(), -5 * 3600);
+
+        // Skipped hour on DST start has no instant
+        let gap = NaiveDate::from_ymd_opt(2025, 3, 9).unwrap().and_hms_opt(2, 30, 0).unwrap();
+        assert_eq!(timestamp(&gap), None);
+        set_current(Zone::Local);
+    }
+}
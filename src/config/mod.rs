@@ -1,10 +1,17 @@
+// nanoserde's DeJson derive expands to matches that clippy would rather see as `?`
+#![allow(clippy::question_mark)]
+
 use std::{
     collections::HashMap,
     fs,
-    path::PathBuf,
+    path::{
+        Path,
+        PathBuf,
+    },
 };
 
 use nanoserde::DeJson;
+use serde_json::Value;
 
 const DB_NAME: &str = "ctm.db";
 const DEFAULT_DATA_DIR: &[&str] = &[".local", "share", "ctm"];
@@ -22,6 +29,59 @@ pub struct ProjectConfig {
     pub prompt_template: Option<String>,
 }
 
+/// A set of tasks created together by `ctm template apply`.
+/// Text fields may use `{name}` variables, filled in from `vars` and `--var name=value`.
+#[derive(Default, DeJson, Clone)]
+pub struct TemplateConfig {
+    /// Shown by `ctm template list`
+    #[nserde(default)]
+    pub description: Option<String>,
+    /// Content of a parent task the tasks become subtasks of, e.g. "Release {version}"
+    #[nserde(default)]
+    pub title: Option<String>,
+    /// Defaults for every task of the template
+    #[nserde(default)]
+    pub category: Option<String>,
+    #[nserde(default)]
+    pub priority: Option<String>,
+    #[nserde(default)]
+    pub estimate: Option<String>,
+    #[nserde(default)]
+    pub project: Option<String>,
+    /// Deadline relative to when the template is applied, e.g. "in 3 business days"
+    #[nserde(default)]
+    pub due: Option<String>,
+    /// Default values of variables
+    #[nserde(default)]
+    pub vars: Option<HashMap<String, String>>,
+    #[nserde(default)]
+    pub tasks: Vec<TemplateTask>,
+}
+
+/// One task of a template, unset fields fall back to the template's.
+#[derive(Default, DeJson, Clone)]
+pub struct TemplateTask {
+    /// Checked when the template is applied, so that one bad task doesn't hide the whole config
+    #[nserde(default)]
+    pub content: String,
+    #[nserde(default)]
+    pub category: Option<String>,
+    #[nserde(default)]
+    pub priority: Option<String>,
+    #[nserde(default)]
+    pub estimate: Option<String>,
+    #[nserde(default)]
+    pub project: Option<String>,
+    #[nserde(default)]
+    pub due: Option<String>,
+    /// Username of the assignee, `--for` wins over it
+    #[nserde(default)]
+    pub assignee: Option<String>,
+    /// Positions (from 1) of earlier tasks of the template that must be done first
+    #[nserde(default)]
+    pub after: Option<Vec<usize>>,
+}
+
 #[derive(Default, DeJson)]
 pub struct Config {
     /// Only supports full path.
@@ -33,6 +93,18 @@ pub struct Config {
     /// Project configurations keyed by name
     #[nserde(default)]
     pub projects: Option<HashMap<String, ProjectConfig>>,
+    /// Active namespace, set by `ctm ns switch`
+    #[nserde(default)]
+    pub namespace: Option<String>,
+    /// Default user, set by `ctm ns switch --user`
+    #[nserde(default)]
+    pub user: Option<String>,
+    /// Company holidays (YYYY-MM-DD) skipped when counting business days
+    #[nserde(default)]
+    pub holidays: Option<Vec<String>>,
+    /// Task templates keyed by name, see `ctm template`
+    #[nserde(default)]
+    pub templates: Option<HashMap<String, TemplateConfig>>,
 }
 
 pub fn get_data_path() -> Result<PathBuf, String> {
@@ -67,15 +139,71 @@ fn get_config_data_dir(home_dir: PathBuf) -> Option<String> {
     }
 }
 
+fn get_config_path() -> Option<PathBuf> {
+    let home_dir = home::home_dir()?;
+    Some(CONFIG_PATH.iter().fold(home_dir, |p, d| p.join(d)))
+}
+
 /// Load the full config from ~/.config/ctm/config.json
 pub fn load_config() -> Option<Config> {
-    let home_dir = home::home_dir()?;
-    let config_path = CONFIG_PATH.iter().fold(home_dir, |p, d| p.join(d));
-    if !config_path.exists() {
-        return None;
+    load_config_from(&get_config_path()?).ok().flatten()
+}
+
+/// Fail if the config exists but cannot be parsed. `load_config` reads such a
+/// config as no config at all, so this is checked once up front instead.
+pub fn check_config() -> Result<(), String> {
+    match get_config_path() {
+        Some(path) => load_config_from(&path).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+/// Load the config at `path`, `Ok(None)` if there is no config file yet.
+/// Unlike `load_config`, a malformed file is an error so that callers
+/// about to write the config back don't overwrite it.
+pub fn load_config_from(path: &Path) -> Result<Option<Config>, String> {
+    if !path.exists() {
+        return Ok(None);
     }
-    let config_content = fs::read_to_string(&config_path).ok()?;
-    DeJson::deserialize_json(&config_content).ok()
+    let config_content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+    DeJson::deserialize_json(&config_content)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse config {}: {}", path.display(), e))
+}
+
+/// Persist the active namespace, and optionally the default user,
+/// keeping every other setting of the existing config.
+pub fn set_active_namespace(namespace: &str, user: Option<&str>) -> Result<(), String> {
+    let path = get_config_path().ok_or_else(|| String::from("cannot find home directory"))?;
+    set_active_namespace_in(&path, namespace, user)
+}
+
+// Only the keys being set are touched, the file goes through serde_json rather than `Config`
+// so that keys this version doesn't know about, or a newer one added, survive the write.
+fn set_active_namespace_in(path: &Path, namespace: &str, user: Option<&str>) -> Result<(), String> {
+    let mut config = if path.exists() {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse config {}: {}", path.display(), e))?
+    } else {
+        Value::Object(Default::default())
+    };
+    let settings = config
+        .as_object_mut()
+        .ok_or_else(|| format!("Failed to parse config {}: not a JSON object", path.display()))?;
+    settings.insert("namespace".to_string(), Value::from(namespace));
+    if let Some(user) = user {
+        settings.insert("user".to_string(), Value::from(user));
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| format!("Failed to write config {}: {}", path.display(), e))
 }
 
 /// Get project configuration by name
@@ -85,6 +213,23 @@ pub fn get_project(name: &str) -> Option<ProjectConfig> {
     projects.get(name).cloned()
 }
 
+/// Get a task template by name
+pub fn get_template(name: &str) -> Option<TemplateConfig> {
+    let config = load_config()?;
+    let templates = config.templates?;
+    templates.get(name).cloned()
+}
+
+/// All configured templates, sorted by name
+pub fn list_templates() -> Vec<(String, TemplateConfig)> {
+    let mut templates: Vec<(String, TemplateConfig)> = load_config()
+        .and_then(|c| c.templates)
+        .map(|t| t.into_iter().collect())
+        .unwrap_or_default();
+    templates.sort_by(|a, b| a.0.cmp(&b.0));
+    templates
+}
+
 /// Get terminal profile name (default: "Ubuntu")
 pub fn get_terminal_profile() -> String {
     load_config()
@@ -140,4 +285,82 @@ mod tests {
             .unwrap_err()
             .contains("must be absolute or home relative"));
     }
+
+    #[test]
+    fn test_set_active_namespace_keeps_projects() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ctm").join("config.json");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            &path,
+            r#"{"data_dir": "~/tasks", "theme": "dark", "projects": {"api": {"path": "/src/api", "conda_env": "py311", "gpu": true}},
+                "templates": {"release": {"title": "Release {version}", "due": "in 2 days",
+                    "tasks": [{"content": "Tag v{version}"}, {"content": "Announce", "after": [1]}]}}}"#,
+        )
+        .unwrap();
+
+        set_active_namespace_in(&path, "backend", None).unwrap();
+        let config = load_config_from(&path).unwrap().unwrap();
+        assert_eq!(config.namespace.as_deref(), Some("backend"));
+        assert_eq!(config.user, None);
+        assert_eq!(config.data_dir, "~/tasks");
+        let api = &config.projects.as_ref().unwrap()["api"];
+        assert_eq!(api.path, "/src/api");
+        assert_eq!(api.conda_env.as_deref(), Some("py311"));
+        assert_eq!(api.claude_flags, None);
+
+        set_active_namespace_in(&path, "frontend", Some("sarah")).unwrap();
+        set_active_namespace_in(&path, "default", None).unwrap();
+        let config = load_config_from(&path).unwrap().unwrap();
+        assert_eq!(config.namespace.as_deref(), Some("default"));
+        assert_eq!(config.user.as_deref(), Some("sarah"));
+        assert!(config.projects.unwrap().contains_key("api"));
+        let release = &config.templates.as_ref().unwrap()["release"];
+        assert_eq!(release.title.as_deref(), Some("Release {version}"));
+        assert_eq!(release.tasks.len(), 2);
+        assert_eq!(release.tasks[1].after, Some(vec![1]));
+        assert_eq!(release.tasks[1].category, None);
+
+        // Keys this version doesn't know about are kept as they were
+        let raw: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(raw["theme"], "dark");
+        assert_eq!(raw["projects"]["api"]["gpu"], true);
+        assert!(raw["projects"]["api"].get("claude_flags").is_none());
+    }
+
+    #[test]
+    fn test_set_active_namespace_new_and_malformed_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("config.json");
+        assert!(load_config_from(&path).unwrap().is_none());
+        set_active_namespace_in(&path, "backend", None).unwrap();
+        let config = load_config_from(&path).unwrap().unwrap();
+        assert_eq!(config.namespace.as_deref(), Some("backend"));
+
+        // A config we cannot parse is left untouched
+        fs::write(&path, "{ not json").unwrap();
+        assert!(set_active_namespace_in(&path, "frontend", None).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ not json");
+    }
+
+    #[test]
+    fn test_template_task_without_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(
+            &path,
+            r#"{"holidays": ["2026-12-25"], "templates": {"release": {"tasks": [{"category": "work"}]}}}"#,
+        )
+        .unwrap();
+
+        let config = load_config_from(&path).unwrap().unwrap();
+        assert_eq!(config.holidays, Some(vec!["2026-12-25".to_string()]));
+        let release = &config.templates.unwrap()["release"];
+        assert_eq!(release.tasks[0].content, "");
+        assert_eq!(release.tasks[0].category.as_deref(), Some("work"));
+
+        fs::write(&path, r#"{"templates": {"release": {"tasks": [{"content": 1}]}}}"#).unwrap();
+        let err = load_config_from(&path).err().unwrap();
+        assert!(err.starts_with("Failed to parse config"));
+    }
 }
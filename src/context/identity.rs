@@ -1,5 +1,18 @@
 use rusqlite::Connection;
 
+use crate::{
+    args::{
+        format::OutputFormat,
+        timezone::{parse_zone, Zone},
+    },
+    config::{load_config, Config},
+    context::{Permission, Role},
+    db::{
+        item::Item,
+        namespace::{get_namespace_by_id, get_user_role, list_user_namespace_ids},
+    },
+};
+
 /// Runtime context containing the current user and namespace.
 /// This is resolved at startup and passed through to all command handlers.
 #[derive(Debug, Clone)]
@@ -8,6 +21,14 @@ pub struct Context {
     pub current_user_name: String,
     pub current_namespace_id: i64,
     pub current_namespace_name: String,
+    // Role of the current user in the current namespace
+    pub current_role: Role,
+    // Set by --all-namespaces, widens namespace scoping to every namespace the user is a member of
+    pub all_namespaces: bool,
+    // Set by --json / --format, how commands print items
+    pub output: OutputFormat,
+    // Zone times are parsed and shown in: user's zone > namespace's zone > system zone
+    pub time_zone: Zone,
 }
 
 impl Context {
@@ -21,69 +42,198 @@ impl Context {
         as_user: Option<&str>,
         namespace: Option<&str>,
     ) -> Result<Self, String> {
-        // Resolve username with priority: --as flag > CTM_USER env > system $USER
+        Self::resolve_with_config(conn, as_user, namespace, load_config().as_ref())
+    }
+
+    /// Same as `resolve`, with the config passed in rather than read from disk.
+    pub fn resolve_with_config(
+        conn: &Connection,
+        as_user: Option<&str>,
+        namespace: Option<&str>,
+        config: Option<&Config>,
+    ) -> Result<Self, String> {
+        // Resolve username with priority: --as flag > CTM_USER env > config > system $USER
         let username = as_user
             .map(|s| s.to_string())
             .or_else(|| std::env::var("CTM_USER").ok())
+            .or_else(|| config.and_then(|c| c.user.clone()))
             .or_else(|| std::env::var("USER").ok())
             .or_else(|| std::env::var("USERNAME").ok()) // Windows fallback
             .unwrap_or_else(|| "default".to_string());
 
         // Look up user in database
-        let user_result: Result<(i64, String), rusqlite::Error> = conn.query_row(
-            "SELECT id, name FROM users WHERE name = ?1",
+        let user_result: Result<(i64, String, Option<String>), rusqlite::Error> = conn.query_row(
+            "SELECT id, name, time_zone FROM users WHERE name = ?1",
             [&username],
-            |row| Ok((row.get(0)?, row.get(1)?)),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
         );
 
-        let (user_id, user_name) = user_result.map_err(|_| {
+        let (user_id, user_name, user_zone) = user_result.map_err(|_| {
             format!(
                 "User '{}' not found. Run a command first to auto-create, or use 'ctm user create {}'",
                 username, username
             )
         })?;
 
-        // Resolve namespace with priority: --ns flag > CTM_NAMESPACE env > "default"
+        // Resolve namespace with priority: --ns flag > CTM_NAMESPACE env > config > "default"
         let ns_name = namespace
             .map(|s| s.to_string())
             .or_else(|| std::env::var("CTM_NAMESPACE").ok())
+            .or_else(|| config.and_then(|c| c.namespace.clone()))
             .unwrap_or_else(|| "default".to_string());
 
         // Look up namespace and verify user has access
-        let ns_result: Result<i64, rusqlite::Error> = conn.query_row(
-            "SELECT n.id FROM namespaces n
+        let ns_result: Result<(i64, String, Option<String>), rusqlite::Error> = conn.query_row(
+            "SELECT n.id, un.role, n.time_zone FROM namespaces n
              INNER JOIN user_namespaces un ON n.id = un.namespace_id
              WHERE n.name = ?1 AND un.user_id = ?2",
             rusqlite::params![&ns_name, user_id],
-            |row| row.get(0),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
         );
 
-        let namespace_id = ns_result.map_err(|_| {
+        let (namespace_id, role, ns_zone) = ns_result.map_err(|_| {
             format!(
                 "Namespace '{}' not found or user '{}' does not have access",
                 ns_name, user_name
             )
         })?;
 
+        let time_zone = match user_zone.or(ns_zone) {
+            Some(name) => parse_zone(&name)?,
+            None => Zone::Local,
+        };
+
         Ok(Context {
             current_user_id: user_id,
             current_user_name: user_name,
             current_namespace_id: namespace_id,
             current_namespace_name: ns_name,
+            current_role: Role::parse(&role)?,
+            all_namespaces: false,
+            output: OutputFormat::Table,
+            time_zone,
         })
     }
 
+    pub fn with_all_namespaces(mut self, all_namespaces: bool) -> Self {
+        self.all_namespaces = all_namespaces;
+        self
+    }
+
+    pub fn with_output(mut self, output: OutputFormat) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Namespaces that queries should be restricted to, the current one,
+    /// or every namespace the user is a member of when --all-namespaces is set.
+    pub fn namespace_filter(&self, conn: &Connection) -> Result<Vec<i64>, String> {
+        if self.all_namespaces {
+            list_user_namespace_ids(conn, self.current_user_id)
+        } else {
+            Ok(vec![self.current_namespace_id])
+        }
+    }
+
+    /// Ensures an item resolved from the cache belongs to the current namespace,
+    /// or with --all-namespaces to a namespace the user is a member of.
+    pub fn check_namespace(&self, conn: &Connection, item: &Item) -> Result<(), String> {
+        let namespace_id = item.namespace_id.unwrap_or(self.current_namespace_id);
+        if namespace_id == self.current_namespace_id {
+            return Ok(());
+        }
+        if !self.all_namespaces {
+            return Err(format!(
+                "Item is not in namespace '{}', use --all-namespaces to access it",
+                self.current_namespace_name
+            ));
+        }
+        if get_user_role(conn, self.current_user_id, namespace_id)?.is_some() {
+            return Ok(());
+        }
+        let ns_name = get_namespace_by_id(conn, namespace_id)?
+            .map(|ns| ns.name)
+            .unwrap_or_else(|| namespace_id.to_string());
+        Err(format!(
+            "Permission denied: user '{}' is not a member of namespace '{}'",
+            self.current_user_name, ns_name
+        ))
+    }
+
+    /// Ensures the current user's role in the current namespace grants `permission`.
+    pub fn require(&self, permission: Permission) -> Result<(), String> {
+        if self.current_role.allows(permission) {
+            return Ok(());
+        }
+        Err(format!(
+            "Permission denied: role '{}' in namespace '{}' cannot {}",
+            self.current_role,
+            self.current_namespace_name,
+            permission.description()
+        ))
+    }
+
+    /// Same as `require`, for an arbitrary namespace the user may be a member of.
+    pub fn require_in(
+        &self,
+        conn: &Connection,
+        namespace_id: i64,
+        permission: Permission,
+    ) -> Result<(), String> {
+        if namespace_id == self.current_namespace_id {
+            return self.require(permission);
+        }
+
+        let ns_name = get_namespace_by_id(conn, namespace_id)?
+            .map(|ns| ns.name)
+            .unwrap_or_else(|| namespace_id.to_string());
+        let role = match get_user_role(conn, self.current_user_id, namespace_id)? {
+            Some(role) => Role::parse(&role)?,
+            None => {
+                return Err(format!(
+                    "Permission denied: user '{}' is not a member of namespace '{}'",
+                    self.current_user_name, ns_name
+                ))
+            }
+        };
+
+        if role.allows(permission) {
+            return Ok(());
+        }
+        Err(format!(
+            "Permission denied: role '{}' in namespace '{}' cannot {}",
+            role,
+            ns_name,
+            permission.description()
+        ))
+    }
+
+    /// Checks an item resolved from the cache is in scope and that the
+    /// current user may modify it in the namespace it belongs to.
+    pub fn authorize_item(&self, conn: &Connection, item: &Item) -> Result<(), String> {
+        self.check_namespace(conn, item)?;
+        let namespace_id = item.namespace_id.unwrap_or(self.current_namespace_id);
+        self.require_in(conn, namespace_id, Permission::EditItems)
+    }
+
     /// Get the default context (for backwards compatibility or tests).
     /// This assumes the auto-setup has created the default user and namespace.
+    /// The config file is ignored so a switched namespace doesn't leak into tests.
     pub fn default_from_db(conn: &Connection) -> Result<Self, String> {
-        Self::resolve(conn, None, None)
+        Self::resolve_with_config(conn, None, None, None)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tests::get_test_conn;
+    use crate::{
+        db::{
+            namespace::{add_user_to_namespace, create_namespace, set_namespace_time_zone},
+            user::{create_user, get_user_by_name, set_user_time_zone},
+        },
+        tests::get_test_conn,
+    };
 
     #[test]
     fn test_context_resolve_default() {
@@ -103,11 +253,142 @@ mod tests {
     fn test_context_resolve_nonexistent_user() {
         let (conn, _temp_file) = get_test_conn();
 
-        let result = Context::resolve(&conn, Some("nonexistent_user"), None);
+        let result = Context::resolve_with_config(&conn, Some("nonexistent_user"), None, None);
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("not found"));
     }
 
+    #[test]
+    fn test_context_namespace_scope() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        assert_eq!(ctx.namespace_filter(&conn).unwrap(), vec![ctx.current_namespace_id]);
+        let work_ns = create_namespace(&conn, "work", None, ctx.current_user_id).unwrap();
+        create_user(&conn, "eve", None, None).unwrap();
+        let eve = get_user_by_name(&conn, "eve").unwrap().unwrap();
+        let eve_ns = create_namespace(&conn, "eve-private", None, eve.id).unwrap();
+
+        let mut item = Item::new("task".to_string(), "work".to_string(), "x".to_string());
+        item.namespace_id = Some(ctx.current_namespace_id);
+        assert!(ctx.check_namespace(&conn, &item).is_ok());
+
+        item.namespace_id = Some(work_ns);
+        let result = ctx.check_namespace(&conn, &item);
+        assert!(result.unwrap_err().contains("--all-namespaces"));
+
+        // Every namespace the user is a member of, and none other
+        let ctx = ctx.with_all_namespaces(true);
+        assert_eq!(ctx.namespace_filter(&conn).unwrap(), vec![ctx.current_namespace_id, work_ns]);
+        assert!(ctx.check_namespace(&conn, &item).is_ok());
+        item.namespace_id = Some(eve_ns);
+        let err = ctx.check_namespace(&conn, &item).unwrap_err();
+        assert!(err.contains("not a member of namespace 'eve-private'"));
+    }
+
+    #[test]
+    fn test_context_require_by_role() {
+        let (conn, _temp_file) = get_test_conn();
+        let owner = Context::default_from_db(&conn).unwrap();
+        assert_eq!(owner.current_role, Role::Owner);
+        assert!(owner.require(Permission::DeleteNamespace).is_ok());
+
+        for (name, role) in [("ann", "admin"), ("mel", "member"), ("vic", "viewer")] {
+            create_user(&conn, name, None, None).unwrap();
+            add_user_to_namespace(&conn, "default", name, role).unwrap();
+        }
+
+        let admin = Context::resolve_with_config(&conn, Some("ann"), None, None).unwrap();
+        assert!(admin.require(Permission::EditItems).is_ok());
+        assert!(admin.require(Permission::ManageMembers).is_ok());
+        assert!(admin.require(Permission::DeleteNamespace).is_ok());
+
+        let member = Context::resolve_with_config(&conn, Some("mel"), None, None).unwrap();
+        assert!(member.require(Permission::EditItems).is_ok());
+        let err = member.require(Permission::ManageMembers).unwrap_err();
+        assert!(err.contains("role 'member'"));
+        assert!(member.require(Permission::DeleteNamespace).is_err());
+
+        let viewer = Context::resolve_with_config(&conn, Some("vic"), None, None).unwrap();
+        let err = viewer.require(Permission::EditItems).unwrap_err();
+        assert_eq!(
+            err,
+            "Permission denied: role 'viewer' in namespace 'default' cannot modify items"
+        );
+    }
+
+    #[test]
+    fn test_context_authorize_item_other_namespace() {
+        let (conn, _temp_file) = get_test_conn();
+        let owner = Context::default_from_db(&conn).unwrap();
+        create_user(&conn, "vic", None, None).unwrap();
+        add_user_to_namespace(&conn, "default", "vic", "member").unwrap();
+        let work_ns = create_namespace(&conn, "work", None, owner.current_user_id).unwrap();
+
+        let mut item = Item::new("task".to_string(), "work".to_string(), "x".to_string());
+        item.namespace_id = Some(work_ns);
+
+        // Not a member of "work" at all
+        let ctx = Context::resolve_with_config(&conn, Some("vic"), None, None)
+            .unwrap()
+            .with_all_namespaces(true);
+        assert!(ctx.authorize_item(&conn, &item).unwrap_err().contains("not a member"));
+
+        // Member of "default", but only a viewer of "work"
+        add_user_to_namespace(&conn, "work", "vic", "viewer").unwrap();
+        assert!(ctx.authorize_item(&conn, &item).unwrap_err().contains("role 'viewer'"));
+
+        add_user_to_namespace(&conn, "work", "vic", "member").unwrap();
+        assert!(ctx.authorize_item(&conn, &item).is_ok());
+    }
+
+    #[test]
+    fn test_context_resolve_from_config() {
+        let (conn, _temp_file) = get_test_conn();
+        let owner = Context::default_from_db(&conn).unwrap();
+        create_namespace(&conn, "backend", None, owner.current_user_id).unwrap();
+        create_user(&conn, "sarah", None, None).unwrap();
+        add_user_to_namespace(&conn, "backend", "sarah", "member").unwrap();
+
+        let config = Config {
+            namespace: Some("backend".to_string()),
+            ..Default::default()
+        };
+        let ctx = Context::resolve_with_config(&conn, None, None, Some(&config)).unwrap();
+        assert_eq!(ctx.current_namespace_name, "backend");
+
+        // --ns takes precedence over the config
+        let ctx =
+            Context::resolve_with_config(&conn, None, Some("default"), Some(&config)).unwrap();
+        assert_eq!(ctx.current_namespace_name, "default");
+
+        let config = Config {
+            namespace: Some("backend".to_string()),
+            user: Some("sarah".to_string()),
+            ..Default::default()
+        };
+        if std::env::var("CTM_USER").is_err() {
+            let ctx = Context::resolve_with_config(&conn, None, None, Some(&config)).unwrap();
+            assert_eq!(ctx.current_user_name, "sarah");
+            assert_eq!(ctx.current_role, Role::Member);
+        }
+    }
+
+    #[test]
+    fn test_context_time_zone_precedence() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        assert_eq!(ctx.time_zone, Zone::Local);
+
+        set_namespace_time_zone(&conn, ctx.current_namespace_id, Some("Europe/Berlin")).unwrap();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        assert_eq!(ctx.time_zone.name(), "Europe/Berlin");
+
+        // The user's own zone wins over the namespace default
+        set_user_time_zone(&conn, ctx.current_user_id, Some("Asia/Tokyo")).unwrap();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        assert_eq!(ctx.time_zone.name(), "Asia/Tokyo");
+    }
+
     #[test]
     fn test_context_resolve_nonexistent_namespace() {
         let (conn, _temp_file) = get_test_conn();
@@ -116,7 +397,7 @@ mod tests {
         let ctx = Context::default_from_db(&conn).unwrap();
 
         // Try to access a non-existent namespace
-        let result = Context::resolve(&conn, Some(&ctx.current_user_name), Some("nonexistent_ns"));
+        let result = Context::resolve_with_config(&conn, Some(&ctx.current_user_name), Some("nonexistent_ns"), None);
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("not found"));
     }
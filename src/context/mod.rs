@@ -1,3 +1,5 @@
 mod identity;
+mod permission;
 
 pub use identity::Context;
+pub use permission::{Permission, Role};
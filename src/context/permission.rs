@@ -0,0 +1,107 @@
+use std::fmt;
+
+/// Role of a user within a namespace, as stored in `user_namespaces.role`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Owner,
+    Admin,
+    Member,
+    Viewer,
+}
+
+/// Actions that are gated on the role of the current user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// Create, update, complete or delete items
+    EditItems,
+    /// Add or remove namespace members
+    ManageMembers,
+    /// Grant, change or take away the owner role
+    ManageOwners,
+    /// Change namespace settings such as its time zone
+    ManageSettings,
+    /// Delete the namespace itself
+    DeleteNamespace,
+}
+
+impl Role {
+    pub fn parse(role: &str) -> Result<Self, String> {
+        match role {
+            "owner" => Ok(Role::Owner),
+            "admin" => Ok(Role::Admin),
+            "member" => Ok(Role::Member),
+            "viewer" => Ok(Role::Viewer),
+            _ => Err(format!("Unknown role '{}'", role)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::Admin => "admin",
+            Role::Member => "member",
+            Role::Viewer => "viewer",
+        }
+    }
+
+    pub fn allows(&self, permission: Permission) -> bool {
+        match permission {
+            Permission::EditItems => *self != Role::Viewer,
+            Permission::ManageMembers | Permission::ManageSettings | Permission::DeleteNamespace => {
+                matches!(self, Role::Owner | Role::Admin)
+            }
+            Permission::ManageOwners => *self == Role::Owner,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Permission {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Permission::EditItems => "modify items",
+            Permission::ManageMembers => "manage members",
+            Permission::ManageOwners => "manage owners",
+            Permission::ManageSettings => "change namespace settings",
+            Permission::DeleteNamespace => "delete the namespace",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_permissions() {
+        let permissions = [
+            Permission::EditItems,
+            Permission::ManageMembers,
+            Permission::ManageSettings,
+            Permission::DeleteNamespace,
+        ];
+        assert!(permissions.iter().all(|p| Role::Owner.allows(*p)));
+        assert!(permissions.iter().all(|p| Role::Admin.allows(*p)));
+        assert!(permissions.iter().all(|p| !Role::Viewer.allows(*p)));
+        assert!(Role::Owner.allows(Permission::ManageOwners));
+        assert!(!Role::Admin.allows(Permission::ManageOwners));
+
+        assert!(Role::Member.allows(Permission::EditItems));
+        assert!(!Role::Member.allows(Permission::ManageMembers));
+        assert!(!Role::Member.allows(Permission::ManageSettings));
+        assert!(!Role::Member.allows(Permission::DeleteNamespace));
+    }
+
+    #[test]
+    fn test_role_parse() {
+        for role in [Role::Owner, Role::Admin, Role::Member, Role::Viewer] {
+            assert_eq!(Role::parse(role.as_str()).unwrap(), role);
+        }
+        assert!(Role::parse("guest").is_err());
+    }
+}
@@ -0,0 +1,295 @@
+use std::path::PathBuf;
+
+use rusqlite::{params, params_from_iter, types::Value, Connection};
+
+use crate::db::{conn::init_table, crud::placeholders};
+
+/// How many items `archive_items` moved.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ArchiveCounts {
+    pub tasks: usize,
+    pub records: usize,
+}
+
+// Items leave the hot database with everything hanging off them,
+// in this order so that foreign keys in the archive hold.
+const ITEM_ROWS: [(&str, &str); 6] = [
+    ("task_notes", "item_id IN (SELECT id FROM temp.archiving)"),
+    ("task_links", "item_id IN (SELECT id FROM temp.archiving)"),
+    ("item_tags", "item_id IN (SELECT id FROM temp.archiving)"),
+    (
+        "item_dependencies",
+        "item_id IN (SELECT id FROM temp.archiving) AND depends_on_id IN (SELECT id FROM temp.archiving)",
+    ),
+    ("time_entries", "item_id IN (SELECT id FROM temp.archiving)"),
+    ("audit_log", "item_id IN (SELECT id FROM temp.archiving)"),
+];
+
+/// The archive is a sibling SQLite file of the database, `ctm.db` archives to `ctm-archive.db`.
+pub fn archive_path(conn: &Connection) -> Result<PathBuf, String> {
+    let path = conn
+        .path()
+        .filter(|path| !path.is_empty())
+        .ok_or_else(|| "The database has no file to put an archive next to".to_string())?;
+    let stem = path.strip_suffix(".db").unwrap_or(path);
+    Ok(PathBuf::from(format!("{}-archive.db", stem)))
+}
+
+/// Opens the archive for reading, it has the same schema as the database.
+pub fn open_archive(conn: &Connection) -> Result<Connection, String> {
+    let path = archive_path(conn)?;
+    if !path.exists() {
+        return Err("Nothing has been archived yet, see ctm archive".to_string());
+    }
+    let archive = Connection::open(&path).map_err(|e| e.to_string())?;
+    init_table(&archive).map_err(|e| e.to_string())?;
+    Ok(archive)
+}
+
+/// Moves closed tasks and records older than `before` to the archive,
+/// with their notes, links, tags, time entries and audit rows.
+/// Tasks count from when they were last changed, which for a closed task is when it was closed.
+/// A task stays while one of its subtasks does, and items in the trash stay until purged.
+/// Records of a recurring task stay with it, its remaining occurrences are counted from them.
+/// Commands that changed the archived items drop out of `ctm undo`.
+/// Only items of `namespace_ids` are moved when given.
+pub fn archive_items(conn: &Connection, before: i64, namespace_ids: Option<&[i64]>) -> Result<ArchiveCounts, String> {
+    let path = archive_path(conn)?;
+    // Creates or migrates the archive
+    let archive = Connection::open(&path).map_err(|e| e.to_string())?;
+    init_table(&archive).map_err(|e| e.to_string())?;
+    drop(archive);
+
+    // Attaching can't happen within a transaction
+    conn.execute("ATTACH DATABASE ?1 AS archive", params![path.to_string_lossy()])
+        .map_err(|e| e.to_string())?;
+    let result = move_items(conn, before, namespace_ids);
+    conn.execute("DETACH DATABASE archive", []).map_err(|e| e.to_string())?;
+    result.map_err(|e| format!("Failed to archive: {}", e))
+}
+
+fn move_items(conn: &Connection, before: i64, namespace_ids: Option<&[i64]>) -> rusqlite::Result<ArchiveCounts> {
+    let tx = conn.unchecked_transaction()?;
+
+    let mut params: Vec<Value> = vec![Value::from(before); 3];
+    let namespace_condition = match namespace_ids {
+        Some(ns) => {
+            params.extend(ns.iter().map(|&id| Value::from(id)));
+            format!("i.namespace_id IN ({})", placeholders(ns.len()))
+        }
+        None => "1".to_string(),
+    };
+
+    // Closed means done, cancelled, duplicate or removed
+    tx.execute("CREATE TEMP TABLE archiving (id INTEGER PRIMARY KEY)", [])?;
+    tx.execute(
+        &format!(
+            "INSERT INTO temp.archiving (id)
+             SELECT i.id FROM main.items i
+             WHERE i.deleted_at IS NULL AND (
+                 (i.action IN ('record', 'recurring_task_record') AND i.create_time < ?
+                     AND NOT EXISTS (SELECT 1 FROM main.items r WHERE r.id = i.recurring_task_id))
+                 OR (i.action = 'task' AND i.status IN (1, 2, 3, 5)
+                     AND COALESCE(i.modify_time, i.create_time) < ?
+                     AND NOT EXISTS (
+                         SELECT 1 FROM main.items c
+                         WHERE c.parent_id = i.id
+                           AND NOT (c.status IN (1, 2, 3, 5) AND COALESCE(c.modify_time, c.create_time) < ?)
+                     ))
+             ) AND {}",
+            namespace_condition
+        ),
+        params_from_iter(params),
+    )?;
+    let counts = tx.query_row(
+        "SELECT COALESCE(SUM(i.action = 'task'), 0), COALESCE(SUM(i.action != 'task'), 0)
+         FROM main.items i INNER JOIN temp.archiving a ON a.id = i.id",
+        [],
+        |row| {
+            Ok(ArchiveCounts {
+                tasks: row.get::<_, i64>(0)? as usize,
+                records: row.get::<_, i64>(1)? as usize,
+            })
+        },
+    )?;
+
+    if counts.tasks + counts.records > 0 {
+        // Whoever the archived rows refer to, as they are now
+        upsert_rows(&tx, "users", "1")?;
+        upsert_rows(&tx, "namespaces", "1")?;
+        upsert_rows(
+            &tx,
+            "tags",
+            "id IN (SELECT tag_id FROM main.item_tags WHERE item_id IN (SELECT id FROM temp.archiving))",
+        )?;
+
+        // A parent may stay behind, the link is only kept when it is archived too
+        copy_rows(&tx, "items", "id IN (SELECT id FROM temp.archiving)", "INSERT")?;
+        tx.execute(
+            "UPDATE archive.items SET parent_id = NULL
+             WHERE id IN (SELECT id FROM temp.archiving)
+               AND parent_id NOT IN (SELECT id FROM archive.items)",
+            [],
+        )?;
+        for (table, condition) in ITEM_ROWS {
+            copy_rows(&tx, table, condition, "INSERT")?;
+        }
+
+        // Deleting the items takes their remaining rows along, commands that
+        // changed them can't be undone anymore
+        tx.execute("DELETE FROM main.audit_log WHERE item_id IN (SELECT id FROM temp.archiving)", [])?;
+        tx.execute(
+            "DELETE FROM main.journal WHERE id IN (
+                 SELECT journal_id FROM main.journal_changes WHERE item_id IN (SELECT id FROM temp.archiving)
+             )",
+            [],
+        )?;
+        tx.execute("DELETE FROM main.items WHERE id IN (SELECT id FROM temp.archiving)", [])?;
+    }
+
+    tx.execute("DROP TABLE temp.archiving", [])?;
+    tx.commit()?;
+    Ok(counts)
+}
+
+// Copies the rows of a table matching `condition` from the database to the archive,
+// by column name since migrations may have ordered the columns differently.
+fn copy_rows(conn: &Connection, table: &str, condition: &str, insert: &str) -> rusqlite::Result<usize> {
+    let stmt = conn.prepare(&format!("SELECT * FROM main.{} LIMIT 0", table))?;
+    let columns = stmt.column_names().join(", ");
+    conn.execute(
+        &format!(
+            "{} INTO archive.{} ({}) SELECT {} FROM main.{} WHERE {}",
+            insert, table, columns, columns, table, condition
+        ),
+        [],
+    )
+}
+
+// Copies rows like `copy_rows`, updating the ones the archive already has in place.
+// Replacing them would delete them first, and with them the archived rows referring to them.
+fn upsert_rows(conn: &Connection, table: &str, condition: &str) -> rusqlite::Result<usize> {
+    let stmt = conn.prepare(&format!("SELECT * FROM main.{} LIMIT 0", table))?;
+    let names = stmt.column_names();
+    let columns = names.join(", ");
+    let updates = names
+        .iter()
+        .filter(|column| **column != "id")
+        .map(|column| format!("{} = excluded.{}", column, column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    conn.execute(
+        &format!(
+            "INSERT INTO archive.{} ({}) SELECT {} FROM main.{} WHERE {} ON CONFLICT(id) DO UPDATE SET {}",
+            table, columns, columns, table, condition, updates
+        ),
+        [],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actions::recurrence::remaining_occurrences,
+        context::Context,
+        db::{
+            crud::get_item,
+            journal::{self, list_entries},
+            note::add_note,
+            search::search,
+            tag::{add_tags, get_tags_for_item},
+        },
+        tests::{
+            get_test_conn,
+            insert_record,
+            insert_recurring_record,
+            insert_recurring_task,
+            insert_subtask,
+            insert_task,
+            update_status,
+        },
+    };
+
+    #[test]
+    fn test_archive_items() {
+        let (conn, _temp_file) = get_test_conn();
+        let done = insert_task(&conn, "work", "migrate the invoices", "today");
+        let open = insert_task(&conn, "work", "still open", "today");
+        let record = insert_record(&conn, "work", "shipped it", "yesterday");
+        add_note(&conn, done, "check the ledger", None).unwrap();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let user_id = ctx.current_user_id;
+        journal::begin(user_id, ctx.current_namespace_id, "ctm done #1");
+        update_status(&conn, done, 1);
+        journal::end();
+        assert_eq!(list_entries(&conn, user_id, 10).unwrap().len(), 1);
+        // A closed parent stays while its subtask is open
+        let parent = insert_task(&conn, "work", "epic", "today");
+        insert_subtask(&conn, parent, "part one");
+        update_status(&conn, parent, 1);
+
+        let later = get_item(&conn, done).unwrap().modify_time.unwrap() + 1;
+        let counts = archive_items(&conn, later, None).unwrap();
+        assert_eq!(counts, ArchiveCounts { tasks: 1, records: 1 });
+        assert!(get_item(&conn, done).is_err());
+        assert!(get_item(&conn, record).is_err());
+        assert!(get_item(&conn, open).is_ok());
+        assert!(get_item(&conn, parent).is_ok());
+        assert!(search(&conn, "ledger", None, 10).unwrap().is_empty());
+
+        // Still found in the archive, notes included
+        let archive = open_archive(&conn).unwrap();
+        let hits = search(&archive, "ledger", None, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].item.id, Some(done));
+        assert_eq!(get_item(&archive, done).unwrap().status, 1);
+
+        // The command that closed it can't be undone anymore
+        assert!(list_entries(&conn, user_id, 10).unwrap().is_empty());
+
+        // Nothing left to move
+        assert_eq!(archive_items(&conn, later, None).unwrap(), ArchiveCounts::default());
+        std::fs::remove_file(archive_path(&conn).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_recurring_records_stay() {
+        let (conn, _temp_file) = get_test_conn();
+        let id = insert_recurring_task(&conn, "work", "Check-in", "Daily 9AM for 5 times");
+        insert_recurring_record(&conn, "work", "first", id, 0);
+        insert_recurring_record(&conn, "work", "second", id, 0);
+        let record = insert_record(&conn, "work", "shipped it", "yesterday");
+        let remaining = || remaining_occurrences(&conn, &get_item(&conn, id).unwrap()).unwrap();
+        assert_eq!(remaining(), Some(3));
+
+        let later = chrono::Local::now().timestamp() + 60;
+        assert_eq!(archive_items(&conn, later, None).unwrap(), ArchiveCounts { tasks: 0, records: 1 });
+        assert!(get_item(&conn, record).is_err());
+        assert_eq!(remaining(), Some(3));
+        std::fs::remove_file(archive_path(&conn).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_archive_twice_keeps_tags() {
+        let (conn, _temp_file) = get_test_conn();
+        let first = insert_task(&conn, "work", "rotate the keys", "today");
+        let second = insert_task(&conn, "work", "renew the certificates", "today");
+        for id in [first, second] {
+            add_tags(&conn, id, &["ops".to_string()]).unwrap();
+        }
+
+        update_status(&conn, first, 1);
+        let later = get_item(&conn, first).unwrap().modify_time.unwrap() + 1;
+        assert_eq!(archive_items(&conn, later, None).unwrap().tasks, 1);
+        update_status(&conn, second, 1);
+        let later = get_item(&conn, second).unwrap().modify_time.unwrap() + 1;
+        assert_eq!(archive_items(&conn, later, None).unwrap().tasks, 1);
+
+        // The tag was archived with the first task, the second run must not drop its link
+        let archive = open_archive(&conn).unwrap();
+        assert_eq!(get_tags_for_item(&archive, first).unwrap(), vec!["ops"]);
+        assert_eq!(get_tags_for_item(&archive, second).unwrap(), vec!["ops"]);
+        std::fs::remove_file(archive_path(&conn).unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,323 @@
+use rusqlite::{params, Connection};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::db::item::Item;
+
+pub const AUDIT_CREATE: &str = "create";
+pub const AUDIT_UPDATE: &str = "update";
+pub const AUDIT_DELETE: &str = "delete";
+pub const AUDIT_COMPLETE: &str = "complete";
+
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub item_id: Option<i64>,
+    pub action: String,
+    pub field_name: Option<String>,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub created_at: i64,
+    pub created_by: Option<i64>,
+}
+
+impl AuditEntry {
+    pub fn from_row(row: &rusqlite::Row) -> Result<Self, rusqlite::Error> {
+        Ok(AuditEntry {
+            item_id: row.get("item_id")?,
+            action: row.get("action")?,
+            field_name: row.get("field_name")?,
+            old_value: row.get("old_value")?,
+            new_value: row.get("new_value")?,
+            created_at: row.get("created_at")?,
+            created_by: row.get("created_by")?,
+        })
+    }
+}
+
+/// A single field-level difference between two versions of an item.
+pub type FieldChange = (&'static str, Option<String>, Option<String>);
+
+/// Compares the persisted fields of two items and returns the ones that changed.
+pub fn diff_items(old: &Item, new: &Item) -> Vec<FieldChange> {
+    fn opt<T: ToString>(value: &Option<T>) -> Option<String> {
+        value.as_ref().map(ToString::to_string)
+    }
+
+    let fields: Vec<FieldChange> = vec![
+        ("category", Some(old.category.clone()), Some(new.category.clone())),
+        ("content", Some(old.content.clone()), Some(new.content.clone())),
+        ("target_time", opt(&old.target_time), opt(&new.target_time)),
+        ("status", Some(old.status.to_string()), Some(new.status.to_string())),
+        ("cron_schedule", opt(&old.cron_schedule), opt(&new.cron_schedule)),
+        ("human_schedule", opt(&old.human_schedule), opt(&new.human_schedule)),
+        ("recurring_task_id", opt(&old.recurring_task_id), opt(&new.recurring_task_id)),
+        ("good_until", opt(&old.good_until), opt(&new.good_until)),
+        ("reminder_days", opt(&old.reminder_days), opt(&new.reminder_days)),
+        ("project", opt(&old.project), opt(&new.project)),
+        ("owner_id", opt(&old.owner_id), opt(&new.owner_id)),
+        ("assignee_id", opt(&old.assignee_id), opt(&new.assignee_id)),
+        ("namespace_id", opt(&old.namespace_id), opt(&new.namespace_id)),
+        ("priority", opt(&old.priority), opt(&new.priority)),
+        ("estimate_minutes", opt(&old.estimate_minutes), opt(&new.estimate_minutes)),
+        ("github_issue", opt(&old.github_issue), opt(&new.github_issue)),
+        ("parent_id", opt(&old.parent_id), opt(&new.parent_id)),
+        ("recur_until", opt(&old.recur_until), opt(&new.recur_until)),
+        ("recur_count", opt(&old.recur_count), opt(&new.recur_count)),
+        ("deleted_at", opt(&old.deleted_at), opt(&new.deleted_at)),
+    ];
+
+    fields
+        .into_iter()
+        .filter(|(_, old_value, new_value)| old_value != new_value)
+        .collect()
+}
+
+/// Writes a single audit row for an item mutation.
+#[allow(clippy::too_many_arguments)]
+pub fn log_change(
+    conn: &Connection,
+    item_id: i64,
+    namespace_id: Option<i64>,
+    action: &str,
+    field_name: Option<&str>,
+    old_value: Option<&str>,
+    new_value: Option<&str>,
+    created_by: Option<i64>,
+) -> Result<(), rusqlite::Error> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    conn.execute(
+        "INSERT INTO audit_log (item_id, namespace_id, table_name, action, field_name, old_value, new_value, created_at, created_by)
+         VALUES (?1, ?2, 'items', ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            item_id,
+            namespace_id,
+            action,
+            field_name,
+            old_value,
+            new_value,
+            now,
+            created_by
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Records the creation of an item.
+pub fn log_create(
+    conn: &Connection,
+    item_id: i64,
+    item: &Item,
+    created_by: Option<i64>,
+) -> Result<(), rusqlite::Error> {
+    log_change(
+        conn,
+        item_id,
+        item.namespace_id,
+        AUDIT_CREATE,
+        Some("content"),
+        None,
+        Some(&item.content),
+        created_by,
+    )
+}
+
+/// Records every changed field between `old` and `new` under the given action.
+/// A completion is always recorded, even when no persisted field changed
+/// (e.g. completing an iteration of a recurring task).
+pub fn log_update(
+    conn: &Connection,
+    old: &Item,
+    new: &Item,
+    action: &str,
+    created_by: Option<i64>,
+) -> Result<usize, rusqlite::Error> {
+    let item_id = new.id.or(old.id).unwrap_or_default();
+    let changes = diff_items(old, new);
+
+    if changes.is_empty() && action == AUDIT_COMPLETE {
+        log_change(conn, item_id, new.namespace_id, action, None, None, None, created_by)?;
+        return Ok(1);
+    }
+
+    for (field, old_value, new_value) in &changes {
+        log_change(
+            conn,
+            item_id,
+            new.namespace_id,
+            action,
+            Some(field),
+            old_value.as_deref(),
+            new_value.as_deref(),
+            created_by,
+        )?;
+    }
+
+    Ok(changes.len())
+}
+
+/// Records the deletion of an item, keeping its content for later reference.
+pub fn log_delete(
+    conn: &Connection,
+    item: &Item,
+    created_by: Option<i64>,
+) -> Result<(), rusqlite::Error> {
+    log_change(
+        conn,
+        item.id.unwrap_or_default(),
+        item.namespace_id,
+        AUDIT_DELETE,
+        Some("content"),
+        Some(&item.content),
+        None,
+        created_by,
+    )
+}
+
+/// Gets the audit trail of an item, ordered oldest first.
+pub fn get_history_for_item(conn: &Connection, item_id: i64) -> Result<Vec<AuditEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT item_id, action, field_name, old_value, new_value, created_at, created_by
+             FROM audit_log
+             WHERE item_id = ?1
+             ORDER BY created_at ASC, id ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let entries = stmt
+        .query_map([item_id], AuditEntry::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(entries)
+}
+
+/// Gets audit entries of a namespace created after `since`, newest first.
+pub fn get_log_since(
+    conn: &Connection,
+    namespace_id: i64,
+    since: i64,
+    limit: usize,
+) -> Result<Vec<AuditEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT item_id, action, field_name, old_value, new_value, created_at, created_by
+             FROM audit_log
+             WHERE namespace_id = ?1 AND created_at > ?2
+             ORDER BY created_at DESC, id DESC
+             LIMIT ?3",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let entries = stmt
+        .query_map(params![namespace_id, since, limit as i64], AuditEntry::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{
+            crud::{delete_item, get_item, insert_item, update_item},
+            namespace::create_namespace,
+        },
+        tests::{get_test_conn, insert_task},
+    };
+
+    #[test]
+    fn test_diff_items() {
+        let old = Item::new("task".to_string(), "work".to_string(), "report".to_string());
+        let mut new = old.clone();
+        assert!(diff_items(&old, &new).is_empty());
+
+        new.category = "home".to_string();
+        new.target_time = Some(1000);
+        let changes = diff_items(&old, &new);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(
+            changes[0],
+            ("category", Some("work".to_string()), Some("home".to_string()))
+        );
+        assert_eq!(changes[1], ("target_time", None, Some("1000".to_string())));
+    }
+
+    #[test]
+    fn test_history_records_mutations() {
+        let (conn, _temp_file) = get_test_conn();
+        let mut item = Item::new("task".to_string(), "work".to_string(), "report".to_string());
+        item.namespace_id = Some(1);
+        let item_id = insert_item(&conn, &item, Some(1)).unwrap();
+
+        let mut stored = get_item(&conn, item_id).unwrap();
+        stored.target_time = Some(2000);
+        stored.priority = Some(0);
+        update_item(&conn, &stored, Some(1)).unwrap();
+
+        // Saving an unchanged item writes nothing
+        update_item(&conn, &stored, Some(1)).unwrap();
+
+        let history = get_history_for_item(&conn, item_id).unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].action, AUDIT_CREATE);
+        assert_eq!(history[1].action, AUDIT_UPDATE);
+        assert_eq!(history[1].field_name.as_deref(), Some("target_time"));
+        assert_eq!(history[1].new_value.as_deref(), Some("2000"));
+        assert_eq!(history[2].field_name.as_deref(), Some("priority"));
+        assert!(history[2].old_value.is_none());
+        assert!(history.iter().all(|e| e.created_by == Some(1)));
+
+        // Deleting detaches the entries from the item but keeps them in the namespace log
+        delete_item(&conn, item_id, Some(1)).unwrap();
+        assert!(get_history_for_item(&conn, item_id).unwrap().is_empty());
+        let log = get_log_since(&conn, 1, 0, 100).unwrap();
+        assert_eq!(log.len(), 4);
+        assert_eq!(log[0].action, AUDIT_DELETE);
+        assert_eq!(log[0].old_value.as_deref(), Some("report"));
+        assert!(log[0].item_id.is_none());
+    }
+
+    #[test]
+    fn test_log_update_complete_without_changes() {
+        let (conn, _temp_file) = get_test_conn();
+        let item_id = insert_task(&conn, "work", "standup", "today");
+        let item = get_item(&conn, item_id).unwrap();
+
+        let written = log_update(&conn, &item, &item, AUDIT_COMPLETE, None).unwrap();
+        assert_eq!(written, 1);
+        let written = log_update(&conn, &item, &item, AUDIT_UPDATE, None).unwrap();
+        assert_eq!(written, 0);
+
+        let history = get_history_for_item(&conn, item_id).unwrap();
+        assert_eq!(history.last().unwrap().action, AUDIT_COMPLETE);
+        assert!(history.last().unwrap().field_name.is_none());
+    }
+
+    #[test]
+    fn test_get_log_since() {
+        let (conn, _temp_file) = get_test_conn();
+        let other_ns = create_namespace(&conn, "other", None, 1).unwrap();
+        let mut item = Item::new("task".to_string(), "work".to_string(), "a".to_string());
+        item.namespace_id = Some(1);
+        insert_item(&conn, &item, None).unwrap();
+        item.namespace_id = Some(other_ns);
+        insert_item(&conn, &item, None).unwrap();
+
+        let entries = get_log_since(&conn, 1, 0, 100).unwrap();
+        assert_eq!(entries.len(), 1);
+        let entries = get_log_since(&conn, other_ns, 0, 100).unwrap();
+        assert_eq!(entries.len(), 1);
+
+        let entries = get_log_since(&conn, 1, i64::MAX - 1, 100).unwrap();
+        assert!(entries.is_empty());
+    }
+}
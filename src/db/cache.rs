@@ -1,6 +1,9 @@
-use std::time::{
-    SystemTime,
-    UNIX_EPOCH,
+use std::{
+    sync::OnceLock,
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
 };
 
 use rusqlite::{
@@ -11,15 +14,94 @@ use rusqlite::{
 
 use crate::db::item::Item;
 
-pub fn store(conn: &Connection, items: &[Item]) -> Result<()> {
-    let current_time = SystemTime::now()
+// Seconds after which a cached listing is no longer valid
+const CACHE_TTL: i64 = 3600;
+
+/// Identifier of the cache a process reads and writes, so that
+/// concurrent shells and agents each keep their own list indexes.
+/// Priority: CTM_SESSION env > controlling terminal > parent process id
+///
+/// The terminal is the one the process is attached to, not what stdin points at, so
+/// `ctm done 3 < /dev/null` still uses the indexes of the `ctm list` before it.
+/// Without any terminal (cron, CI, some agents) commands run by the same parent share
+/// a cache, set CTM_SESSION for anything else.
+pub fn session_id() -> &'static str {
+    static SESSION: OnceLock<String> = OnceLock::new();
+    SESSION.get_or_init(|| resolve_session(std::env::var("CTM_SESSION").ok(), detect_tty, detect_parent))
+}
+
+fn resolve_session(
+    env: Option<String>,
+    tty: impl FnOnce() -> Option<String>,
+    parent: impl FnOnce() -> String,
+) -> String {
+    match env {
+        Some(session) if !session.is_empty() => session,
+        _ => tty().unwrap_or_else(parent),
+    }
+}
+
+#[cfg(unix)]
+fn detect_tty() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    if let Some(tty) = std::fs::read_to_string("/proc/self/stat").ok().and_then(|stat| tty_from_stat(&stat)) {
+        return Some(tty);
+    }
+    // Whichever standard stream is still a terminal
+    [libc::STDOUT_FILENO, libc::STDERR_FILENO, libc::STDIN_FILENO]
+        .into_iter()
+        .find_map(|fd| {
+            // SAFETY: ttyname returns null or a NUL terminated string that stays valid until the next call
+            let name = unsafe { libc::ttyname(fd) };
+            if name.is_null() {
+                None
+            } else {
+                Some(unsafe { std::ffi::CStr::from_ptr(name) }.to_string_lossy().into_owned())
+            }
+        })
+}
+
+#[cfg(not(unix))]
+fn detect_tty() -> Option<String> {
+    None
+}
+
+// The controlling terminal is the 7th field of /proc/self/stat, 0 when there is none.
+// The 2nd field is the command name in parentheses, which may itself contain spaces and parentheses.
+#[cfg(target_os = "linux")]
+fn tty_from_stat(stat: &str) -> Option<String> {
+    let (_, fields) = stat.rsplit_once(')')?;
+    match fields.split_whitespace().nth(4)? {
+        "0" => None,
+        tty_nr => Some(format!("tty:{}", tty_nr)),
+    }
+}
+
+#[cfg(unix)]
+fn detect_parent() -> String {
+    format!("ppid:{}", std::os::unix::process::parent_id())
+}
+
+#[cfg(not(unix))]
+fn detect_parent() -> String {
+    "default".to_string()
+}
+
+fn now() -> i64 {
+    SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards")
-        .as_secs() as i64;
+        .as_secs() as i64
+}
 
+pub fn store(conn: &Connection, items: &[Item]) -> Result<()> {
+    store_in(conn, session_id(), items)
+}
+
+pub fn store_in(conn: &Connection, session: &str, items: &[Item]) -> Result<()> {
     // Store current time at index 0
     // For invalidations after some time.
-    let mut kv: Vec<(i64, i64)> = vec![(0, current_time)];
+    let mut kv: Vec<(i64, i64)> = vec![(0, now())];
 
     let items_kv: Vec<(i64, i64)> = items
         .iter()
@@ -28,53 +110,69 @@ pub fn store(conn: &Connection, items: &[Item]) -> Result<()> {
         .collect();
 
     kv.extend(items_kv);
-    store_kv(conn, kv)
+    store_kv(conn, session, kv)?;
+    gc_sessions(conn)
 }
 
 // add a next token marker
 pub fn store_with_next(conn: &Connection, items: &[Item]) -> Result<()> {
     store(conn, items)?;
     conn.execute(
-        "INSERT OR REPLACE INTO cache (key, value) VALUES (?1, ?2)",
-        [-1, items.len() as i64],
+        "INSERT OR REPLACE INTO cache (session, key, value) VALUES (?1, ?2, ?3)",
+        params![session_id(), -1, items.len() as i64],
     )?;
     Ok(())
 }
 
 pub fn validate_cache(conn: &Connection) -> Result<bool> {
-    let timestamp = match read(conn, 0)? {
+    validate_cache_in(conn, session_id())
+}
+
+pub fn validate_cache_in(conn: &Connection, session: &str) -> Result<bool> {
+    let timestamp = match read_in(conn, session, 0)? {
         Some(t) => t,
         None => return Ok(false),
     };
-    let current_time = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards")
-        .as_secs() as i64;
-    if current_time - timestamp > 3600 {
-        clear(conn)?;
+    if now() - timestamp > CACHE_TTL {
+        clear_in(conn, session)?;
         return Ok(false);
     }
     Ok(true)
 }
 
-fn store_kv(conn: &Connection, kv: Vec<(i64, i64)>) -> Result<()> {
+fn store_kv(conn: &Connection, session: &str, kv: Vec<(i64, i64)>) -> Result<()> {
     let tx = conn.unchecked_transaction()?;
     {
-        let mut stmt =
-            tx.prepare_cached("INSERT OR REPLACE INTO cache (key, value) VALUES (?1, ?2)")?;
+        let mut stmt = tx.prepare_cached(
+            "INSERT OR REPLACE INTO cache (session, key, value) VALUES (?1, ?2, ?3)",
+        )?;
 
         for (key, value) in kv {
-            stmt.execute(params![key, value])?;
+            stmt.execute(params![session, key, value])?;
         }
     }
     tx.commit()?;
     Ok(())
 }
 
+/// Drops the caches of every session whose last listing has expired.
+fn gc_sessions(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "DELETE FROM cache WHERE session IN
+            (SELECT session FROM cache WHERE key = 0 AND value < ?1)",
+        params![now() - CACHE_TTL],
+    )?;
+    Ok(())
+}
+
 pub fn read(conn: &Connection, index: i64) -> Result<Option<i64>> {
+    read_in(conn, session_id(), index)
+}
+
+pub fn read_in(conn: &Connection, session: &str, index: i64) -> Result<Option<i64>> {
     let result = conn.query_row(
-        "SELECT value FROM cache WHERE key = ?1",
-        params![index],
+        "SELECT value FROM cache WHERE session = ?1 AND key = ?2",
+        params![session, index],
         |row| row.get(0),
     );
 
@@ -90,7 +188,11 @@ pub fn get_next_index(conn: &Connection) -> Result<Option<i64>> {
 }
 
 pub fn clear(conn: &Connection) -> Result<()> {
-    conn.execute("DELETE FROM cache", [])?;
+    clear_in(conn, session_id())
+}
+
+pub fn clear_in(conn: &Connection, session: &str) -> Result<()> {
+    conn.execute("DELETE FROM cache WHERE session = ?1", params![session])?;
     Ok(())
 }
 
@@ -99,11 +201,32 @@ mod tests {
     use super::*;
     use crate::tests::get_test_conn;
 
+    #[test]
+    fn test_resolve_session() {
+        let no_tty = || None;
+        let tty = || Some("tty:34817".to_string());
+        let parent = || "ppid:42".to_string();
+        assert_eq!(resolve_session(Some("agent-1".to_string()), tty, parent), "agent-1");
+        assert_eq!(resolve_session(Some(String::new()), tty, parent), "tty:34817");
+        assert_eq!(resolve_session(None, tty, parent), "tty:34817");
+        assert_eq!(resolve_session(None, no_tty, parent), "ppid:42");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_tty_from_stat() {
+        let stat = "4242 (ctm (dev) x) S 4200 4242 4200 34817 4242 4194304 95 0 0 0";
+        assert_eq!(tty_from_stat(stat), Some("tty:34817".to_string()));
+        let detached = "4242 (ctm) S 1 4242 4242 0 -1 4194304 95 0 0 0";
+        assert_eq!(tty_from_stat(detached), None);
+        assert_eq!(tty_from_stat("garbage"), None);
+    }
+
     #[test]
     fn test_cache() {
         let kv = vec![(1, 3), (2, 9), (3, 8)];
         let (conn, _temp_file) = get_test_conn();
-        store_kv(&conn, kv).unwrap();
+        store_kv(&conn, session_id(), kv).unwrap();
 
         let val = read(&conn, 1).expect("Error reading key value");
         assert_eq!(val, Some(3));
@@ -129,11 +252,7 @@ mod tests {
     #[test]
     fn test_validate_cache_fresh() {
         let (conn, _temp_file) = get_test_conn();
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards")
-            .as_secs() as i64;
-        store_kv(&conn, vec![(0, current_time)]).expect("Failed to store timestamp");
+        store_kv(&conn, session_id(), vec![(0, now())]).expect("Failed to store timestamp");
         let valid = validate_cache(&conn).expect("Failed to validate cache");
         assert!(valid);
     }
@@ -144,12 +263,8 @@ mod tests {
         let (conn, _temp_file) = get_test_conn();
 
         // Create timestamp from more than an hour ago
-        let expired_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards")
-            .as_secs() as i64
-            - 3601; // 1 hour + 1 second
-        store_kv(&conn, vec![(0, expired_time)]).expect("Failed to store timestamp");
+        let expired_time = now() - 3601; // 1 hour + 1 second
+        store_kv(&conn, session_id(), vec![(0, expired_time)]).expect("Failed to store timestamp");
         let valid = validate_cache(&conn).expect("Failed to validate cache");
         assert!(!valid);
     }
@@ -204,4 +319,34 @@ mod tests {
         store_with_next(&conn, &items).expect("Failed to store items in cache");
         assert_eq!(get_next_index(&conn).unwrap(), Some(3));
     }
+
+    #[test]
+    fn test_sessions_are_isolated() {
+        let (conn, _temp_file) = get_test_conn();
+        let mut first = Item::new("task".to_string(), "work".to_string(), "a".to_string());
+        first.id = Some(10);
+        let mut second = first.clone();
+        second.id = Some(20);
+
+        store_in(&conn, "agent", &[first]).unwrap();
+        store_in(&conn, "human", &[second]).unwrap();
+        assert_eq!(read_in(&conn, "agent", 1).unwrap(), Some(10));
+        assert_eq!(read_in(&conn, "human", 1).unwrap(), Some(20));
+
+        clear_in(&conn, "human").unwrap();
+        assert!(!validate_cache_in(&conn, "human").unwrap());
+        assert!(validate_cache_in(&conn, "agent").unwrap());
+    }
+
+    #[test]
+    fn test_stale_sessions_are_collected() {
+        let (conn, _temp_file) = get_test_conn();
+        store_kv(&conn, "old", vec![(0, now() - CACHE_TTL - 1), (1, 42)]).unwrap();
+        store_kv(&conn, "recent", vec![(0, now() - 60), (1, 43)]).unwrap();
+
+        store_in(&conn, "current", &[]).unwrap();
+        assert_eq!(read_in(&conn, "old", 1).unwrap(), None);
+        assert_eq!(read_in(&conn, "recent", 1).unwrap(), Some(43));
+        assert!(validate_cache_in(&conn, "current").unwrap());
+    }
 }
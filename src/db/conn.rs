@@ -5,7 +5,7 @@ use crate::config::get_data_path;
 
 // Going forward, all schema changes require toggling
 // this DB_VERSION to a higher number.
-const SCHEMA_VERSION: i32 = 5;
+const SCHEMA_VERSION: i32 = 18;
 
 pub fn init_table(conn: &Connection) -> Result<(), rusqlite::Error> {
     let current_version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
@@ -42,7 +42,11 @@ pub fn init_table(conn: &Connection) -> Result<(), rusqlite::Error> {
             namespace_id INTEGER REFERENCES namespaces(id),
             priority INTEGER DEFAULT 1,
             estimate_minutes INTEGER,
-            github_issue TEXT
+            github_issue TEXT,
+            parent_id INTEGER REFERENCES items(id) ON DELETE SET NULL,
+            recur_until INTEGER,
+            recur_count INTEGER,
+            deleted_at INTEGER
         )",
         [],
     )?;
@@ -70,11 +74,19 @@ pub fn init_table(conn: &Connection) -> Result<(), rusqlite::Error> {
         [],
     )?;
 
-    // Create cache table for list commands
+    // Migrate from version 7 to 8 - the cache became per session,
+    // its content is disposable so the old table is simply recreated.
+    if current_version < 8 && current_version > 0 {
+        conn.execute("DROP TABLE IF EXISTS cache", [])?;
+    }
+
+    // Create cache table for list commands, one index space per session
     conn.execute(
         "CREATE TABLE IF NOT EXISTS cache (
-            key INTEGER PRIMARY KEY,
-            value INTEGER NOT NULL
+            session TEXT NOT NULL,
+            key INTEGER NOT NULL,
+            value INTEGER NOT NULL,
+            PRIMARY KEY (session, key)
         )",
         [],
     )?;
@@ -85,6 +97,7 @@ pub fn init_table(conn: &Connection) -> Result<(), rusqlite::Error> {
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             name TEXT NOT NULL UNIQUE,
             display_name TEXT,
+            time_zone TEXT,
             created_at INTEGER NOT NULL,
             created_by INTEGER REFERENCES users(id)
         )",
@@ -97,6 +110,7 @@ pub fn init_table(conn: &Connection) -> Result<(), rusqlite::Error> {
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             name TEXT NOT NULL UNIQUE,
             description TEXT,
+            time_zone TEXT,
             created_at INTEGER NOT NULL,
             created_by INTEGER REFERENCES users(id)
         )",
@@ -141,11 +155,92 @@ pub fn init_table(conn: &Connection) -> Result<(), rusqlite::Error> {
         [],
     )?;
 
+    // Task dependencies: item_id cannot start before depends_on_id is closed
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS item_dependencies (
+            item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+            depends_on_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+            created_at INTEGER NOT NULL,
+            created_by INTEGER REFERENCES users(id),
+            PRIMARY KEY (item_id, depends_on_id)
+        )",
+        [],
+    )?;
+
+    // Time tracking: work spans per user, ended_at is NULL while a timer runs
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS time_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+            user_id INTEGER NOT NULL REFERENCES users(id),
+            started_at INTEGER NOT NULL,
+            ended_at INTEGER
+        )",
+        [],
+    )?;
+
+    // Free-form tags, many-to-many with items
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS item_tags (
+            item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+            tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+            PRIMARY KEY (item_id, tag_id)
+        )",
+        [],
+    )?;
+
+    // Saved list invocations, personal to a user or shared within a namespace
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS views (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            args TEXT NOT NULL,
+            user_id INTEGER REFERENCES users(id) ON DELETE CASCADE,
+            namespace_id INTEGER REFERENCES namespaces(id) ON DELETE CASCADE,
+            created_by INTEGER NOT NULL REFERENCES users(id),
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    // Journal of the commands each user can undo, with snapshots of what they changed
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS journal (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            namespace_id INTEGER REFERENCES namespaces(id),
+            command TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            undone_at INTEGER
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS journal_changes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            journal_id INTEGER NOT NULL REFERENCES journal(id) ON DELETE CASCADE,
+            kind TEXT NOT NULL CHECK(kind IN ('item', 'tag', 'note', 'link', 'dependency', 'time_entry')),
+            item_id INTEGER NOT NULL,
+            before TEXT,
+            after TEXT
+        )",
+        [],
+    )?;
+
     // Audit log
     conn.execute(
         "CREATE TABLE IF NOT EXISTS audit_log (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             item_id INTEGER REFERENCES items(id) ON DELETE SET NULL,
+            namespace_id INTEGER REFERENCES namespaces(id),
             table_name TEXT NOT NULL,
             action TEXT NOT NULL CHECK(action IN ('create', 'update', 'delete', 'complete')),
             field_name TEXT,
@@ -185,6 +280,42 @@ pub fn init_table(conn: &Connection) -> Result<(), rusqlite::Error> {
         conn.execute("ALTER TABLE items ADD COLUMN github_issue TEXT", [])?;
     }
 
+    // Migrate from version 5 to 6 - scope audit entries to a namespace.
+    // audit_log only exists from v5 onwards, older databases get it created above.
+    if current_version == 5 {
+        conn.execute("ALTER TABLE audit_log ADD COLUMN namespace_id INTEGER REFERENCES namespaces(id)", [])?;
+        conn.execute(
+            "UPDATE audit_log SET namespace_id = (SELECT namespace_id FROM items WHERE items.id = audit_log.item_id)",
+            [],
+        )?;
+    }
+
+    // Migrate from version 9 to 10 - add parent_id column for subtasks
+    if current_version < 10 && current_version > 0 {
+        conn.execute(
+            "ALTER TABLE items ADD COLUMN parent_id INTEGER REFERENCES items(id) ON DELETE SET NULL",
+            [],
+        )?;
+    }
+
+    // Migrate from version 11 to 12 - add end date and occurrence count of recurring tasks
+    if current_version < 12 && current_version > 0 {
+        conn.execute("ALTER TABLE items ADD COLUMN recur_until INTEGER", [])?;
+        conn.execute("ALTER TABLE items ADD COLUMN recur_count INTEGER", [])?;
+    }
+
+    // Migrate from version 12 to 13 - add time zones of users and namespaces,
+    // before version 5 both tables are created above with the column
+    if (5..13).contains(&current_version) {
+        conn.execute("ALTER TABLE users ADD COLUMN time_zone TEXT", [])?;
+        conn.execute("ALTER TABLE namespaces ADD COLUMN time_zone TEXT", [])?;
+    }
+
+    // Migrate from version 17 to 18 - deleted items stay in the trash until purged
+    if current_version < 18 && current_version > 0 {
+        conn.execute("ALTER TABLE items ADD COLUMN deleted_at INTEGER", [])?;
+    }
+
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_recurring_task_id_good_until ON items(recurring_task_id, good_until)",
         [],
@@ -195,20 +326,127 @@ pub fn init_table(conn: &Connection) -> Result<(), rusqlite::Error> {
     conn.execute("CREATE INDEX IF NOT EXISTS idx_assignee_id ON items(assignee_id)", [])?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_namespace_id ON items(namespace_id)", [])?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_priority ON items(priority)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_parent_id ON items(parent_id)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_deleted_at ON items(deleted_at)", [])?;
 
     // Indexes for task_links and task_notes
     conn.execute("CREATE INDEX IF NOT EXISTS idx_task_links_item_id ON task_links(item_id)", [])?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_task_notes_item_id ON task_notes(item_id)", [])?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_audit_log_item_id ON audit_log(item_id)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_time_entries_item_id ON time_entries(item_id)", [])?;
+    // At most one running timer per user
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_time_entries_running ON time_entries(user_id) WHERE ended_at IS NULL",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_item_tags_tag_id ON item_tags(tag_id)", [])?;
+    // View names are unique per user and per namespace
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_views_user_name ON views(user_id, name) WHERE user_id IS NOT NULL",
+        [],
+    )?;
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_views_namespace_name ON views(namespace_id, name) WHERE namespace_id IS NOT NULL",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_item_dependencies_depends_on_id ON item_dependencies(depends_on_id)",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_journal_user_id ON journal(user_id)", [])?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_journal_changes_journal_id ON journal_changes(journal_id)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_audit_log_namespace_created_at ON audit_log(namespace_id, created_at)",
+        [],
+    )?;
+
+    create_search_index(conn)?;
 
     // Auto-setup default user and namespace on first run or upgrade to v5
     setup_default_user_and_namespace(conn, current_version)?;
 
+    // Migrate from version 6 to 7 - records used to be created without a namespace,
+    // move them to the default namespace now that every read path is scoped.
+    if current_version < 7 && current_version > 0 {
+        conn.execute(
+            "UPDATE items SET namespace_id = (SELECT id FROM namespaces WHERE name = 'default')
+             WHERE namespace_id IS NULL",
+            [],
+        )?;
+    }
+
+    // Migrate from version 10 to 11 - index existing content for full-text search
+    if current_version < 11 && current_version > 0 {
+        rebuild_search_index(conn)?;
+    }
+
     conn.execute(&format!("PRAGMA user_version = {SCHEMA_VERSION}"), [])?;
 
     Ok(())
 }
 
+/// Full-text index over item content, notes and link titles.
+/// Each indexed text gets a rowid derived from its source row, `id * 3 + kind`
+/// with kind 0 for item content, 1 for notes and 2 for link titles,
+/// so that triggers can update it without scanning the index.
+fn create_search_index(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+            body,
+            field UNINDEXED,
+            item_id UNINDEXED,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS items_search_insert AFTER INSERT ON items BEGIN
+            INSERT INTO search_index (rowid, body, field, item_id)
+            VALUES (new.id * 3, new.content, 'content', new.id);
+        END;
+        CREATE TRIGGER IF NOT EXISTS items_search_update AFTER UPDATE OF content ON items
+        WHEN old.content IS NOT new.content BEGIN
+            DELETE FROM search_index WHERE rowid = old.id * 3;
+            INSERT INTO search_index (rowid, body, field, item_id)
+            VALUES (new.id * 3, new.content, 'content', new.id);
+        END;
+        CREATE TRIGGER IF NOT EXISTS items_search_delete AFTER DELETE ON items BEGIN
+            DELETE FROM search_index WHERE rowid = old.id * 3;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS task_notes_search_insert AFTER INSERT ON task_notes BEGIN
+            INSERT INTO search_index (rowid, body, field, item_id)
+            VALUES (new.id * 3 + 1, new.content, 'note', new.item_id);
+        END;
+        CREATE TRIGGER IF NOT EXISTS task_notes_search_delete AFTER DELETE ON task_notes BEGIN
+            DELETE FROM search_index WHERE rowid = old.id * 3 + 1;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS task_links_search_insert AFTER INSERT ON task_links
+        WHEN new.title IS NOT NULL BEGIN
+            INSERT INTO search_index (rowid, body, field, item_id)
+            VALUES (new.id * 3 + 2, new.title, 'link', new.item_id);
+        END;
+        CREATE TRIGGER IF NOT EXISTS task_links_search_delete AFTER DELETE ON task_links BEGIN
+            DELETE FROM search_index WHERE rowid = old.id * 3 + 2;
+        END;",
+    )
+}
+
+/// Re-indexes every item, note and link title from scratch.
+fn rebuild_search_index(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "DELETE FROM search_index;
+        INSERT INTO search_index (rowid, body, field, item_id)
+            SELECT id * 3, content, 'content', id FROM items;
+        INSERT INTO search_index (rowid, body, field, item_id)
+            SELECT id * 3 + 1, content, 'note', item_id FROM task_notes;
+        INSERT INTO search_index (rowid, body, field, item_id)
+            SELECT id * 3 + 2, title, 'link', item_id FROM task_links WHERE title IS NOT NULL;",
+    )
+}
+
 /// Creates default user (from system $USER) and namespace on first run or v5 upgrade.
 /// Migrates existing items to the default user/namespace.
 fn setup_default_user_and_namespace(conn: &Connection, from_version: i32) -> Result<(), rusqlite::Error> {
@@ -324,6 +562,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_migrate_global_cache_to_sessions() {
+        let (conn, _temp_file) = get_test_conn();
+        conn.execute("DROP TABLE cache", []).unwrap();
+        conn.execute(
+            "CREATE TABLE cache (key INTEGER PRIMARY KEY, value INTEGER NOT NULL)",
+            [],
+        )
+        .unwrap();
+        conn.execute("INSERT INTO cache (key, value) VALUES (1, 42)", []).unwrap();
+        // Columns added after v7 are migrated again
+        conn.execute("DROP INDEX idx_parent_id", []).unwrap();
+        conn.execute("ALTER TABLE items DROP COLUMN parent_id", []).unwrap();
+        conn.execute("ALTER TABLE items DROP COLUMN recur_until", []).unwrap();
+        conn.execute("ALTER TABLE items DROP COLUMN recur_count", []).unwrap();
+        conn.execute("ALTER TABLE users DROP COLUMN time_zone", []).unwrap();
+        conn.execute("ALTER TABLE namespaces DROP COLUMN time_zone", []).unwrap();
+        conn.execute("DROP INDEX idx_deleted_at", []).unwrap();
+        conn.execute("ALTER TABLE items DROP COLUMN deleted_at", []).unwrap();
+        conn.execute("PRAGMA user_version = 7", []).unwrap();
+
+        init_table(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO cache (session, key, value) VALUES ('s', 1, 42)",
+            [],
+        )
+        .unwrap();
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM cache", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    fn test_migrate_builds_search_index() {
+        let (conn, _temp_file) = get_test_conn();
+        let id = crate::tests::insert_task(&conn, "work", "migrate the invoices", "today");
+        crate::db::note::add_note(&conn, id, "check the ledger", None).unwrap();
+        conn.execute("DELETE FROM search_index", []).unwrap();
+        conn.execute("ALTER TABLE items DROP COLUMN recur_until", []).unwrap();
+        conn.execute("ALTER TABLE items DROP COLUMN recur_count", []).unwrap();
+        conn.execute("ALTER TABLE users DROP COLUMN time_zone", []).unwrap();
+        conn.execute("ALTER TABLE namespaces DROP COLUMN time_zone", []).unwrap();
+        conn.execute("DROP INDEX idx_deleted_at", []).unwrap();
+        conn.execute("ALTER TABLE items DROP COLUMN deleted_at", []).unwrap();
+        conn.execute("PRAGMA user_version = 10", []).unwrap();
+
+        init_table(&conn).unwrap();
+        for query in ["invoices", "ledger"] {
+            let hits = crate::db::search::search(&conn, query, None, 10).unwrap();
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].item.id, Some(id));
+        }
+    }
+
     #[test]
     fn test_init_table_version_logic() {
         let (conn, _temp_file) = get_test_conn();
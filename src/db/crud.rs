@@ -1,27 +1,38 @@
-use std::time::{
-    SystemTime,
-    UNIX_EPOCH,
+use std::{
+    collections::HashMap,
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
 };
 
 use rusqlite::{
     params,
     params_from_iter,
+    types::Value,
     Connection,
     Result,
 };
 
-use crate::db::item::{
-    Item,
-    ItemQuery,
-    Offset,
+use crate::db::{
+    audit,
+    dependency,
+    journal,
+    item::{
+        Item,
+        ItemQuery,
+        Offset,
+    },
+    tag,
 };
 
-const VALID_ORDER_COLUMNS: &[&str] = &["id", "create_time", "target_time"];
+const VALID_ORDER_COLUMNS: &[&str] = &["id", "create_time", "target_time", "deleted_at"];
 
-pub fn insert_item(conn: &Connection, item: &Item) -> Result<i64> {
+/// Inserts an item and records its creation in the audit log on behalf of `actor`.
+pub fn insert_item(conn: &Connection, item: &Item, actor: Option<i64>) -> Result<i64> {
     conn.execute(
-        "INSERT INTO items (action, category, content, create_time, target_time, cron_schedule, human_schedule, recurring_task_id, good_until, reminder_days, project, owner_id, assignee_id, namespace_id, priority, estimate_minutes, github_issue)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        "INSERT INTO items (action, category, content, create_time, target_time, cron_schedule, human_schedule, recurring_task_id, good_until, reminder_days, project, owner_id, assignee_id, namespace_id, priority, estimate_minutes, github_issue, parent_id, recur_until, recur_count, status)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
         params![
             item.action,
             item.category,
@@ -39,14 +50,34 @@ pub fn insert_item(conn: &Connection, item: &Item) -> Result<i64> {
             item.namespace_id,
             item.priority,
             item.estimate_minutes,
-            item.github_issue
+            item.github_issue,
+            item.parent_id,
+            item.recur_until,
+            item.recur_count,
+            item.status
         ],
     )?;
 
-    Ok(conn.last_insert_rowid())
+    let item_id = conn.last_insert_rowid();
+    audit::log_create(conn, item_id, item, actor)?;
+    journal::record_item(conn, item_id, None)?;
+    Ok(item_id)
+}
+
+/// Updates an item, recording every changed field in the audit log.
+pub fn update_item(conn: &Connection, item: &Item, actor: Option<i64>) -> Result<()> {
+    save_item(conn, item, audit::AUDIT_UPDATE, actor)
 }
 
-pub fn update_item(conn: &Connection, item: &Item) -> Result<()> {
+/// Same as `update_item` but the changes are audited as a completion.
+pub fn complete_item(conn: &Connection, item: &Item, actor: Option<i64>) -> Result<()> {
+    save_item(conn, item, audit::AUDIT_COMPLETE, actor)
+}
+
+fn save_item(conn: &Connection, item: &Item, audit_action: &str, actor: Option<i64>) -> Result<()> {
+    let old_item = get_item(conn, item.id.unwrap_or_default())?;
+    let before = journal::before(conn, item.id.unwrap_or_default())?;
+
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
@@ -70,8 +101,11 @@ pub fn update_item(conn: &Connection, item: &Item) -> Result<()> {
             namespace_id = ?14,
             priority = ?15,
             estimate_minutes = ?16,
-            github_issue = ?17
-        WHERE id = ?18",
+            github_issue = ?17,
+            parent_id = ?18,
+            recur_until = ?19,
+            recur_count = ?20
+        WHERE id = ?21",
         params![
             item.category,
             item.content,
@@ -90,16 +124,22 @@ pub fn update_item(conn: &Connection, item: &Item) -> Result<()> {
             item.priority,
             item.estimate_minutes,
             item.github_issue,
+            item.parent_id,
+            item.recur_until,
+            item.recur_count,
             item.id
         ],
     )?;
 
+    audit::log_update(conn, &old_item, item, audit_action, actor)?;
+    journal::record_item(conn, item.id.unwrap_or_default(), before)?;
     Ok(())
 }
 
+/// Gets an item, items in the trash don't exist for it.
 pub fn get_item(conn: &Connection, item_id: i64) -> Result<Item> {
     let item = conn.query_row(
-        "SELECT * FROM items WHERE id = ?1",
+        "SELECT * FROM items WHERE id = ?1 AND deleted_at IS NULL",
         params![item_id],
         Item::from_row,
     )?;
@@ -107,8 +147,73 @@ pub fn get_item(conn: &Connection, item_id: i64) -> Result<Item> {
     Ok(item)
 }
 
-pub fn delete_item(conn: &Connection, item_id: i64) -> Result<()> {
+/// Gets an item whether or not it is in the trash.
+pub fn get_stored_item(conn: &Connection, item_id: i64) -> Result<Item> {
+    conn.query_row("SELECT * FROM items WHERE id = ?1", params![item_id], Item::from_row)
+}
+
+/// Gets the direct subtasks of a task, oldest first.
+pub fn get_subtasks(conn: &Connection, parent_id: i64) -> Result<Vec<Item>> {
+    query_items(conn, &ItemQuery::new().with_parent_id(parent_id))
+}
+
+/// Sets the runtime `subtask_progress` of every item that has subtasks.
+/// Any closed status (done, cancelled, duplicate, removed) counts as progress.
+pub fn mark_subtask_progress(conn: &Connection, items: &mut [Item]) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT parent_id, SUM(CASE WHEN status IN (1, 2, 3, 5) THEN 1 ELSE 0 END), COUNT(*)
+         FROM items WHERE parent_id IS NOT NULL AND deleted_at IS NULL GROUP BY parent_id",
+    )?;
+    let progress = stmt
+        .query_map([], |row| {
+            let closed: i64 = row.get(1)?;
+            let total: i64 = row.get(2)?;
+            Ok((row.get::<_, i64>(0)?, (closed as usize, total as usize)))
+        })?
+        .collect::<Result<HashMap<_, _>>>()?;
+
+    for item in items.iter_mut() {
+        item.subtask_progress = item.id.and_then(|id| progress.get(&id).copied());
+    }
+    Ok(())
+}
+
+/// Moves an item to the trash, where it is hidden from every query until
+/// restored or purged. Its notes, links and tags are kept.
+pub fn trash_item(conn: &Connection, item_id: i64, actor: Option<i64>) -> Result<()> {
+    let item = get_item(conn, item_id)?;
+    let before = journal::before(conn, item_id)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    audit::log_delete(conn, &item, actor)?;
+    conn.execute("UPDATE items SET deleted_at = ?1 WHERE id = ?2", params![now, item_id])?;
+    journal::record_item(conn, item_id, before)?;
+
+    Ok(())
+}
+
+/// Takes an item out of the trash.
+pub fn restore_item(conn: &Connection, item_id: i64, actor: Option<i64>) -> Result<()> {
+    let old_item = get_stored_item(conn, item_id)?;
+    let before = journal::before(conn, item_id)?;
+    conn.execute("UPDATE items SET deleted_at = NULL WHERE id = ?1", params![item_id])?;
+    audit::log_update(conn, &old_item, &get_item(conn, item_id)?, audit::AUDIT_UPDATE, actor)?;
+    journal::record_item(conn, item_id, before)?;
+
+    Ok(())
+}
+
+/// Deletes an item for good, with its notes, links and tags,
+/// keeping a trace of it in the audit log.
+pub fn delete_item(conn: &Connection, item_id: i64, actor: Option<i64>) -> Result<()> {
+    let item = get_stored_item(conn, item_id)?;
+    let deletion = journal::before_delete(conn, item_id)?;
+    audit::log_delete(conn, &item, actor)?;
     conn.execute("DELETE FROM items WHERE id = ?1", params![item_id])?;
+    journal::record_delete(conn, item_id, deletion)?;
 
     Ok(())
 }
@@ -118,30 +223,26 @@ pub fn query_items(
     item_query: &ItemQuery,
 ) -> Result<Vec<Item>, rusqlite::Error> {
     let mut conditions: Vec<String> = Vec::new();
-    let mut params: Vec<String> = Vec::new();
+    let mut params: Vec<Value> = Vec::new();
 
     if let Some(actions) = &item_query.actions {
-        if actions.len() == 1 {
-            conditions.push("action = ?".to_string());
-            params.push(actions[0].to_string());
-        } else {
-            let action_list = actions
-                .iter()
-                .map(|a| format!("'{}'", a))
-                .collect::<Vec<String>>()
-                .join(", ");
-            conditions.push(format!("action IN ({})", action_list));
-        }
+        conditions.push(format!("action IN ({})", placeholders(actions.len())));
+        params.extend(actions.iter().map(|a| Value::from(a.to_string())));
     }
 
     if let Some(c) = item_query.category {
         conditions.push("category = ?".to_string());
-        params.push(c.to_string());
+        params.push(Value::from(c.to_string()));
+    }
+
+    if let Some(p) = item_query.project {
+        conditions.push("project = ?".to_string());
+        params.push(Value::from(p.to_string()));
     }
 
     if let Some(content) = item_query.content_like {
         conditions.push("content LIKE ?".to_string());
-        params.push(format!("%{}%", content));
+        params.push(Value::from(format!("%{}%", content)));
     }
 
     let ct_min = if let Offset::CreateTime(time) = item_query.offset {
@@ -151,7 +252,7 @@ pub fn query_items(
     };
     if let Some(time) = ct_min {
         conditions.push("create_time > ?".to_string());
-        params.push(time.to_string());
+        params.push(Value::from(time));
     }
 
     let tt_min = if let Offset::TargetTime(time) = item_query.offset {
@@ -161,62 +262,88 @@ pub fn query_items(
     };
     if let Some(time) = tt_min {
         conditions.push("target_time > ?".to_string());
-        params.push(time.to_string());
+        params.push(Value::from(time));
     }
 
     if let Some(ct_max) = item_query.create_time_max {
         conditions.push("create_time <= ?".to_string());
-        params.push(ct_max.to_string());
+        params.push(Value::from(ct_max));
     }
 
     if let Some(tt_max) = item_query.target_time_max {
         conditions.push("target_time <= ?".to_string());
-        params.push(tt_max.to_string());
+        params.push(Value::from(tt_max));
     }
 
     if let Some(gu_min) = item_query.good_until_min {
         conditions.push("good_until > ?".to_string());
-        params.push(gu_min.to_string());
+        params.push(Value::from(gu_min));
     }
 
     if let Some(gu_max) = item_query.good_until_max {
         conditions.push("good_until <= ?".to_string());
-        params.push(gu_max.to_string());
+        params.push(Value::from(gu_max));
     }
 
     if let Some(rt_id) = item_query.recurring_task_id {
         conditions.push("recurring_task_id = ?".to_string());
-        params.push(rt_id.to_string());
+        params.push(Value::from(rt_id));
     }
 
     // Multi-tenant filters
     if let Some(assignee_id) = item_query.assignee_id {
         conditions.push("assignee_id = ?".to_string());
-        params.push(assignee_id.to_string());
+        params.push(Value::from(assignee_id));
     }
 
     if let Some(owner_id) = item_query.owner_id {
         conditions.push("owner_id = ?".to_string());
-        params.push(owner_id.to_string());
+        params.push(Value::from(owner_id));
     }
 
-    if let Some(namespace_id) = item_query.namespace_id {
-        conditions.push("namespace_id = ?".to_string());
-        params.push(namespace_id.to_string());
+    if let Some(ns) = &item_query.namespace_ids {
+        conditions.push(format!("namespace_id IN ({})", placeholders(ns.len())));
+        params.extend(ns.iter().map(|&id| Value::from(id)));
     }
 
     if let Some(cc) = &item_query.statuses {
-        let status_list = cc
-            .iter()
-            .map(ToString::to_string)
-            .collect::<Vec<String>>()
-            .join(", ");
-        conditions.push(format!("status IN ({})", status_list));
+        conditions.push(format!("status IN ({})", placeholders(cc.len())));
+        params.extend(cc.iter().map(|&s| Value::from(s)));
+    }
+
+    if let Some(parent_id) = item_query.parent_id {
+        conditions.push("parent_id = ?".to_string());
+        params.push(Value::from(parent_id));
+    }
+
+    if item_query.ready_only {
+        conditions.push(dependency::ready_condition());
+    }
+
+    for tag in &item_query.tags {
+        conditions.push(tag::tag_condition(false));
+        params.push(Value::from(tag.to_string()));
+    }
+
+    for tag in &item_query.excluded_tags {
+        conditions.push(tag::tag_condition(true));
+        params.push(Value::from(tag.to_string()));
+    }
+
+    if let Some(condition) = item_query.condition {
+        conditions.push(format!("({})", condition.sql));
+        params.extend(condition.params.iter().cloned());
+    }
+
+    if item_query.trash {
+        conditions.push("deleted_at IS NOT NULL".to_string());
+    } else {
+        conditions.push("deleted_at IS NULL".to_string());
     }
 
     if let Offset::Id(rowid) = item_query.offset {
         conditions.push("id > ?".to_string());
-        params.push(rowid.to_string());
+        params.push(Value::from(rowid));
     }
 
     let mut querystr = String::from("SELECT * FROM items");
@@ -241,7 +368,7 @@ pub fn query_items(
 
     if let Some(limit) = item_query.limit {
         querystr.push_str(" LIMIT ?");
-        params.push(limit.to_string());
+        params.push(Value::from(limit as i64));
     }
 
     let mut stmt = conn.prepare(&querystr)?;
@@ -256,6 +383,11 @@ pub fn query_items(
     Ok(items)
 }
 
+// "?, ?, ?" for binding a list of `count` values
+pub(crate) fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -271,6 +403,7 @@ mod tests {
             insert_record,
             insert_recurring_record,
             insert_recurring_task,
+            insert_subtask,
             insert_task,
             update_status,
         },
@@ -288,7 +421,7 @@ mod tests {
     fn test_insert_item() {
         let (conn, _temp_file) = get_test_conn();
         let item = get_test_item("task", "work", "meeting");
-        let result = insert_item(&conn, &item);
+        let result = insert_item(&conn, &item, None);
         assert!(
             result.is_ok(),
             "Cannot insert basic item: {:?}",
@@ -300,7 +433,7 @@ mod tests {
     fn test_get_item() {
         let (conn, _temp_file) = get_test_conn();
         let item = get_test_item("task", "work", "meeting");
-        let item_id = insert_item(&conn, &item).unwrap();
+        let item_id = insert_item(&conn, &item, None).unwrap();
         let item_db = get_item(&conn, item_id);
         assert!(
             item_db.is_ok(),
@@ -317,10 +450,10 @@ mod tests {
     fn test_update_item() {
         let (conn, _temp_file) = get_test_conn();
         let item = get_test_item("task", "work", "meeting");
-        let item_id = insert_item(&conn, &item).unwrap();
+        let item_id = insert_item(&conn, &item, None).unwrap();
         let mut item_db = get_item(&conn, item_id).unwrap();
         item_db.status = 1;
-        let result = update_item(&conn, &item_db);
+        let result = update_item(&conn, &item_db, None);
         assert!(result.is_ok(), "Cannot update item: {:?}", result.err());
         let updated_item = get_item(&conn, item_id).unwrap();
         assert_eq!(updated_item.status, 1);
@@ -336,7 +469,7 @@ mod tests {
 
         // Update category
         recurring_item.category = "meetings".to_string();
-        let result = update_item(&conn, &recurring_item);
+        let result = update_item(&conn, &recurring_item, None);
         assert!(
             result.is_ok(),
             "Cannot update recurring item: {:?}",
@@ -354,16 +487,16 @@ mod tests {
     fn test_delete_item() {
         let (conn, _temp_file) = get_test_conn();
         let item1 = get_test_item("task", "work", "meeting 1");
-        let item1_id = insert_item(&conn, &item1).unwrap();
+        let item1_id = insert_item(&conn, &item1, None).unwrap();
         let item2 = get_test_item("task", "work", "meeting 2");
-        let item2_id = insert_item(&conn, &item2).unwrap();
+        let item2_id = insert_item(&conn, &item2, None).unwrap();
         let item_query = ItemQuery::new().with_action(TASK);
         let items = query_items(&conn, &item_query).unwrap();
         assert_eq!(items.len(), 2);
-        delete_item(&conn, item2_id).expect("Unable to delete item");
+        delete_item(&conn, item2_id, None).expect("Unable to delete item");
         let items = query_items(&conn, &item_query).unwrap();
         assert_eq!(items.len(), 1);
-        delete_item(&conn, item1_id).expect("Unable to delete item");
+        delete_item(&conn, item1_id, None).expect("Unable to delete item");
         let items = query_items(&conn, &item_query).unwrap();
         assert_eq!(items.len(), 0);
     }
@@ -811,4 +944,30 @@ mod tests {
             assert!(record.good_until.unwrap() > 1500);
         }
     }
+
+    #[test]
+    fn test_subtask_progress() {
+        let (conn, _temp_file) = get_test_conn();
+        let parent_id = insert_task(&conn, "work", "release", "today");
+        let other_id = insert_task(&conn, "work", "unrelated", "today");
+        for content in ["changelog", "tag", "announce"] {
+            insert_subtask(&conn, parent_id, content);
+        }
+
+        let subtasks = get_subtasks(&conn, parent_id).unwrap();
+        assert_eq!(subtasks.len(), 3);
+        assert!(subtasks.iter().all(|t| t.parent_id == Some(parent_id)));
+        update_status(&conn, subtasks[0].id.unwrap(), 1);
+        update_status(&conn, subtasks[1].id.unwrap(), 2);
+
+        let mut items = vec![get_item(&conn, parent_id).unwrap(), get_item(&conn, other_id).unwrap()];
+        mark_subtask_progress(&conn, &mut items).unwrap();
+        assert_eq!(items[0].subtask_progress, Some((2, 3)));
+        assert_eq!(items[1].subtask_progress, None);
+
+        // Subtasks are detached, not removed, with their parent
+        delete_item(&conn, parent_id, None).unwrap();
+        let orphan = get_item(&conn, subtasks[2].id.unwrap()).unwrap();
+        assert_eq!(orphan.parent_id, None);
+    }
 }
@@ -0,0 +1,247 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::Connection;
+
+use crate::db::{
+    item::Item,
+    journal::{self, CHANGE_DEPENDENCY},
+};
+
+// Statuses of a dependency that still block its dependents: ongoing, suspended, pending
+const BLOCKING_STATUSES: &str = "0, 4, 6";
+
+/// Records that `item_id` cannot start before `depends_on_id` is closed.
+/// Rejects self references and any edge that would close a cycle.
+pub fn add_dependency(
+    conn: &Connection,
+    item_id: i64,
+    depends_on_id: i64,
+    created_by: Option<i64>,
+) -> Result<(), String> {
+    if item_id == depends_on_id {
+        return Err("A task cannot depend on itself".to_string());
+    }
+
+    if let Some(path) = find_path(conn, depends_on_id, item_id)? {
+        let chain = std::iter::once(item_id)
+            .chain(path)
+            .map(|id| format!("#{}", id))
+            .collect::<Vec<_>>()
+            .join(" → ");
+        return Err(format!("Dependency would create a cycle: {}", chain));
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    let before = journal::before_row(conn, CHANGE_DEPENDENCY, &[item_id, depends_on_id]).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR IGNORE INTO item_dependencies (item_id, depends_on_id, created_at, created_by)
+         VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![item_id, depends_on_id, now, created_by],
+    )
+    .map_err(|e| e.to_string())?;
+    journal::record_row(conn, CHANGE_DEPENDENCY, &[item_id, depends_on_id], before).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Removes a dependency, returns whether it existed.
+pub fn remove_dependency(conn: &Connection, item_id: i64, depends_on_id: i64) -> Result<bool, String> {
+    let before = journal::before_row(conn, CHANGE_DEPENDENCY, &[item_id, depends_on_id]).map_err(|e| e.to_string())?;
+    let removed = conn
+        .execute(
+            "DELETE FROM item_dependencies WHERE item_id = ?1 AND depends_on_id = ?2",
+            [item_id, depends_on_id],
+        )
+        .map_err(|e| e.to_string())?;
+    journal::record_row(conn, CHANGE_DEPENDENCY, &[item_id, depends_on_id], before).map_err(|e| e.to_string())?;
+    Ok(removed > 0)
+}
+
+/// Gets the ids of the items `item_id` depends on.
+pub fn get_dependencies(conn: &Connection, item_id: i64) -> Result<Vec<i64>, String> {
+    query_ids(
+        conn,
+        "SELECT d.depends_on_id FROM item_dependencies d
+         INNER JOIN items i ON i.id = d.depends_on_id
+         WHERE d.item_id = ?1 AND i.deleted_at IS NULL
+         ORDER BY d.depends_on_id",
+        item_id,
+    )
+}
+
+/// Gets the ids of the items waiting on `item_id`.
+pub fn get_dependents(conn: &Connection, item_id: i64) -> Result<Vec<i64>, String> {
+    query_ids(
+        conn,
+        "SELECT d.item_id FROM item_dependencies d
+         INNER JOIN items i ON i.id = d.item_id
+         WHERE d.depends_on_id = ?1 AND i.deleted_at IS NULL
+         ORDER BY d.item_id",
+        item_id,
+    )
+}
+
+/// Sets the runtime `blocked` flag of every item with an open dependency.
+pub fn mark_blocked(conn: &Connection, items: &mut [Item]) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT DISTINCT d.item_id FROM item_dependencies d
+             INNER JOIN items i ON i.id = d.depends_on_id
+             WHERE i.status IN ({}) AND i.deleted_at IS NULL",
+            BLOCKING_STATUSES
+        ))
+        .map_err(|e| e.to_string())?;
+    let blocked = stmt
+        .query_map([], |row| row.get::<_, i64>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<HashSet<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    for item in items.iter_mut() {
+        item.blocked = item.id.is_some_and(|id| blocked.contains(&id));
+    }
+    Ok(())
+}
+
+/// SQL condition matching items without open dependencies, for use on the `items` table.
+pub fn ready_condition() -> String {
+    format!(
+        "NOT EXISTS (SELECT 1 FROM item_dependencies d
+         INNER JOIN items b ON b.id = d.depends_on_id
+         WHERE d.item_id = items.id AND b.status IN ({}) AND b.deleted_at IS NULL)",
+        BLOCKING_STATUSES
+    )
+}
+
+// Depth first search along depends_on edges, returns the path from `from` to `to`.
+fn find_path(conn: &Connection, from: i64, to: i64) -> Result<Option<Vec<i64>>, String> {
+    let mut parents: HashMap<i64, i64> = HashMap::new();
+    let mut visited: HashSet<i64> = HashSet::from([from]);
+    let mut stack = vec![from];
+
+    while let Some(current) = stack.pop() {
+        if current == to {
+            let mut path = vec![current];
+            let mut node = current;
+            while let Some(&parent) = parents.get(&node) {
+                path.push(parent);
+                node = parent;
+            }
+            path.reverse();
+            return Ok(Some(path));
+        }
+        for next in get_dependencies(conn, current)? {
+            if visited.insert(next) {
+                parents.insert(next, current);
+                stack.push(next);
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn query_ids(conn: &Connection, sql: &str, id: i64) -> Result<Vec<i64>, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map([id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<i64>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::crud::{delete_item, get_item, trash_item},
+        tests::{get_test_conn, insert_task, update_status},
+    };
+
+    #[test]
+    fn test_add_and_remove_dependency() {
+        let (conn, _temp_file) = get_test_conn();
+        let a = insert_task(&conn, "work", "design", "today");
+        let b = insert_task(&conn, "work", "build", "tomorrow");
+
+        add_dependency(&conn, b, a, None).unwrap();
+        // Adding twice is a no-op
+        add_dependency(&conn, b, a, None).unwrap();
+        assert_eq!(get_dependencies(&conn, b).unwrap(), vec![a]);
+        assert_eq!(get_dependents(&conn, a).unwrap(), vec![b]);
+
+        assert!(remove_dependency(&conn, b, a).unwrap());
+        assert!(!remove_dependency(&conn, b, a).unwrap());
+        assert!(get_dependencies(&conn, b).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let (conn, _temp_file) = get_test_conn();
+        let a = insert_task(&conn, "work", "a", "today");
+        let b = insert_task(&conn, "work", "b", "today");
+        let c = insert_task(&conn, "work", "c", "today");
+
+        assert!(add_dependency(&conn, a, a, None).unwrap_err().contains("itself"));
+
+        add_dependency(&conn, b, a, None).unwrap();
+        add_dependency(&conn, c, b, None).unwrap();
+        let err = add_dependency(&conn, a, c, None).unwrap_err();
+        assert_eq!(
+            err,
+            format!("Dependency would create a cycle: #{} → #{} → #{} → #{}", a, c, b, a)
+        );
+
+        // Diamonds are fine
+        add_dependency(&conn, c, a, None).unwrap();
+    }
+
+    #[test]
+    fn test_mark_blocked() {
+        let (conn, _temp_file) = get_test_conn();
+        let a = insert_task(&conn, "work", "a", "today");
+        let b = insert_task(&conn, "work", "b", "today");
+        add_dependency(&conn, b, a, None).unwrap();
+
+        let mut items = vec![get_item(&conn, a).unwrap(), get_item(&conn, b).unwrap()];
+        mark_blocked(&conn, &mut items).unwrap();
+        assert!(!items[0].blocked);
+        assert!(items[1].blocked);
+
+        update_status(&conn, a, 1);
+        mark_blocked(&conn, &mut items).unwrap();
+        assert!(!items[1].blocked);
+    }
+
+    #[test]
+    fn test_dependencies_removed_with_item() {
+        let (conn, _temp_file) = get_test_conn();
+        let a = insert_task(&conn, "work", "a", "today");
+        let b = insert_task(&conn, "work", "b", "today");
+        add_dependency(&conn, b, a, None).unwrap();
+
+        delete_item(&conn, a, None).unwrap();
+        assert!(get_dependencies(&conn, b).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_trashed_dependency_does_not_block() {
+        let (conn, _temp_file) = get_test_conn();
+        let a = insert_task(&conn, "work", "a", "today");
+        let b = insert_task(&conn, "work", "b", "today");
+        add_dependency(&conn, b, a, None).unwrap();
+
+        trash_item(&conn, a, None).unwrap();
+        let mut items = vec![get_item(&conn, b).unwrap()];
+        mark_blocked(&conn, &mut items).unwrap();
+        assert!(!items[0].blocked);
+        assert!(get_dependencies(&conn, b).unwrap().is_empty());
+    }
+}
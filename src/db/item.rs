@@ -3,7 +3,7 @@ use std::time::{
     UNIX_EPOCH,
 };
 
-use rusqlite::Row;
+use rusqlite::{types::Value, Row};
 
 #[derive(Debug, Clone)]
 pub struct Item {
@@ -44,9 +44,24 @@ pub struct Item {
     pub estimate_minutes: Option<i64>,
     // github_issue: linked GitHub issue (e.g., "owner/repo#42")
     pub github_issue: Option<String>,
+    // parent_id: task this item is a subtask of
+    pub parent_id: Option<i64>,
+    // recur_until / recur_count bound a recurring task: no occurrence after
+    // recur_until, at most recur_count completions. Closed once exhausted.
+    pub recur_until: Option<i64>,
+    pub recur_count: Option<i64>,
+    // deleted_at: when the item was moved to the trash, hidden from every query
+    // until restored. Only changed by crud::trash_item / crud::restore_item.
+    pub deleted_at: Option<i64>,
     // Runtime-only field applicable to recurring task, not persisted to db
     // Computed at application layer indicating if a recurring_task is completed.
     pub recurring_interval_complete: bool,
+    // Runtime-only field, set when the task has a dependency that is still open.
+    pub blocked: bool,
+    // Runtime-only field, (closed, total) subtasks when the task has any.
+    pub subtask_progress: Option<(usize, usize)>,
+    // Runtime-only field, names of the tags attached to the item.
+    pub tags: Vec<String>,
 }
 
 pub const TASK: &str = "task";
@@ -54,6 +69,12 @@ pub const RECORD: &str = "record";
 pub const RECURRING_TASK: &str = "recurring_task";
 pub const RECURRING_TASK_RECORD: &str = "recurring_task_record";
 
+// Status of a recurring task record, telling how its iteration was handled.
+// Skipped and snoozed iterations are not completions.
+pub const RECORD_COMPLETED: u8 = 0;
+pub const RECORD_SKIPPED: u8 = 2;
+pub const RECORD_SNOOZED: u8 = 4;
+
 impl Item {
     pub fn new(action: String, category: String, content: String) -> Self {
         let now = SystemTime::now()
@@ -82,7 +103,14 @@ impl Item {
             priority: None,
             estimate_minutes: None,
             github_issue: None,
+            parent_id: None,
+            recur_until: None,
+            recur_count: None,
+            deleted_at: None,
             recurring_interval_complete: false,
+            blocked: false,
+            subtask_progress: None,
+            tags: Vec::new(),
         }
     }
 
@@ -155,7 +183,14 @@ impl Item {
             priority: row.get("priority").ok(),
             estimate_minutes: row.get("estimate_minutes").ok(),
             github_issue: row.get("github_issue").ok(),
+            parent_id: row.get("parent_id").ok(),
+            recur_until: row.get("recur_until").ok(),
+            recur_count: row.get("recur_count").ok(),
+            deleted_at: row.get("deleted_at").ok(),
             recurring_interval_complete: false,
+            blocked: false,
+            subtask_progress: None,
+            tags: Vec::new(),
         })
     }
 }
@@ -165,6 +200,7 @@ impl Item {
 pub struct ItemQuery<'a> {
     pub actions: Option<Vec<&'a str>>,
     pub category: Option<&'a str>,
+    pub project: Option<&'a str>,
     pub content_like: Option<&'a str>,
     pub create_time_min: Option<i64>,
     pub create_time_max: Option<i64>,
@@ -180,7 +216,24 @@ pub struct ItemQuery<'a> {
     // Multi-tenant filters
     pub assignee_id: Option<i64>,
     pub owner_id: Option<i64>,
-    pub namespace_id: Option<i64>,
+    pub namespace_ids: Option<Vec<i64>>,
+    // Only items without open dependencies
+    pub ready_only: bool,
+    pub parent_id: Option<i64>,
+    // Items carrying all of `tags` and none of `excluded_tags`
+    pub tags: Vec<&'a str>,
+    pub excluded_tags: Vec<&'a str>,
+    // Compiled filter expression of the list commands
+    pub condition: Option<&'a Condition>,
+    // Items in the trash instead of every other item
+    pub trash: bool,
+}
+
+/// SQL condition on the `items` table with the values bound to its `?` placeholders, in order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Condition {
+    pub sql: String,
+    pub params: Vec<Value>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -198,6 +251,7 @@ impl<'a> ItemQuery<'a> {
         ItemQuery {
             actions: None,
             category: None,
+            project: None,
             content_like: None,
             create_time_min: None,
             create_time_max: None,
@@ -212,7 +266,13 @@ impl<'a> ItemQuery<'a> {
             order_by: None,
             assignee_id: None,
             owner_id: None,
-            namespace_id: None,
+            namespace_ids: None,
+            ready_only: false,
+            parent_id: None,
+            tags: Vec::new(),
+            excluded_tags: Vec::new(),
+            condition: None,
+            trash: false,
         }
     }
 
@@ -231,6 +291,11 @@ impl<'a> ItemQuery<'a> {
         self
     }
 
+    pub fn with_project(mut self, project: &'a str) -> Self {
+        self.project = Some(project);
+        self
+    }
+
     pub fn with_content_like(mut self, content: &'a str) -> Self {
         self.content_like = Some(content);
         self
@@ -320,7 +385,42 @@ impl<'a> ItemQuery<'a> {
     }
 
     pub fn with_namespace_id(mut self, namespace_id: i64) -> Self {
-        self.namespace_id = Some(namespace_id);
+        self.namespace_ids = Some(vec![namespace_id]);
+        self
+    }
+
+    pub fn with_namespace_ids(mut self, namespace_ids: Vec<i64>) -> Self {
+        self.namespace_ids = Some(namespace_ids);
+        self
+    }
+
+    pub fn with_parent_id(mut self, parent_id: i64) -> Self {
+        self.parent_id = Some(parent_id);
+        self
+    }
+
+    pub fn with_ready_only(mut self) -> Self {
+        self.ready_only = true;
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<&'a str>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn without_tags(mut self, tags: Vec<&'a str>) -> Self {
+        self.excluded_tags = tags;
+        self
+    }
+
+    pub fn with_condition(mut self, condition: &'a Condition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    pub fn with_trash(mut self) -> Self {
+        self.trash = true;
         self
     }
 }
@@ -0,0 +1,700 @@
+use std::{
+    cell::RefCell,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::{
+    params,
+    params_from_iter,
+    types::{Value, ValueRef},
+    Connection,
+    Result,
+};
+
+use crate::db::{audit, crud, tag};
+
+/// Commands kept per user for `ctm undo`, older ones are dropped.
+const JOURNAL_LIMIT: i64 = 50;
+
+pub const CHANGE_ITEM: &str = "item";
+pub const CHANGE_TAG: &str = "tag";
+pub const CHANGE_NOTE: &str = "note";
+pub const CHANGE_LINK: &str = "link";
+pub const CHANGE_DEPENDENCY: &str = "dependency";
+pub const CHANGE_TIME_ENTRY: &str = "time_entry";
+
+// Rows that foreign keys delete along with an item: kind, table, condition on the item id
+// and the columns telling a row apart, for changes made to the rows themselves
+const DEPENDENT_ROWS: &[(&str, &str, &str, &[&str])] = &[
+    (CHANGE_NOTE, "task_notes", "item_id = ?1", &["id"]),
+    (CHANGE_LINK, "task_links", "item_id = ?1", &["id"]),
+    (
+        CHANGE_DEPENDENCY,
+        "item_dependencies",
+        "item_id = ?1 OR depends_on_id = ?1",
+        &["item_id", "depends_on_id"],
+    ),
+    (CHANGE_TIME_ENTRY, "time_entries", "item_id = ?1", &["id"]),
+];
+
+// The command being run, set once at startup. Its journal entry is only
+// created with the first change, so read-only commands leave no trace.
+// Thread local so tests running in parallel don't share it.
+struct Current {
+    user_id: i64,
+    namespace_id: i64,
+    command: String,
+    entry_id: Option<i64>,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Current>> = const { RefCell::new(None) };
+}
+
+/// Starts journaling the changes of `command` made on behalf of a user.
+pub fn begin(user_id: i64, namespace_id: i64, command: &str) {
+    CURRENT.with(|current| {
+        *current.borrow_mut() = Some(Current {
+            user_id,
+            namespace_id,
+            command: command.to_string(),
+            entry_id: None,
+        })
+    });
+}
+
+/// Stops journaling, later changes can't be undone.
+pub fn end() {
+    CURRENT.with(|current| *current.borrow_mut() = None);
+}
+
+fn is_open() -> bool {
+    CURRENT.with(|current| current.borrow().is_some())
+}
+
+/// A journaled command of a user.
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub id: i64,
+    pub command: String,
+    pub namespace_id: Option<i64>,
+    pub created_at: i64,
+    pub changes: usize,
+}
+
+impl JournalEntry {
+    pub fn from_row(row: &rusqlite::Row) -> Result<Self> {
+        Ok(JournalEntry {
+            id: row.get("id")?,
+            command: row.get("command")?,
+            namespace_id: row.get("namespace_id")?,
+            created_at: row.get("created_at")?,
+            changes: row.get::<_, i64>("changes")? as usize,
+        })
+    }
+}
+
+/// A single reversible change of a journaled command. Rows are stored as
+/// JSON snapshots keyed by column, tags by name; `before` is unset for additions
+/// and `after` for removals.
+#[derive(Debug, Clone)]
+struct Change {
+    kind: String,
+    item_id: i64,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+/// An item about to be deleted, with the rows that go with it.
+#[derive(Debug)]
+pub struct Deletion {
+    item: String,
+    rows: Vec<(&'static str, String)>,
+    tags: Vec<String>,
+    subtasks: Vec<(i64, String)>,
+}
+
+/// Takes a snapshot of an item before changing it, `None` when no command is journaled.
+pub fn before(conn: &Connection, item_id: i64) -> Result<Option<String>> {
+    if !is_open() {
+        return Ok(None);
+    }
+    snapshot(conn, item_id)
+}
+
+/// Records how an item changed since `before`, an item that didn't exist before was created.
+pub fn record_item(conn: &Connection, item_id: i64, before: Option<String>) -> Result<()> {
+    if !is_open() {
+        return Ok(());
+    }
+    let after = snapshot(conn, item_id)?;
+    if let (Some(before), Some(after)) = (&before, &after) {
+        if same_row(before, after) {
+            return Ok(());
+        }
+    }
+    record(conn, CHANGE_ITEM, item_id, before, after)
+}
+
+/// Records a tag attached to (`added`) or detached from an item.
+pub fn record_tag(conn: &Connection, item_id: i64, tag: &str, added: bool) -> Result<()> {
+    if !is_open() {
+        return Ok(());
+    }
+    let (before, after) = if added {
+        (None, Some(tag.to_string()))
+    } else {
+        (Some(tag.to_string()), None)
+    };
+    record(conn, CHANGE_TAG, item_id, before, after)
+}
+
+/// Takes snapshots of an item about to be deleted and of everything deleting it
+/// removes or detaches, `None` when no command is journaled.
+pub fn before_delete(conn: &Connection, item_id: i64) -> Result<Option<Deletion>> {
+    if !is_open() {
+        return Ok(None);
+    }
+    let Some(item) = snapshot(conn, item_id)? else {
+        return Ok(None);
+    };
+
+    let mut rows = Vec::new();
+    for (kind, table, condition, _) in DEPENDENT_ROWS {
+        for row in snapshot_rows(conn, table, condition, &[item_id])? {
+            rows.push((*kind, row));
+        }
+    }
+    let mut stmt = conn.prepare(
+        "SELECT t.name FROM item_tags it INNER JOIN tags t ON t.id = it.tag_id WHERE it.item_id = ?1",
+    )?;
+    let tags = stmt.query_map([item_id], |row| row.get(0))?.collect::<Result<Vec<String>>>()?;
+
+    let mut stmt = conn.prepare("SELECT id FROM items WHERE parent_id = ?1")?;
+    let subtask_ids = stmt.query_map([item_id], |row| row.get(0))?.collect::<Result<Vec<i64>>>()?;
+    let mut subtasks = Vec::new();
+    for subtask_id in subtask_ids {
+        if let Some(subtask) = snapshot(conn, subtask_id)? {
+            subtasks.push((subtask_id, subtask));
+        }
+    }
+
+    Ok(Some(Deletion {
+        item,
+        rows,
+        tags,
+        subtasks,
+    }))
+}
+
+/// Records the deletion of an item. The item goes last, so that undoing
+/// puts it back before the rows and subtasks referring to it.
+pub fn record_delete(conn: &Connection, item_id: i64, deletion: Option<Deletion>) -> Result<()> {
+    let Some(deletion) = deletion else {
+        return Ok(());
+    };
+    for (subtask_id, before) in deletion.subtasks {
+        record_item(conn, subtask_id, Some(before))?;
+    }
+    for (kind, row) in deletion.rows {
+        record(conn, kind, item_id, Some(row), None)?;
+    }
+    for tag in deletion.tags {
+        record_tag(conn, item_id, &tag, false)?;
+    }
+    record(conn, CHANGE_ITEM, item_id, Some(deletion.item), None)
+}
+
+/// Takes a snapshot of a note, link, dependency or time entry before changing it,
+/// `key` is the row id, or both item ids of a dependency. `None` when no command is journaled.
+pub fn before_row(conn: &Connection, kind: &str, key: &[i64]) -> Result<Option<String>> {
+    if !is_open() {
+        return Ok(None);
+    }
+    snapshot_row(conn, kind, key)
+}
+
+/// Records how a note, link, dependency or time entry changed since `before_row`,
+/// a row that didn't exist before was added and one that is gone was removed.
+pub fn record_row(conn: &Connection, kind: &str, key: &[i64], before: Option<String>) -> Result<()> {
+    if !is_open() {
+        return Ok(());
+    }
+    let after = snapshot_row(conn, kind, key)?;
+    let item_id = match after.as_deref().or(before.as_deref()) {
+        Some(row) => parse(row)?.get("item_id").and_then(|id| id.as_i64()).unwrap_or_default(),
+        None => return Ok(()),
+    };
+    if before == after {
+        return Ok(());
+    }
+    record(conn, kind, item_id, before, after)
+}
+
+fn record(conn: &Connection, kind: &str, item_id: i64, before: Option<String>, after: Option<String>) -> Result<()> {
+    let entry_id = entry_id(conn)?;
+    conn.execute(
+        "INSERT INTO journal_changes (journal_id, kind, item_id, before, after) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![entry_id, kind, item_id, before, after],
+    )?;
+    Ok(())
+}
+
+// The entry of the current command, created on its first change
+fn entry_id(conn: &Connection) -> Result<i64> {
+    CURRENT.with(|current| {
+        let mut current = current.borrow_mut();
+        let current = current.as_mut().expect("journal is open");
+        if let Some(entry_id) = current.entry_id {
+            return Ok(entry_id);
+        }
+
+        conn.execute(
+            "INSERT INTO journal (user_id, namespace_id, command, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![current.user_id, current.namespace_id, current.command, now()],
+        )?;
+        let entry_id = conn.last_insert_rowid();
+        prune(conn, current.user_id)?;
+        current.entry_id = Some(entry_id);
+        Ok(entry_id)
+    })
+}
+
+fn prune(conn: &Connection, user_id: i64) -> Result<()> {
+    conn.execute(
+        "DELETE FROM journal_changes WHERE journal_id IN (
+            SELECT id FROM journal WHERE user_id = ?1 ORDER BY id DESC LIMIT -1 OFFSET ?2
+        )",
+        params![user_id, JOURNAL_LIMIT],
+    )?;
+    conn.execute(
+        "DELETE FROM journal WHERE id IN (
+            SELECT id FROM journal WHERE user_id = ?1 ORDER BY id DESC LIMIT -1 OFFSET ?2
+        )",
+        params![user_id, JOURNAL_LIMIT],
+    )?;
+    Ok(())
+}
+
+/// Lists the commands of a user that can still be undone, newest first.
+pub fn list_entries(conn: &Connection, user_id: i64, limit: usize) -> Result<Vec<JournalEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT j.id, j.command, j.namespace_id, j.created_at,
+                (SELECT COUNT(*) FROM journal_changes c WHERE c.journal_id = j.id) AS changes
+             FROM journal j
+             WHERE j.user_id = ?1 AND j.undone_at IS NULL
+             ORDER BY j.id DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let entries = stmt
+        .query_map(params![user_id, limit as i64], JournalEntry::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(entries)
+}
+
+/// Reverses the changes of a journaled command in one transaction, newest first,
+/// and returns the ids of the items it touched. Unless `force` is set, an item
+/// changed since by something that wasn't undone is an error and nothing is changed.
+pub fn undo_entry(conn: &Connection, entry: &JournalEntry, actor: i64, force: bool) -> Result<Vec<i64>, String> {
+    let changes = get_changes(conn, entry.id).map_err(|e| e.to_string())?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut item_ids: Vec<i64> = Vec::new();
+    for change in changes.iter().rev() {
+        if change.kind == CHANGE_ITEM && !force {
+            check_unchanged(&tx, change)?;
+        }
+        undo_change(&tx, change, actor).map_err(|e| format!("#{}: {}", change.item_id, e))?;
+        if !item_ids.contains(&change.item_id) {
+            item_ids.push(change.item_id);
+        }
+    }
+    tx.execute("UPDATE journal SET undone_at = ?1 WHERE id = ?2", params![now(), entry.id])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    item_ids.reverse();
+    Ok(item_ids)
+}
+
+/// Ids of the items a journaled command changed, in the order it first changed them.
+pub fn changed_item_ids(conn: &Connection, entry: &JournalEntry) -> Result<Vec<i64>, String> {
+    let mut item_ids: Vec<i64> = Vec::new();
+    for change in get_changes(conn, entry.id).map_err(|e| e.to_string())? {
+        if !item_ids.contains(&change.item_id) {
+            item_ids.push(change.item_id);
+        }
+    }
+    Ok(item_ids)
+}
+
+fn get_changes(conn: &Connection, entry_id: i64) -> Result<Vec<Change>> {
+    let mut stmt = conn.prepare(
+        "SELECT kind, item_id, before, after FROM journal_changes WHERE journal_id = ?1 ORDER BY id",
+    )?;
+    let changes = stmt
+        .query_map([entry_id], |row| {
+            Ok(Change {
+                kind: row.get("kind")?,
+                item_id: row.get("item_id")?,
+                before: row.get("before")?,
+                after: row.get("after")?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(changes)
+}
+
+fn check_unchanged(conn: &Connection, change: &Change) -> Result<(), String> {
+    let current = snapshot(conn, change.item_id).map_err(|e| e.to_string())?;
+    let unchanged = match (&current, &change.after) {
+        (Some(current), Some(after)) => same_row(current, after),
+        (None, None) => true,
+        _ => false,
+    };
+    if unchanged {
+        Ok(())
+    } else {
+        Err(format!(
+            "#{} was changed since, nothing was undone. Add --force to undo anyway",
+            change.item_id
+        ))
+    }
+}
+
+fn undo_change(conn: &Connection, change: &Change, actor: i64) -> Result<(), String> {
+    let actor = Some(actor);
+    match (change.kind.as_str(), &change.before) {
+        (CHANGE_TAG, Some(tag)) => tag::add_tags(conn, change.item_id, std::slice::from_ref(tag)),
+        (CHANGE_TAG, None) => {
+            let tag = change.after.clone().unwrap_or_default();
+            tag::remove_tags(conn, change.item_id, &[tag]).map(|_| ())
+        }
+        // Created by the command
+        (CHANGE_ITEM, None) => match snapshot(conn, change.item_id).map_err(|e| e.to_string())? {
+            Some(_) => crud::delete_item(conn, change.item_id, actor).map_err(|e| e.to_string()),
+            None => Ok(()),
+        },
+        (CHANGE_ITEM, Some(before)) => restore(conn, change.item_id, before, actor).map_err(|e| e.to_string()),
+        // Deleted along with an item or on its own, or changed since
+        (kind, Some(row)) => {
+            let (table, _) = dependent_table(kind).ok_or_else(|| format!("Unknown change '{}'", kind))?;
+            let insert = if change.after.is_some() { "INSERT OR REPLACE" } else { "INSERT OR IGNORE" };
+            insert_row(conn, table, row, insert).map_err(|e| e.to_string())
+        }
+        // Added by the command
+        (kind, None) => match &change.after {
+            Some(row) => {
+                let (table, key) = dependent_table(kind).ok_or_else(|| format!("Unknown change '{}'", kind))?;
+                delete_row(conn, table, key, row).map_err(|e| e.to_string())
+            }
+            None => Ok(()),
+        },
+    }
+}
+
+// Table and key columns of a note, link, dependency or time entry change
+fn dependent_table(kind: &str) -> Option<(&'static str, &'static [&'static str])> {
+    DEPENDENT_ROWS
+        .iter()
+        .find(|(dependent, _, _, _)| *dependent == kind)
+        .map(|(_, table, _, key)| (*table, *key))
+}
+
+fn key_condition(key: &[&str]) -> String {
+    key.iter()
+        .enumerate()
+        .map(|(i, column)| format!("{} = ?{}", column, i + 1))
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+fn snapshot_row(conn: &Connection, kind: &str, key: &[i64]) -> Result<Option<String>> {
+    let (table, columns) = dependent_table(kind).expect("a dependent row kind");
+    Ok(snapshot_rows(conn, table, &key_condition(columns), key)?.pop())
+}
+
+// Deletes the row a snapshot was taken of, found by its key columns
+fn delete_row(conn: &Connection, table: &str, columns: &[&str], snapshot: &str) -> Result<()> {
+    let values = parse(snapshot)?;
+    let params: Vec<Value> = columns
+        .iter()
+        .map(|column| values.get(*column).map(to_sql).unwrap_or(Value::Null))
+        .collect();
+    conn.execute(
+        &format!("DELETE FROM {} WHERE {}", table, key_condition(columns)),
+        params_from_iter(params),
+    )?;
+    Ok(())
+}
+
+// Puts an item back as it was in `snapshot`, recreating it with its id when
+// it was deleted. Columns added after the snapshot was taken keep their value.
+fn restore(conn: &Connection, item_id: i64, snapshot: &str, actor: Option<i64>) -> Result<()> {
+    if self::snapshot(conn, item_id)?.is_none() {
+        insert_row(conn, "items", snapshot, "INSERT")?;
+        audit::log_create(conn, item_id, &crud::get_stored_item(conn, item_id)?, actor)?;
+        return Ok(());
+    }
+
+    let values = parse(snapshot)?;
+    let columns: Vec<String> = columns(conn, "items")?
+        .into_iter()
+        .filter(|column| column != "id" && column != "modify_time" && values.contains_key(column))
+        .collect();
+    let assignments: Vec<String> = columns.iter().map(|column| format!("{} = ?", column)).collect();
+    let mut params: Vec<Value> = columns.iter().map(|column| to_sql(&values[column])).collect();
+    params.push(Value::from(item_id));
+
+    let old_item = crud::get_stored_item(conn, item_id)?;
+    conn.execute(
+        &format!("UPDATE items SET {}, modify_time = {} WHERE id = ?", assignments.join(", "), now()),
+        params_from_iter(params),
+    )?;
+    audit::log_update(conn, &old_item, &crud::get_stored_item(conn, item_id)?, audit::AUDIT_UPDATE, actor)?;
+    Ok(())
+}
+
+// Inserts a row from its snapshot, only the columns the table still has
+fn insert_row(conn: &Connection, table: &str, snapshot: &str, insert: &str) -> Result<()> {
+    let values = parse(snapshot)?;
+    let columns: Vec<String> = columns(conn, table)?
+        .into_iter()
+        .filter(|column| values.contains_key(column))
+        .collect();
+    let params: Vec<Value> = columns.iter().map(|column| to_sql(&values[column])).collect();
+    conn.execute(
+        &format!(
+            "{} INTO {} ({}) VALUES ({})",
+            insert,
+            table,
+            columns.join(", "),
+            vec!["?"; columns.len()].join(", ")
+        ),
+        params_from_iter(params),
+    )?;
+    Ok(())
+}
+
+fn columns(conn: &Connection, table: &str) -> Result<Vec<String>> {
+    let stmt = conn.prepare(&format!("SELECT * FROM {} LIMIT 0", table))?;
+    Ok(stmt.column_names().into_iter().map(String::from).collect())
+}
+
+fn snapshot(conn: &Connection, item_id: i64) -> Result<Option<String>> {
+    Ok(snapshot_rows(conn, "items", "id = ?1", &[item_id])?.pop())
+}
+
+// The rows of a table matching a condition on ids, as JSON objects keyed by column
+fn snapshot_rows(conn: &Connection, table: &str, condition: &str, ids: &[i64]) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {} WHERE {}", table, condition))?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let rows = stmt
+        .query_map(params_from_iter(ids), |row| {
+            let mut values = serde_json::Map::new();
+            for (i, column) in columns.iter().enumerate() {
+                let value = match row.get_ref(i)? {
+                    ValueRef::Integer(n) => serde_json::Value::from(n),
+                    ValueRef::Real(f) => serde_json::Value::from(f),
+                    ValueRef::Text(text) => serde_json::Value::from(String::from_utf8_lossy(text)),
+                    ValueRef::Null | ValueRef::Blob(_) => serde_json::Value::Null,
+                };
+                values.insert(column.clone(), value);
+            }
+            Ok(serde_json::Value::Object(values).to_string())
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+fn parse(snapshot: &str) -> Result<serde_json::Map<String, serde_json::Value>> {
+    serde_json::from_str(snapshot).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn to_sql(value: &serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        _ => Value::Null,
+    }
+}
+
+// Whether two snapshots are of the same row, ignoring when it was last saved
+fn same_row(a: &str, b: &str) -> bool {
+    let without_modify_time = |snapshot: &str| {
+        parse(snapshot).map(|mut values| {
+            values.remove("modify_time");
+            values
+        })
+    };
+    matches!((without_modify_time(a), without_modify_time(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::Context,
+        db::{
+            crud::{get_item, update_item},
+            dependency,
+            note,
+            time_entry,
+        },
+        tests::{get_test_conn, insert_subtask, insert_task},
+    };
+
+    // Journals as the default user, returns its id
+    fn begin_default(conn: &Connection, command: &str) -> i64 {
+        let ctx = Context::default_from_db(conn).unwrap();
+        begin(ctx.current_user_id, ctx.current_namespace_id, command);
+        ctx.current_user_id
+    }
+
+    fn latest(conn: &Connection, user_id: i64) -> JournalEntry {
+        list_entries(conn, user_id, 1).unwrap().remove(0)
+    }
+
+    #[test]
+    fn test_undo_update_and_delete() {
+        let (conn, _temp_file) = get_test_conn();
+        let id = insert_task(&conn, "work", "write docs", "tomorrow");
+
+        let me = begin_default(&conn, "ctm update 1 -c infra +docs");
+        let mut item = get_item(&conn, id).unwrap();
+        item.category = "infra".to_string();
+        update_item(&conn, &item, Some(me)).unwrap();
+        tag::add_tags(&conn, id, &["docs".to_string()]).unwrap();
+        end();
+
+        begin_default(&conn, "ctm delete 1");
+        crud::delete_item(&conn, id, Some(me)).unwrap();
+        end();
+
+        let entries = list_entries(&conn, me, 10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].command, "ctm delete 1");
+        assert_eq!(entries[1].changes, 2);
+
+        // The deleted task comes back with its id, then its old category and tags
+        assert_eq!(undo_entry(&conn, &latest(&conn, me), me, false).unwrap(), vec![id]);
+        assert_eq!(get_item(&conn, id).unwrap().category, "infra");
+        undo_entry(&conn, &latest(&conn, me), me, false).unwrap();
+        assert_eq!(get_item(&conn, id).unwrap().category, "work");
+        assert!(tag::get_tags_for_item(&conn, id).unwrap().is_empty());
+        assert!(list_entries(&conn, me, 10).unwrap().is_empty());
+
+        // Undoing is audited but not journaled
+        let history = audit::get_history_for_item(&conn, id).unwrap();
+        assert_eq!(history.last().unwrap().old_value.as_deref(), Some("infra"));
+    }
+
+    #[test]
+    fn test_undo_delete_restores_related_rows() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let me = ctx.current_user_id;
+        let parent = insert_task(&conn, "work", "release", "tomorrow");
+        let subtask = insert_subtask(&conn, parent, "changelog");
+        let other = insert_task(&conn, "work", "announce", "tomorrow");
+        note::add_note(&conn, parent, "almost there", Some(me)).unwrap();
+        tag::add_tags(&conn, parent, &["ops".to_string()]).unwrap();
+        dependency::add_dependency(&conn, other, parent, Some(me)).unwrap();
+
+        begin_default(&conn, "ctm delete 1");
+        crud::delete_item(&conn, parent, Some(me)).unwrap();
+        end();
+        assert_eq!(get_item(&conn, subtask).unwrap().parent_id, None);
+        assert!(dependency::get_dependencies(&conn, other).unwrap().is_empty());
+
+        undo_entry(&conn, &latest(&conn, me), me, false).unwrap();
+        assert_eq!(get_item(&conn, parent).unwrap().content, "release");
+        assert_eq!(note::get_notes_for_item(&conn, parent).unwrap().len(), 1);
+        assert_eq!(tag::get_tags_for_item(&conn, parent).unwrap(), vec!["ops"]);
+        assert_eq!(dependency::get_dependencies(&conn, other).unwrap(), vec![parent]);
+        assert_eq!(get_item(&conn, subtask).unwrap().parent_id, Some(parent));
+    }
+
+    #[test]
+    fn test_undo_insert() {
+        let (conn, _temp_file) = get_test_conn();
+        let me = begin_default(&conn, "ctm task a");
+        let id = insert_task(&conn, "work", "a", "tomorrow");
+        end();
+        let other = insert_task(&conn, "work", "b", "tomorrow");
+
+        undo_entry(&conn, &latest(&conn, me), me, false).unwrap();
+        assert!(crud::get_item(&conn, id).is_err());
+        assert!(crud::get_item(&conn, other).is_ok());
+    }
+
+    #[test]
+    fn test_undo_refuses_later_changes() {
+        let (conn, _temp_file) = get_test_conn();
+        let id = insert_task(&conn, "work", "a", "tomorrow");
+
+        let me = begin_default(&conn, "ctm update 1 -c infra");
+        let mut item = get_item(&conn, id).unwrap();
+        item.category = "infra".to_string();
+        update_item(&conn, &item, Some(me)).unwrap();
+        end();
+        item.content = "changed elsewhere".to_string();
+        update_item(&conn, &item, None).unwrap();
+
+        let err = undo_entry(&conn, &latest(&conn, me), me, false).unwrap_err();
+        assert!(err.contains("--force"), "{}", err);
+        assert_eq!(get_item(&conn, id).unwrap().category, "infra");
+
+        undo_entry(&conn, &latest(&conn, me), me, true).unwrap();
+        let item = get_item(&conn, id).unwrap();
+        assert_eq!((item.category.as_str(), item.content.as_str()), ("work", "a"));
+    }
+
+    #[test]
+    fn test_unchanged_items_are_not_journaled() {
+        let (conn, _temp_file) = get_test_conn();
+        let id = insert_task(&conn, "work", "a", "tomorrow");
+
+        let me = begin_default(&conn, "ctm update 1");
+        update_item(&conn, &get_item(&conn, id).unwrap(), Some(me)).unwrap();
+        end();
+        assert!(list_entries(&conn, me, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_undo_row_changes() {
+        let (conn, _temp_file) = get_test_conn();
+        let id = insert_task(&conn, "work", "a", "tomorrow");
+        let note_id = note::add_note(&conn, id, "first", None).unwrap();
+        let me = Context::default_from_db(&conn).unwrap().current_user_id;
+        let timer = time_entry::start_timer(&conn, id, me, 1_000).unwrap();
+
+        begin_default(&conn, "ctm stop");
+        time_entry::stop_timer(&conn, timer, 1_600).unwrap();
+        note::delete_note(&conn, note_id).unwrap();
+        end();
+        assert_eq!(latest(&conn, me).changes, 2);
+
+        // The timer runs again and the note is back
+        undo_entry(&conn, &latest(&conn, me), me, false).unwrap();
+        assert_eq!(time_entry::get_running_timer(&conn, me).unwrap().map(|entry| entry.id), Some(timer));
+        let notes = note::get_notes_for_item(&conn, id).unwrap();
+        assert_eq!(notes.iter().map(|note| note.content.as_str()).collect::<Vec<_>>(), vec!["first"]);
+    }
+}
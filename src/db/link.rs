@@ -1,6 +1,8 @@
 use rusqlite::Connection;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::db::journal::{self, CHANGE_LINK};
+
 /// Link types for task associations
 pub const LINK_TYPE_COMMIT: &str = "commit";
 pub const LINK_TYPE_ISSUE: &str = "issue";
@@ -75,6 +77,7 @@ pub fn add_link(
     .map_err(|e| e.to_string())?;
 
     let link_id = conn.last_insert_rowid();
+    journal::record_row(conn, CHANGE_LINK, &[link_id], None).map_err(|e| e.to_string())?;
     Ok(link_id)
 }
 
@@ -124,6 +127,7 @@ pub fn get_links_by_type(
 
 /// Deletes a specific link by ID.
 pub fn delete_link(conn: &Connection, link_id: i64) -> Result<(), String> {
+    let before = journal::before_row(conn, CHANGE_LINK, &[link_id]).map_err(|e| e.to_string())?;
     let deleted = conn
         .execute("DELETE FROM task_links WHERE id = ?1", [link_id])
         .map_err(|e| e.to_string())?;
@@ -131,6 +135,7 @@ pub fn delete_link(conn: &Connection, link_id: i64) -> Result<(), String> {
     if deleted == 0 {
         return Err(format!("Link {} not found", link_id));
     }
+    journal::record_row(conn, CHANGE_LINK, &[link_id], before).map_err(|e| e.to_string())?;
 
     Ok(())
 }
@@ -1,8 +1,16 @@
+pub mod archive;
+pub mod audit;
 pub mod cache;
 pub mod conn;
 pub mod crud;
+pub mod dependency;
 pub mod item;
+pub mod journal;
 pub mod link;
 pub mod namespace;
 pub mod note;
+pub mod search;
+pub mod tag;
+pub mod time_entry;
 pub mod user;
+pub mod view;
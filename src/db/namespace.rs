@@ -160,6 +160,22 @@ pub fn delete_namespace(conn: &Connection, name: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Retrieves the time zone name of a namespace, None when it is not set.
+pub fn get_namespace_time_zone(conn: &Connection, id: i64) -> Result<Option<String>, String> {
+    conn.query_row("SELECT time_zone FROM namespaces WHERE id = ?1", [id], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+/// Sets or clears (None) the time zone name of a namespace.
+pub fn set_namespace_time_zone(conn: &Connection, id: i64, time_zone: Option<&str>) -> Result<(), String> {
+    conn.execute(
+        "UPDATE namespaces SET time_zone = ?1 WHERE id = ?2",
+        rusqlite::params![time_zone, id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
 /// Adds a user to a namespace with the specified role.
 pub fn add_user_to_namespace(
     conn: &Connection,
@@ -185,6 +201,13 @@ pub fn add_user_to_namespace(
     let user = crate::db::user::get_user_by_name(conn, user_name)?
         .ok_or_else(|| format!("User '{}' not found", user_name))?;
 
+    if role != "owner" && is_last_owner(conn, user.id, namespace.id)? {
+        return Err(format!(
+            "Cannot make '{}' {} in '{}': they are the only owner. Assign another owner first.",
+            user_name, role, namespace_name
+        ));
+    }
+
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
@@ -217,23 +240,7 @@ pub fn remove_user_from_namespace(
         .ok_or_else(|| format!("User '{}' not found", user_name))?;
 
     // Check this isn't the last owner
-    let owner_count: i64 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM user_namespaces WHERE namespace_id = ?1 AND role = 'owner'",
-            [namespace.id],
-            |row| row.get(0),
-        )
-        .map_err(|e| e.to_string())?;
-
-    let is_owner: bool = conn
-        .query_row(
-            "SELECT role = 'owner' FROM user_namespaces WHERE user_id = ?1 AND namespace_id = ?2",
-            [user.id, namespace.id],
-            |row| row.get(0),
-        )
-        .unwrap_or(false);
-
-    if is_owner && owner_count <= 1 {
+    if is_last_owner(conn, user.id, namespace.id)? {
         return Err(format!(
             "Cannot remove '{}' from '{}': they are the only owner. Assign another owner first.",
             user_name, namespace_name
@@ -257,6 +264,21 @@ pub fn remove_user_from_namespace(
     Ok(())
 }
 
+/// Whether the user is the only owner of the namespace.
+pub fn is_last_owner(conn: &Connection, user_id: i64, namespace_id: i64) -> Result<bool, String> {
+    if get_user_role(conn, user_id, namespace_id)?.as_deref() != Some("owner") {
+        return Ok(false);
+    }
+    let owner_count: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM user_namespaces WHERE namespace_id = ?1 AND role = 'owner'",
+            [namespace_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(owner_count <= 1)
+}
+
 /// Gets the role of a user in a namespace.
 pub fn get_user_role(
     conn: &Connection,
@@ -281,6 +303,21 @@ pub fn get_user_role(
     Ok(role)
 }
 
+/// Ids of the namespaces a user is a member of.
+pub fn list_user_namespace_ids(conn: &Connection, user_id: i64) -> Result<Vec<i64>, String> {
+    let mut stmt = conn
+        .prepare("SELECT namespace_id FROM user_namespaces WHERE user_id = ?1 ORDER BY namespace_id")
+        .map_err(|e| e.to_string())?;
+
+    let ids = stmt
+        .query_map([user_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<i64>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(ids)
+}
+
 /// Lists all members of a namespace.
 pub fn list_namespace_members(
     conn: &Connection,
@@ -1,6 +1,8 @@
 use rusqlite::Connection;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::db::journal::{self, CHANGE_NOTE};
+
 #[derive(Debug, Clone)]
 pub struct TaskNote {
     pub id: i64,
@@ -41,6 +43,7 @@ pub fn add_note(
     .map_err(|e| e.to_string())?;
 
     let note_id = conn.last_insert_rowid();
+    journal::record_row(conn, CHANGE_NOTE, &[note_id], None).map_err(|e| e.to_string())?;
     Ok(note_id)
 }
 
@@ -66,6 +69,7 @@ pub fn get_notes_for_item(conn: &Connection, item_id: i64) -> Result<Vec<TaskNot
 
 /// Deletes a specific note by ID.
 pub fn delete_note(conn: &Connection, note_id: i64) -> Result<(), String> {
+    let before = journal::before_row(conn, CHANGE_NOTE, &[note_id]).map_err(|e| e.to_string())?;
     let deleted = conn
         .execute("DELETE FROM task_notes WHERE id = ?1", [note_id])
         .map_err(|e| e.to_string())?;
@@ -73,6 +77,7 @@ pub fn delete_note(conn: &Connection, note_id: i64) -> Result<(), String> {
     if deleted == 0 {
         return Err(format!("Note {} not found", note_id));
     }
+    journal::record_row(conn, CHANGE_NOTE, &[note_id], before).map_err(|e| e.to_string())?;
 
     Ok(())
 }
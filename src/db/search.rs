@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use rusqlite::{params_from_iter, types::Value, Connection};
+
+use crate::db::{
+    crud::{get_item, placeholders},
+    item::Item,
+};
+
+// Markers around matched terms in snippets
+const MATCH_START: &str = "\x1b[1m";
+const MATCH_END: &str = "\x1b[0m";
+
+// Tokens of context shown around a match
+const SNIPPET_TOKENS: i32 = 12;
+
+/// An item matching a search, with the fields that matched in rank order
+/// and a snippet of the best matching one.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub item: Item,
+    pub fields: Vec<String>,
+    pub snippet: String,
+}
+
+/// Translates user input into an FTS5 query.
+/// Words must all match, `"a b"` matches a phrase, a trailing `*` matches a prefix
+/// and a bare `OR` between terms matches either side.
+/// Everything else is quoted so punctuation such as `#` or `-` is taken literally.
+pub fn to_fts_query(input: &str) -> Result<String, String> {
+    let mut terms: Vec<String> = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let phrase: String = chars.by_ref().take_while(|&c| c != '"').collect();
+            if !phrase.trim().is_empty() {
+                terms.push(quote(phrase.trim()));
+            }
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '"' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            if word == "OR" {
+                terms.push(word);
+            } else if let Some(prefix) = word.strip_suffix('*').filter(|p| !p.is_empty()) {
+                terms.push(format!("{}*", quote(prefix)));
+            } else if word != "*" {
+                terms.push(quote(&word));
+            }
+        }
+    }
+
+    // OR needs a term on both sides
+    while terms.first().is_some_and(|t| t == "OR") {
+        terms.remove(0);
+    }
+    while terms.last().is_some_and(|t| t == "OR") {
+        terms.pop();
+    }
+    terms.dedup_by(|a, b| a == "OR" && b == "OR");
+
+    if terms.is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+    Ok(terms.join(" "))
+}
+
+fn quote(term: &str) -> String {
+    format!("\"{}\"", term.replace('"', "\"\""))
+}
+
+/// Searches item content, notes and link titles, best matches first.
+/// Results are restricted to `namespace_ids` when given.
+pub fn search(
+    conn: &Connection,
+    query: &str,
+    namespace_ids: Option<&[i64]>,
+    limit: usize,
+) -> Result<Vec<SearchHit>, String> {
+    let fts_query = to_fts_query(query)?;
+    let mut params: Vec<Value> = vec![Value::from(fts_query)];
+    let namespace_condition = match namespace_ids {
+        Some(ns) => {
+            params.extend(ns.iter().map(|&id| Value::from(id)));
+            format!("AND items.namespace_id IN ({})", placeholders(ns.len()))
+        }
+        None => String::new(),
+    };
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT search_index.item_id, search_index.field,
+                    snippet(search_index, 0, '{}', '{}', '…', {})
+             FROM search_index
+             INNER JOIN items ON items.id = search_index.item_id
+             WHERE search_index MATCH ? {}
+               AND items.deleted_at IS NULL
+             ORDER BY bm25(search_index)",
+            MATCH_START, MATCH_END, SNIPPET_TOKENS, namespace_condition
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(params_from_iter(params), |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid search query: {}", e))?;
+
+    // Several fields of one item may match, keep the item at its best rank
+    let mut hits: Vec<SearchHit> = Vec::new();
+    let mut positions: HashMap<i64, usize> = HashMap::new();
+    for (item_id, field, snippet) in rows {
+        if let Some(&pos) = positions.get(&item_id) {
+            if !hits[pos].fields.contains(&field) {
+                hits[pos].fields.push(field);
+            }
+            continue;
+        }
+        if hits.len() == limit {
+            continue;
+        }
+        let item = get_item(conn, item_id).map_err(|e| format!("Failed to get item: {:?}", e))?;
+        positions.insert(item_id, hits.len());
+        hits.push(SearchHit {
+            item,
+            fields: vec![field],
+            snippet,
+        });
+    }
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{crud::update_item, link::add_link, namespace::create_namespace, note::add_note},
+        tests::{get_test_conn, insert_record, insert_task},
+    };
+
+    fn plain(snippet: &str) -> String {
+        snippet.replace(MATCH_START, "").replace(MATCH_END, "")
+    }
+
+    #[test]
+    fn test_to_fts_query() {
+        assert_eq!(to_fts_query("deploy api").unwrap(), r#""deploy" "api""#);
+        assert_eq!(to_fts_query(r#""release notes" v2"#).unwrap(), r#""release notes" "v2""#);
+        assert_eq!(to_fts_query("depl*").unwrap(), r#""depl"*"#);
+        assert_eq!(to_fts_query("a OR b").unwrap(), r#""a" OR "b""#);
+        assert_eq!(to_fts_query("OR a OR OR b OR").unwrap(), r#""a" OR "b""#);
+        assert_eq!(to_fts_query("#42 -x").unwrap(), r##""#42" "-x""##);
+        assert_eq!(to_fts_query(r#"say "hi"#).unwrap(), r#""say" "hi""#);
+        assert!(to_fts_query("  ").is_err());
+        assert!(to_fts_query(r#"* "" OR"#).is_err());
+    }
+
+    #[test]
+    fn test_search_fields() {
+        let (conn, _temp_file) = get_test_conn();
+        let deploy = insert_task(&conn, "work", "Deploy the billing service", "today");
+        let review = insert_task(&conn, "work", "Review pull requests", "today");
+        let record = insert_record(&conn, "work", "Met with billing team", "today");
+        add_note(&conn, review, "blocked on the billing migration", None).unwrap();
+        add_link(&conn, deploy, "url", "https://example.com", Some("Runbook for rollout"), None).unwrap();
+
+        let hits = search(&conn, "billing", None, 10).unwrap();
+        let ids: Vec<i64> = hits.iter().map(|h| h.item.id.unwrap()).collect();
+        assert_eq!(ids.len(), 3);
+        assert!(ids.contains(&deploy) && ids.contains(&review) && ids.contains(&record));
+        let review_hit = hits.iter().find(|h| h.item.id == Some(review)).unwrap();
+        assert_eq!(review_hit.fields, vec!["note"]);
+        assert!(plain(&review_hit.snippet).contains("billing migration"));
+
+        let hits = search(&conn, "runbook", None, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].fields, vec!["link"]);
+
+        // Prefix and phrase queries
+        assert_eq!(search(&conn, "roll*", None, 10).unwrap().len(), 1);
+        assert_eq!(search(&conn, r#""billing service""#, None, 10).unwrap().len(), 1);
+        assert_eq!(search(&conn, r#""service billing""#, None, 10).unwrap().len(), 0);
+
+        // Limit counts items, not matched fields
+        assert_eq!(search(&conn, "billing", None, 2).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_search_ranking() {
+        let (conn, _temp_file) = get_test_conn();
+        insert_task(&conn, "work", "Write docs for the cache layer and other things too", "today");
+        let best = insert_task(&conn, "work", "cache cache cache", "today");
+
+        let hits = search(&conn, "cache", None, 10).unwrap();
+        assert_eq!(hits[0].item.id, Some(best));
+    }
+
+    #[test]
+    fn test_search_index_follows_changes() {
+        let (conn, _temp_file) = get_test_conn();
+        let id = insert_task(&conn, "work", "Draft proposal", "today");
+        let note_id = add_note(&conn, id, "ask finance", None).unwrap();
+
+        let mut item = get_item(&conn, id).unwrap();
+        item.content = "Final proposal".to_string();
+        update_item(&conn, &item, None).unwrap();
+        assert!(search(&conn, "draft", None, 10).unwrap().is_empty());
+        assert_eq!(search(&conn, "final", None, 10).unwrap().len(), 1);
+
+        crate::db::note::delete_note(&conn, note_id).unwrap();
+        assert!(search(&conn, "finance", None, 10).unwrap().is_empty());
+
+        crate::db::crud::delete_item(&conn, id, None).unwrap();
+        assert!(search(&conn, "proposal", None, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_namespace() {
+        let (conn, _temp_file) = get_test_conn();
+        let default_id = insert_task(&conn, "work", "Quarterly report", "today");
+        let ctx = crate::context::Context::default_from_db(&conn).unwrap();
+        let other_ns = create_namespace(&conn, "other", None, ctx.current_user_id).unwrap();
+        let mut other = get_item(&conn, insert_task(&conn, "work", "Quarterly budget", "today")).unwrap();
+        other.namespace_id = Some(other_ns);
+        update_item(&conn, &other, None).unwrap();
+
+        let default_ns = get_item(&conn, default_id).unwrap().namespace_id.unwrap();
+        let hits = search(&conn, "quarterly", Some(&[default_ns]), 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].item.id, Some(default_id));
+        assert_eq!(search(&conn, "quarterly", Some(&[default_ns, other_ns]), 10).unwrap().len(), 2);
+        assert_eq!(search(&conn, "quarterly", None, 10).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::Connection;
+
+use crate::db::{item::Item, journal};
+
+/// Attaches tags to an item, creating the ones that don't exist yet.
+/// Tags the item already carries are left alone.
+pub fn add_tags(conn: &Connection, item_id: i64, tags: &[String]) -> Result<(), String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    for tag in tags {
+        conn.execute(
+            "INSERT OR IGNORE INTO tags (name, created_at) VALUES (?1, ?2)",
+            rusqlite::params![tag, now],
+        )
+        .map_err(|e| e.to_string())?;
+        let added = conn
+            .execute(
+                "INSERT OR IGNORE INTO item_tags (item_id, tag_id)
+                 SELECT ?1, id FROM tags WHERE name = ?2",
+                rusqlite::params![item_id, tag],
+            )
+            .map_err(|e| e.to_string())?;
+        if added > 0 {
+            journal::record_tag(conn, item_id, tag, true).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// Detaches tags from an item, returns how many it carried.
+pub fn remove_tags(conn: &Connection, item_id: i64, tags: &[String]) -> Result<usize, String> {
+    let mut removed = 0;
+    for tag in tags {
+        let count = conn
+            .execute(
+                "DELETE FROM item_tags
+                 WHERE item_id = ?1 AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
+                rusqlite::params![item_id, tag],
+            )
+            .map_err(|e| e.to_string())?;
+        if count > 0 {
+            journal::record_tag(conn, item_id, tag, false).map_err(|e| e.to_string())?;
+        }
+        removed += count;
+    }
+    Ok(removed)
+}
+
+/// Gets the tag names of an item, sorted by name.
+pub fn get_tags_for_item(conn: &Connection, item_id: i64) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.name FROM item_tags it
+             INNER JOIN tags t ON t.id = it.tag_id
+             WHERE it.item_id = ?1
+             ORDER BY t.name",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let tags = stmt
+        .query_map([item_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(tags)
+}
+
+/// Sets the runtime `tags` of every item from the db.
+pub fn mark_tags(conn: &Connection, items: &mut [Item]) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT it.item_id, t.name FROM item_tags it
+             INNER JOIN tags t ON t.id = it.tag_id
+             ORDER BY t.name",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        let (item_id, name) = row.map_err(|e| e.to_string())?;
+        tags.entry(item_id).or_default().push(name);
+    }
+
+    for item in items.iter_mut() {
+        item.tags = item
+            .id
+            .and_then(|id| tags.remove(&id))
+            .unwrap_or_default();
+    }
+    Ok(())
+}
+
+/// SQL condition matching items that carry (or, when `exclude` is set, don't carry)
+/// the tag bound to the next `?` parameter, for use on the `items` table.
+pub fn tag_condition(exclude: bool) -> String {
+    format!(
+        "{}EXISTS (SELECT 1 FROM item_tags it
+         INNER JOIN tags t ON t.id = it.tag_id
+         WHERE it.item_id = items.id AND t.name = ?)",
+        if exclude { "NOT " } else { "" }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{
+            crud::{get_item, query_items},
+            item::{ItemQuery, TASK},
+        },
+        tests::{get_test_conn, insert_task},
+    };
+
+    fn tags(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_add_and_remove_tags() {
+        let (conn, _temp_file) = get_test_conn();
+        let task_id = insert_task(&conn, "work", "Fix login", "today");
+
+        add_tags(&conn, task_id, &tags(&["security", "backend"])).unwrap();
+        // Adding twice is a no-op
+        add_tags(&conn, task_id, &tags(&["backend"])).unwrap();
+        assert_eq!(get_tags_for_item(&conn, task_id).unwrap(), vec!["backend", "security"]);
+
+        assert_eq!(remove_tags(&conn, task_id, &tags(&["backend", "missing"])).unwrap(), 1);
+        assert_eq!(get_tags_for_item(&conn, task_id).unwrap(), vec!["security"]);
+    }
+
+    #[test]
+    fn test_mark_tags() {
+        let (conn, _temp_file) = get_test_conn();
+        let a = insert_task(&conn, "work", "a", "today");
+        let b = insert_task(&conn, "work", "b", "today");
+        add_tags(&conn, a, &tags(&["ops"])).unwrap();
+
+        let mut items = vec![get_item(&conn, a).unwrap(), get_item(&conn, b).unwrap()];
+        mark_tags(&conn, &mut items).unwrap();
+        assert_eq!(items[0].tags, vec!["ops"]);
+        assert!(items[1].tags.is_empty());
+    }
+
+    #[test]
+    fn test_query_items_by_tag() {
+        let (conn, _temp_file) = get_test_conn();
+        let a = insert_task(&conn, "work", "a", "today");
+        let b = insert_task(&conn, "work", "b", "today");
+        let c = insert_task(&conn, "work", "c", "today");
+        add_tags(&conn, a, &tags(&["backend", "security"])).unwrap();
+        add_tags(&conn, b, &tags(&["backend"])).unwrap();
+
+        let ids = |query: ItemQuery| -> Vec<i64> {
+            query_items(&conn, &query.with_action(TASK))
+                .unwrap()
+                .iter()
+                .filter_map(|item| item.id)
+                .collect()
+        };
+        assert_eq!(ids(ItemQuery::new().with_tags(vec!["backend"])), vec![a, b]);
+        assert_eq!(ids(ItemQuery::new().with_tags(vec!["backend", "security"])), vec![a]);
+        assert_eq!(ids(ItemQuery::new().without_tags(vec!["security"])), vec![b, c]);
+        assert_eq!(
+            ids(ItemQuery::new().with_tags(vec!["backend"]).without_tags(vec!["security"])),
+            vec![b]
+        );
+    }
+}
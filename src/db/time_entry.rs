@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use rusqlite::Connection;
+
+use crate::db::journal::{self, CHANGE_TIME_ENTRY};
+
+/// A span of work on a task, `ended_at` is None while the timer is running.
+#[derive(Debug, Clone)]
+pub struct TimeEntry {
+    pub id: i64,
+    pub item_id: i64,
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+}
+
+impl TimeEntry {
+    pub fn from_row(row: &rusqlite::Row) -> Result<Self, rusqlite::Error> {
+        Ok(TimeEntry {
+            id: row.get("id")?,
+            item_id: row.get("item_id")?,
+            started_at: row.get("started_at")?,
+            ended_at: row.get("ended_at")?,
+        })
+    }
+
+    /// Whole minutes worked, counting a running timer up to `now`.
+    pub fn minutes(&self, now: i64) -> i64 {
+        (self.ended_at.unwrap_or(now) - self.started_at).max(0) / 60
+    }
+}
+
+/// Starts a timer for the user on a task.
+/// A user has at most one running timer, enforced by a unique index.
+pub fn start_timer(conn: &Connection, item_id: i64, user_id: i64, now: i64) -> Result<i64, String> {
+    conn.execute(
+        "INSERT INTO time_entries (item_id, user_id, started_at) VALUES (?1, ?2, ?3)",
+        rusqlite::params![item_id, user_id, now],
+    )
+    .map_err(|e| {
+        if e.to_string().contains("UNIQUE constraint failed") {
+            "A timer is already running, stop it first".to_string()
+        } else {
+            e.to_string()
+        }
+    })?;
+    let entry_id = conn.last_insert_rowid();
+    journal::record_row(conn, CHANGE_TIME_ENTRY, &[entry_id], None).map_err(|e| e.to_string())?;
+    Ok(entry_id)
+}
+
+/// Gets the running timer of a user, if any.
+pub fn get_running_timer(conn: &Connection, user_id: i64) -> Result<Option<TimeEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, item_id, started_at, ended_at
+             FROM time_entries
+             WHERE user_id = ?1 AND ended_at IS NULL",
+        )
+        .map_err(|e| e.to_string())?;
+
+    stmt.query_row([user_id], TimeEntry::from_row)
+        .map(Some)
+        .or_else(|e| {
+            if e == rusqlite::Error::QueryReturnedNoRows {
+                Ok(None)
+            } else {
+                Err(e.to_string())
+            }
+        })
+}
+
+/// Stops a running timer at `now`.
+pub fn stop_timer(conn: &Connection, entry_id: i64, now: i64) -> Result<(), String> {
+    let before = journal::before_row(conn, CHANGE_TIME_ENTRY, &[entry_id]).map_err(|e| e.to_string())?;
+    let updated = conn
+        .execute(
+            "UPDATE time_entries SET ended_at = ?1 WHERE id = ?2 AND ended_at IS NULL",
+            rusqlite::params![now, entry_id],
+        )
+        .map_err(|e| e.to_string())?;
+
+    if updated == 0 {
+        return Err(format!("Timer {} is not running", entry_id));
+    }
+    journal::record_row(conn, CHANGE_TIME_ENTRY, &[entry_id], before).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Stops at `now` every timer still running on a task, whoever started it.
+pub fn stop_timers_on_item(conn: &Connection, item_id: i64, now: i64) -> Result<(), String> {
+    let running = get_time_entries_for_item(conn, item_id)?
+        .into_iter()
+        .filter(|entry| entry.ended_at.is_none());
+    for entry in running {
+        stop_timer(conn, entry.id, now)?;
+    }
+    Ok(())
+}
+
+/// Records `minutes` of work on a task that ended at `now`.
+pub fn log_time(
+    conn: &Connection,
+    item_id: i64,
+    user_id: i64,
+    minutes: i64,
+    now: i64,
+) -> Result<i64, String> {
+    conn.execute(
+        "INSERT INTO time_entries (item_id, user_id, started_at, ended_at) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![item_id, user_id, now - minutes * 60, now],
+    )
+    .map_err(|e| e.to_string())?;
+    let entry_id = conn.last_insert_rowid();
+    journal::record_row(conn, CHANGE_TIME_ENTRY, &[entry_id], None).map_err(|e| e.to_string())?;
+    Ok(entry_id)
+}
+
+/// Gets all time entries of a task, oldest first.
+pub fn get_time_entries_for_item(conn: &Connection, item_id: i64) -> Result<Vec<TimeEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, item_id, started_at, ended_at
+             FROM time_entries
+             WHERE item_id = ?1
+             ORDER BY started_at ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let entries = stmt
+        .query_map([item_id], TimeEntry::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(entries)
+}
+
+/// Minutes spent per task over finished entries, keyed by item id.
+pub fn spent_minutes_by_item(conn: &Connection) -> Result<HashMap<i64, i64>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT item_id, SUM(ended_at - started_at) / 60
+             FROM time_entries
+             WHERE ended_at IS NOT NULL
+             GROUP BY item_id",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let spent = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(spent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{get_test_conn, insert_task};
+
+    #[test]
+    fn test_start_and_stop_timer() {
+        let (conn, _temp_file) = get_test_conn();
+        let task_id = insert_task(&conn, "work", "Test task", "today");
+
+        let entry_id = start_timer(&conn, task_id, 1, 1_000).unwrap();
+        let running = get_running_timer(&conn, 1).unwrap().unwrap();
+        assert_eq!(running.id, entry_id);
+        assert_eq!(running.minutes(1_000 + 600), 10);
+
+        // Only one running timer per user
+        let err = start_timer(&conn, task_id, 1, 1_100).unwrap_err();
+        assert!(err.contains("already running"));
+
+        stop_timer(&conn, entry_id, 1_000 + 1_800).unwrap();
+        assert!(get_running_timer(&conn, 1).unwrap().is_none());
+        assert!(stop_timer(&conn, entry_id, 5_000).is_err());
+
+        let entries = get_time_entries_for_item(&conn, task_id).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].minutes(9_999), 30);
+    }
+
+    #[test]
+    fn test_spent_minutes_by_item() {
+        let (conn, _temp_file) = get_test_conn();
+        let task1_id = insert_task(&conn, "work", "Task 1", "today");
+        let task2_id = insert_task(&conn, "work", "Task 2", "today");
+
+        log_time(&conn, task1_id, 1, 45, 10_000).unwrap();
+        log_time(&conn, task1_id, 1, 30, 20_000).unwrap();
+        log_time(&conn, task2_id, 1, 15, 20_000).unwrap();
+        // Running timers don't count yet
+        start_timer(&conn, task2_id, 1, 20_000).unwrap();
+
+        let spent = spent_minutes_by_item(&conn).unwrap();
+        assert_eq!(spent.get(&task1_id), Some(&75));
+        assert_eq!(spent.get(&task2_id), Some(&15));
+    }
+}
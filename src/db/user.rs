@@ -1,6 +1,11 @@
 use rusqlite::Connection;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::db::{
+    crud::placeholders,
+    namespace::{get_namespace_by_id, is_last_owner, list_user_namespace_ids},
+};
+
 #[derive(Debug, Clone)]
 pub struct User {
     pub id: i64,
@@ -105,6 +110,26 @@ pub fn list_users(conn: &Connection) -> Result<Vec<User>, String> {
     Ok(users)
 }
 
+/// Lists the users that are members of any of `namespace_ids`.
+pub fn list_namespace_users(conn: &Connection, namespace_ids: &[i64]) -> Result<Vec<User>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, name, display_name, created_at, created_by FROM users
+             WHERE id IN (SELECT user_id FROM user_namespaces WHERE namespace_id IN ({}))
+             ORDER BY name",
+            placeholders(namespace_ids.len())
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let users = stmt
+        .query_map(rusqlite::params_from_iter(namespace_ids), User::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(users)
+}
+
 /// Deletes a user by name. Returns error if user has tasks or namespace memberships.
 pub fn delete_user(conn: &Connection, name: &str) -> Result<(), String> {
     // First get the user ID
@@ -127,6 +152,19 @@ pub fn delete_user(conn: &Connection, name: &str) -> Result<(), String> {
         ));
     }
 
+    // A namespace must keep an owner
+    for namespace_id in list_user_namespace_ids(conn, user.id)? {
+        if is_last_owner(conn, user.id, namespace_id)? {
+            let namespace = get_namespace_by_id(conn, namespace_id)?
+                .map(|ns| ns.name)
+                .unwrap_or_else(|| namespace_id.to_string());
+            return Err(format!(
+                "Cannot delete user '{}': they are the only owner of '{}'. Assign another owner first.",
+                name, namespace
+            ));
+        }
+    }
+
     // Delete user (CASCADE will remove user_namespaces entries)
     let deleted = conn
         .execute("DELETE FROM users WHERE id = ?1", [user.id])
@@ -139,6 +177,22 @@ pub fn delete_user(conn: &Connection, name: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Retrieves the time zone name of a user, None when it is not set.
+pub fn get_user_time_zone(conn: &Connection, id: i64) -> Result<Option<String>, String> {
+    conn.query_row("SELECT time_zone FROM users WHERE id = ?1", [id], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+/// Sets or clears (None) the time zone name of a user.
+pub fn set_user_time_zone(conn: &Connection, id: i64, time_zone: Option<&str>) -> Result<(), String> {
+    conn.execute(
+        "UPDATE users SET time_zone = ?1 WHERE id = ?2",
+        rusqlite::params![time_zone, id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,6 +229,19 @@ mod tests {
         assert!(!users.is_empty());
     }
 
+    #[test]
+    fn test_list_namespace_users() {
+        let (conn, _temp_file) = get_test_conn();
+        let ann = create_user(&conn, "ann", None, None).unwrap();
+        create_user(&conn, "bob", None, None).unwrap();
+        let team = crate::db::namespace::create_namespace(&conn, "team", None, ann).unwrap();
+
+        let users = list_namespace_users(&conn, &[team]).unwrap();
+        let names: Vec<&str> = users.iter().map(|user| user.name.as_str()).collect();
+        assert_eq!(names, vec!["ann"]);
+        assert!(list_namespace_users(&conn, &[]).unwrap().is_empty());
+    }
+
     #[test]
     fn test_delete_user() {
         let (conn, _temp_file) = get_test_conn();
@@ -186,4 +253,18 @@ mod tests {
         let user = get_user_by_name(&conn, "todelete").unwrap();
         assert!(user.is_none());
     }
+
+    #[test]
+    fn test_user_time_zone() {
+        let (conn, _temp_file) = get_test_conn();
+
+        let user_id = create_user(&conn, "traveller", None, None).unwrap();
+        assert_eq!(get_user_time_zone(&conn, user_id).unwrap(), None);
+
+        set_user_time_zone(&conn, user_id, Some("Asia/Tokyo")).unwrap();
+        assert_eq!(get_user_time_zone(&conn, user_id).unwrap(), Some("Asia/Tokyo".to_string()));
+
+        set_user_time_zone(&conn, user_id, None).unwrap();
+        assert_eq!(get_user_time_zone(&conn, user_id).unwrap(), None);
+    }
 }
@@ -0,0 +1,198 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::Connection;
+
+/// Who a view belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewScope {
+    /// Only visible to this user, in every namespace
+    Personal(i64),
+    /// Visible to every member of this namespace
+    Shared(i64),
+}
+
+impl ViewScope {
+    fn columns(&self) -> (Option<i64>, Option<i64>) {
+        match *self {
+            ViewScope::Personal(user_id) => (Some(user_id), None),
+            ViewScope::Shared(namespace_id) => (None, Some(namespace_id)),
+        }
+    }
+}
+
+/// A saved `list` invocation, `args` are the words following `ctm list`.
+#[derive(Debug, Clone)]
+pub struct View {
+    pub name: String,
+    pub args: Vec<String>,
+    pub scope: ViewScope,
+}
+
+impl View {
+    pub fn from_row(row: &rusqlite::Row) -> Result<Self, rusqlite::Error> {
+        let args: String = row.get("args")?;
+        let args = serde_json::from_str(&args).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+        })?;
+        let user_id: Option<i64> = row.get("user_id")?;
+        let scope = match user_id {
+            Some(user_id) => ViewScope::Personal(user_id),
+            None => ViewScope::Shared(row.get("namespace_id")?),
+        };
+        Ok(View {
+            name: row.get("name")?,
+            args,
+            scope,
+        })
+    }
+}
+
+/// Saves a view, replacing the arguments of one with the same name in the scope.
+/// Returns true when an existing view was replaced.
+pub fn save_view(
+    conn: &Connection,
+    name: &str,
+    args: &[String],
+    scope: ViewScope,
+    created_by: i64,
+) -> Result<bool, String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let args = serde_json::to_string(args).map_err(|e| e.to_string())?;
+    let (user_id, namespace_id) = scope.columns();
+
+    let updated = conn
+        .execute(
+            "UPDATE views SET args = ?1, created_by = ?2, created_at = ?3
+             WHERE name = ?4 AND user_id IS ?5 AND namespace_id IS ?6",
+            rusqlite::params![args, created_by, now, name, user_id, namespace_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated > 0 {
+        return Ok(true);
+    }
+
+    conn.execute(
+        "INSERT INTO views (name, args, user_id, namespace_id, created_by, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![name, args, user_id, namespace_id, created_by, now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(false)
+}
+
+/// Finds the view a user means by `name` in a namespace,
+/// their own view wins over a shared one of the same name.
+pub fn get_view(conn: &Connection, name: &str, user_id: i64, namespace_id: i64) -> Result<Option<View>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT name, args, user_id, namespace_id
+             FROM views
+             WHERE name = ?1 AND (user_id = ?2 OR namespace_id = ?3)
+             ORDER BY user_id IS NULL
+             LIMIT 1",
+        )
+        .map_err(|e| e.to_string())?;
+
+    stmt.query_row(rusqlite::params![name, user_id, namespace_id], View::from_row)
+        .map(Some)
+        .or_else(|e| {
+            if e == rusqlite::Error::QueryReturnedNoRows {
+                Ok(None)
+            } else {
+                Err(e.to_string())
+            }
+        })
+}
+
+/// Lists the views of a user and those shared in a namespace, sorted by name.
+pub fn list_views(conn: &Connection, user_id: i64, namespace_id: i64) -> Result<Vec<View>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT name, args, user_id, namespace_id
+             FROM views
+             WHERE user_id = ?1 OR namespace_id = ?2
+             ORDER BY name, user_id IS NULL",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let views = stmt
+        .query_map(rusqlite::params![user_id, namespace_id], View::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(views)
+}
+
+/// Deletes the view with `name` in the scope, returns whether it existed.
+pub fn delete_view(conn: &Connection, name: &str, scope: ViewScope) -> Result<bool, String> {
+    let (user_id, namespace_id) = scope.columns();
+    let deleted = conn
+        .execute(
+            "DELETE FROM views WHERE name = ?1 AND user_id IS ?2 AND namespace_id IS ?3",
+            rusqlite::params![name, user_id, namespace_id],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(deleted > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::Context,
+        db::{namespace::create_namespace, user::create_user},
+        tests::get_test_conn,
+    };
+
+    fn args(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn test_save_and_get_view() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let (me, ns) = (ctx.current_user_id, ctx.current_namespace_id);
+
+        assert!(!save_view(&conn, "backlog", &args(&["task", "-s", "all"]), ViewScope::Personal(me), me).unwrap());
+        let view = get_view(&conn, "backlog", me, ns).unwrap().unwrap();
+        assert_eq!(view.args, vec!["task", "-s", "all"]);
+        assert_eq!(view.scope, ViewScope::Personal(me));
+
+        // Saving again replaces the arguments
+        assert!(save_view(&conn, "backlog", &args(&["task", "overdue"]), ViewScope::Personal(me), me).unwrap());
+        assert_eq!(get_view(&conn, "backlog", me, ns).unwrap().unwrap().args, vec!["task", "overdue"]);
+        assert_eq!(list_views(&conn, me, ns).unwrap().len(), 1);
+
+        assert!(get_view(&conn, "missing", me, ns).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_shared_views() {
+        let (conn, _temp_file) = get_test_conn();
+        let ctx = Context::default_from_db(&conn).unwrap();
+        let (me, ns) = (ctx.current_user_id, ctx.current_namespace_id);
+        let bob = create_user(&conn, "bob", None, None).unwrap();
+        let other_ns = create_namespace(&conn, "other", None, me).unwrap();
+
+        save_view(&conn, "team", &args(&["task", "--all-users"]), ViewScope::Shared(ns), me).unwrap();
+        // Shared within the namespace only
+        assert!(get_view(&conn, "team", bob, ns).unwrap().is_some());
+        assert!(get_view(&conn, "team", bob, other_ns).unwrap().is_none());
+
+        // A personal view shadows the shared one
+        save_view(&conn, "team", &args(&["task"]), ViewScope::Personal(bob), bob).unwrap();
+        let view = get_view(&conn, "team", bob, ns).unwrap().unwrap();
+        assert_eq!(view.scope, ViewScope::Personal(bob));
+        assert_eq!(list_views(&conn, bob, ns).unwrap().len(), 2);
+        assert_eq!(list_views(&conn, me, ns).unwrap().len(), 1);
+
+        assert!(delete_view(&conn, "team", ViewScope::Personal(bob)).unwrap());
+        assert!(!delete_view(&conn, "team", ViewScope::Personal(bob)).unwrap());
+        assert_eq!(get_view(&conn, "team", bob, ns).unwrap().unwrap().scope, ViewScope::Shared(ns));
+    }
+}
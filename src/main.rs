@@ -15,6 +15,10 @@ use context::Context;
 
 fn main() {
     let cli_args = CliArgs::parse();
+    if let Err(err) = config::check_config() {
+        print_red(&format!("Error reading config: {}", err));
+        exit(1)
+    }
     let conn = match db::conn::connect() {
         Ok(conn) => conn,
         Err(err) => {
@@ -29,16 +33,39 @@ fn main() {
         cli_args.as_user.as_deref(),
         cli_args.namespace.as_deref(),
     ) {
-        Ok(ctx) => ctx,
+        Ok(ctx) => ctx
+            .with_all_namespaces(cli_args.all_namespaces)
+            .with_output(cli_args.output_format()),
         Err(err) => {
             print_red(&format!("Error resolving identity: {}", err));
             exit(1)
         }
     };
 
+    args::timezone::set_current(ctx.time_zone);
+    let holidays = config::load_config().and_then(|c| c.holidays).unwrap_or_default();
+    if let Err(err) = args::timestr::set_holidays(&holidays) {
+        print_red(&format!("Error reading holidays from config: {}", err));
+        exit(1)
+    }
+
+    // Journal what the command changes so that `ctm undo` can reverse it,
+    // the entry is only created with the first change so read-only commands leave none
+    let command: Vec<String> = std::env::args().skip(1).collect();
+    db::journal::begin(
+        ctx.current_user_id,
+        ctx.current_namespace_id,
+        &format!("ctm {}", actions::view::shell_words(&command)),
+    );
+
     let result = actions::handler::handle_commands(&conn, &ctx, cli_args);
-    if result.is_err() {
-        print_red(&format!("Error: {}", result.unwrap_err()));
+    if let Err(err) = result {
+        // Keep stdout parseable for scripts reading JSON
+        if ctx.output.is_table() {
+            print_red(&format!("Error: {}", err));
+        } else {
+            eprintln!("{}", serde_json::json!({ "error": err }));
+        }
         exit(1)
     }
 }
@@ -14,6 +14,15 @@ use crate::{
     },
 };
 
+// Items inserted by the helpers land in the default namespace,
+// as they would when created through the CLI.
+fn default_namespace_id(conn: &Connection) -> Option<i64> {
+    conn.query_row("SELECT id FROM namespaces WHERE name = 'default'", [], |row| {
+        row.get(0)
+    })
+    .ok()
+}
+
 pub fn get_test_conn() -> (Connection, NamedTempFile) {
     let temp_file = NamedTempFile::new().unwrap();
     let db_path = temp_file.path().to_str().unwrap();
@@ -24,30 +33,45 @@ pub fn get_test_conn() -> (Connection, NamedTempFile) {
 
 pub fn insert_task(conn: &Connection, category: &str, content: &str, timestr: &str) -> i64 {
     let target_time = timestr::to_unix_epoch(timestr).unwrap();
-    let new_task = Item::with_target_time(
+    let mut new_task = Item::with_target_time(
         "task".to_string(),
         category.to_string(),
         content.to_string(),
         Some(target_time),
     );
-    insert_item(conn, &new_task).unwrap()
+    new_task.namespace_id = default_namespace_id(conn);
+    insert_item(conn, &new_task, None).unwrap()
+}
+
+pub fn insert_subtask(conn: &Connection, parent_id: i64, content: &str) -> i64 {
+    let parent = get_item(conn, parent_id).unwrap();
+    let mut subtask = Item::with_target_time(
+        "task".to_string(),
+        parent.category,
+        content.to_string(),
+        parent.target_time,
+    );
+    subtask.namespace_id = parent.namespace_id;
+    subtask.parent_id = Some(parent_id);
+    insert_item(conn, &subtask, None).unwrap()
 }
 
 pub fn insert_record(conn: &Connection, category: &str, content: &str, timestr: &str) -> i64 {
     let create_time = timestr::to_unix_epoch(timestr).unwrap();
-    let new_record = Item::with_create_time(
+    let mut new_record = Item::with_create_time(
         "record".to_string(),
         category.to_string(),
         content.to_string(),
         create_time,
     );
-    insert_item(conn, &new_record).unwrap()
+    new_record.namespace_id = default_namespace_id(conn);
+    insert_item(conn, &new_record, None).unwrap()
 }
 
 pub fn update_status(conn: &Connection, rowid: i64, status_code: u8) {
     let mut task = get_item(conn, rowid).unwrap();
     task.status = status_code;
-    update_item(conn, &task).unwrap();
+    update_item(conn, &task, None).unwrap();
 }
 
 pub fn insert_recurring_task(
@@ -56,14 +80,17 @@ pub fn insert_recurring_task(
     content: &str,
     human_schedule: &str,
 ) -> i64 {
-    let cron_schedule = timestr::parse_recurring_timestr(human_schedule).unwrap();
-    let recurring_task = Item::create_recurring_task(
+    let schedule = timestr::parse_recurring_schedule(human_schedule).unwrap();
+    let mut recurring_task = Item::create_recurring_task(
         category.to_string(),
         content.to_string(),
-        cron_schedule,
+        schedule.cron,
         human_schedule.to_string(),
     );
-    insert_item(conn, &recurring_task).unwrap()
+    recurring_task.recur_until = schedule.until;
+    recurring_task.recur_count = schedule.count;
+    recurring_task.namespace_id = default_namespace_id(conn);
+    insert_item(conn, &recurring_task, None).unwrap()
 }
 
 pub fn insert_recurring_record(
@@ -73,11 +100,12 @@ pub fn insert_recurring_record(
     recurring_task_id: i64,
     good_until: i64,
 ) -> i64 {
-    let record = Item::create_recurring_record(
+    let mut record = Item::create_recurring_record(
         category.to_string(),
         content.to_string(),
         recurring_task_id,
         good_until,
     );
-    insert_item(conn, &record).unwrap()
+    record.namespace_id = default_namespace_id(conn);
+    insert_item(conn, &record, None).unwrap()
 }